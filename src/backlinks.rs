@@ -0,0 +1,233 @@
+use gtk::prelude::*;
+use gtk::{ApplicationWindow, Box, Button, Expander, Label, ListBox, Orientation, ScrolledWindow};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::link_index::{LinkIndex, Mention};
+use crate::utils::show_error_dialog;
+
+/// Collapsible panel under the editor listing the notes that link to the active note
+/// ("Linked mentions") and the notes that mention its title without linking ("Unlinked mentions")
+#[derive(Clone)]
+pub struct BacklinksPanel {
+    expander: Expander,
+    linked_list: ListBox,
+    unlinked_list: ListBox,
+    /// Source note of each row in the linked list
+    linked_sources: Rc<RefCell<Vec<String>>>,
+    index: LinkIndex,
+    window: ApplicationWindow,
+    /// Title of the note the panel is showing
+    current_title: Rc<RefCell<Option<String>>>,
+}
+
+impl BacklinksPanel {
+    pub fn new(window: &ApplicationWindow, index: &LinkIndex) -> Self {
+        let linked_list = ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(vec!["backlinks-list"])
+            .build();
+        linked_list.set_placeholder(Some(&placeholder_label("No notes link here yet")));
+
+        let unlinked_list = ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(vec!["backlinks-list"])
+            .build();
+        unlinked_list.set_placeholder(Some(&placeholder_label("No unlinked mentions")));
+
+        let content = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(4)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_bottom(8)
+            .build();
+        content.append(&section_label("Linked mentions"));
+        content.append(&linked_list);
+        content.append(&section_label("Unlinked mentions"));
+        content.append(&unlinked_list);
+
+        let scrolled_window = ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .max_content_height(220)
+            .propagate_natural_height(true)
+            .build();
+        scrolled_window.set_child(Some(&content));
+
+        let expander = Expander::builder()
+            .label("Backlinks")
+            .child(&scrolled_window)
+            .css_classes(vec!["backlinks-panel"])
+            .visible(false) // Shown once a note is open
+            .build();
+
+        let panel = BacklinksPanel {
+            expander,
+            linked_list,
+            unlinked_list,
+            linked_sources: Rc::new(RefCell::new(Vec::new())),
+            index: index.clone(),
+            window: window.clone(),
+            current_title: Rc::new(RefCell::new(None)),
+        };
+
+        // Open the linking note when a backlink is clicked
+        let window_for_rows = window.clone();
+        let linked_sources_for_rows = panel.linked_sources.clone();
+        panel.linked_list.connect_row_activated(move |_, row| {
+            let source = match linked_sources_for_rows.borrow().get(row.index() as usize) {
+                Some(source) => source.clone(),
+                None => return,
+            };
+            if let Err(e) = WidgetExt::activate_action(&window_for_rows, "win.open-note", Some(&source.to_variant())) {
+                eprintln!("Error opening note {}: {}", source, e);
+            }
+        });
+
+        let panel_for_index = panel.clone();
+        index.connect_changed(move || panel_for_index.refresh());
+
+        panel
+    }
+
+    /// The widget to add to the editor area
+    pub fn widget(&self) -> &Expander {
+        &self.expander
+    }
+
+    /// Show the backlinks of a note, or hide the panel when no note is open
+    pub fn show_for(&self, title: Option<&str>) {
+        *self.current_title.borrow_mut() = title.map(str::to_string);
+        self.refresh();
+    }
+
+    /// Expand or collapse the panel
+    pub fn toggle(&self) {
+        if self.expander.is_visible() {
+            self.expander.set_expanded(!self.expander.is_expanded());
+        }
+    }
+
+    /// Rebuild both lists from the index
+    pub fn refresh(&self) {
+        let title = match self.current_title.borrow().clone() {
+            Some(title) => title,
+            None => {
+                self.expander.set_visible(false);
+                return;
+            }
+        };
+        self.expander.set_visible(true);
+
+        clear_list(&self.linked_list);
+        clear_list(&self.unlinked_list);
+
+        let backlinks = self.index.backlinks(&title);
+        for backlink in &backlinks {
+            self.linked_list.append(&mention_row(&backlink.source, &backlink.context));
+        }
+        *self.linked_sources.borrow_mut() = backlinks.iter().map(|backlink| backlink.source.clone()).collect();
+
+        let mentions = self.index.unlinked_mentions(&title);
+        for mention in &mentions {
+            let row = mention_row(&mention.source, &mention.context);
+            row.set_activatable(false);
+            if let Some(row_box) = row.child().and_then(|child| child.downcast::<Box>().ok()) {
+                row_box.append(&self.link_button(mention, &title));
+            }
+            self.unlinked_list.append(&row);
+        }
+
+        self.expander.set_label(Some(&format!(
+            "Backlinks ({}) \u{00B7} Unlinked mentions ({})",
+            backlinks.len(),
+            mentions.len()
+        )));
+    }
+
+    /// "Link" button that turns a mention into a [[link]]
+    fn link_button(&self, mention: &Mention, title: &str) -> Button {
+        let button = Button::builder()
+            .label("Link")
+            .valign(gtk::Align::Center)
+            .tooltip_text(format!("Replace this mention with [[{}]]", title))
+            .css_classes(vec!["link-mention-button"])
+            .build();
+
+        let panel = self.clone();
+        let mention = mention.clone();
+        let title = title.to_string();
+        button.connect_clicked(move |_| {
+            if let Err(e) = panel.index.link_mention(&mention, &title) {
+                show_error_dialog(&panel.window, "Link Failed", &format!("Could not link the mention: {}", e));
+                panel.refresh();
+            }
+        });
+        button
+    }
+}
+
+fn clear_list(list: &ListBox) {
+    while let Some(row) = list.row_at_index(0) {
+        list.remove(&row);
+    }
+}
+
+fn section_label(text: &str) -> Label {
+    Label::builder()
+        .label(text)
+        .xalign(0.0)
+        .margin_top(6)
+        .css_classes(vec!["backlinks-section"])
+        .build()
+}
+
+fn placeholder_label(text: &str) -> Label {
+    Label::builder()
+        .label(text)
+        .xalign(0.0)
+        .margin_top(4)
+        .margin_bottom(4)
+        .css_classes(vec!["dim-label"])
+        .build()
+}
+
+/// A row with the source note's title above the line that references it
+fn mention_row(source: &str, context: &str) -> gtk::ListBoxRow {
+    let text_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(2)
+        .hexpand(true)
+        .build();
+
+    let source_label = Label::builder()
+        .label(source)
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .css_classes(vec!["note-title"])
+        .build();
+    let context_label = Label::builder()
+        .label(context)
+        .xalign(0.0)
+        .wrap(true)
+        .lines(2)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .css_classes(vec!["dim-label", "note-preview"])
+        .build();
+    text_box.append(&source_label);
+    text_box.append(&context_label);
+
+    let row_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .margin_top(4)
+        .margin_bottom(4)
+        .build();
+    row_box.append(&text_box);
+
+    let row = gtk::ListBoxRow::builder()
+        .css_classes(vec!["backlink-row"])
+        .build();
+    row.set_child(Some(&row_box));
+    row
+}
@@ -0,0 +1,321 @@
+// Command-line interface: `penscript <command> ...` reads and changes notes from a
+// terminal or a script without opening a window. It goes through the same `Note`
+// model and notes directory as the app, and never initialises GTK.
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use crate::export::{self, Theme};
+use crate::manuscript::{self, Format, Manuscript};
+use crate::note::Note;
+use crate::utils::check_note_title;
+
+/// Names of the commands, checked by `main` before starting the app
+pub const COMMANDS: [&str; 9] = ["new", "list", "cat", "append", "search", "rename", "delete", "export", "help"];
+
+const USAGE: &str = "\
+Usage: penscript [COMMAND] [ARGUMENTS]
+
+Without a command, the app window opens.
+
+Commands:
+  new <title> [--stdin] [--folder <folder>]
+                        Create a note, with the text read from standard input if
+                        --stdin is given, and print its path
+  list [--json]         List notes, most recently modified first
+  cat <title>           Print a note
+  append <title> [text...]
+                        Add the text, or standard input if there is none, to the
+                        end of a note
+  search <query>        Print the lines of notes containing the query, and the
+                        titles of notes it is part of (case-insensitive)
+  rename <title> <new title>
+                        Rename a note and update the [[links]] to it
+  delete <title>        Delete a note
+  export <title> <file> [--dark]
+                        Export a note as HTML, EPUB or DOCX, chosen by the file's
+                        extension (.html, .epub or .docx)
+  export --folder <folder> <destination> [--dark]
+                        Export a folder (\"\" for all notes) as HTML pages into the
+                        destination folder, or as one EPUB or DOCX book
+  help                  Show this help";
+
+/// One note in the output of `list --json`
+#[derive(Serialize)]
+struct NoteInfo {
+    title: String,
+    folder: String,
+    path: String,
+    /// RFC 3339 modification time
+    modified: Option<String>,
+    tags: Vec<String>,
+    words: usize,
+}
+
+/// Arguments of a command: its positional arguments, the flags given
+/// (e.g. "--json") and the options with their values (e.g. "--folder")
+struct Arguments {
+    positional: Vec<String>,
+    flags: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Arguments {
+    /// Split `args`, accepting only the given flags and options. Everything after
+    /// "--" is positional, so titles starting with dashes can be passed.
+    fn parse(args: &[String], flags: &[&str], options: &[&str]) -> Result<Self, String> {
+        let mut arguments = Arguments { positional: Vec::new(), flags: Vec::new(), options: Vec::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                arguments.positional.extend(args.by_ref().cloned());
+            } else if flags.contains(&arg.as_str()) {
+                arguments.flags.push(arg.clone());
+            } else if options.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                arguments.options.push((arg.clone(), value.clone()));
+            } else if arg.starts_with("--") {
+                return Err(format!("Unknown option {}", arg));
+            } else {
+                arguments.positional.push(arg.clone());
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn has(&self, flag: &str) -> bool {
+        self.flags.iter().any(|given| given == flag)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(given, _)| given == name).map(|(_, value)| value.as_str())
+    }
+
+    /// The positional arguments, which must be exactly `names` long
+    fn expect(&self, names: &[&str]) -> Result<&[String], String> {
+        if self.positional.len() != names.len() {
+            let expected = names.iter().map(|name| format!("<{}>", name)).collect::<Vec<_>>().join(" ");
+            return Err(format!("Expected {}", expected));
+        }
+        Ok(&self.positional)
+    }
+}
+
+/// Run a command (one of `COMMANDS`) with the arguments that follow it
+pub fn run(command: &str, args: &[String]) -> Result<(), String> {
+    match command {
+        "new" => new_note(args),
+        "list" => list(args),
+        "cat" => cat(args),
+        "append" => append(args),
+        "search" => search(args),
+        "rename" => rename(args),
+        "delete" => delete(args),
+        "export" => export(args),
+        "help" => output(&format!("{}\n", USAGE)),
+        _ => Err(format!("Unknown command {}\n\n{}", command, USAGE)),
+    }
+}
+
+fn new_note(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &["--stdin"], &["--folder"])?;
+    let title = arguments.expect(&["title"])?[0].trim().to_string();
+    check_note_title(&title)?;
+    if Note::path_for_title(&title).exists() {
+        return Err(format!("A note named \"{}\" already exists.", title));
+    }
+    let folder = arguments.option("--folder").unwrap_or("").trim_matches('/');
+    if folder.split('/').any(|part| part == "..") {
+        return Err(format!("\"{}\" is not a valid folder.", folder));
+    }
+    // Read before creating the file, so a failed read leaves nothing behind
+    let content = if arguments.has("--stdin") { read_stdin()? } else { String::new() };
+
+    let mut note = Note::new_in_folder(folder, &title)?;
+    if !content.is_empty() {
+        note.content = content;
+        note.save()?;
+    }
+    output(&format!("{}\n", note.path.display()))
+}
+
+fn list(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &["--json"], &[])?;
+    arguments.expect(&[])?;
+    let notes = Note::get_all()?;
+    if !arguments.has("--json") {
+        let titles: String = notes.iter().map(|note| format!("{}\n", note.title)).collect();
+        return output(&titles);
+    }
+
+    let infos: Vec<NoteInfo> = notes.iter()
+        .map(|note| NoteInfo {
+            title: note.title.clone(),
+            folder: note.folder(),
+            path: note.path.display().to_string(),
+            modified: note.modified_time.map(|time| DateTime::<Local>::from(time).to_rfc3339()),
+            tags: note.tags(),
+            words: crate::text_stats::count_words(&note.content),
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&infos)
+        .map_err(|e| format!("Failed to write the note list: {}", e))?;
+    output(&format!("{}\n", json))
+}
+
+fn cat(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[], &[])?;
+    let note = find_note(&arguments.expect(&["title"])?[0])?;
+    let mut text = note.content;
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    output(&text)
+}
+
+fn append(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[], &[])?;
+    let (title, words) = match arguments.positional.split_first() {
+        Some(split) => split,
+        None => return Err("Expected <title> [text...]".to_string()),
+    };
+    let mut note = find_note(title)?;
+    let text = if words.is_empty() { read_stdin()? } else { format!("{}\n", words.join(" ")) };
+    if text.is_empty() {
+        return Ok(());
+    }
+    // Start on a new line, as if the text had been typed at the end of the note
+    if !note.content.is_empty() && !note.content.ends_with('\n') {
+        note.content.push('\n');
+    }
+    note.content.push_str(&text);
+    note.save()
+}
+
+fn search(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[], &[])?;
+    let query = arguments.positional.join(" ").to_lowercase();
+    if query.trim().is_empty() {
+        return Err("Expected <query>".to_string());
+    }
+    let mut results = String::new();
+    for note in Note::get_all()? {
+        let mut matched = false;
+        for (number, line) in note.content.lines().enumerate() {
+            if line.to_lowercase().contains(&query) {
+                results.push_str(&format!("{}:{}: {}\n", note.title, number + 1, line.trim()));
+                matched = true;
+            }
+        }
+        // A note found by its title only, as in the sidebar search
+        if !matched && note.title.to_lowercase().contains(&query) {
+            results.push_str(&format!("{}\n", note.title));
+        }
+    }
+    output(&results)
+}
+
+fn rename(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[], &[])?;
+    let titles = arguments.expect(&["title", "new title"])?;
+    let new_title = titles[1].trim();
+    check_note_title(new_title)?;
+    let mut note = find_note(&titles[0])?;
+    note.rename(new_title)
+}
+
+fn delete(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &[], &[])?;
+    find_note(&arguments.expect(&["title"])?[0])?.delete()
+}
+
+fn export(args: &[String]) -> Result<(), String> {
+    let arguments = Arguments::parse(args, &["--dark"], &["--folder"])?;
+    let theme = if arguments.has("--dark") { Theme::Dark } else { Theme::Light };
+    let destination_arg = match arguments.option("--folder") {
+        Some(_) => arguments.expect(&["destination"])?[0].clone(),
+        None => arguments.expect(&["title", "file"])?[1].clone(),
+    };
+    let destination = Path::new(&destination_arg);
+    let format = match destination.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase).as_deref() {
+        Some("epub") => Some(Format::Epub),
+        Some("docx") => Some(Format::Docx),
+        _ => None,
+    };
+
+    // A whole folder: HTML pages, or one book with a chapter per note
+    if let Some(folder) = arguments.option("--folder") {
+        let folder = folder.trim_matches('/');
+        let format = match format {
+            Some(format) => format,
+            None => return export::export_folder(folder, destination, theme).map(|_| ()),
+        };
+        let titles = manuscript::folder_titles(folder)?;
+        let book_title = match folder.rsplit('/').next() {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => "Notes".to_string(),
+        };
+        return write_book(&book_title, &titles, format, destination);
+    }
+
+    let note = find_note(&arguments.positional[0])?;
+    match format {
+        Some(format) => write_book(&note.title, std::slice::from_ref(&note.title), format, destination),
+        None if destination.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm")) => {
+            let note_dir = note.path.parent().unwrap_or(Path::new(""));
+            create_parent(destination)?;
+            export::export_note(&note.title, &note.content, note_dir, destination, theme)
+        }
+        None => Err(format!("Cannot tell the format of {}: use a .html, .epub or .docx file", destination.display())),
+    }
+}
+
+/// Compile notes into an EPUB or DOCX file, with the paper size set in the app
+fn write_book(title: &str, titles: &[String], format: Format, destination: &Path) -> Result<(), String> {
+    let mut book = Manuscript::from_titles(title, "", titles)?;
+    book.paper_size = crate::settings::Settings::load().print.paper_size;
+    create_parent(destination)?;
+    book.write(format, destination)
+}
+
+/// Create the folder a file is written to, if it does not exist yet
+fn create_parent(destination: &Path) -> Result<(), String> {
+    match destination.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create folder {:?}: {}", parent, e)),
+        _ => Ok(()),
+    }
+}
+
+/// The note with a title, in any folder; titles are matched ignoring case if no
+/// note has exactly that title
+fn find_note(title: &str) -> Result<Note, String> {
+    let title = title.trim();
+    let path = Note::path_for_title(title);
+    if path.exists() {
+        return Note::load(&path);
+    }
+    Note::get_all()?
+        .into_iter()
+        .find(|note| crate::markdown::title_key(&note.title) == crate::markdown::title_key(title))
+        .ok_or_else(|| format!("No note named \"{}\".", title))
+}
+
+/// Write to standard output. A reader that stops early (`penscript list | head`)
+/// is not an error.
+fn output(text: &str) -> Result<(), String> {
+    match io::stdout().lock().write_all(text.as_bytes()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(format!("Failed to write the output: {}", e)),
+        _ => Ok(()),
+    }
+}
+
+fn read_stdin() -> Result<String, String> {
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text)
+        .map_err(|e| format!("Failed to read standard input: {}", e))?;
+    Ok(text)
+}
@@ -0,0 +1,750 @@
+// DOCX output for manuscripts: the notes are converted from Markdown events straight
+// to WordprocessingML, with built-in styles (headings, quote, code, lists, tables)
+// so the document can be restyled in Word or LibreOffice. Each chapter starts on a
+// new page and the table of contents is a regular TOC field, pre-filled with links
+// to the headings, that word processors can update to add page numbers.
+
+use chrono::Utc;
+use pulldown_cmark::{CowStr, Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd};
+use std::path::Path;
+
+use crate::export::escape_html;
+use crate::manuscript::{image_size, Archive, ImageStore, Manuscript};
+
+// Image types Word can show without conversion
+const IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif"];
+// English Metric Units per pixel at 96 dpi, and per twip (1/20 point)
+const EMU_PER_PIXEL: u64 = 9525;
+const EMU_PER_TWIP: u64 = 635;
+// Page margins, in twips (2.5 cm)
+const MARGIN: u64 = 1418;
+
+/// Page width and height in twips for a GTK paper name
+fn page_size(paper_size: &str) -> (u64, u64) {
+    match paper_size {
+        "iso_a5" => (8391, 11906),
+        "na_letter" => (12240, 15840),
+        "na_legal" => (12240, 20160),
+        _ => (11906, 16838), // A4
+    }
+}
+
+/// The paragraph being written
+#[derive(Default)]
+struct Paragraph {
+    style: Option<String>,
+    /// Numbering instance and list level of a list item
+    numbering: Option<(usize, usize)>,
+    /// Left indentation in twips, for paragraphs continuing a list item
+    indent: Option<u64>,
+    page_break_before: bool,
+    /// Bookmark around the paragraph, for links and the table of contents
+    bookmark: Option<String>,
+    bottom_border: bool,
+    runs: String,
+}
+
+/// Character formatting of the text being read
+#[derive(Default)]
+struct RunFormat {
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    link: usize,
+    superscript: bool,
+}
+
+/// Converts the chapters to the body of `document.xml`, collecting what the other
+/// parts of the package need (relationships, list numbering, bookmarks)
+struct Writer<'a> {
+    manuscript: &'a Manuscript,
+    images: ImageStore,
+    body: String,
+    paragraph: Option<Paragraph>,
+    format: RunFormat,
+    /// External hyperlink targets; relationship ids follow the fixed ones
+    hyperlinks: Vec<String>,
+    /// Start number of each ordered list's numbering instance (instance 1 is for bullets)
+    ordered_lists: Vec<(usize, u64)>,
+    /// Numbering instance of each open list
+    lists: Vec<usize>,
+    quote_depth: usize,
+    /// Marker written before the first text of a list item or footnote
+    pending_marker: Option<String>,
+    in_table_head: bool,
+    /// Alt text of the image being read, with the image's index
+    image: Option<(usize, String)>,
+    /// Whether an unresolved link is being read (its text is kept without a link)
+    plain_link: bool,
+    /// Text of the code block being read
+    code_block: Option<String>,
+    /// Table of contents entries: level, text, bookmark
+    contents: Vec<(u8, String, String)>,
+    /// Bookmarks of the headings of the chapter being written, by heading text
+    chapter_headings: Vec<(String, String)>,
+    next_heading: usize,
+    next_bookmark: usize,
+    next_drawing: usize,
+}
+
+fn chapter_bookmark(index: usize) -> String {
+    format!("_Chapter{}", index + 1)
+}
+
+fn text_run(text: &str) -> String {
+    format!("<w:r><w:t xml:space=\"preserve\">{}</w:t></w:r>", escape_html(text))
+}
+
+impl<'a> Writer<'a> {
+    fn new(manuscript: &'a Manuscript) -> Self {
+        Writer {
+            manuscript,
+            images: ImageStore::default(),
+            body: String::new(),
+            paragraph: None,
+            format: RunFormat::default(),
+            hyperlinks: Vec::new(),
+            ordered_lists: Vec::new(),
+            lists: Vec::new(),
+            quote_depth: 0,
+            pending_marker: None,
+            in_table_head: false,
+            image: None,
+            plain_link: false,
+            code_block: None,
+            contents: Vec::new(),
+            chapter_headings: Vec::new(),
+            next_heading: 0,
+            next_bookmark: 0,
+            next_drawing: 0,
+        }
+    }
+
+    fn start_paragraph(&mut self, style: Option<&str>) {
+        self.finish_paragraph();
+        let style = style.map(String::from).or_else(|| (self.quote_depth > 0).then(|| "Quote".to_string()));
+        self.paragraph = Some(Paragraph { style, ..Default::default() });
+    }
+
+    fn finish_paragraph(&mut self) {
+        let paragraph = match self.paragraph.take() {
+            Some(paragraph) => paragraph,
+            None => return,
+        };
+        self.body.push_str("<w:p><w:pPr>");
+        if let Some(style) = &paragraph.style {
+            self.body.push_str(&format!("<w:pStyle w:val=\"{}\"/>", style));
+        }
+        if paragraph.page_break_before {
+            self.body.push_str("<w:pageBreakBefore/>");
+        }
+        if let Some((instance, level)) = paragraph.numbering {
+            self.body.push_str(&format!("<w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{}\"/></w:numPr>", level, instance));
+        }
+        if paragraph.bottom_border {
+            self.body.push_str("<w:pBdr><w:bottom w:val=\"single\" w:sz=\"6\" w:space=\"1\" w:color=\"AAAAAA\"/></w:pBdr>");
+        }
+        if let Some(indent) = paragraph.indent {
+            self.body.push_str(&format!("<w:ind w:left=\"{}\"/>", indent));
+        }
+        self.body.push_str("</w:pPr>");
+        match &paragraph.bookmark {
+            Some(name) => {
+                let id = self.next_bookmark;
+                self.next_bookmark += 1;
+                self.body.push_str(&format!("<w:bookmarkStart w:id=\"{}\" w:name=\"{}\"/>{}<w:bookmarkEnd w:id=\"{}\"/>", id, name, paragraph.runs, id));
+            }
+            None => self.body.push_str(&paragraph.runs),
+        }
+        self.body.push_str("</w:p>\n");
+    }
+
+    /// Append runs to the current paragraph, starting one if needed
+    fn push_runs(&mut self, runs: &str) {
+        if self.paragraph.is_none() {
+            self.start_paragraph(None);
+        }
+        let marker = self.pending_marker.take();
+        if let Some(paragraph) = self.paragraph.as_mut() {
+            if let Some(marker) = marker.filter(|marker| !marker.is_empty()) {
+                paragraph.runs.push_str(&text_run(&marker));
+            }
+            paragraph.runs.push_str(runs);
+        }
+    }
+
+    /// A run of text with the current formatting (`code` for inline code)
+    fn push_text(&mut self, text: &str, code: bool) {
+        if let Some((_, alt)) = self.image.as_mut() {
+            alt.push_str(text);
+            return;
+        }
+        let mut properties = String::new();
+        if code {
+            properties.push_str("<w:rStyle w:val=\"InlineCode\"/>");
+        } else if self.format.link > 0 {
+            properties.push_str("<w:rStyle w:val=\"Hyperlink\"/>");
+        }
+        if self.format.bold > 0 || self.in_table_head {
+            properties.push_str("<w:b/>");
+        }
+        if self.format.italic > 0 {
+            properties.push_str("<w:i/>");
+        }
+        if self.format.strike > 0 {
+            properties.push_str("<w:strike/>");
+        }
+        if self.format.superscript {
+            properties.push_str("<w:vertAlign w:val=\"superscript\"/>");
+        }
+        let run = format!("<w:r><w:rPr>{}</w:rPr><w:t xml:space=\"preserve\">{}</w:t></w:r>", properties, escape_html(text));
+        self.push_runs(&run);
+    }
+
+    fn new_bookmark(&mut self) -> String {
+        self.next_heading += 1;
+        format!("_Toc{}", self.next_heading)
+    }
+
+    /// Start the chapter's page at the open paragraph, with the chapter's bookmark
+    fn mark_chapter_start(&mut self, index: usize) {
+        let bookmark = chapter_bookmark(index);
+        if let Some(paragraph) = self.paragraph.as_mut() {
+            paragraph.page_break_before = true;
+            paragraph.bookmark = Some(bookmark.clone());
+        }
+        self.contents.push((1, self.manuscript.chapters[index].title.clone(), bookmark));
+    }
+
+    /// Numbering instance for a new ordered list starting at `start`
+    fn ordered_list(&mut self, start: u64) -> usize {
+        let instance = self.ordered_lists.len() + 2;
+        self.ordered_lists.push((instance, start));
+        instance
+    }
+
+    fn write_chapter(&mut self, index: usize) {
+        let manuscript = self.manuscript;
+        let chapter = &manuscript.chapters[index];
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+            | Options::ENABLE_WIKILINKS;
+        let events: Vec<Event> = Parser::new_ext(&chapter.markdown, options).collect();
+        let base_dir = chapter.base_dir.clone();
+        self.chapter_headings.clear();
+
+        // The chapter opens on a new page with its title, unless the note starts with one
+        let has_title = crate::export::starts_with_title(&chapter.markdown);
+        let mut chapter_start = true;
+        if !has_title {
+            self.start_paragraph(Some("Heading1"));
+            self.push_text(&chapter.title, false);
+            self.mark_chapter_start(index);
+            chapter_start = false;
+        }
+        let mut in_metadata = false;
+
+        for event in events {
+            match event {
+                Event::Start(Tag::MetadataBlock(_)) => in_metadata = true,
+                Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+                _ if in_metadata => {}
+
+                // Blocks
+                Event::Start(Tag::Paragraph) if self.pending_marker.is_none() => {
+                    self.start_paragraph(None);
+                    if !self.lists.is_empty() {
+                        // A later paragraph of a list item lines up with the item's text
+                        if let Some(paragraph) = self.paragraph.as_mut() {
+                            paragraph.indent = Some(720 * self.lists.len() as u64);
+                        }
+                    }
+                }
+                Event::Start(Tag::Heading { level, .. }) => {
+                    let level = match level {
+                        HeadingLevel::H1 => 1,
+                        HeadingLevel::H2 => 2,
+                        HeadingLevel::H3 => 3,
+                        HeadingLevel::H4 => 4,
+                        HeadingLevel::H5 => 5,
+                        HeadingLevel::H6 => 6,
+                    };
+                    self.start_paragraph(Some(&format!("Heading{}", level)));
+                    // The heading opening the chapter is its title
+                    if !(chapter_start && has_title && level == 1) {
+                        let bookmark = self.new_bookmark();
+                        self.chapter_headings.push((String::new(), bookmark.clone()));
+                        if let Some(paragraph) = self.paragraph.as_mut() {
+                            paragraph.bookmark = Some(bookmark);
+                        }
+                    }
+                }
+                Event::End(TagEnd::Heading(_)) => {
+                    // Remember the heading's text for the table of contents and links
+                    let text = self.paragraph.as_ref().map(|paragraph| plain_text(&paragraph.runs)).unwrap_or_default();
+                    let style_level = self.paragraph.as_ref()
+                        .and_then(|paragraph| paragraph.style.as_deref())
+                        .and_then(|style| style.strip_prefix("Heading"))
+                        .and_then(|level| level.parse::<u8>().ok())
+                        .unwrap_or(1);
+                    match self.paragraph.as_ref().and_then(|paragraph| paragraph.bookmark.clone()) {
+                        Some(bookmark) if bookmark == chapter_bookmark(index) => {
+                            // The note's own title names the chapter
+                            if let Some(entry) = self.contents.iter_mut().rev().find(|(_, _, name)| *name == bookmark) {
+                                entry.1 = text;
+                            }
+                        }
+                        Some(bookmark) => {
+                            if let Some(entry) = self.chapter_headings.iter_mut().find(|(_, name)| *name == bookmark) {
+                                entry.0 = text.clone();
+                            }
+                            if style_level <= 3 {
+                                self.contents.push((style_level.max(2), text, bookmark));
+                            }
+                        }
+                        None => {}
+                    }
+                    self.finish_paragraph();
+                }
+                Event::Start(Tag::CodeBlock(_)) => {
+                    self.start_paragraph(Some("Code"));
+                    self.code_block = Some(String::new());
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    // Lines of a code block are kept in one paragraph
+                    let code = self.code_block.take().unwrap_or_default();
+                    for (i, line) in code.strip_suffix('\n').unwrap_or(&code).split('\n').enumerate() {
+                        if i > 0 {
+                            self.push_runs("<w:r><w:br/></w:r>");
+                        }
+                        self.push_text(line, false);
+                    }
+                    self.finish_paragraph();
+                }
+                Event::Start(Tag::BlockQuote(_)) => {
+                    self.finish_paragraph();
+                    self.quote_depth += 1;
+                }
+                Event::End(TagEnd::BlockQuote(_)) => {
+                    self.finish_paragraph();
+                    self.quote_depth = self.quote_depth.saturating_sub(1);
+                }
+                Event::Start(Tag::List(first_number)) => {
+                    self.finish_paragraph();
+                    let instance = match first_number {
+                        Some(start) => self.ordered_list(start),
+                        None => 1,
+                    };
+                    self.lists.push(instance);
+                }
+                Event::End(TagEnd::List(_)) => {
+                    self.finish_paragraph();
+                    self.lists.pop();
+                }
+                Event::Start(Tag::Item) => {
+                    self.start_paragraph(Some("ListParagraph"));
+                    let level = self.lists.len().saturating_sub(1).min(8);
+                    let instance = self.lists.last().copied().unwrap_or(1);
+                    if let Some(paragraph) = self.paragraph.as_mut() {
+                        paragraph.numbering = Some((instance, level));
+                    }
+                    self.pending_marker = Some(String::new());
+                }
+                Event::TaskListMarker(checked) => {
+                    self.pending_marker = Some(if checked { "☒ " } else { "☐ " }.to_string());
+                }
+                Event::Start(Tag::FootnoteDefinition(label)) => {
+                    self.start_paragraph(Some("FootnoteText"));
+                    self.format.superscript = true;
+                    self.push_text(&label, false);
+                    self.format.superscript = false;
+                    self.pending_marker = Some(" ".to_string());
+                }
+                Event::End(TagEnd::Paragraph | TagEnd::Item | TagEnd::FootnoteDefinition) => {
+                    self.pending_marker = None;
+                    self.finish_paragraph();
+                }
+                Event::Rule => {
+                    self.start_paragraph(None);
+                    if let Some(paragraph) = self.paragraph.as_mut() {
+                        paragraph.bottom_border = true;
+                    }
+                    self.finish_paragraph();
+                }
+
+                // Tables
+                Event::Start(Tag::Table(alignments)) => {
+                    self.finish_paragraph();
+                    self.body.push_str("<w:tbl><w:tblPr><w:tblStyle w:val=\"TableGrid\"/><w:tblW w:w=\"0\" w:type=\"auto\"/></w:tblPr><w:tblGrid>");
+                    for _ in &alignments {
+                        self.body.push_str("<w:gridCol/>");
+                    }
+                    self.body.push_str("</w:tblGrid>\n");
+                }
+                Event::End(TagEnd::Table) => {
+                    // Word needs a paragraph between a table and what follows
+                    self.body.push_str("</w:tbl>\n<w:p/>\n");
+                }
+                Event::Start(Tag::TableHead) => {
+                    self.in_table_head = true;
+                    self.body.push_str("<w:tr><w:trPr><w:tblHeader/></w:trPr>");
+                }
+                Event::End(TagEnd::TableHead) => {
+                    self.in_table_head = false;
+                    self.body.push_str("</w:tr>\n");
+                }
+                Event::Start(Tag::TableRow) => self.body.push_str("<w:tr>"),
+                Event::End(TagEnd::TableRow) => self.body.push_str("</w:tr>\n"),
+                Event::Start(Tag::TableCell) => {
+                    self.body.push_str("<w:tc><w:tcPr><w:tcW w:w=\"0\" w:type=\"auto\"/></w:tcPr>");
+                    self.start_paragraph(Some("TableText"));
+                }
+                Event::End(TagEnd::TableCell) => {
+                    // A cell must hold a paragraph, even when empty
+                    if self.paragraph.is_none() {
+                        self.start_paragraph(Some("TableText"));
+                    }
+                    self.finish_paragraph();
+                    self.body.push_str("</w:tc>");
+                }
+
+                // Inline
+                Event::Start(Tag::Emphasis) => self.format.italic += 1,
+                Event::End(TagEnd::Emphasis) => self.format.italic = self.format.italic.saturating_sub(1),
+                Event::Start(Tag::Strong) => self.format.bold += 1,
+                Event::End(TagEnd::Strong) => self.format.bold = self.format.bold.saturating_sub(1),
+                Event::Start(Tag::Strikethrough) => self.format.strike += 1,
+                Event::End(TagEnd::Strikethrough) => self.format.strike = self.format.strike.saturating_sub(1),
+                Event::Start(Tag::Link { link_type, dest_url, .. }) => self.start_link(link_type, &dest_url),
+                Event::End(TagEnd::Link) => {
+                    if self.plain_link {
+                        self.plain_link = false;
+                    } else {
+                        self.format.link = self.format.link.saturating_sub(1);
+                        self.push_runs("</w:hyperlink>");
+                    }
+                }
+                Event::Start(Tag::Image { dest_url, .. }) => {
+                    match self.images.add(&dest_url, &base_dir, IMAGE_TYPES) {
+                        Some(image) => self.image = Some((image, String::new())),
+                        None => self.push_text("[", false), // Shown as its description
+                    }
+                }
+                Event::End(TagEnd::Image) => match self.image.take() {
+                    Some((image, alt)) => {
+                        let drawing = self.drawing(image, &alt);
+                        self.push_runs(&drawing);
+                    }
+                    None => self.push_text("]", false),
+                },
+                Event::Code(code) => self.push_text(&code, true),
+                Event::Text(text) => match self.code_block.as_mut() {
+                    Some(code) => code.push_str(&text),
+                    None => self.push_text(&text, false),
+                },
+                Event::Html(raw) | Event::InlineHtml(raw) => self.push_text(&raw, false),
+                Event::SoftBreak => self.push_text(" ", false),
+                Event::HardBreak => self.push_runs("<w:r><w:br/></w:r>"),
+                Event::FootnoteReference(label) => {
+                    self.format.superscript = true;
+                    self.push_text(&label, false);
+                    self.format.superscript = false;
+                }
+                Event::InlineMath(math) | Event::DisplayMath(math) => self.push_text(&math, true),
+                _ => {}
+            }
+
+            // The first paragraph of the chapter starts the page and carries the chapter's bookmark
+            if chapter_start && self.paragraph.is_some() {
+                chapter_start = false;
+                self.mark_chapter_start(index);
+            }
+        }
+        self.finish_paragraph();
+    }
+
+    /// Open a hyperlink: web links, links to other chapters, or plain text for the rest
+    fn start_link(&mut self, link_type: LinkType, dest_url: &CowStr) {
+        let target = match link_type {
+            LinkType::WikiLink { .. } => {
+                let (title, heading) = match dest_url.split_once('#') {
+                    Some((title, heading)) => (title, Some(heading)),
+                    None => (dest_url.as_ref(), None),
+                };
+                if title.trim().is_empty() {
+                    // A heading of this chapter
+                    heading.and_then(|heading| self.chapter_headings.iter()
+                        .find(|(text, _)| text.eq_ignore_ascii_case(heading.trim()))
+                        .map(|(_, bookmark)| format!("w:anchor=\"{}\"", bookmark)))
+                } else {
+                    self.manuscript.chapter_index(title)
+                        .map(|chapter| format!("w:anchor=\"{}\"", chapter_bookmark(chapter)))
+                }
+            }
+            _ if dest_url.contains(':') => {
+                self.hyperlinks.push(dest_url.to_string());
+                Some(format!("r:id=\"rIdLink{}\"", self.hyperlinks.len()))
+            }
+            _ => None, // Relative links have nothing to point at in the document
+        };
+        match target {
+            Some(target) => {
+                self.format.link += 1;
+                self.push_runs(&format!("<w:hyperlink {}>", target));
+            }
+            None => self.plain_link = true,
+        }
+    }
+
+    /// An inline picture, scaled down to the text width if needed
+    fn drawing(&mut self, index: usize, alt: &str) -> String {
+        self.next_drawing += 1;
+        let id = self.next_drawing;
+        let images = self.images.images.borrow();
+        let image = &images[index];
+        let (page_width, _) = page_size(&self.manuscript.paper_size);
+        let max_width = (page_width - 2 * MARGIN) * EMU_PER_TWIP;
+        let (width, height) = image_size(&image.data).unwrap_or((400, 300));
+        let (mut cx, mut cy) = (width as u64 * EMU_PER_PIXEL, height as u64 * EMU_PER_PIXEL);
+        if cx > max_width {
+            cy = cy * max_width / cx;
+            cx = max_width;
+        }
+        format!(
+            "<w:r><w:drawing><wp:inline distT=\"0\" distB=\"0\" distL=\"0\" distR=\"0\">\
+             <wp:extent cx=\"{cx}\" cy=\"{cy}\"/><wp:docPr id=\"{id}\" name=\"Picture {id}\" descr=\"{alt}\"/>\
+             <a:graphic xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\">\
+             <a:graphicData uri=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">\
+             <pic:pic xmlns:pic=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">\
+             <pic:nvPicPr><pic:cNvPr id=\"{id}\" name=\"{name}\"/><pic:cNvPicPr/></pic:nvPicPr>\
+             <pic:blipFill><a:blip r:embed=\"rIdImage{number}\"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill>\
+             <pic:spPr><a:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"{cx}\" cy=\"{cy}\"/></a:xfrm>\
+             <a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic>\
+             </wp:inline></w:drawing></w:r>",
+            alt = escape_html(alt),
+            name = escape_html(&image.name),
+            number = index + 1,
+        )
+    }
+
+    /// Title page and table of contents, before the chapters
+    fn front_matter(&self) -> String {
+        let mut front = String::new();
+        front.push_str(&format!("<w:p><w:pPr><w:pStyle w:val=\"Title\"/></w:pPr>{}</w:p>\n", text_run(&self.manuscript.title)));
+        if !self.manuscript.author.is_empty() {
+            front.push_str(&format!("<w:p><w:pPr><w:pStyle w:val=\"Subtitle\"/></w:pPr>{}</w:p>\n", text_run(&self.manuscript.author)));
+        }
+        front.push_str(&format!("<w:p><w:pPr><w:pStyle w:val=\"TOCHeading\"/><w:pageBreakBefore/></w:pPr>{}</w:p>\n", text_run("Contents")));
+        let count = self.contents.len();
+        for (i, (level, text, bookmark)) in self.contents.iter().enumerate() {
+            front.push_str(&format!("<w:p><w:pPr><w:pStyle w:val=\"TOC{}\"/></w:pPr>", level));
+            if i == 0 {
+                front.push_str("<w:r><w:fldChar w:fldCharType=\"begin\"/></w:r>\
+                    <w:r><w:instrText xml:space=\"preserve\"> TOC \\o \"1-3\" \\h \\z \\u </w:instrText></w:r>\
+                    <w:r><w:fldChar w:fldCharType=\"separate\"/></w:r>");
+            }
+            front.push_str(&format!("<w:hyperlink w:anchor=\"{}\" w:history=\"1\">{}</w:hyperlink>", bookmark, text_run(text)));
+            if i + 1 == count {
+                front.push_str("<w:r><w:fldChar w:fldCharType=\"end\"/></w:r>");
+            }
+            front.push_str("</w:p>\n");
+        }
+        front
+    }
+}
+
+/// The text of a paragraph's runs, without the markup
+fn plain_text(runs: &str) -> String {
+    let mut text = String::new();
+    let mut rest = runs;
+    while let Some(start) = rest.find("<w:t xml:space=\"preserve\">") {
+        rest = &rest[start + "<w:t xml:space=\"preserve\">".len()..];
+        let end = rest.find("</w:t>").unwrap_or(rest.len());
+        text.push_str(&rest[..end]);
+        rest = &rest[end..];
+    }
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&amp;", "&")
+}
+
+fn styles() -> String {
+    let heading = |level: usize, size: usize, space_before: usize| format!(
+        "<w:style w:type=\"paragraph\" w:styleId=\"Heading{level}\"><w:name w:val=\"heading {level}\"/><w:basedOn w:val=\"Normal\"/>\
+         <w:next w:val=\"Normal\"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before=\"{space_before}\" w:after=\"120\"/><w:outlineLvl w:val=\"{outline}\"/></w:pPr>\
+         <w:rPr><w:rFonts w:ascii=\"Calibri\" w:hAnsi=\"Calibri\"/><w:b/><w:sz w:val=\"{size}\"/></w:rPr></w:style>\n",
+        outline = level - 1,
+    );
+    let toc = |level: usize| format!(
+        "<w:style w:type=\"paragraph\" w:styleId=\"TOC{level}\"><w:name w:val=\"toc {level}\"/><w:basedOn w:val=\"Normal\"/>\
+         <w:pPr><w:spacing w:after=\"60\"/><w:ind w:left=\"{indent}\"/></w:pPr></w:style>\n",
+        indent = (level - 1) * 360,
+    );
+    let mut styles = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:styles xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\n\
+         <w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii=\"Cambria\" w:hAnsi=\"Cambria\" w:eastAsia=\"Cambria\" w:cs=\"Cambria\"/>\
+         <w:sz w:val=\"24\"/><w:lang w:val=\"en-US\"/></w:rPr></w:rPrDefault>\
+         <w:pPrDefault><w:pPr><w:spacing w:after=\"160\" w:line=\"300\" w:lineRule=\"auto\"/></w:pPr></w:pPrDefault></w:docDefaults>\n\
+         <w:style w:type=\"paragraph\" w:default=\"1\" w:styleId=\"Normal\"><w:name w:val=\"Normal\"/><w:qFormat/></w:style>\n\
+         <w:style w:type=\"paragraph\" w:styleId=\"Title\"><w:name w:val=\"Title\"/><w:basedOn w:val=\"Normal\"/><w:qFormat/>\
+         <w:pPr><w:spacing w:before=\"3600\" w:after=\"480\"/><w:jc w:val=\"center\"/></w:pPr><w:rPr><w:b/><w:sz w:val=\"64\"/></w:rPr></w:style>\n\
+         <w:style w:type=\"paragraph\" w:styleId=\"Subtitle\"><w:name w:val=\"Subtitle\"/><w:basedOn w:val=\"Normal\"/><w:qFormat/>\
+         <w:pPr><w:jc w:val=\"center\"/></w:pPr><w:rPr><w:sz w:val=\"32\"/></w:rPr></w:style>\n\
+         <w:style w:type=\"paragraph\" w:styleId=\"TOCHeading\"><w:name w:val=\"TOC Heading\"/><w:basedOn w:val=\"Heading1\"/>\
+         <w:next w:val=\"Normal\"/><w:pPr><w:outlineLvl w:val=\"9\"/></w:pPr></w:style>\n\
+         <w:style w:type=\"paragraph\" w:styleId=\"Quote\"><w:name w:val=\"Quote\"/><w:basedOn w:val=\"Normal\"/><w:qFormat/>\
+         <w:pPr><w:ind w:left=\"720\" w:right=\"720\"/></w:pPr><w:rPr><w:i/><w:color w:val=\"555555\"/></w:rPr></w:style>\n\
+         <w:style w:type=\"paragraph\" w:styleId=\"Code\"><w:name w:val=\"Code\"/><w:basedOn w:val=\"Normal\"/>\
+         <w:pPr><w:shd w:val=\"clear\" w:color=\"auto\" w:fill=\"F4F4F4\"/><w:spacing w:after=\"160\" w:line=\"240\" w:lineRule=\"auto\"/></w:pPr>\
+         <w:rPr><w:rFonts w:ascii=\"Consolas\" w:hAnsi=\"Consolas\" w:cs=\"Consolas\"/><w:sz w:val=\"19\"/></w:rPr></w:style>\n\
+         <w:style w:type=\"paragraph\" w:styleId=\"ListParagraph\"><w:name w:val=\"List Paragraph\"/><w:basedOn w:val=\"Normal\"/><w:qFormat/>\
+         <w:pPr><w:spacing w:after=\"60\"/><w:contextualSpacing/></w:pPr></w:style>\n\
+         <w:style w:type=\"paragraph\" w:styleId=\"TableText\"><w:name w:val=\"Table Text\"/><w:basedOn w:val=\"Normal\"/>\
+         <w:pPr><w:spacing w:after=\"0\"/></w:pPr></w:style>\n\
+         <w:style w:type=\"paragraph\" w:styleId=\"FootnoteText\"><w:name w:val=\"footnote text\"/><w:basedOn w:val=\"Normal\"/>\
+         <w:rPr><w:sz w:val=\"20\"/></w:rPr></w:style>\n\
+         <w:style w:type=\"character\" w:styleId=\"InlineCode\"><w:name w:val=\"Inline Code\"/>\
+         <w:rPr><w:rFonts w:ascii=\"Consolas\" w:hAnsi=\"Consolas\" w:cs=\"Consolas\"/><w:sz w:val=\"21\"/></w:rPr></w:style>\n\
+         <w:style w:type=\"character\" w:styleId=\"Hyperlink\"><w:name w:val=\"Hyperlink\"/>\
+         <w:rPr><w:color w:val=\"3584E4\"/><w:u w:val=\"single\"/></w:rPr></w:style>\n\
+         <w:style w:type=\"table\" w:styleId=\"TableGrid\"><w:name w:val=\"Table Grid\"/><w:tblPr><w:tblBorders>\
+         <w:top w:val=\"single\" w:sz=\"4\" w:color=\"BBBBBB\"/><w:left w:val=\"single\" w:sz=\"4\" w:color=\"BBBBBB\"/>\
+         <w:bottom w:val=\"single\" w:sz=\"4\" w:color=\"BBBBBB\"/><w:right w:val=\"single\" w:sz=\"4\" w:color=\"BBBBBB\"/>\
+         <w:insideH w:val=\"single\" w:sz=\"4\" w:color=\"BBBBBB\"/><w:insideV w:val=\"single\" w:sz=\"4\" w:color=\"BBBBBB\"/>\
+         </w:tblBorders><w:tblCellMar><w:left w:w=\"108\" w:type=\"dxa\"/><w:right w:w=\"108\" w:type=\"dxa\"/></w:tblCellMar></w:tblPr></w:style>\n",
+    );
+    for (level, size, space_before) in [(1, 40, 0), (2, 32, 360), (3, 28, 280), (4, 26, 240), (5, 24, 240), (6, 24, 240)] {
+        styles.push_str(&heading(level, size, space_before));
+    }
+    for level in 1..=3 {
+        styles.push_str(&toc(level));
+    }
+    styles.push_str("</w:styles>\n");
+    styles
+}
+
+/// Bullet and numbered list definitions, with one numbering instance per ordered list
+fn numbering(ordered_lists: &[(usize, u64)]) -> String {
+    let mut numbering = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:numbering xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\n",
+    );
+    for (abstract_id, ordered) in [(0, false), (1, true)] {
+        numbering.push_str(&format!("<w:abstractNum w:abstractNumId=\"{}\"><w:multiLevelType w:val=\"hybridMultilevel\"/>", abstract_id));
+        for level in 0..9 {
+            let (format, text) = if ordered {
+                ("decimal", format!("%{}.", level + 1))
+            } else {
+                ("bullet", ["•", "◦", "▪"][level % 3].to_string())
+            };
+            numbering.push_str(&format!(
+                "<w:lvl w:ilvl=\"{level}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"{format}\"/><w:lvlText w:val=\"{text}\"/>\
+                 <w:lvlJc w:val=\"left\"/><w:pPr><w:ind w:left=\"{left}\" w:hanging=\"360\"/></w:pPr></w:lvl>",
+                left = 720 * (level + 1),
+            ));
+        }
+        numbering.push_str("</w:abstractNum>\n");
+    }
+    numbering.push_str("<w:num w:numId=\"1\"><w:abstractNumId w:val=\"0\"/></w:num>\n");
+    for (instance, start) in ordered_lists {
+        numbering.push_str(&format!("<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"1\"/>", instance));
+        for level in 0..9 {
+            numbering.push_str(&format!("<w:lvlOverride w:ilvl=\"{}\"><w:startOverride w:val=\"{}\"/></w:lvlOverride>", level, start));
+        }
+        numbering.push_str("</w:num>\n");
+    }
+    numbering.push_str("</w:numbering>\n");
+    numbering
+}
+
+/// Write a manuscript as a DOCX file
+pub fn write_docx(manuscript: &Manuscript, destination: &Path) -> Result<(), String> {
+    let mut writer = Writer::new(manuscript);
+    for index in 0..manuscript.chapters.len() {
+        writer.write_chapter(index);
+    }
+
+    let (page_width, page_height) = page_size(&manuscript.paper_size);
+    let document = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" \
+         xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\" \
+         xmlns:wp=\"http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing\">\n<w:body>\n{}{}\
+         <w:sectPr><w:pgSz w:w=\"{}\" w:h=\"{}\"/><w:pgMar w:top=\"{m}\" w:right=\"{m}\" w:bottom=\"{m}\" w:left=\"{m}\" \
+         w:header=\"708\" w:footer=\"708\" w:gutter=\"0\"/></w:sectPr>\n</w:body>\n</w:document>\n",
+        writer.front_matter(),
+        writer.body,
+        page_width,
+        page_height,
+        m = MARGIN,
+    );
+
+    let images = writer.images.images.borrow();
+    let mut relationships = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n\
+         <Relationship Id=\"rIdStyles\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles\" Target=\"styles.xml\"/>\n\
+         <Relationship Id=\"rIdNumbering\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering\" Target=\"numbering.xml\"/>\n",
+    );
+    for (index, image) in images.iter().enumerate() {
+        relationships.push_str(&format!(
+            "<Relationship Id=\"rIdImage{}\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/image\" Target=\"media/{}\"/>\n",
+            index + 1,
+            escape_html(&image.name),
+        ));
+    }
+    for (index, url) in writer.hyperlinks.iter().enumerate() {
+        relationships.push_str(&format!(
+            "<Relationship Id=\"rIdLink{}\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink\" Target=\"{}\" TargetMode=\"External\"/>\n",
+            index + 1,
+            escape_html(url),
+        ));
+    }
+    relationships.push_str("</Relationships>\n");
+
+    let content_types = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+        <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\n\
+        <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\n\
+        <Default Extension=\"xml\" ContentType=\"application/xml\"/>\n\
+        <Default Extension=\"png\" ContentType=\"image/png\"/>\n\
+        <Default Extension=\"jpg\" ContentType=\"image/jpeg\"/>\n\
+        <Default Extension=\"jpeg\" ContentType=\"image/jpeg\"/>\n\
+        <Default Extension=\"gif\" ContentType=\"image/gif\"/>\n\
+        <Override PartName=\"/word/document.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\n\
+        <Override PartName=\"/word/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml\"/>\n\
+        <Override PartName=\"/word/numbering.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml\"/>\n\
+        <Override PartName=\"/docProps/core.xml\" ContentType=\"application/vnd.openxmlformats-package.core-properties+xml\"/>\n\
+        </Types>\n";
+    let package_relationships = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+        <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n\
+        <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"word/document.xml\"/>\n\
+        <Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties\" Target=\"docProps/core.xml\"/>\n\
+        </Relationships>\n";
+    let core_properties = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:dcterms=\"http://purl.org/dc/terms/\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\
+         <dc:title>{}</dc:title><dc:creator>{}</dc:creator><dc:language>{}</dc:language>\
+         <dcterms:created xsi:type=\"dcterms:W3CDTF\">{}</dcterms:created></cp:coreProperties>\n",
+        escape_html(&manuscript.title),
+        escape_html(&manuscript.author),
+        escape_html(&manuscript.language),
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+    );
+
+    let mut archive = Archive::create(destination)?;
+    archive.add("[Content_Types].xml", content_types.as_bytes(), true)?;
+    archive.add("_rels/.rels", package_relationships.as_bytes(), true)?;
+    archive.add("docProps/core.xml", core_properties.as_bytes(), true)?;
+    archive.add("word/document.xml", document.as_bytes(), true)?;
+    archive.add("word/_rels/document.xml.rels", relationships.as_bytes(), true)?;
+    archive.add("word/styles.xml", styles().as_bytes(), true)?;
+    archive.add("word/numbering.xml", numbering(&writer.ordered_lists).as_bytes(), true)?;
+    for image in images.iter() {
+        archive.add(&format!("word/media/{}", image.name), &image.data, false)?;
+    }
+    archive.finish()
+}
@@ -0,0 +1,539 @@
+use gtk::prelude::*;
+use gtk::{EventControllerKey, TextBuffer, TextView};
+use gtk::gdk::{Key, ModifierType};
+use gtk::glib::Propagation;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::markdown::{self, InlineToggle, ListKind, LIST_INDENT};
+use crate::settings::{EditorSettings, Settings};
+
+/// Attach the Markdown editing assists (list continuation, smart indent, auto-pairing)
+pub fn install_markdown_assists(text_view: &TextView, settings: &Rc<RefCell<Settings>>) {
+    let key_controller = EventControllerKey::new();
+    // Run before the TextView's own handler so Enter/Tab can be intercepted
+    key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+    let text_view_for_key = text_view.clone();
+    let settings_for_key = settings.clone();
+    key_controller.connect_key_pressed(move |_, key, _keycode, state| {
+        if !text_view_for_key.is_editable() {
+            return Propagation::Proceed;
+        }
+        // Leave Ctrl/Alt combinations to the shortcut handlers
+        if state.intersects(ModifierType::CONTROL_MASK | ModifierType::ALT_MASK) {
+            return Propagation::Proceed;
+        }
+
+        let editor_settings = settings_for_key.borrow().editor.clone();
+        let buffer = text_view_for_key.buffer();
+        let shift = state.contains(ModifierType::SHIFT_MASK);
+
+        let handled = match key {
+            Key::Return | Key::KP_Enter if !shift => handle_enter(&buffer, &editor_settings),
+            Key::Tab if !shift => editor_settings.smart_indent && indent_list_lines(&buffer, false),
+            Key::Tab | Key::ISO_Left_Tab => editor_settings.smart_indent && indent_list_lines(&buffer, true),
+            Key::BackSpace => editor_settings.auto_pair && delete_empty_pair(&buffer),
+            _ => match key.to_unicode() {
+                Some(ch) if editor_settings.auto_pair => handle_pair_char(&buffer, ch),
+                _ => false,
+            },
+        };
+
+        if handled {
+            text_view_for_key.scroll_mark_onscreen(&buffer.get_insert());
+            Propagation::Stop
+        } else {
+            Propagation::Proceed
+        }
+    });
+
+    text_view.add_controller(key_controller);
+}
+
+/// Get the text of a line, without its trailing newline
+pub fn line_text(buffer: &TextBuffer, line: i32) -> String {
+    match buffer.iter_at_line(line) {
+        Some(start) => {
+            let mut end = start;
+            // forward_to_line_end() would skip to the next line if already at the end
+            if !end.ends_line() {
+                end.forward_to_line_end();
+            }
+            buffer.text(&start, &end, true).to_string()
+        }
+        None => String::new(),
+    }
+}
+
+/// Replace `len` chars at `offset` on `line` with `text`
+pub fn replace_in_line(buffer: &TextBuffer, line: i32, offset: usize, len: usize, text: &str) {
+    if let (Some(mut start), Some(mut end)) = (
+        buffer.iter_at_line_offset(line, offset as i32),
+        buffer.iter_at_line_offset(line, (offset + len) as i32),
+    ) {
+        buffer.delete(&mut start, &mut end);
+        buffer.insert(&mut start, text);
+    }
+}
+
+/// Enter on a list/quote line: continue the prefix, or end the list on an empty item
+fn handle_enter(buffer: &TextBuffer, editor_settings: &EditorSettings) -> bool {
+    if buffer.has_selection() {
+        return false;
+    }
+
+    let mut cursor = buffer.iter_at_mark(&buffer.get_insert());
+    let line = cursor.line();
+    let text = line_text(buffer, line);
+
+    match markdown::parse_list_item(&text) {
+        Some(item) if editor_settings.list_continuation => {
+            if (cursor.line_offset() as usize) < item.prefix_chars {
+                return false; // Cursor is inside the marker itself
+            }
+
+            buffer.begin_user_action();
+            if item.is_empty() {
+                if !item.indent.is_empty() && editor_settings.smart_indent {
+                    // Empty nested item: step out one level first
+                    replace_in_line(buffer, line, 0, markdown::outdent_width(&text), "");
+                } else {
+                    // Empty item ends the list
+                    replace_in_line(buffer, line, 0, text.chars().count(), "");
+                }
+            } else {
+                buffer.insert(&mut cursor, &format!("\n{}{}", item.indent, item.next_marker()));
+                if matches!(item.kind, ListKind::Ordered { .. }) {
+                    renumber_ordered_list(buffer, line + 1);
+                }
+            }
+            buffer.end_user_action();
+            true
+        }
+        _ if editor_settings.smart_indent => {
+            // Keep the indentation of plain indented lines
+            let indent = markdown::leading_whitespace(&text).to_string();
+            if indent.is_empty() || (cursor.line_offset() as usize) < indent.chars().count() {
+                return false;
+            }
+            buffer.begin_user_action();
+            buffer.insert(&mut cursor, &format!("\n{}", indent));
+            buffer.end_user_action();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Renumber the ordered list that continues after `line`
+pub fn renumber_ordered_list(buffer: &TextBuffer, line: i32) {
+    let start = match buffer.iter_at_line(line) {
+        Some(iter) => iter,
+        None => return,
+    };
+    let rest = buffer.text(&start, &buffer.end_iter(), true).to_string();
+    let lines: Vec<&str> = rest.split('\n').collect();
+
+    for (index, number_start, number_end, number) in markdown::renumber_ordered_list(&lines) {
+        replace_in_line(
+            buffer,
+            line + index as i32,
+            number_start,
+            number_end - number_start,
+            &number.to_string(),
+        );
+    }
+}
+
+/// Tab/Shift+Tab on list items: indent or outdent every selected item line.
+/// Returns false (letting Tab insert a tab) if any non-blank line is not a list item.
+fn indent_list_lines(buffer: &TextBuffer, outdent: bool) -> bool {
+    let (start, end) = buffer.selection_bounds().unwrap_or_else(|| {
+        let cursor = buffer.iter_at_mark(&buffer.get_insert());
+        (cursor, cursor)
+    });
+    let first_line = start.line();
+    let mut last_line = end.line();
+    if end.starts_line() && last_line > first_line {
+        last_line -= 1; // A selection ending at column 0 doesn't include that line
+    }
+
+    let lines: Vec<String> = (first_line..=last_line).map(|l| line_text(buffer, l)).collect();
+    let all_items = lines.iter()
+        .all(|l| l.trim().is_empty() || markdown::parse_list_item(l).is_some());
+    if !all_items || lines.iter().all(|l| l.trim().is_empty()) {
+        return false;
+    }
+
+    buffer.begin_user_action();
+    for (index, text) in lines.iter().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let line = first_line + index as i32;
+        if outdent {
+            replace_in_line(buffer, line, 0, markdown::outdent_width(text), "");
+        } else {
+            replace_in_line(buffer, line, 0, 0, LIST_INDENT);
+        }
+    }
+    buffer.end_user_action();
+    true
+}
+
+/// Wrap the selection in a pair, insert bracket pairs, or step over an auto-inserted closer
+fn handle_pair_char(buffer: &TextBuffer, ch: char) -> bool {
+    if let Some((start, end)) = buffer.selection_bounds() {
+        let close = match markdown::closing_pair(ch) {
+            Some(close) => close,
+            None => return false,
+        };
+        // Work with offsets since inserting invalidates iterators
+        let start_offset = start.offset();
+        let end_offset = end.offset();
+
+        buffer.begin_user_action();
+        let mut end = buffer.iter_at_offset(end_offset);
+        buffer.insert(&mut end, &close.to_string());
+        let mut start = buffer.iter_at_offset(start_offset);
+        buffer.insert(&mut start, &ch.to_string());
+        // Keep the wrapped text selected so markers can be stacked (e.g. ** for bold)
+        buffer.select_range(
+            &buffer.iter_at_offset(start_offset + 1),
+            &buffer.iter_at_offset(end_offset + 1),
+        );
+        buffer.end_user_action();
+        return true;
+    }
+
+    let mut cursor = buffer.iter_at_mark(&buffer.get_insert());
+
+    // Typing a closing bracket in front of the same one just moves past it
+    if matches!(ch, ')' | ']' | '}') && cursor.char() == ch {
+        cursor.forward_char();
+        buffer.place_cursor(&cursor);
+        return true;
+    }
+
+    if markdown::pairs_without_selection(ch) {
+        let next = cursor.char();
+        if cursor.is_end() || next.is_whitespace() || matches!(next, ')' | ']' | '}') {
+            let close = markdown::closing_pair(ch).unwrap_or(ch);
+            let offset = cursor.offset();
+            buffer.begin_user_action();
+            buffer.insert(&mut cursor, &format!("{}{}", ch, close));
+            buffer.place_cursor(&buffer.iter_at_offset(offset + 1));
+            buffer.end_user_action();
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Backspace between an empty bracket pair removes both halves
+fn delete_empty_pair(buffer: &TextBuffer) -> bool {
+    if buffer.has_selection() {
+        return false;
+    }
+    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+    let mut before = cursor;
+    if !before.backward_char() {
+        return false;
+    }
+    let open = before.char();
+    if !markdown::pairs_without_selection(open) || markdown::closing_pair(open) != Some(cursor.char()) {
+        return false;
+    }
+
+    let mut after = cursor;
+    after.forward_char();
+    buffer.begin_user_action();
+    buffer.delete(&mut before, &mut after);
+    buffer.end_user_action();
+    true
+}
+
+/// Formatting commands that wrap/unwrap the selection or toggle line prefixes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatCommand {
+    Bold,
+    Italic,
+    Code,
+    Link,
+    Heading,
+    Quote,
+    Checklist,
+}
+
+impl FormatCommand {
+    /// All commands, in toolbar order
+    pub const ALL: [FormatCommand; 7] = [
+        FormatCommand::Bold,
+        FormatCommand::Italic,
+        FormatCommand::Code,
+        FormatCommand::Link,
+        FormatCommand::Heading,
+        FormatCommand::Quote,
+        FormatCommand::Checklist,
+    ];
+
+    /// Short label shown on the floating toolbar
+    pub fn toolbar_label(&self) -> &'static str {
+        match self {
+            FormatCommand::Bold => "B",
+            FormatCommand::Italic => "I",
+            FormatCommand::Code => "</>",
+            FormatCommand::Link => "Link",
+            FormatCommand::Heading => "H",
+            FormatCommand::Quote => "\u{201C}",
+            FormatCommand::Checklist => "\u{2611}",
+        }
+    }
+
+    /// Name of the window action that runs this command (see `shortcuts::COMMANDS`)
+    pub fn action_name(&self) -> &'static str {
+        match self {
+            FormatCommand::Bold => "format-bold",
+            FormatCommand::Italic => "format-italic",
+            FormatCommand::Code => "format-code",
+            FormatCommand::Link => "format-link",
+            FormatCommand::Heading => "format-heading",
+            FormatCommand::Quote => "format-quote",
+            FormatCommand::Checklist => "format-checklist",
+        }
+    }
+
+    /// Human-readable description, used for tooltips
+    pub fn description(&self) -> &'static str {
+        match self {
+            FormatCommand::Bold => "Bold",
+            FormatCommand::Italic => "Italic",
+            FormatCommand::Code => "Inline code",
+            FormatCommand::Link => "Insert link",
+            FormatCommand::Heading => "Cycle heading level",
+            FormatCommand::Quote => "Toggle quote",
+            FormatCommand::Checklist => "Toggle checklist item",
+        }
+    }
+}
+
+/// Apply a formatting command to the editor's buffer as a single undoable action
+pub fn apply_format(text_view: &TextView, command: FormatCommand) {
+    if !text_view.is_editable() {
+        return;
+    }
+    let buffer = text_view.buffer();
+    buffer.begin_user_action();
+    match command {
+        FormatCommand::Bold => toggle_inline_marker(&buffer, "**"),
+        FormatCommand::Italic => toggle_inline_marker(&buffer, "*"),
+        FormatCommand::Code => toggle_inline_marker(&buffer, "`"),
+        FormatCommand::Link => insert_link(&buffer),
+        FormatCommand::Heading => cycle_heading(&buffer),
+        FormatCommand::Quote => toggle_quote(&buffer),
+        FormatCommand::Checklist => toggle_checklist(&buffer),
+    }
+    buffer.end_user_action();
+    text_view.scroll_mark_onscreen(&buffer.get_insert());
+}
+
+/// Selection bounds as char offsets, or the cursor offset twice if nothing is selected
+fn selection_offsets(buffer: &TextBuffer) -> (i32, i32) {
+    match buffer.selection_bounds() {
+        Some((start, end)) => (start.offset(), end.offset()),
+        None => {
+            let offset = buffer.iter_at_mark(&buffer.get_insert()).offset();
+            (offset, offset)
+        }
+    }
+}
+
+/// Text between two char offsets
+fn text_between(buffer: &TextBuffer, start: i32, end: i32) -> String {
+    buffer.text(&buffer.iter_at_offset(start.max(0)), &buffer.iter_at_offset(end), true).to_string()
+}
+
+/// Delete `len` chars at `offset`
+fn delete_at(buffer: &TextBuffer, offset: i32, len: i32) {
+    let mut start = buffer.iter_at_offset(offset);
+    let mut end = buffer.iter_at_offset(offset + len);
+    buffer.delete(&mut start, &mut end);
+}
+
+/// Insert text at a char offset
+fn insert_at(buffer: &TextBuffer, offset: i32, text: &str) {
+    let mut iter = buffer.iter_at_offset(offset);
+    buffer.insert(&mut iter, text);
+}
+
+/// Wrap or unwrap the selection in an inline marker such as `**`
+fn toggle_inline_marker(buffer: &TextBuffer, marker: &str) {
+    let (start, end) = selection_offsets(buffer);
+    let marker_len = marker.chars().count() as i32;
+    // Three chars of context are enough to tell * from ** inside ***both***
+    let before = text_between(buffer, start - 3, start);
+    let after = text_between(buffer, end, end + 3);
+    let selected = text_between(buffer, start, end);
+
+    match markdown::inline_toggle(&before, &selected, &after, marker) {
+        InlineToggle::UnwrapInside => {
+            delete_at(buffer, end - marker_len, marker_len);
+            delete_at(buffer, start, marker_len);
+            select_offsets(buffer, start, end - 2 * marker_len);
+        }
+        InlineToggle::UnwrapOutside => {
+            delete_at(buffer, end, marker_len);
+            delete_at(buffer, start - marker_len, marker_len);
+            select_offsets(buffer, start - marker_len, end - marker_len);
+        }
+        InlineToggle::Wrap => {
+            insert_at(buffer, end, marker);
+            insert_at(buffer, start, marker);
+            // Keep the text selected, or put the cursor between an empty pair
+            select_offsets(buffer, start + marker_len, end + marker_len);
+        }
+    }
+}
+
+/// Select a range by char offsets (placing the cursor if the range is empty)
+fn select_offsets(buffer: &TextBuffer, start: i32, end: i32) {
+    buffer.select_range(&buffer.iter_at_offset(end), &buffer.iter_at_offset(start));
+}
+
+/// Turn the selection into `[selection](url)` and select the URL placeholder
+fn insert_link(buffer: &TextBuffer) {
+    let (start, end) = selection_offsets(buffer);
+    let selected = text_between(buffer, start, end);
+
+    if selected.starts_with("http://") || selected.starts_with("https://") {
+        // Selection is the URL itself: wrap it and put the cursor in the label
+        insert_at(buffer, end, ")");
+        insert_at(buffer, start, "[](");
+        select_offsets(buffer, start + 1, start + 1);
+    } else if start == end {
+        insert_at(buffer, start, "[text](url)");
+        select_offsets(buffer, start + 1, start + 5);
+    } else {
+        let label_len = end - start;
+        insert_at(buffer, end, "](url)");
+        insert_at(buffer, start, "[");
+        select_offsets(buffer, start + label_len + 3, start + label_len + 6);
+    }
+}
+
+/// Lines covered by the selection (or the cursor line)
+fn selected_lines(buffer: &TextBuffer) -> std::ops::RangeInclusive<i32> {
+    let (start, end) = buffer.selection_bounds().unwrap_or_else(|| {
+        let cursor = buffer.iter_at_mark(&buffer.get_insert());
+        (cursor, cursor)
+    });
+    let mut last_line = end.line();
+    if end.starts_line() && last_line > start.line() {
+        last_line -= 1; // A selection ending at column 0 doesn't include that line
+    }
+    start.line()..=last_line
+}
+
+/// Cycle the heading level of the cursor line: none → # → … → ###### → none
+fn cycle_heading(buffer: &TextBuffer) {
+    let line = buffer.iter_at_mark(&buffer.get_insert()).line();
+    let text = line_text(buffer, line);
+    let (level, prefix_len) = markdown::heading_level(&text);
+    replace_in_line(buffer, line, 0, prefix_len, &markdown::next_heading_prefix(level));
+}
+
+/// Quote the selected lines that are not quoted yet, or unquote them if they all are
+fn toggle_quote(buffer: &TextBuffer) {
+    let lines = selected_lines(buffer);
+    let all_quoted = lines.clone()
+        .map(|line| line_text(buffer, line))
+        .all(|text| markdown::quote_prefix_len(&text).is_some());
+
+    for line in lines {
+        let text = line_text(buffer, line);
+        match (all_quoted, markdown::quote_prefix_len(&text)) {
+            (true, Some(len)) => replace_in_line(buffer, line, 0, len, ""),
+            // Lines already quoted keep a single level
+            (false, None) => replace_in_line(buffer, line, 0, 0, "> "),
+            _ => {}
+        }
+    }
+}
+
+/// Toggle a checklist box on every selected line
+fn toggle_checklist(buffer: &TextBuffer) {
+    for line in selected_lines(buffer) {
+        let text = line_text(buffer, line);
+        if text.trim().is_empty() && buffer.has_selection() {
+            continue; // Don't turn blank lines inside a selection into tasks
+        }
+        let (start, len, replacement) = markdown::toggle_task(&text);
+        replace_in_line(buffer, line, start, len, &replacement);
+    }
+}
+
+/// Attach the floating formatting toolbar that appears above a text selection
+pub fn install_formatting_toolbar(text_view: &TextView, settings: &Rc<RefCell<Settings>>) {
+    let toolbar_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(2)
+        .css_classes(vec!["format-toolbar"])
+        .build();
+
+    let popover = gtk::Popover::builder()
+        .child(&toolbar_box)
+        .autohide(false) // Must not grab focus away from the editor
+        .has_arrow(true)
+        .position(gtk::PositionType::Top)
+        .can_focus(false)
+        .build();
+    popover.set_parent(text_view);
+
+    for command in FormatCommand::ALL {
+        let button = gtk::Button::builder()
+            .label(command.toolbar_label())
+            .tooltip_text(command.description())
+            .focus_on_click(false)
+            .css_classes(vec!["format-button"])
+            .build();
+        let text_view_for_button = text_view.clone();
+        button.connect_clicked(move |_| {
+            apply_format(&text_view_for_button, command);
+        });
+        toolbar_box.append(&button);
+    }
+
+    // Keep the toolbar attached to the selection as it changes
+    let buffer = text_view.buffer();
+    let text_view_for_marks = text_view.clone();
+    let popover_for_marks = popover.clone();
+    let settings_for_marks = settings.clone();
+    buffer.connect_mark_set(move |buffer, _, mark| {
+        let name = mark.name();
+        if name.as_deref() != Some("insert") && name.as_deref() != Some("selection_bound") {
+            return;
+        }
+        let enabled = settings_for_marks.borrow().editor.formatting_toolbar;
+        match buffer.selection_bounds() {
+            Some((start, _)) if enabled && text_view_for_marks.has_focus() => {
+                let location = text_view_for_marks.iter_location(&start);
+                let (x, y) = text_view_for_marks.buffer_to_window_coords(
+                    gtk::TextWindowType::Widget,
+                    location.x(),
+                    location.y(),
+                );
+                popover_for_marks.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x, y, 1, location.height())));
+                if !popover_for_marks.is_visible() {
+                    popover_for_marks.popup();
+                }
+            }
+            _ => {
+                if popover_for_marks.is_visible() {
+                    popover_for_marks.popdown();
+                }
+            }
+        }
+    });
+}
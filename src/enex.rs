@@ -0,0 +1,773 @@
+// Evernote exports (.enex): the notes of an export file, their ENML bodies (Evernote's
+// restricted XHTML) converted to Markdown, and their attached files.
+// A small tolerant tokenizer is enough for both: ENEX files are machine written, and
+// ENML sometimes carries HTML entities such as `&nbsp;` that XML parsers reject.
+
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, Engine, GeneralPurpose, GeneralPurposeConfig};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use md5::{Digest, Md5};
+
+/// A note of an export file
+#[derive(Default)]
+pub struct EnexNote {
+    pub title: String,
+    /// The note's body as ENML
+    pub content: String,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub resources: Vec<Resource>,
+}
+
+/// A file attached to a note
+#[derive(Default)]
+pub struct Resource {
+    pub data: Vec<u8>,
+    /// Why the data could not be read, if it could not
+    pub error: Option<String>,
+    pub mime: String,
+    pub file_name: Option<String>,
+}
+
+impl Resource {
+    /// MD5 of the data in hex, which `<en-media hash="…">` refers to
+    pub fn hash(&self) -> String {
+        Md5::digest(&self.data).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+enum Token {
+    Open { name: String, attributes: Vec<(String, String)>, self_closing: bool },
+    Close(String),
+    Text(String),
+}
+
+/// Splits markup into tags and text. Comments, processing instructions and the
+/// doctype are skipped; CDATA sections become text.
+struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+            if !self.rest.starts_with('<') {
+                let end = self.rest.find('<').unwrap_or(self.rest.len());
+                let text = &self.rest[..end];
+                self.rest = &self.rest[end..];
+                return Some(Token::Text(decode_entities(text)));
+            }
+            if let Some(after) = self.rest.strip_prefix("<![CDATA[") {
+                let end = after.find("]]>").unwrap_or(after.len());
+                self.rest = after.get(end + 3..).unwrap_or("");
+                return Some(Token::Text(after[..end].to_string()));
+            }
+            if let Some(after) = self.rest.strip_prefix("<!--") {
+                self.rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+                continue;
+            }
+            if let Some(after) = self.rest.strip_prefix("<?") {
+                self.rest = after.find("?>").map_or("", |end| &after[end + 2..]);
+                continue;
+            }
+            if self.rest.starts_with("<!") {
+                self.rest = &self.rest[declaration_end(self.rest)..];
+                continue;
+            }
+            // A '<' that does not start a tag is text
+            if !self.rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+                self.rest = &self.rest[1..];
+                return Some(Token::Text("<".to_string()));
+            }
+
+            let end = tag_end(self.rest);
+            let tag = self.rest[1..end].trim_end_matches('>');
+            self.rest = &self.rest[end..];
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(Token::Close(name.trim().to_lowercase()));
+            }
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            return Some(Token::Open {
+                name: tag[..name_end].to_lowercase(),
+                attributes: attributes(&tag[name_end..]),
+                self_closing,
+            });
+        }
+    }
+}
+
+/// Index just after the '>' closing the tag at the start of `markup`
+fn tag_end(markup: &str) -> usize {
+    let mut quote: Option<char> = None;
+    for (index, c) in markup.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return index + 1,
+            _ => {}
+        }
+    }
+    markup.len()
+}
+
+/// Index just after a `<!…>` declaration, which may hold a bracketed internal subset
+fn declaration_end(markup: &str) -> usize {
+    let mut depth = 0;
+    for (index, c) in markup.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '>' if depth <= 0 => return index + 1,
+            _ => {}
+        }
+    }
+    markup.len()
+}
+
+/// Attributes of a tag, with lowercase names and decoded values
+fn attributes(markup: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = markup.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest.find(|c: char| c == '=' || c.is_whitespace()).unwrap_or(rest.len());
+        let name = rest[..name_end].to_lowercase();
+        rest = rest[name_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining.trim_start();
+        }
+        if !name.is_empty() {
+            attributes.push((name, value));
+        }
+    }
+    attributes
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes.iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Replace character references and the common named entities
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..].find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|name| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            "ndash" => Some('–'),
+            "mdash" => Some('—'),
+            "hellip" => Some('…'),
+            "lsquo" => Some('‘'),
+            "rsquo" => Some('’'),
+            "ldquo" => Some('“'),
+            "rdquo" => Some('”'),
+            "laquo" => Some('«'),
+            "raquo" => Some('»'),
+            "bull" => Some('•'),
+            "middot" => Some('·'),
+            "copy" => Some('©'),
+            "reg" => Some('®'),
+            "trade" => Some('™'),
+            "deg" => Some('°'),
+            "times" => Some('×'),
+            "euro" => Some('€'),
+            _ => {
+                let number = name.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (entity, character) {
+            (Some(name), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[name.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Dates are written as e.g. 20240131T094500Z
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text.trim(), "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|date| date.and_utc())
+}
+
+// Base64 as Evernote writes it, with or without the final padding
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Decode base64, ignoring line breaks
+fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let compact: String = text.split_whitespace().collect();
+    BASE64.decode(compact).map_err(|e| format!("Invalid attachment data: {}", e))
+}
+
+/// The notes of an export file
+pub fn parse(xml: &str) -> Result<Vec<EnexNote>, String> {
+    if !xml.contains("<en-export") {
+        return Err("This is not an Evernote export (.enex) file.".to_string());
+    }
+    let mut notes = Vec::new();
+    let mut note: Option<EnexNote> = None;
+    let mut resource: Option<Resource> = None;
+    // Names of the open elements and the text of the innermost one
+    let mut open: Vec<String> = Vec::new();
+    let mut text = String::new();
+
+    for token in (Tokens { rest: xml }) {
+        match token {
+            Token::Open { name, self_closing, .. } => {
+                match name.as_str() {
+                    "note" => note = Some(EnexNote::default()),
+                    "resource" => resource = Some(Resource::default()),
+                    _ => {}
+                }
+                text.clear();
+                if !self_closing {
+                    open.push(name);
+                }
+            }
+            Token::Text(value) => text.push_str(&value),
+            Token::Close(name) => {
+                let position = match open.iter().rposition(|element| *element == name) {
+                    Some(position) => position,
+                    None => continue,
+                };
+                open.truncate(position);
+                let value = std::mem::take(&mut text);
+                let parent = open.last().map(String::as_str).unwrap_or("");
+                match (parent, name.as_str(), note.as_mut(), resource.as_mut()) {
+                    ("note", "title", Some(note), _) => note.title = value.trim().to_string(),
+                    ("note", "content", Some(note), _) => note.content = value,
+                    ("note", "created", Some(note), _) => note.created = parse_date(&value),
+                    ("note", "updated", Some(note), _) => note.updated = parse_date(&value),
+                    ("note", "tag", Some(note), _) => note.tags.push(value.trim().to_string()),
+                    ("resource", "data", _, Some(resource)) => match base64_decode(&value) {
+                        Ok(data) => resource.data = data,
+                        Err(e) => resource.error = Some(e),
+                    },
+                    ("resource", "mime", _, Some(resource)) => resource.mime = value.trim().to_string(),
+                    ("resource-attributes", "file-name", _, Some(resource)) => {
+                        resource.file_name = Some(value.trim().to_string()).filter(|name| !name.is_empty());
+                    }
+                    (_, "resource", Some(note), _) => note.resources.extend(resource.take()),
+                    (_, "note", _, _) => notes.extend(note.take()),
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(notes)
+}
+
+// Marks the element that opened a code block in `Converter::open`
+const CODE_BLOCK: &str = "```";
+
+/// An open element: its name, the Markdown written for its start and end, and
+/// where its content starts in the current line
+struct OpenElement {
+    name: String,
+    opener: String,
+    closer: String,
+    start: usize,
+}
+
+/// Converts ENML to Markdown one token at a time
+struct Converter<'a> {
+    markdown: String,
+    /// Inline text of the block being read
+    line: String,
+    open: Vec<OpenElement>,
+    /// Heading level of the block being read
+    heading: Option<usize>,
+    /// Marker of the list item whose first line has not been written yet
+    pending_marker: Option<String>,
+    /// Open lists: the next number of ordered lists, None for bullet lists
+    lists: Vec<Option<u32>>,
+    quote_depth: usize,
+    /// Text of the code block being read
+    code: Option<String>,
+    /// Rows of cells of the table being read, and how many tables are open (only
+    /// the outermost one is kept as a table)
+    table: Option<Vec<Vec<String>>>,
+    table_depth: usize,
+    /// Depth inside elements whose text is not shown (style, script, encrypted text)
+    hidden: usize,
+    last_was_item: bool,
+    /// Markdown for an `<en-media>` resource hash
+    media: &'a mut dyn FnMut(&str) -> Option<String>,
+}
+
+/// Inline Markdown for the style attribute of a span
+fn style_markers(style: &str) -> &'static str {
+    let style = style.to_lowercase().replace(' ', "");
+    let bold = style.contains("font-weight:bold") || style.contains("font-weight:700");
+    let italic = style.contains("font-style:italic");
+    let strike = style.contains("text-decoration:line-through");
+    match (bold, italic, strike) {
+        (true, true, _) => "***",
+        (true, false, _) => "**",
+        (false, true, _) => "*",
+        (false, false, true) => "~~",
+        _ => "",
+    }
+}
+
+impl Converter<'_> {
+    fn push_block(&mut self, block: &str, is_item: bool) {
+        let prefix = "> ".repeat(self.quote_depth);
+        if !self.markdown.is_empty() {
+            self.markdown.push('\n');
+            if !(is_item && self.last_was_item) {
+                // A blank line, still inside the quote
+                self.markdown.push_str(prefix.trim_end());
+                self.markdown.push('\n');
+            }
+        }
+        for (index, line) in block.lines().enumerate() {
+            if index > 0 {
+                self.markdown.push('\n');
+            }
+            self.markdown.push_str(&prefix);
+            self.markdown.push_str(line);
+        }
+        self.last_was_item = is_item;
+    }
+
+    /// Whether an open element's start marker is in the current line
+    fn is_in_line(&self, element: &OpenElement) -> bool {
+        !element.opener.is_empty()
+            && element.start <= self.line.len()
+            && self.line[..element.start].ends_with(&element.opener)
+    }
+
+    /// End the current block, writing its text as a paragraph, heading or list item
+    fn flush(&mut self) {
+        // Emphasis and links still open are closed at the break and reopened after it
+        let mut reopened = Vec::new();
+        for index in (0..self.open.len()).rev() {
+            if !self.is_in_line(&self.open[index]) {
+                continue;
+            }
+            let element = &self.open[index];
+            if self.line[element.start..].trim().is_empty() {
+                self.line.truncate(element.start - element.opener.len());
+            } else {
+                self.line.truncate(self.line.trim_end().len());
+                self.line.push_str(&element.closer);
+            }
+            reopened.push(index);
+        }
+
+        let text = self.line.trim().to_string();
+        self.line.clear();
+        self.write_text(text);
+
+        for index in reopened.into_iter().rev() {
+            let element = &mut self.open[index];
+            self.line.push_str(&element.opener);
+            element.start = self.line.len();
+        }
+    }
+
+    fn write_text(&mut self, text: String) {
+        if let (Some(table), 1) = (self.table.as_mut(), self.table_depth) {
+            if let Some(cell) = table.last_mut().and_then(|row| row.last_mut()) {
+                if !cell.is_empty() && !text.is_empty() {
+                    cell.push(' ');
+                }
+                cell.push_str(&text.replace('|', "\\|"));
+            }
+            return;
+        }
+        let heading = self.heading.take();
+        if text.is_empty() {
+            return;
+        }
+        let marker = self.pending_marker.take();
+        let is_item = marker.is_some();
+        let block = match (heading, marker) {
+            (Some(level), _) => format!("{} {}", "#".repeat(level), text),
+            (None, Some(marker)) => marker + &text,
+            // More text of a list item lines up with the item's text
+            (None, None) => format!("{}{}", "    ".repeat(self.lists.len()), text),
+        };
+        self.push_block(&block, is_item);
+    }
+
+    fn finish_table(&mut self, rows: Vec<Vec<String>>) {
+        let rows: Vec<Vec<String>> = rows.into_iter().filter(|row| !row.is_empty()).collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 1 {
+            // A single column is layout, not data: keep the cells as paragraphs
+            for row in rows {
+                for cell in row.iter().filter(|cell| !cell.is_empty()) {
+                    self.push_block(&cell.replace("\\|", "|"), false);
+                }
+            }
+            return;
+        }
+        if columns == 0 {
+            return;
+        }
+        let mut lines = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<&str> = (0..columns).map(|column| row.get(column).map_or("", String::as_str)).collect();
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                lines.push(format!("|{}|", vec![" --- "; columns].join("|")));
+            }
+        }
+        self.push_block(&lines.join("\n"), false);
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.hidden > 0 {
+            return;
+        }
+        if let Some(code) = self.code.as_mut() {
+            code.push_str(&text.replace('\u{a0}', " "));
+            return;
+        }
+        let mut previous = self.line.chars().last();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c.is_whitespace() {
+                // Runs of spaces and line breaks are one space, as in HTML
+                if previous.is_some_and(|previous| !previous.is_whitespace()) {
+                    self.line.push(' ');
+                    previous = Some(' ');
+                }
+                continue;
+            }
+            let next = chars.peek().copied();
+            let escape = match c {
+                '\\' | '*' | '`' | '[' | ']' => true,
+                // Underscores inside words do not start emphasis
+                '_' => !(previous.is_some_and(char::is_alphanumeric) && next.is_some_and(char::is_alphanumeric)),
+                '<' => next.is_some_and(|next| next.is_ascii_alphabetic() || next == '/'),
+                _ => false,
+            };
+            if escape {
+                self.line.push('\\');
+            }
+            self.line.push(c);
+            previous = Some(c);
+        }
+    }
+
+    fn open_element(&mut self, name: &str, attributes: &[(String, String)], self_closing: bool) {
+        if let Some(code) = self.code.as_mut() {
+            // Inside a code block only line breaks matter
+            if name == "br" || ((name == "div" || name == "p") && !code.is_empty() && !code.ends_with('\n')) {
+                code.push('\n');
+            }
+            if !self_closing && name != "br" {
+                self.open.push(OpenElement { name: name.to_string(), opener: String::new(), closer: String::new(), start: 0 });
+            }
+            return;
+        }
+
+        let mut opener = String::new();
+        let mut closer = String::new();
+        match name {
+            "style" | "script" | "head" | "title" | "en-crypt" => self.hidden += 1,
+            "br" => self.flush(),
+            "hr" => {
+                self.flush();
+                self.push_block("---", false);
+            }
+            "pre" => {
+                self.flush();
+                self.code = Some(String::new());
+                closer = CODE_BLOCK.to_string();
+            }
+            "div" if attribute(attributes, "style").is_some_and(|style| style.contains("-en-codeblock")) => {
+                self.flush();
+                self.code = Some(String::new());
+                closer = CODE_BLOCK.to_string();
+            }
+            "div" | "p" | "section" | "article" | "header" | "footer" | "center" | "en-note" => self.flush(),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                self.heading = name[1..].parse().ok();
+            }
+            "ul" | "ol" => {
+                self.flush();
+                let start = attribute(attributes, "start").and_then(|start| start.parse().ok()).unwrap_or(1);
+                self.lists.push((name == "ol").then_some(start));
+            }
+            "li" => {
+                self.flush();
+                let indent = "    ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.pending_marker = Some(indent + &marker);
+            }
+            "blockquote" => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            "table" => {
+                self.flush();
+                self.table_depth += 1;
+                if self.table_depth == 1 {
+                    self.table = Some(Vec::new());
+                }
+            }
+            "tr" if self.table_depth == 1 => {
+                self.flush();
+                if let Some(table) = self.table.as_mut() {
+                    table.push(Vec::new());
+                }
+            }
+            "td" | "th" if self.table_depth == 1 => {
+                self.flush();
+                if let Some(table) = self.table.as_mut() {
+                    if table.is_empty() {
+                        table.push(Vec::new());
+                    }
+                    if let Some(row) = table.last_mut() {
+                        row.push(String::new());
+                    }
+                }
+            }
+            "en-todo" => {
+                let checked = attribute(attributes, "checked").is_some_and(|checked| checked == "true");
+                // A to-do outside a list becomes a task list item
+                if self.pending_marker.is_none() && self.lists.is_empty() && self.line.trim().is_empty() && self.table.is_none() {
+                    self.pending_marker = Some("- ".to_string());
+                }
+                self.line.push_str(if checked { "[x] " } else { "[ ] " });
+            }
+            "en-media" => {
+                let markdown = attribute(attributes, "hash").and_then(|hash| (self.media)(hash));
+                if let Some(markdown) = markdown {
+                    self.line.push_str(&markdown);
+                }
+            }
+            "img" => {
+                if let Some(src) = attribute(attributes, "src").filter(|src| !src.is_empty()) {
+                    let alt = attribute(attributes, "alt").unwrap_or("");
+                    self.line.push_str(&format!("![{}]({})", alt.replace(['[', ']'], ""), crate::markdown::link_destination(src)));
+                }
+            }
+            "a" => match attribute(attributes, "href").filter(|href| !href.is_empty()) {
+                // Links between notes carry the linked note's title as their text
+                Some(href) if href.starts_with("evernote:") => {
+                    opener = "[[".to_string();
+                    closer = "]]".to_string();
+                }
+                Some(href) => {
+                    opener = "[".to_string();
+                    closer = format!("]({})", crate::markdown::link_destination(href));
+                }
+                None => {}
+            },
+            "b" | "strong" => {
+                opener = "**".to_string();
+                closer = opener.clone();
+            }
+            "i" | "em" => {
+                opener = "*".to_string();
+                closer = opener.clone();
+            }
+            "s" | "strike" | "del" => {
+                opener = "~~".to_string();
+                closer = opener.clone();
+            }
+            "code" | "tt" => {
+                opener = "`".to_string();
+                closer = opener.clone();
+            }
+            "span" | "font" => {
+                opener = style_markers(attribute(attributes, "style").unwrap_or("")).to_string();
+                closer = opener.clone();
+            }
+            _ => {}
+        }
+
+        if self_closing {
+            return;
+        }
+        if closer != CODE_BLOCK {
+            self.line.push_str(&opener);
+        }
+        self.open.push(OpenElement { name: name.to_string(), opener, closer, start: self.line.len() });
+    }
+
+    fn close_element(&mut self, element: OpenElement) {
+        if element.closer == CODE_BLOCK {
+            if let Some(code) = self.code.take() {
+                let block = format!("```\n{}\n```", code.trim_matches('\n'));
+                self.push_block(&block, false);
+            }
+            return;
+        }
+        if let Some(code) = self.code.as_mut() {
+            if (element.name == "div" || element.name == "p") && !code.ends_with('\n') {
+                code.push('\n');
+            }
+            return;
+        }
+
+        match element.name.as_str() {
+            "style" | "script" | "head" | "title" | "en-crypt" => self.hidden = self.hidden.saturating_sub(1),
+            "div" | "p" | "section" | "article" | "header" | "footer" | "center" | "en-note"
+            | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.flush(),
+            "li" => {
+                self.flush();
+                self.pending_marker = None;
+            }
+            "ul" | "ol" => {
+                self.flush();
+                self.lists.pop();
+            }
+            "blockquote" => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            "td" | "th" if self.table_depth == 1 => self.flush(),
+            "table" => {
+                self.flush();
+                self.table_depth = self.table_depth.saturating_sub(1);
+                if self.table_depth == 0 {
+                    if let Some(rows) = self.table.take() {
+                        self.finish_table(rows);
+                    }
+                }
+            }
+            _ if !element.closer.is_empty() => self.close_inline(element),
+            _ => {}
+        }
+    }
+
+    /// Close emphasis or a link, keeping the markers next to the text they wrap
+    fn close_inline(&mut self, element: OpenElement) {
+        if !self.is_in_line(&element) {
+            return;
+        }
+        let opener_start = element.start - element.opener.len();
+        if self.line[element.start..].trim().is_empty() {
+            // Nothing inside: drop the opener
+            let inside = self.line[element.start..].to_string();
+            self.line.truncate(opener_start);
+            self.line.push_str(&inside);
+            return;
+        }
+        // Spaces just inside the markers move outside them
+        let inside = self.line[element.start..].to_string();
+        let leading = &inside[..inside.len() - inside.trim_start().len()];
+        let trailing = &inside[inside.trim_end().len()..];
+        let mut text = inside.trim().to_string();
+        if element.opener == "[[" {
+            // Note titles are matched as written, without Markdown escapes
+            text = text.replace('\\', "");
+        }
+        let rebuilt = format!("{}{}{}{}{}", leading, element.opener, text, element.closer, trailing);
+        self.line.truncate(opener_start);
+        self.line.push_str(&rebuilt);
+    }
+
+    fn close(&mut self, name: &str) {
+        let position = match self.open.iter().rposition(|element| element.name == name) {
+            Some(position) => position,
+            None => return,
+        };
+        // Elements left open inside are closed with it
+        while self.open.len() > position {
+            if let Some(element) = self.open.pop() {
+                self.close_element(element);
+            }
+        }
+    }
+}
+
+/// Markdown for a note's ENML body. `media` gives the Markdown that stands for the
+/// resource an `<en-media>` tag refers to by hash.
+pub fn enml_to_markdown(enml: &str, media: &mut dyn FnMut(&str) -> Option<String>) -> String {
+    let mut converter = Converter {
+        markdown: String::new(),
+        line: String::new(),
+        open: Vec::new(),
+        heading: None,
+        pending_marker: None,
+        lists: Vec::new(),
+        quote_depth: 0,
+        code: None,
+        table: None,
+        table_depth: 0,
+        hidden: 0,
+        last_was_item: false,
+        media,
+    };
+    for token in (Tokens { rest: enml }) {
+        match token {
+            Token::Open { name, attributes, self_closing } => converter.open_element(&name, &attributes, self_closing),
+            Token::Close(name) => converter.close(&name),
+            Token::Text(text) => converter.text(&text),
+        }
+    }
+    while let Some(element) = converter.open.pop() {
+        converter.close_element(element);
+    }
+    converter.flush();
+    let mut markdown = converter.markdown;
+    markdown.push('\n');
+    markdown
+}
@@ -0,0 +1,196 @@
+// EPUB 3 output for manuscripts: one XHTML file per chapter, a title page, a
+// navigation document that doubles as the printed table of contents, and the
+// chapters' images, packaged as described in the EPUB Open Container Format.
+
+use chrono::Utc;
+use md5::{Digest, Md5};
+use std::path::Path;
+
+use crate::export::{self, escape_html};
+use crate::manuscript::{chapter_contents, Archive, ImageStore, Manuscript};
+
+// Image types EPUB reading systems must support
+const IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/svg+xml", "image/webp"];
+
+const STYLESHEET: &str = "body { font-family: serif; line-height: 1.5; margin: 0 5%; }
+h1, h2, h3, h4, h5, h6 { font-family: sans-serif; line-height: 1.25; }
+h1 { margin: 2em 0 1em; }
+.title-page { text-align: center; margin-top: 30%; }
+.title-page h1 { font-size: 2.2em; }
+.title-page .author { font-size: 1.3em; margin-top: 2em; }
+nav ol { list-style: none; padding-left: 1.2em; }
+a { color: #3584e4; }
+.missing-link { color: inherit; }
+code, pre { font-family: monospace; font-size: 0.9em; }
+pre { white-space: pre-wrap; background: #f4f4f4; padding: 0.8em; }
+blockquote { margin: 1em 0; padding-left: 1em; border-left: 3px solid #dddddd; font-style: italic; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #dddddd; padding: 0.3em 0.6em; }
+img { max-width: 100%; }
+li > input[type=checkbox] { margin-right: 0.4em; }
+";
+
+fn chapter_file(index: usize) -> String {
+    format!("chapter-{}.xhtml", index + 1)
+}
+
+/// An XHTML content document
+fn xhtml_document(title: &str, language: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{lang}\" xml:lang=\"{lang}\">\n\
+         <head>\n<meta charset=\"utf-8\"/>\n<title>{}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n\
+         <body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        body,
+        lang = escape_html(language),
+    )
+}
+
+/// Nested ordered lists from (level, item markup) pairs
+fn nested_list(items: &[(u8, String)]) -> String {
+    let mut html = String::from("<ol>\n");
+    let mut levels: Vec<u8> = Vec::new(); // Level of each open list
+    for (level, item) in items {
+        match levels.last() {
+            None => levels.push(*level),
+            Some(last) if level > last => {
+                html.push_str("\n<ol>\n");
+                levels.push(*level);
+            }
+            Some(_) => {
+                html.push_str("</li>\n");
+                while levels.len() > 1 && levels.last().is_some_and(|last| level < last) {
+                    html.push_str("</ol>\n</li>\n");
+                    levels.pop();
+                }
+            }
+        }
+        html.push_str("<li>");
+        html.push_str(item);
+    }
+    if !levels.is_empty() {
+        html.push_str("</li>\n");
+    }
+    for _ in 1..levels.len() {
+        html.push_str("</ol>\n</li>\n");
+    }
+    html.push_str("</ol>\n");
+    html
+}
+
+/// A stable identifier for the book, derived from its title and author: the same
+/// book gets the same identifier on every export and with every build of the app
+fn book_identifier(manuscript: &Manuscript) -> String {
+    let mut digest = Md5::new();
+    digest.update(format!("penscript\0{}\0{}", manuscript.title, manuscript.author));
+    let mut bytes: [u8; 16] = digest.finalize().into();
+    // A name-based version 3 UUID, which is made from an MD5 digest (RFC 4122)
+    bytes[6] = (bytes[6] & 0x0f) | 0x30;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("urn:uuid:{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Write a manuscript as an EPUB 3 file
+pub fn write_epub(manuscript: &Manuscript, destination: &Path) -> Result<(), String> {
+    let language = &manuscript.language;
+    let images = ImageStore::default();
+
+    // Chapters, with links between them and their table of contents entries
+    let mut chapter_documents = Vec::new();
+    let mut contents: Vec<(u8, String)> = Vec::new();
+    for (index, chapter) in manuscript.chapters.iter().enumerate() {
+        let resolve_link = |target: &str| manuscript.chapter_index(target).map(chapter_file);
+        let resolve_image = |dest: &str| images.add(dest, &chapter.base_dir, IMAGE_TYPES)
+            .map(|image| format!("images/{}", images.images.borrow()[image].name));
+        let (html, headings) = export::render_html(&chapter.markdown, &resolve_link, &resolve_image, true);
+
+        let mut body = String::from("<section epub:type=\"chapter\">\n");
+        if !export::starts_with_title(&chapter.markdown) {
+            body.push_str(&format!("<h1>{}</h1>\n", escape_html(&chapter.title)));
+        }
+        body.push_str(&html);
+        body.push_str("</section>\n");
+        chapter_documents.push(xhtml_document(&chapter.title, language, &body));
+
+        for (level, text, id) in chapter_contents(chapter, &headings) {
+            let href = match id {
+                Some(id) => format!("{}#{}", chapter_file(index), id),
+                None => chapter_file(index),
+            };
+            contents.push((level, format!("<a href=\"{}\">{}</a>", escape_html(&href), escape_html(&text))));
+        }
+    }
+
+    let mut title_page = format!("<section epub:type=\"titlepage\" class=\"title-page\">\n<h1>{}</h1>\n", escape_html(&manuscript.title));
+    if !manuscript.author.is_empty() {
+        title_page.push_str(&format!("<p class=\"author\">{}</p>\n", escape_html(&manuscript.author)));
+    }
+    title_page.push_str("</section>\n");
+
+    let nav = format!(
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n{}</nav>\n\
+         <nav epub:type=\"landmarks\" hidden=\"hidden\">\n<ol>\n\
+         <li><a epub:type=\"titlepage\" href=\"title.xhtml\">Title Page</a></li>\n\
+         <li><a epub:type=\"toc\" href=\"nav.xhtml\">Contents</a></li>\n\
+         <li><a epub:type=\"bodymatter\" href=\"{}\">Start</a></li>\n</ol>\n</nav>\n",
+        nested_list(&contents),
+        chapter_file(0),
+    );
+
+    // Package document: metadata, every file of the book and the reading order
+    let mut manifest = String::from(
+        "<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n\
+         <item id=\"title\" href=\"title.xhtml\" media-type=\"application/xhtml+xml\"/>\n\
+         <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    let mut spine = String::from("<itemref idref=\"title\"/>\n<itemref idref=\"nav\"/>\n");
+    for index in 0..chapter_documents.len() {
+        manifest.push_str(&format!("<item id=\"chapter-{n}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n", chapter_file(index), n = index + 1));
+        spine.push_str(&format!("<itemref idref=\"chapter-{}\"/>\n", index + 1));
+    }
+    for (index, image) in images.images.borrow().iter().enumerate() {
+        manifest.push_str(&format!("<item id=\"image-{}\" href=\"images/{}\" media-type=\"{}\"/>\n", index + 1, escape_html(&image.name), image.mime));
+    }
+    let mut metadata = format!(
+        "<dc:identifier id=\"book-id\">{}</dc:identifier>\n<dc:title>{}</dc:title>\n<dc:language>{}</dc:language>\n\
+         <meta property=\"dcterms:modified\">{}</meta>\n",
+        book_identifier(manuscript),
+        escape_html(&manuscript.title),
+        escape_html(language),
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+    );
+    if !manuscript.author.is_empty() {
+        metadata.push_str(&format!("<dc:creator>{}</dc:creator>\n", escape_html(&manuscript.author)));
+    }
+    let package = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{}\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{}</metadata>\n\
+         <manifest>\n{}</manifest>\n<spine>\n{}</spine>\n</package>\n",
+        escape_html(language),
+        metadata,
+        manifest,
+        spine,
+    );
+    let container = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+        <rootfiles>\n<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n</rootfiles>\n</container>\n";
+
+    // The mimetype file must come first and be stored uncompressed
+    let mut archive = Archive::create(destination)?;
+    archive.add("mimetype", b"application/epub+zip", false)?;
+    archive.add("META-INF/container.xml", container.as_bytes(), true)?;
+    archive.add("OEBPS/content.opf", package.as_bytes(), true)?;
+    archive.add("OEBPS/style.css", STYLESHEET.as_bytes(), true)?;
+    archive.add("OEBPS/title.xhtml", xhtml_document(&manuscript.title, language, &title_page).as_bytes(), true)?;
+    archive.add("OEBPS/nav.xhtml", xhtml_document("Contents", language, &nav).as_bytes(), true)?;
+    for (index, document) in chapter_documents.iter().enumerate() {
+        archive.add(&format!("OEBPS/{}", chapter_file(index)), document.as_bytes(), true)?;
+    }
+    for image in images.images.borrow().iter() {
+        archive.add(&format!("OEBPS/images/{}", image.name), &image.data, false)?;
+    }
+    archive.finish()
+}
@@ -0,0 +1,293 @@
+// Export notes to self-contained HTML files: CommonMark (with tables, task lists
+// and strikethrough) rendered by pulldown-cmark, the app's light or dark colours
+// embedded as CSS, local images inlined, and [[links]] between exported notes
+// rewritten to relative links to the exported files.
+// Everything here works on strings and paths so it stays independent of GTK.
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use pulldown_cmark::{html, CowStr, Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::note::Note;
+
+/// Colour scheme of an exported page, matching the app's themes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    fn colors(self) -> (&'static str, &'static str, &'static str, &'static str) {
+        // Background, text, subtle background (code, table headers), borders
+        match self {
+            Theme::Light => ("#ffffff", "#333333", "#f4f4f4", "#dddddd"),
+            Theme::Dark => ("#1e1e1e", "#f0f0f0", "#2a2a2a", "#3a3a3a"),
+        }
+    }
+}
+
+/// Stylesheet embedded in every exported page
+pub fn stylesheet(theme: Theme) -> String {
+    let (background, text, subtle, border) = theme.colors();
+    format!(
+        "body {{ margin: 0; background: {background}; color: {text}; \
+           font: 17px/1.6 system-ui, -apple-system, \"Segoe UI\", sans-serif; }}
+article {{ max-width: 46em; margin: 0 auto; padding: 3em 1.5em; }}
+h1, h2, h3, h4, h5, h6 {{ line-height: 1.25; margin: 1.6em 0 0.6em; }}
+a {{ color: #3584e4; }}
+.missing-link {{ color: inherit; opacity: 0.7; }}
+code, pre {{ font-family: ui-monospace, \"DejaVu Sans Mono\", monospace; font-size: 0.9em; background: {subtle}; }}
+code {{ padding: 0.1em 0.3em; border-radius: 4px; }}
+pre {{ padding: 1em; border-radius: 6px; overflow-x: auto; }}
+pre code {{ padding: 0; background: none; }}
+blockquote {{ margin: 1em 0; padding: 0 1em; border-left: 3px solid {border}; opacity: 0.85; }}
+table {{ border-collapse: collapse; margin: 1em 0; }}
+th, td {{ border: 1px solid {border}; padding: 0.4em 0.8em; }}
+th {{ background: {subtle}; }}
+hr {{ border: none; border-top: 1px solid {border}; margin: 2em 0; }}
+img {{ max-width: 100%; }}
+li:has(> input[type=checkbox]) {{ list-style: none; margin-left: -1.4em; }}
+"
+    )
+}
+
+/// Where a wiki link target was exported to, relative to the page being written
+pub type LinkResolver<'a> = dyn Fn(&str) -> Option<String> + 'a;
+
+/// New source of an image (e.g. a data URI), or None to keep the one in the note
+pub type ImageResolver<'a> = dyn Fn(&str) -> Option<String> + 'a;
+
+/// A heading of a rendered note, for tables of contents
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    /// The heading's `id` in the rendered HTML
+    pub id: String,
+}
+
+/// Anchor of a heading, e.g. "Next Steps!" -> "next-steps"
+pub fn heading_slug(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// Percent-encode a relative path for use in an `href`
+pub fn encode_href(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Path from the folder of `from` to `to`, both relative to the same root
+pub fn relative_path(from: &str, to: &str) -> String {
+    let from_dirs: Vec<&str> = from.split('/').collect();
+    let from_dirs = &from_dirs[..from_dirs.len() - 1];
+    let to_parts: Vec<&str> = to.split('/').collect();
+    let common = from_dirs.iter().zip(&to_parts).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<&str> = vec![".."; from_dirs.len() - common];
+    parts.extend(&to_parts[common..]);
+    parts.join("/")
+}
+
+/// File of an image in a note, if it is a local file of a supported type, with its MIME type
+pub fn local_image(dest: &str, base_dir: &Path) -> Option<(PathBuf, &'static str)> {
+    if dest.contains("://") || dest.starts_with("data:") {
+        return None;
+    }
+    let path = base_dir.join(dest);
+    let mime = match path.extension()?.to_str()?.to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        _ => return None,
+    };
+    path.is_file().then_some((path, mime))
+}
+
+/// A local image as a data URI, so the page does not depend on other files
+fn embed_image(dest: &str, base_dir: &Path) -> Option<String> {
+    let (path, mime) = local_image(dest, base_dir)?;
+    let data = fs::read(&path).ok()?;
+    Some(format!("data:{};base64,{}", mime, BASE64_STANDARD.encode(&data)))
+}
+
+/// Render Markdown to an HTML fragment, with the note's headings. Wiki links whose
+/// target `resolve_link` does not know become plain text. With `xhtml`, raw HTML in
+/// the note is shown as text so the output stays well-formed XML.
+pub fn render_html(markdown: &str, resolve_link: &LinkResolver, resolve_image: &ImageResolver, xhtml: bool) -> (String, Vec<TocEntry>) {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS // Front matter is not shown
+        | Options::ENABLE_WIKILINKS;
+    let mut events: Vec<Event> = Parser::new_ext(markdown, options).collect();
+
+    // Give headings ids, so `[[Note#Heading]]` links can point at them
+    let mut used_ids: HashMap<String, usize> = HashMap::new();
+    let mut headings = Vec::new();
+    let mut i = 0;
+    while i < events.len() {
+        if let Event::Start(Tag::Heading { id: None, .. }) = &events[i] {
+            let mut text = String::new();
+            for event in &events[i + 1..] {
+                match event {
+                    Event::End(TagEnd::Heading(_)) => break,
+                    Event::Text(t) | Event::Code(t) => text.push_str(t),
+                    _ => {}
+                }
+            }
+            let slug = heading_slug(&text);
+            let count = used_ids.entry(slug.clone()).or_insert(0);
+            let id = if *count == 0 { slug.clone() } else { format!("{}-{}", slug, count) };
+            *count += 1;
+            if let Event::Start(Tag::Heading { id: heading_id, level, .. }) = &mut events[i] {
+                headings.push(TocEntry { level: *level as u8, text, id: id.clone() });
+                *heading_id = Some(CowStr::from(id));
+            }
+        }
+        i += 1;
+    }
+
+    // Rewrite wiki links and images
+    let mut unresolved_link = false;
+    let events = events.into_iter().map(|event| match event {
+        Event::Start(Tag::Link { link_type: LinkType::WikiLink { .. }, dest_url, title, id }) => {
+            let (target, heading) = match dest_url.split_once('#') {
+                Some((target, heading)) => (target.trim(), Some(heading)),
+                None => (dest_url.trim(), None),
+            };
+            let href = if target.is_empty() {
+                heading.map(|_| String::new()) // Link within the same note
+            } else {
+                resolve_link(target)
+            };
+            match href {
+                Some(mut href) => {
+                    if let Some(heading) = heading {
+                        href.push('#');
+                        href.push_str(&heading_slug(heading));
+                    }
+                    Event::Start(Tag::Link { link_type: LinkType::Inline, dest_url: href.into(), title, id })
+                }
+                None => {
+                    unresolved_link = true;
+                    Event::Html(CowStr::from("<span class=\"missing-link\">"))
+                }
+            }
+        }
+        Event::End(TagEnd::Link) if unresolved_link => {
+            unresolved_link = false;
+            Event::Html(CowStr::from("</span>"))
+        }
+        Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+            let dest_url = resolve_image(&dest_url).map(CowStr::from).unwrap_or(dest_url);
+            Event::Start(Tag::Image { link_type, dest_url, title, id })
+        }
+        Event::Html(raw) | Event::InlineHtml(raw) if xhtml => Event::Text(raw),
+        event => event,
+    });
+
+    let mut body = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut body, events);
+    (body, headings)
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A complete, self-contained HTML page around a rendered fragment
+pub fn html_document(title: &str, body: &str, theme: Theme) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<article>\n{}</article>\n</body>\n</html>\n",
+        escape_html(title),
+        stylesheet(theme),
+        body,
+    )
+}
+
+/// Whether the body of a note starts with a level 1 heading (otherwise the export adds its title)
+pub fn starts_with_title(markdown: &str) -> bool {
+    let (_, body) = crate::frontmatter::split(markdown);
+    Parser::new(body).next().is_some_and(|event| matches!(event, Event::Start(Tag::Heading { level: HeadingLevel::H1, .. })))
+}
+
+/// Export one note (or an excerpt of it) to an HTML file. Links to other notes become plain text.
+pub fn export_note(title: &str, markdown: &str, note_dir: &Path, destination: &Path, theme: Theme) -> Result<(), String> {
+    let mut body = String::new();
+    if !starts_with_title(markdown) {
+        body.push_str(&format!("<h1>{}</h1>\n", escape_html(title)));
+    }
+    body.push_str(&render_html(markdown, &|_| None, &|dest| embed_image(dest, note_dir), false).0);
+    fs::write(destination, html_document(title, &body, theme))
+        .map_err(|e| format!("Failed to write {:?}: {}", destination, e))
+}
+
+/// Export every note in a folder of the notes directory ("" for all notes) into
+/// `destination`, keeping subfolders, with links between them pointing at the
+/// exported files. Returns the number of notes exported.
+pub fn export_folder(folder: &str, destination: &Path, theme: Theme) -> Result<usize, String> {
+    let notes: Vec<Note> = Note::get_all()?
+        .into_iter()
+        .filter(|note| {
+            let note_folder = note.folder();
+            folder.is_empty() || note_folder == folder || note_folder.starts_with(&format!("{}/", folder))
+        })
+        .collect();
+
+    // Output path of each note, relative to the destination (and to the exported folder)
+    let output_path = |note: &Note| -> String {
+        let relative = note.relative_path();
+        let relative = relative.strip_prefix(&format!("{}/", folder)).unwrap_or(&relative).to_string();
+        match relative.strip_suffix(".md") {
+            Some(stem) => format!("{}.html", stem),
+            None => format!("{}.html", relative),
+        }
+    };
+    let outputs: HashMap<String, String> = notes.iter()
+        .map(|note| (crate::markdown::title_key(&note.title), output_path(note)))
+        .collect();
+
+    for note in &notes {
+        let output = output_path(note);
+        let resolve = |target: &str| outputs.get(&crate::markdown::title_key(target))
+            .map(|target_output| encode_href(&relative_path(&output, target_output)));
+        let mut body = String::new();
+        if !starts_with_title(&note.content) {
+            body.push_str(&format!("<h1>{}</h1>\n", escape_html(&note.title)));
+        }
+        let note_dir = note.path.parent().map(Path::to_path_buf).unwrap_or_else(PathBuf::new);
+        body.push_str(&render_html(&note.content, &resolve, &|dest| embed_image(dest, &note_dir), false).0);
+
+        let path = destination.join(&output);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create folder {:?}: {}", parent, e))?;
+        }
+        fs::write(&path, html_document(&note.title, &body, theme))
+            .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    }
+    Ok(notes.len())
+}
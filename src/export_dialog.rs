@@ -0,0 +1,197 @@
+use gtk::prelude::*;
+use gtk::{gio, ApplicationWindow, Box, Button, DropDown, FileDialog, Orientation, TextView};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::export::{self, Theme};
+use crate::note::Note;
+use crate::utils::labelled_row;
+
+// Scope choices, in the order of the dropdown
+const SCOPE_NOTE: u32 = 0;
+const SCOPE_SELECTION: u32 = 1;
+const SCOPE_FOLDER: u32 = 2;
+
+/// Folders of the notes directory, including parents of nested folders ("" is all notes)
+fn folders() -> Vec<String> {
+    let mut folders = BTreeSet::new();
+    folders.insert(String::new());
+    match Note::get_all() {
+        Ok(notes) => {
+            for note in notes {
+                let folder = note.folder();
+                let mut prefix = String::new();
+                for part in folder.split('/').filter(|part| !part.is_empty()) {
+                    if !prefix.is_empty() {
+                        prefix.push('/');
+                    }
+                    prefix.push_str(part);
+                    folders.insert(prefix.clone());
+                }
+            }
+        }
+        Err(e) => eprintln!("Error listing folders: {}", e),
+    }
+    folders.into_iter().collect()
+}
+
+/// Ask what to export (the open note, the selected text or a folder) and in which
+/// theme, then where to save it
+pub fn show_export_dialog(parent: &ApplicationWindow, text_view: &TextView, note: Option<(String, PathBuf)>) {
+    let dialog = ApplicationWindow::builder()
+        .transient_for(parent)
+        .modal(true)
+        .title("Export as HTML")
+        .default_width(400)
+        .resizable(false)
+        .css_classes(vec!["export-dialog"])
+        .build();
+
+    let content_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .margin_start(20)
+        .margin_end(20)
+        .margin_top(20)
+        .margin_bottom(20)
+        .spacing(12)
+        .build();
+
+    let buffer = text_view.buffer();
+    let has_selection = buffer.has_selection();
+    let scope_choice = DropDown::from_strings(&["This Note", "Selection", "Folder"]);
+    scope_choice.set_hexpand(true);
+    // Start with the most specific scope available
+    scope_choice.set_selected(if has_selection {
+        SCOPE_SELECTION
+    } else if note.is_some() {
+        SCOPE_NOTE
+    } else {
+        SCOPE_FOLDER
+    });
+    content_box.append(&labelled_row("Export", &scope_choice));
+
+    let folders = folders();
+    let folder_names: Vec<String> = folders.iter()
+        .map(|folder| if folder.is_empty() { "All Notes".to_string() } else { folder.clone() })
+        .collect();
+    let folder_refs: Vec<&str> = folder_names.iter().map(String::as_str).collect();
+    let folder_choice = DropDown::from_strings(&folder_refs);
+    folder_choice.set_hexpand(true);
+    // Default to the open note's folder
+    if let Some((_, path)) = &note {
+        let notes_dir = crate::utils::get_notes_dir();
+        let folder = path.parent()
+            .and_then(|parent| parent.strip_prefix(&notes_dir).ok())
+            .map(|folder| folder.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if let Some(index) = folders.iter().position(|known| *known == folder) {
+            folder_choice.set_selected(index as u32);
+        }
+    }
+    let folder_row = labelled_row("Folder", &folder_choice);
+    content_box.append(&folder_row);
+
+    let theme_choice = DropDown::from_strings(&["Light", "Dark"]);
+    theme_choice.set_hexpand(true);
+    theme_choice.set_selected(if parent.has_css_class("dark-mode") { 1 } else { 0 });
+    content_box.append(&labelled_row("Theme", &theme_choice));
+
+    let button_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(gtk::Align::End)
+        .spacing(8)
+        .margin_top(8)
+        .build();
+    let cancel_button = Button::builder()
+        .label("Cancel")
+        .build();
+    let export_button = Button::builder()
+        .label("Export…")
+        .css_classes(vec!["suggested-action"])
+        .build();
+    button_box.append(&cancel_button);
+    button_box.append(&export_button);
+    content_box.append(&button_box);
+
+    let dialog_for_cancel = dialog.clone();
+    cancel_button.connect_clicked(move |_| {
+        dialog_for_cancel.close();
+    });
+
+    // Only offer what can be exported: a note must be open, text must be selected
+    let has_note = note.is_some();
+    let update_scope = {
+        let folder_row = folder_row.clone();
+        let export_button = export_button.clone();
+        move |scope: u32| {
+            folder_row.set_visible(scope == SCOPE_FOLDER);
+            export_button.set_sensitive(match scope {
+                SCOPE_NOTE => has_note,
+                SCOPE_SELECTION => has_selection && has_note,
+                _ => true,
+            });
+        }
+    };
+    update_scope(scope_choice.selected());
+    scope_choice.connect_selected_notify(move |choice| update_scope(choice.selected()));
+
+    let dialog_for_export = dialog.clone();
+    let parent_for_export = parent.clone();
+    export_button.connect_clicked(move |_| {
+        let theme = if theme_choice.selected() == 1 { Theme::Dark } else { Theme::Light };
+        let scope = scope_choice.selected();
+        dialog_for_export.close();
+
+        if scope == SCOPE_FOLDER {
+            let folder = folders.get(folder_choice.selected() as usize).cloned().unwrap_or_default();
+            let file_dialog = FileDialog::builder()
+                .title("Export Folder To")
+                .modal(true)
+                .build();
+            let parent_for_result = parent_for_export.clone();
+            file_dialog.select_folder(Some(&parent_for_export), gio::Cancellable::NONE, move |result| {
+                let destination = match result.ok().and_then(|file| file.path()) {
+                    Some(path) => path,
+                    None => return, // Cancelled
+                };
+                if let Err(e) = export::export_folder(&folder, &destination, theme) {
+                    crate::utils::show_error_dialog(&parent_for_result, "Export Error", &e);
+                }
+            });
+            return;
+        }
+
+        let (title, path) = match &note {
+            Some(note) => note.clone(),
+            None => return,
+        };
+        // The editor's text, so unsaved changes are included
+        let (start, end) = if scope == SCOPE_SELECTION {
+            buffer.selection_bounds().unwrap_or_else(|| buffer.bounds())
+        } else {
+            buffer.bounds()
+        };
+        let markdown = buffer.text(&start, &end, true).to_string();
+        let note_dir = path.parent().map(Path::to_path_buf).unwrap_or_else(crate::utils::get_notes_dir);
+
+        let file_dialog = FileDialog::builder()
+            .title("Export as HTML")
+            .initial_name(format!("{}.html", title))
+            .modal(true)
+            .build();
+        let parent_for_result = parent_for_export.clone();
+        file_dialog.save(Some(&parent_for_export), gio::Cancellable::NONE, move |result| {
+            let destination = match result.ok().and_then(|file| file.path()) {
+                Some(path) => path,
+                None => return, // Cancelled
+            };
+            if let Err(e) = export::export_note(&title, &markdown, &note_dir, &destination, theme) {
+                crate::utils::show_error_dialog(&parent_for_result, "Export Error", &e);
+            }
+        });
+    });
+
+    dialog.set_child(Some(&content_box));
+    dialog.set_default_widget(Some(&export_button));
+    dialog.present();
+}
@@ -0,0 +1,154 @@
+use gtk::prelude::*;
+use gtk::{gdk, graphene, ApplicationWindow, EventControllerMotion, TextTag, TextView, Widget};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::markdown;
+use crate::settings::{FocusUnit, Settings};
+
+// Opacity of the text outside the focused sentence/line/paragraph
+const DIM_ALPHA: f32 = 0.25;
+// Distance from a window edge (in pixels) that brings hidden bars back
+const EDGE_REVEAL_PX: f64 = 6.0;
+
+/// Window edge a hidden bar comes back from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+}
+
+/// A bar hidden while focus mode is on
+struct ChromePart {
+    widget: Widget,
+    edge: Edge,
+    /// Visibility before focus mode started (bars the user had hidden stay hidden)
+    was_visible: Cell<bool>,
+}
+
+/// Distraction-free mode: dims all text except the current sentence, line or paragraph
+/// and hides the bars until the pointer reaches their window edge
+#[derive(Clone)]
+pub struct FocusMode {
+    window: ApplicationWindow,
+    text_view: TextView,
+    settings: Rc<RefCell<Settings>>,
+    dim_tag: TextTag,
+    enabled: Rc<Cell<bool>>,
+    chrome: Rc<Vec<ChromePart>>,
+}
+
+impl FocusMode {
+    pub fn new(window: &ApplicationWindow, text_view: &TextView, settings: &Rc<RefCell<Settings>>, chrome: Vec<(Widget, Edge)>) -> Self {
+        // Created after the other editor tags so its colour wins over theirs
+        let dim_tag = TextTag::builder()
+            .name("focus-dim")
+            .build();
+        text_view.buffer().tag_table().add(&dim_tag);
+
+        let focus_mode = FocusMode {
+            window: window.clone(),
+            text_view: text_view.clone(),
+            settings: settings.clone(),
+            dim_tag,
+            enabled: Rc::new(Cell::new(false)),
+            chrome: Rc::new(chrome.into_iter()
+                .map(|(widget, edge)| ChromePart { widget, edge, was_visible: Cell::new(true) })
+                .collect()),
+        };
+        focus_mode.connect_signals();
+        focus_mode
+    }
+
+    fn connect_signals(&self) {
+        let buffer = self.text_view.buffer();
+
+        let focus_mode = self.clone();
+        buffer.connect_changed(move |_| {
+            focus_mode.update_dimming();
+        });
+
+        let focus_mode = self.clone();
+        buffer.connect_mark_set(move |buffer, _, mark| {
+            if *mark == buffer.get_insert() {
+                focus_mode.update_dimming();
+            }
+        });
+
+        let motion = EventControllerMotion::new();
+        let focus_mode = self.clone();
+        motion.connect_motion(move |_, x, y| {
+            if focus_mode.enabled.get() {
+                focus_mode.reveal_near_edges(x, y);
+            }
+        });
+        self.window.add_controller(motion);
+    }
+
+    /// Turn focus mode on or off
+    pub fn toggle(&self) {
+        let enabled = !self.enabled.get();
+        self.enabled.set(enabled);
+
+        for part in self.chrome.iter() {
+            if enabled {
+                part.was_visible.set(part.widget.is_visible());
+                part.widget.set_visible(false);
+            } else {
+                part.widget.set_visible(part.was_visible.get());
+            }
+        }
+
+        if enabled {
+            self.window.add_css_class("focus-mode");
+        } else {
+            self.window.remove_css_class("focus-mode");
+        }
+        self.update_dimming();
+        self.text_view.grab_focus();
+    }
+
+    /// Show a bar while the pointer is at its edge or over it, hide it again afterwards
+    fn reveal_near_edges(&self, x: f64, y: f64) {
+        let height = self.window.height() as f64;
+        for part in self.chrome.iter().filter(|part| part.was_visible.get()) {
+            let at_edge = match part.edge {
+                Edge::Top => y <= EDGE_REVEAL_PX,
+                Edge::Bottom => y >= height - EDGE_REVEAL_PX,
+                Edge::Left => x <= EDGE_REVEAL_PX,
+            };
+            let over_widget = part.widget.is_visible() && part.widget.compute_bounds(&self.window)
+                .is_some_and(|bounds| bounds.contains_point(&graphene::Point::new(x as f32, y as f32)));
+            part.widget.set_visible(at_edge || over_widget);
+        }
+    }
+
+    /// Dim everything outside the focused part of the text
+    fn update_dimming(&self) {
+        let buffer = self.text_view.buffer();
+        let (start, end) = buffer.bounds();
+        buffer.remove_tag(&self.dim_tag, &start, &end);
+        if !self.enabled.get() {
+            return;
+        }
+
+        // Follow the theme's text colour, just more transparent
+        let color = self.text_view.color();
+        self.dim_tag.set_foreground_rgba(Some(&gdk::RGBA::new(color.red(), color.green(), color.blue(), DIM_ALPHA)));
+
+        let text = buffer.text(&start, &end, true);
+        let cursor_chars = buffer.iter_at_mark(&buffer.get_insert()).offset() as usize;
+        let cursor = text.char_indices().nth(cursor_chars).map(|(i, _)| i).unwrap_or(text.len());
+        let (focus_start, focus_end) = match self.settings.borrow().writing.focus_unit {
+            FocusUnit::Sentence => markdown::sentence_range(&text, cursor),
+            FocusUnit::Line => markdown::line_range(&text, cursor),
+            FocusUnit::Paragraph => markdown::paragraph_range(&text, cursor),
+        };
+
+        let focus_start = buffer.iter_at_offset(text[..focus_start].chars().count() as i32);
+        let focus_end = buffer.iter_at_offset(text[..focus_end].chars().count() as i32);
+        buffer.apply_tag(&self.dim_tag, &start, &focus_start);
+        buffer.apply_tag(&self.dim_tag, &focus_end, &end);
+    }
+}
@@ -0,0 +1,380 @@
+use gtk::prelude::*;
+use gtk::{glib, cairo, DrawingArea, GestureClick, TextTag, TextView};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::markdown;
+
+// Name of the text tag that hides folded text. Hidden text stays in the buffer,
+// so anything saving the note must read it with include_hidden_chars = true.
+const FOLD_TAG: &str = "folded";
+// Re-apply folds this long after the last edit (regions move as text changes)
+const REFRESH_DELAY_MS: u64 = 250;
+const GUTTER_WIDTH: i32 = 18;
+
+/// A foldable part of a note: everything after `line` up to and including `end`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoldRegion {
+    /// Line that stays visible and carries the fold toggle
+    pub line: usize,
+    /// Last hidden line
+    pub end: usize,
+    /// Stable name of the region, used to remember fold state across edits and sessions
+    pub key: String,
+}
+
+/// Foldable regions of a note: front matter, heading sections and fenced code blocks
+pub fn fold_regions(text: &str) -> Vec<FoldRegion> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut regions = Vec::new();
+
+    // Front matter: keep the opening `---` visible
+    if let (Some(block), _) = crate::frontmatter::split(text) {
+        let closing_line = block.lines().count() + 1;
+        regions.push(FoldRegion { line: 0, end: closing_line, key: "front-matter".to_string() });
+    }
+
+    // Heading sections, without the blank lines before the next heading
+    let headings = markdown::headings(text);
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for index in 0..headings.len() {
+        let heading = &headings[index];
+        let (start, end) = markdown::section_lines(&headings, index, lines.len());
+        let last = (start + 1..end).rev().find(|&line| !lines[line].trim().is_empty());
+        // Duplicate titles are told apart by occurrence
+        let name = format!("heading:{}:{}", heading.level, heading.title);
+        let occurrence = seen.entry(name.clone()).or_insert(0);
+        *occurrence += 1;
+        if let Some(last) = last {
+            regions.push(FoldRegion { line: start, end: last, key: format!("{}#{}", name, occurrence) });
+        }
+    }
+
+    // Fenced code blocks: keep the opening fence visible
+    let mut open_fence: Option<usize> = None;
+    let mut block_number = 0;
+    for (line_number, line) in lines.iter().enumerate() {
+        if markdown::is_code_fence(line) {
+            match open_fence.take() {
+                Some(start) => {
+                    block_number += 1;
+                    regions.push(FoldRegion { line: start, end: line_number, key: format!("code#{}", block_number) });
+                }
+                None => open_fence = Some(line_number),
+            }
+        }
+    }
+
+    regions.sort_by_key(|region| region.line);
+    regions
+}
+
+/// Folded regions per note (by path relative to the notes directory)
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct FoldStore {
+    notes: BTreeMap<String, Vec<String>>,
+}
+
+fn get_fold_store_path() -> PathBuf {
+    glib::user_data_dir().join("penscript/folds.toml")
+}
+
+impl FoldStore {
+    fn load() -> Self {
+        let path = get_fold_store_path();
+        match fs::read_to_string(&path) {
+            Ok(data) => toml::from_str(&data).unwrap_or_else(|e| {
+                eprintln!("Warning: Invalid fold state file {:?}, ignoring it: {}", path, e);
+                FoldStore::default()
+            }),
+            Err(_) => FoldStore::default(), // Nothing folded yet
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = get_fold_store_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create data directory {:?}: {}", parent, e))?;
+        }
+        let data = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize fold state: {}", e))?;
+        fs::write(&path, data)
+            .map_err(|e| format!("Failed to write fold state file: {}", e))
+    }
+}
+
+/// Folding for the editor: toggles in a left gutter, hidden text via an invisible tag,
+/// and fold state remembered per note
+#[derive(Clone)]
+pub struct Folding {
+    text_view: TextView,
+    gutter: DrawingArea,
+    fold_tag: TextTag,
+    regions: Rc<RefCell<Vec<FoldRegion>>>,
+    folded: Rc<RefCell<HashSet<String>>>,
+    /// Note whose fold state is being shown
+    note_key: Rc<RefCell<Option<String>>>,
+    store: Rc<RefCell<FoldStore>>,
+    pending_refresh: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl Folding {
+    pub fn install(text_view: &TextView) -> Self {
+        let fold_tag = TextTag::builder()
+            .name(FOLD_TAG)
+            .invisible(true)
+            .build();
+        text_view.buffer().tag_table().add(&fold_tag);
+
+        let gutter = DrawingArea::builder()
+            .width_request(GUTTER_WIDTH)
+            .css_classes(vec!["fold-gutter"])
+            .build();
+        text_view.set_gutter(gtk::TextWindowType::Left, Some(&gutter));
+
+        let folding = Folding {
+            text_view: text_view.clone(),
+            gutter,
+            fold_tag,
+            regions: Rc::new(RefCell::new(Vec::new())),
+            folded: Rc::new(RefCell::new(HashSet::new())),
+            note_key: Rc::new(RefCell::new(None)),
+            store: Rc::new(RefCell::new(FoldStore::load())),
+            pending_refresh: Rc::new(RefCell::new(None)),
+        };
+        folding.connect_signals();
+        folding
+    }
+
+    fn connect_signals(&self) {
+        let folding = self.clone();
+        self.gutter.set_draw_func(move |_, cr, width, _height| {
+            folding.draw_gutter(cr, width as f64);
+        });
+
+        let click = GestureClick::new();
+        let folding = self.clone();
+        click.connect_pressed(move |_, _n_press, _x, y| {
+            let (_, buffer_y) = folding.text_view.window_to_buffer_coords(gtk::TextWindowType::Left, 0, y as i32);
+            let (iter, _) = folding.text_view.line_at_y(buffer_y);
+            folding.toggle_line(iter.line() as usize);
+        });
+        self.gutter.add_controller(click);
+
+        let buffer = self.text_view.buffer();
+        let folding = self.clone();
+        buffer.connect_changed(move |_| {
+            folding.schedule_refresh();
+        });
+
+        // Unfold whatever the cursor lands in (search results, outline jumps, ...)
+        let folding = self.clone();
+        buffer.connect_mark_set(move |buffer, iter, mark| {
+            // The end of a folded line is where hiding starts; only react inside the hidden text
+            if *mark == buffer.get_insert() && iter.has_tag(&folding.fold_tag) && !iter.starts_tag(Some(&folding.fold_tag)) {
+                folding.reveal_line(iter.line() as usize);
+            }
+        });
+
+        // Keep the toggles next to their lines while scrolling
+        let gutter = self.gutter.clone();
+        let connect_adjustment = move |text_view: &TextView| {
+            if let Some(adjustment) = text_view.vadjustment() {
+                let gutter = gutter.clone();
+                adjustment.connect_value_changed(move |_| gutter.queue_draw());
+            }
+        };
+        connect_adjustment(&self.text_view);
+        self.text_view.connect_vadjustment_notify(connect_adjustment);
+    }
+
+    /// Switch to another note's fold state (call after its text is loaded)
+    pub fn set_note(&self, relative_path: Option<String>) {
+        if let Some(source_id) = self.pending_refresh.borrow_mut().take() {
+            source_id.remove();
+        }
+        *self.folded.borrow_mut() = relative_path.as_ref()
+            .and_then(|path| self.store.borrow().notes.get(path).cloned())
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        *self.note_key.borrow_mut() = relative_path;
+        self.refresh();
+    }
+
+    /// Toggle the innermost fold containing the cursor
+    pub fn toggle_at_cursor(&self) {
+        let buffer = self.text_view.buffer();
+        let line = buffer.iter_at_mark(&buffer.get_insert()).line() as usize;
+        let innermost = self.regions.borrow().iter()
+            .filter(|region| region.line <= line && line <= region.end)
+            .max_by_key(|region| region.line)
+            .map(|region| region.line);
+        if let Some(region_line) = innermost {
+            self.toggle_line(region_line);
+        }
+    }
+
+    /// Expand every fold of the current note
+    pub fn unfold_all(&self) {
+        self.folded.borrow_mut().clear();
+        self.apply();
+        self.save_state();
+    }
+
+    fn toggle_line(&self, line: usize) {
+        let key = match self.regions.borrow().iter().find(|region| region.line == line) {
+            Some(region) => region.key.clone(),
+            None => return,
+        };
+        {
+            let mut folded = self.folded.borrow_mut();
+            if !folded.remove(&key) {
+                folded.insert(key);
+            }
+        }
+        self.apply();
+        self.save_state();
+
+        // Don't leave the cursor (and typing) inside hidden text
+        let buffer = self.text_view.buffer();
+        let cursor = buffer.iter_at_mark(&buffer.get_insert());
+        if cursor.has_tag(&self.fold_tag) && !cursor.starts_tag(Some(&self.fold_tag)) {
+            if let Some(iter) = buffer.iter_at_line(line as i32) {
+                buffer.place_cursor(&iter);
+            }
+        }
+    }
+
+    /// Unfold every region hiding `line`
+    fn reveal_line(&self, line: usize) {
+        let revealed: Vec<String> = self.regions.borrow().iter()
+            .filter(|region| region.line < line && line <= region.end)
+            .map(|region| region.key.clone())
+            .collect();
+        let mut changed = false;
+        for key in revealed {
+            changed |= self.folded.borrow_mut().remove(&key);
+        }
+        if changed {
+            // Don't retag while GTK is still moving the cursor
+            let folding = self.clone();
+            glib::idle_add_local_once(move || {
+                folding.apply();
+                folding.save_state();
+            });
+        }
+    }
+
+    fn schedule_refresh(&self) {
+        if let Some(source_id) = self.pending_refresh.borrow_mut().take() {
+            source_id.remove();
+        }
+        let folding = self.clone();
+        let source_id = glib::timeout_add_local_once(std::time::Duration::from_millis(REFRESH_DELAY_MS), move || {
+            folding.pending_refresh.borrow_mut().take();
+            folding.refresh();
+        });
+        *self.pending_refresh.borrow_mut() = Some(source_id);
+    }
+
+    /// Recompute regions from the text and re-apply the folded ones
+    fn refresh(&self) {
+        let buffer = self.text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true);
+        *self.regions.borrow_mut() = fold_regions(&text);
+        self.apply();
+    }
+
+    /// Hide the text of folded regions
+    fn apply(&self) {
+        let buffer = self.text_view.buffer();
+        let (start, end) = buffer.bounds();
+        buffer.remove_tag(&self.fold_tag, &start, &end);
+
+        let folded = self.folded.borrow();
+        for region in self.regions.borrow().iter().filter(|region| folded.contains(&region.key)) {
+            // From the end of the toggle line to the end of the last hidden line,
+            // so the toggle line keeps the newline of the last hidden one
+            let (Some(mut hide_start), Some(mut hide_end)) = (
+                buffer.iter_at_line(region.line as i32),
+                buffer.iter_at_line(region.end as i32),
+            ) else {
+                continue;
+            };
+            if !hide_start.ends_line() {
+                hide_start.forward_to_line_end();
+            }
+            if !hide_end.ends_line() {
+                hide_end.forward_to_line_end();
+            }
+            buffer.apply_tag(&self.fold_tag, &hide_start, &hide_end);
+        }
+        self.gutter.queue_draw();
+    }
+
+    fn save_state(&self) {
+        let note_key = match self.note_key.borrow().clone() {
+            Some(note_key) => note_key,
+            None => return,
+        };
+        let mut store = self.store.borrow_mut();
+        let mut folded: Vec<String> = self.folded.borrow().iter().cloned().collect();
+        if folded.is_empty() {
+            store.notes.remove(&note_key);
+        } else {
+            folded.sort();
+            store.notes.insert(note_key, folded);
+        }
+        if let Err(e) = store.save() {
+            eprintln!("Error saving fold state: {}", e);
+        }
+    }
+
+    /// Draw a ▸/▾ toggle next to every foldable line in view
+    fn draw_gutter(&self, cr: &cairo::Context, width: f64) {
+        let buffer = self.text_view.buffer();
+        let visible = self.text_view.visible_rect();
+        let folded = self.folded.borrow();
+        let color = if self.text_view.has_css_class("light-mode") { 0.45 } else { 0.6 };
+        cr.set_source_rgba(color, color, color, 0.8);
+
+        for region in self.regions.borrow().iter() {
+            let iter = match buffer.iter_at_line(region.line as i32) {
+                Some(iter) => iter,
+                None => continue,
+            };
+            if iter.has_tag(&self.fold_tag) {
+                continue; // Inside an outer fold
+            }
+            let (line_y, line_height) = self.text_view.line_yrange(&iter);
+            if line_y + line_height < visible.y() || line_y > visible.y() + visible.height() {
+                continue;
+            }
+            let (_, window_y) = self.text_view.buffer_to_window_coords(gtk::TextWindowType::Left, 0, line_y);
+            let center_x = width / 2.0;
+            // Center on the first display line of a wrapped paragraph
+            let center_y = window_y as f64 + (line_height.min(24) as f64) / 2.0;
+            let size = 4.0;
+            if folded.contains(&region.key) {
+                // ▸
+                cr.move_to(center_x - size / 2.0, center_y - size);
+                cr.line_to(center_x + size, center_y);
+                cr.line_to(center_x - size / 2.0, center_y + size);
+            } else {
+                // ▾
+                cr.move_to(center_x - size, center_y - size / 2.0);
+                cr.line_to(center_x + size, center_y - size / 2.0);
+                cr.line_to(center_x, center_y + size);
+            }
+            cr.close_path();
+            cr.fill().ok();
+        }
+    }
+}
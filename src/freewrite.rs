@@ -0,0 +1,400 @@
+use gtk::prelude::*;
+use gtk::{gio, glib, ApplicationWindow, Box, Button, DropDown, Label, Orientation, SpinButton, TextView, Widget};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::note::Note;
+use crate::text_stats;
+use crate::utils::show_confirmation_dialog;
+
+// Actions that open, create or remove notes, disabled while a session is running
+const LOCKED_ACTIONS: &[&str] = &[
+    "new-note",
+    "new-from-template",
+    "open-note",
+    "create-note",
+    "quick-switcher",
+    "rename-note",
+    "delete-note",
+    "start-freewrite",
+    "open-today",
+    "journal-previous",
+    "journal-next",
+    "journal-calendar",
+];
+// How long the "goal reached" message stays in the bottom bar
+const COMPLETE_MESSAGE_SECS: u32 = 5;
+
+/// What ends a freewrite session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    Minutes(u32),
+    Words(usize),
+}
+
+struct Session {
+    goal: Goal,
+    started: Instant,
+    /// Words in the note when the session started
+    start_words: usize,
+    /// Countdown ticking once a second (timed sessions only)
+    timer: Option<glib::SourceId>,
+}
+
+/// Strict freewrite sessions: only forward typing (no Backspace, Delete, cut,
+/// undo or selections) in a fresh note, with note switching locked until the
+/// countdown or word target is reached or the session is abandoned
+#[derive(Clone)]
+pub struct Freewrite {
+    window: ApplicationWindow,
+    text_view: TextView,
+    sidebar: Widget,
+    container: Box,
+    progress_label: Label,
+    abandon_button: Button,
+    session: Rc<RefCell<Option<Session>>>,
+    /// Timer hiding the "goal reached" message
+    pending_hide: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl Freewrite {
+    pub fn new(window: &ApplicationWindow, text_view: &TextView, sidebar: &impl IsA<Widget>) -> Self {
+        let progress_label = Label::builder()
+            .css_classes(vec!["freewrite-progress"])
+            .build();
+        let abandon_button = Button::builder()
+            .label("Abandon")
+            .action_name("win.abandon-freewrite")
+            .tooltip_text("End the session without reaching the goal")
+            .css_classes(vec!["flat", "freewrite-abandon"])
+            .build();
+        let container = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(6)
+            .valign(gtk::Align::Center)
+            .visible(false)
+            .build();
+        container.append(&progress_label);
+        container.append(&abandon_button);
+
+        let freewrite = Freewrite {
+            window: window.clone(),
+            text_view: text_view.clone(),
+            sidebar: sidebar.clone().upcast(),
+            container,
+            progress_label,
+            abandon_button,
+            session: Rc::new(RefCell::new(None)),
+            pending_hide: Rc::new(RefCell::new(None)),
+        };
+        freewrite.connect_signals();
+        freewrite
+    }
+
+    /// The progress display to place in the bottom bar
+    pub fn widget(&self) -> &Box {
+        &self.container
+    }
+
+    fn is_active(&self) -> bool {
+        self.session.borrow().is_some()
+    }
+
+    fn connect_signals(&self) {
+        // Block every way of removing text, at the buffer so that keys, spell-check
+        // replacements, list and format commands and outline moves are all covered
+        let buffer = self.text_view.buffer();
+        // Set when a deletion was blocked: the rest of that edit (e.g. the insertion of a
+        // replacement) is dropped too, so edits are skipped whole instead of half-applied
+        let blocked_edit = Rc::new(Cell::new(false));
+        let blocked_edit_for_begin = blocked_edit.clone();
+        buffer.connect_begin_user_action(move |_| {
+            blocked_edit_for_begin.set(false);
+        });
+        let blocked_edit_for_end = blocked_edit.clone();
+        buffer.connect_end_user_action(move |_| {
+            blocked_edit_for_end.set(false);
+        });
+        let freewrite = self.clone();
+        let blocked_edit_for_delete = blocked_edit.clone();
+        buffer.connect_delete_range(move |buffer, start, end| {
+            if freewrite.is_active() && start != end {
+                buffer.stop_signal_emission_by_name("delete-range");
+                blocked_edit_for_delete.set(true);
+            }
+        });
+        let freewrite = self.clone();
+        buffer.connect_insert_text(move |buffer, _, _| {
+            if freewrite.is_active() && blocked_edit.get() {
+                buffer.stop_signal_emission_by_name("insert-text");
+            }
+        });
+        let freewrite = self.clone();
+        self.text_view.connect_toggle_overwrite(move |text_view| {
+            if freewrite.is_active() {
+                text_view.stop_signal_emission_by_name("toggle-overwrite");
+            }
+        });
+
+        // Keep the caret at the end of the text: no going back and no selections to type over
+        let freewrite = self.clone();
+        buffer.connect_mark_set(move |buffer, iter, mark| {
+            if !freewrite.is_active() || (*mark != buffer.get_insert() && *mark != buffer.selection_bound()) {
+                return;
+            }
+            let end = buffer.end_iter();
+            if *iter != end {
+                buffer.place_cursor(&end);
+            }
+        });
+
+        let freewrite = self.clone();
+        buffer.connect_changed(move |_| {
+            freewrite.update_progress();
+        });
+    }
+
+    /// Ask for the session goal, then start writing in a new note
+    pub fn prompt_start(&self) {
+        if self.is_active() {
+            return;
+        }
+
+        let dialog = ApplicationWindow::builder()
+            .transient_for(&self.window)
+            .modal(true)
+            .title("Freewrite")
+            .default_width(360)
+            .resizable(false)
+            .css_classes(vec!["freewrite-dialog"])
+            .build();
+
+        let content_box = Box::builder()
+            .orientation(Orientation::Vertical)
+            .margin_start(20)
+            .margin_end(20)
+            .margin_top(20)
+            .margin_bottom(20)
+            .spacing(12)
+            .build();
+
+        let description = Label::builder()
+            .label("Write without looking back: Backspace, Delete, cut, undo and selections are disabled, and you stay in the session note until the goal is reached.")
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        content_box.append(&description);
+
+        let goal_row = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(12)
+            .build();
+        let goal_kind = DropDown::from_strings(&["Minutes", "Words"]);
+        let goal_amount = SpinButton::with_range(1.0, 10000.0, 1.0);
+        goal_amount.set_value(15.0);
+        goal_amount.set_hexpand(true);
+        goal_row.append(&goal_amount);
+        goal_row.append(&goal_kind);
+        content_box.append(&goal_row);
+
+        // Sensible starting amounts for each kind of goal
+        let goal_amount_for_kind = goal_amount.clone();
+        goal_kind.connect_selected_notify(move |goal_kind| {
+            goal_amount_for_kind.set_value(if goal_kind.selected() == 0 { 15.0 } else { 500.0 });
+        });
+
+        let button_box = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .halign(gtk::Align::End)
+            .spacing(8)
+            .margin_top(8)
+            .build();
+        let cancel_button = Button::builder()
+            .label("Cancel")
+            .build();
+        let start_button = Button::builder()
+            .label("Start")
+            .css_classes(vec!["suggested-action"])
+            .build();
+        button_box.append(&cancel_button);
+        button_box.append(&start_button);
+        content_box.append(&button_box);
+
+        let dialog_for_cancel = dialog.clone();
+        cancel_button.connect_clicked(move |_| {
+            dialog_for_cancel.close();
+        });
+
+        let dialog_for_start = dialog.clone();
+        let freewrite = self.clone();
+        start_button.connect_clicked(move |_| {
+            let amount = goal_amount.value_as_int().max(1);
+            let goal = if goal_kind.selected() == 0 {
+                Goal::Minutes(amount as u32)
+            } else {
+                Goal::Words(amount as usize)
+            };
+            dialog_for_start.close();
+            freewrite.start(goal);
+        });
+
+        dialog.set_child(Some(&content_box));
+        dialog.set_default_widget(Some(&start_button));
+        dialog.present();
+    }
+
+    /// Create the session note, open it and lock the editor down
+    fn start(&self, goal: Goal) {
+        // Create the note here rather than through win.create-note, which reports its own
+        // errors: a failure must not lock (and wipe the undo history of) the open note
+        let title = Note::generate_timestamped_title("Freewrite");
+        if let Err(e) = Note::new(&title) {
+            crate::utils::show_error_dialog(&self.window, "Freewrite Error", &format!("Failed to create the session note: {}", e));
+            return;
+        }
+        if let Err(e) = WidgetExt::activate_action(&self.window, "win.open-note", Some(&title.to_variant())) {
+            eprintln!("Error opening freewrite note {}: {}", title, e);
+            return;
+        }
+
+        if let Some(source_id) = self.pending_hide.borrow_mut().take() {
+            source_id.remove();
+        }
+
+        let timer = match goal {
+            Goal::Minutes(_) => {
+                let freewrite = self.clone();
+                Some(glib::timeout_add_seconds_local(1, move || freewrite.tick()))
+            }
+            Goal::Words(_) => None,
+        };
+        let buffer = self.text_view.buffer();
+        *self.session.borrow_mut() = Some(Session {
+            goal,
+            started: Instant::now(),
+            start_words: self.word_count(),
+            timer,
+        });
+
+        // Undo would be another way of deleting
+        buffer.set_enable_undo(false);
+        buffer.place_cursor(&buffer.end_iter());
+        self.set_locked(true);
+        self.abandon_button.set_visible(true);
+        self.container.set_visible(true);
+        self.update_progress();
+        self.text_view.grab_focus();
+    }
+
+    /// Countdown step; ends the session when time is up
+    fn tick(&self) -> glib::ControlFlow {
+        let finished = match self.session.borrow_mut().as_mut() {
+            Some(session) => {
+                let finished = self.remaining(session).is_zero();
+                if finished {
+                    // This source is about to end by itself
+                    session.timer = None;
+                }
+                finished
+            }
+            None => return glib::ControlFlow::Break,
+        };
+
+        if finished {
+            self.finish(true);
+            return glib::ControlFlow::Break;
+        }
+        self.update_progress();
+        glib::ControlFlow::Continue
+    }
+
+    fn remaining(&self, session: &Session) -> Duration {
+        match session.goal {
+            Goal::Minutes(minutes) => Duration::from_secs(minutes as u64 * 60).saturating_sub(session.started.elapsed()),
+            Goal::Words(_) => Duration::ZERO,
+        }
+    }
+
+    fn word_count(&self) -> usize {
+        let buffer = self.text_view.buffer();
+        text_stats::count_words(&buffer.text(&buffer.start_iter(), &buffer.end_iter(), true))
+    }
+
+    /// Refresh the bottom bar display and end word-target sessions once the target is met
+    fn update_progress(&self) {
+        let (text, target_met) = {
+            let session = self.session.borrow();
+            let session = match session.as_ref() {
+                Some(session) => session,
+                None => return,
+            };
+            let written = self.word_count().saturating_sub(session.start_words);
+            match session.goal {
+                Goal::Minutes(_) => {
+                    let remaining = self.remaining(session).as_secs();
+                    (format!("Freewrite · {}:{:02} left · {} words", remaining / 60, remaining % 60, written), false)
+                }
+                Goal::Words(target) => (format!("Freewrite · {} / {} words", written, target), written >= target),
+            }
+        };
+        self.progress_label.set_text(&text);
+        if target_met {
+            self.finish(true);
+        }
+    }
+
+    /// End the session early, after confirmation
+    pub fn abandon(&self) {
+        if !self.is_active() {
+            return;
+        }
+        let freewrite = self.clone();
+        show_confirmation_dialog(
+            &self.window,
+            "Abandon Session",
+            "The note keeps what you wrote, but the goal will not be reached.",
+            "Abandon this freewrite session?",
+            move || freewrite.finish(false),
+        );
+    }
+
+    /// Lift the restrictions; a reached goal is announced briefly in the bottom bar
+    fn finish(&self, goal_reached: bool) {
+        let session = match self.session.borrow_mut().take() {
+            Some(session) => session,
+            None => return,
+        };
+        if let Some(source_id) = session.timer {
+            source_id.remove();
+        }
+
+        self.text_view.buffer().set_enable_undo(true);
+        self.set_locked(false);
+        self.abandon_button.set_visible(false);
+
+        if !goal_reached {
+            self.container.set_visible(false);
+            return;
+        }
+        let written = self.word_count().saturating_sub(session.start_words);
+        self.progress_label.set_text(&format!("Freewrite complete · {} words", written));
+        let freewrite = self.clone();
+        let source_id = glib::timeout_add_seconds_local(COMPLETE_MESSAGE_SECS, move || {
+            freewrite.pending_hide.borrow_mut().take();
+            freewrite.container.set_visible(false);
+            glib::ControlFlow::Break
+        });
+        *self.pending_hide.borrow_mut() = Some(source_id);
+    }
+
+    /// Disable (or re-enable) everything that would leave the session note
+    fn set_locked(&self, locked: bool) {
+        for name in LOCKED_ACTIONS {
+            if let Some(action) = self.window.lookup_action(name).and_then(|action| action.downcast::<gio::SimpleAction>().ok()) {
+                action.set_enabled(!locked);
+            }
+        }
+        self.sidebar.set_sensitive(!locked);
+    }
+}
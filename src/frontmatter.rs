@@ -0,0 +1,136 @@
+// Minimal YAML front matter support: a `---` block at the top of a note holding
+// `key: value`, `key: [a, b]` or `key:` followed by `- item` lines.
+
+/// Parsed front matter fields, in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontMatter {
+    pub fields: Vec<(String, Vec<String>)>,
+}
+
+impl FrontMatter {
+    /// First value of a field, if present
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.get_list(key).first().map(String::as_str)
+    }
+
+    /// All values of a field (empty if missing)
+    pub fn get_list(&self, key: &str) -> &[String] {
+        self.fields.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, values)| values.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+/// Split content into the raw front matter block (without the `---` lines) and the body.
+/// Returns `(None, content)` if the note has no front matter.
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    let rest = match content.strip_prefix("---\n").or_else(|| content.strip_prefix("---\r\n")) {
+        Some(rest) => rest,
+        None => return (None, content),
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" || line.trim_end() == "..." {
+            let block = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(block), body);
+        }
+        offset += line.len();
+    }
+    (None, content) // Unterminated: treat as plain text
+}
+
+/// Parse the front matter of a note
+pub fn parse(content: &str) -> FrontMatter {
+    let block = match split(content) {
+        (Some(block), _) => block,
+        (None, _) => return FrontMatter::default(),
+    };
+
+    let mut fields: Vec<(String, Vec<String>)> = Vec::new();
+    for line in block.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // "- item" continues the list of the previous key
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if let Some((_, values)) = fields.last_mut() {
+                values.push(unquote(item));
+            }
+            continue;
+        }
+        if let Some((key, value)) = trimmed.split_once(':') {
+            let value = value.trim();
+            let values = if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                inner.split(',')
+                    .map(unquote)
+                    .filter(|v| !v.is_empty())
+                    .collect()
+            } else if value.is_empty() {
+                Vec::new()
+            } else {
+                vec![unquote(value)]
+            };
+            fields.push((key.trim().to_string(), values));
+        }
+    }
+
+    FrontMatter { fields }
+}
+
+/// Strip surrounding quotes from a scalar
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted {
+        value[1..value.len() - 1].to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Set (or with `None`, remove) a single-value field, adding a front matter block
+/// if needed. The body of the note is left untouched.
+pub fn set_field(content: &str, key: &str, value: Option<&str>) -> String {
+    let (block, body) = match split(content) {
+        (Some(block), body) => (block, body),
+        (None, _) => match value {
+            Some(value) => return format!("---\n{}: {}\n---\n{}", key, value, content),
+            None => return content.to_string(),
+        },
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut replaced = false;
+    let mut in_field = false;
+    for line in block.lines() {
+        let is_key = line.split_once(':').is_some_and(|(k, _)| k == key);
+        if is_key {
+            in_field = true;
+            if let (Some(value), false) = (value, replaced) {
+                lines.push(format!("{}: {}", key, value));
+                replaced = true;
+            }
+            continue;
+        }
+        // "- item" lines belong to the field being replaced
+        if in_field && line.trim_start().starts_with("- ") {
+            continue;
+        }
+        in_field = false;
+        lines.push(line.to_string());
+    }
+    if let (Some(value), false) = (value, replaced) {
+        lines.push(format!("{}: {}", key, value));
+    }
+
+    if lines.iter().all(|line| line.trim().is_empty()) {
+        return body.to_string(); // Nothing left but an empty block
+    }
+    format!("---\n{}\n---\n{}", lines.join("\n"), body)
+}
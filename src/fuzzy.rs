@@ -0,0 +1,52 @@
+// Small fuzzy matcher used by the command palette and the quick switcher.
+
+/// Score how well `query` fuzzy-matches `candidate` (case-insensitive).
+/// Returns None unless every query char appears in order. Higher is better:
+/// consecutive matches, matches at word starts and an early first match score more.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.trim().to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let candidate_chars: Vec<char> = candidate.chars().collect();
+    let mut score: i64 = 0;
+    let mut query_index = 0;
+    let mut previous_match: Option<usize> = None;
+    let mut first_match: Option<usize> = None;
+
+    for (index, ch) in candidate_chars.iter().enumerate() {
+        if query_index == query.len() {
+            break;
+        }
+        if !ch.to_lowercase().eq(std::iter::once(query[query_index])) {
+            continue;
+        }
+
+        score += 10;
+        let at_word_start = index == 0 || {
+            let before = candidate_chars[index - 1];
+            !before.is_alphanumeric() || (before.is_lowercase() && ch.is_uppercase())
+        };
+        if at_word_start {
+            score += 15;
+        }
+        match previous_match {
+            Some(previous) if previous + 1 == index => score += 20, // Consecutive run
+            Some(previous) => score -= (index - previous - 1).min(10) as i64, // Gap penalty
+            None => {}
+        }
+        first_match.get_or_insert(index);
+        previous_match = Some(index);
+        query_index += 1;
+    }
+
+    if query_index < query.len() {
+        return None;
+    }
+
+    // Prefer matches that start early and shorter candidates
+    score -= first_match.unwrap_or(0).min(20) as i64;
+    score -= (candidate_chars.len() / 10) as i64;
+    Some(score)
+}
@@ -0,0 +1,187 @@
+use gtk::prelude::*;
+use gtk::{gio, ApplicationWindow, Box, Label, LevelBar, Orientation};
+use chrono::{Local, NaiveDate};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::settings::Settings;
+use crate::stats::WritingStats;
+
+// Front matter key holding a note's word goal, e.g. `word_goal: 1500`
+const NOTE_GOAL_KEY: &str = "word_goal";
+
+/// Word goal of a note from its front matter, if set
+fn note_goal(content: &str) -> Option<usize> {
+    crate::frontmatter::parse(content)
+        .get(NOTE_GOAL_KEY)
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|goal| *goal > 0)
+}
+
+/// Progress bars in the bottom bar for the open note's word goal and the daily goal,
+/// with a desktop notification when either is reached
+#[derive(Clone)]
+pub struct WordGoals {
+    window: ApplicationWindow,
+    settings: Rc<RefCell<Settings>>,
+    container: Box,
+    note_progress: Box,
+    note_bar: LevelBar,
+    daily_progress: Box,
+    daily_bar: LevelBar,
+    /// Today's words come from the writing log, plus the open note's unsaved words
+    stats: WritingStats,
+    /// Title of the open note
+    title: Rc<RefCell<Option<String>>>,
+    /// Whether the open note's goal notification has been sent
+    note_notified: Rc<Cell<bool>>,
+    /// Day the daily goal notification was last sent
+    daily_notified: Rc<Cell<Option<NaiveDate>>>,
+}
+
+impl WordGoals {
+    pub fn new(window: &ApplicationWindow, settings: &Rc<RefCell<Settings>>, stats: &WritingStats) -> Self {
+        let (note_progress, note_bar) = progress_bar("Note");
+        let (daily_progress, daily_bar) = progress_bar("Today");
+
+        let container = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(12)
+            .valign(gtk::Align::Center)
+            .build();
+        container.append(&note_progress);
+        container.append(&daily_progress);
+
+        let goals = WordGoals {
+            window: window.clone(),
+            settings: settings.clone(),
+            container,
+            note_progress,
+            note_bar,
+            daily_progress,
+            daily_bar,
+            stats: stats.clone(),
+            title: Rc::new(RefCell::new(None)),
+            note_notified: Rc::new(Cell::new(false)),
+            daily_notified: Rc::new(Cell::new(None)),
+        };
+        // A goal already reached earlier today (before a restart) does not notify again
+        let daily_goal = settings.borrow().writing.daily_word_goal;
+        if daily_goal > 0 && goals.stats.words_today() >= daily_goal as i64 {
+            goals.daily_notified.set(Some(Local::now().date_naive()));
+        }
+        goals.refresh(None, 0);
+        goals
+    }
+
+    /// The progress bars to place in the bottom bar
+    pub fn widget(&self) -> &Box {
+        &self.container
+    }
+
+    /// A note was loaded (or closed). Call after `WritingStats::observe`, so its current
+    /// words are the baseline, not progress.
+    pub fn set_note(&self, note: Option<(&str, &str)>, word_count: usize) {
+        *self.title.borrow_mut() = note.map(|(title, _)| title.to_string());
+        // Notes already past their goal do not notify again
+        let goal = note.and_then(|(_, content)| note_goal(content));
+        self.note_notified.set(goal.is_some_and(|goal| word_count >= goal));
+        self.refresh(goal, word_count);
+    }
+
+    /// The open note was edited: update the progress and notify of reached goals
+    pub fn update(&self, content: &str, word_count: usize) {
+        if self.title.borrow().is_none() {
+            return; // Typing without an open note
+        }
+
+        let goal = note_goal(content);
+        if let Some(goal) = goal {
+            if word_count >= goal && !self.note_notified.get() {
+                self.note_notified.set(true);
+                self.notify("note-word-goal", "Note goal reached", &format!("This note has reached its goal of {} words.", goal));
+            }
+        }
+
+        let daily_goal = self.settings.borrow().writing.daily_word_goal;
+        let today = Local::now().date_naive();
+        if daily_goal > 0 && self.daily_notified.get() != Some(today)
+            && self.daily_words_with(word_count) >= daily_goal as i64 {
+            self.daily_notified.set(Some(today));
+            self.notify("daily-word-goal", "Daily goal reached", &format!("You have written {} words today.", daily_goal));
+        }
+
+        self.refresh(goal, word_count);
+    }
+
+    /// Net words written today, with the open note at `word_count` words. Any change in
+    /// word count counts, typed or pasted; daily notes and notes from templates are saved
+    /// with their text before they open, so that text is their baseline instead.
+    fn daily_words_with(&self, word_count: usize) -> i64 {
+        let unsaved = match self.title.borrow().as_deref() {
+            Some(title) => self.stats.unsaved_words(title, word_count),
+            None => 0,
+        };
+        self.stats.words_today() + unsaved
+    }
+
+    /// Show the bars for the goals that are set
+    fn refresh(&self, note_goal: Option<usize>, word_count: usize) {
+        match note_goal {
+            Some(goal) => {
+                self.note_bar.set_max_value(goal as f64);
+                self.note_bar.set_value(word_count.min(goal) as f64);
+                self.note_progress.set_tooltip_text(Some(&format!("{} / {} words in this note", word_count, goal)));
+                self.note_progress.set_visible(true);
+            }
+            None => self.note_progress.set_visible(false),
+        }
+
+        let daily_goal = self.settings.borrow().writing.daily_word_goal;
+        if daily_goal > 0 {
+            let words = self.daily_words_with(word_count).max(0) as usize;
+            self.daily_bar.set_max_value(daily_goal as f64);
+            self.daily_bar.set_value(words.min(daily_goal) as f64);
+            self.daily_progress.set_tooltip_text(Some(&format!("{} / {} words today", words, daily_goal)));
+            self.daily_progress.set_visible(true);
+        } else {
+            self.daily_progress.set_visible(false);
+        }
+    }
+
+    fn notify(&self, id: &str, title: &str, body: &str) {
+        if let Some(app) = self.window.application() {
+            let notification = gio::Notification::new(title);
+            notification.set_body(Some(body));
+            app.send_notification(Some(id), &notification);
+        }
+    }
+}
+
+/// A small caption with a progress bar next to it
+fn progress_bar(caption: &str) -> (Box, LevelBar) {
+    let label = Label::builder()
+        .label(caption)
+        .css_classes(vec!["goal-caption"])
+        .build();
+    let bar = LevelBar::builder()
+        .min_value(0.0)
+        .max_value(1.0)
+        .width_request(80)
+        .valign(gtk::Align::Center)
+        .css_classes(vec!["goal-bar"])
+        .build();
+    // No "low"/"high" colouring: the bar only fills up
+    bar.remove_offset_value(Some(gtk::LEVEL_BAR_OFFSET_LOW));
+    bar.remove_offset_value(Some(gtk::LEVEL_BAR_OFFSET_HIGH));
+    bar.remove_offset_value(Some(gtk::LEVEL_BAR_OFFSET_FULL));
+
+    let container = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .visible(false)
+        .build();
+    container.append(&label);
+    container.append(&bar);
+    (container, bar)
+}
@@ -0,0 +1,660 @@
+use gtk::prelude::*;
+use gtk::{cairo, glib, ApplicationWindow, Box, DrawingArea, DropDown, EventControllerMotion,
+          EventControllerScroll, EventControllerScrollFlags, GestureClick, GestureDrag, Label,
+          Orientation, SpinButton, StringList, ToggleButton};
+use glib::Propagation;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::{Rc, Weak};
+
+use crate::link_index::{LinkIndex, NoteSummary};
+use crate::markdown;
+
+// Ideal edge length of the force-directed layout, in graph units
+const SPRING_LENGTH: f64 = 80.0;
+// Pull towards the origin so unconnected notes don't drift away
+const GRAVITY: f64 = 0.02;
+// Layout stops once nodes move less than this per step
+const MIN_TEMPERATURE: f64 = 0.3;
+const COOLING: f64 = 0.97;
+const MIN_ZOOM: f64 = 0.1;
+const MAX_ZOOM: f64 = 5.0;
+// Labels are hidden below this zoom, except for the active and hovered notes
+const LABEL_ZOOM: f64 = 0.7;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EdgeKind {
+    Link,
+    SharedTag,
+}
+
+struct GraphNode {
+    title: String,
+    x: f64,
+    y: f64,
+    degree: usize,
+}
+
+impl GraphNode {
+    fn radius(&self) -> f64 {
+        4.0 + (self.degree as f64).sqrt() * 2.0
+    }
+}
+
+/// Which notes to show
+#[derive(Clone, Default)]
+struct GraphOptions {
+    /// Show only the neighbourhood of `center` instead of every note
+    local: bool,
+    depth: usize,
+    center: Option<String>,
+    tag: Option<String>,
+    folder: Option<String>,
+}
+
+#[derive(Default)]
+struct GraphState {
+    nodes: Vec<GraphNode>,
+    edges: Vec<(usize, usize, EdgeKind)>,
+    options: GraphOptions,
+    zoom: f64,
+    pan: (f64, f64),
+    temperature: f64,
+    layout_running: bool,
+    hovered: Option<usize>,
+    /// Node being dragged, or None while panning
+    dragged: Option<usize>,
+    drag_origin: (f64, f64),
+}
+
+/// Window drawing the link network between notes
+#[derive(Clone)]
+struct GraphView {
+    drawing_area: DrawingArea,
+    state: Rc<RefCell<GraphState>>,
+    index: LinkIndex,
+    parent: ApplicationWindow,
+}
+
+/// Reference to a `GraphView` that does not keep its window alive
+struct WeakGraphView {
+    drawing_area: glib::WeakRef<DrawingArea>,
+    state: Weak<RefCell<GraphState>>,
+    index: LinkIndex,
+    parent: glib::WeakRef<ApplicationWindow>,
+}
+
+impl WeakGraphView {
+    fn upgrade(&self) -> Option<GraphView> {
+        Some(GraphView {
+            drawing_area: self.drawing_area.upgrade()?,
+            state: self.state.upgrade()?,
+            index: self.index.clone(),
+            parent: self.parent.upgrade()?,
+        })
+    }
+}
+
+/// Open the graph view. `active_title` is the note the local graph is centred on.
+pub fn show_graph_view(parent: &ApplicationWindow, index: &LinkIndex, active_title: Option<String>) {
+    let dialog = ApplicationWindow::builder()
+        .transient_for(parent)
+        .title("Graph")
+        .default_width(900)
+        .default_height(650)
+        .build();
+
+    let drawing_area = DrawingArea::builder()
+        .hexpand(true)
+        .vexpand(true)
+        .css_classes(vec!["graph-view"])
+        .build();
+
+    let summaries = index.summaries();
+    let view = GraphView {
+        drawing_area: drawing_area.clone(),
+        state: Rc::new(RefCell::new(GraphState {
+            options: GraphOptions {
+                local: active_title.is_some(),
+                depth: 1,
+                center: active_title.clone(),
+                ..GraphOptions::default()
+            },
+            zoom: 1.0,
+            ..GraphState::default()
+        })),
+        index: index.clone(),
+        parent: parent.clone(),
+    };
+
+    // --- Controls ---
+    let local_toggle = ToggleButton::builder()
+        .label("Local graph")
+        .active(active_title.is_some())
+        .sensitive(active_title.is_some())
+        .tooltip_text("Show only notes near the active note")
+        .build();
+    let depth_spin = SpinButton::with_range(1.0, 5.0, 1.0);
+    depth_spin.set_value(1.0);
+    depth_spin.set_sensitive(active_title.is_some());
+    depth_spin.set_tooltip_text(Some("Link depth around the active note"));
+
+    let tag_choice = FilterChoice::new("All tags", |tag| format!("#{}", tag), tag_values(&summaries));
+    let tag_dropdown = DropDown::new(Some(tag_choice.model.clone()), gtk::Expression::NONE);
+    let folder_choice = FilterChoice::new("All folders", |folder| if folder.is_empty() { "/".to_string() } else { folder.to_string() }, folder_values(&summaries));
+    let folder_dropdown = DropDown::new(Some(folder_choice.model.clone()), gtk::Expression::NONE);
+
+    let controls = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .margin_top(8)
+        .margin_bottom(8)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    controls.append(&local_toggle);
+    controls.append(&Label::new(Some("Depth")));
+    controls.append(&depth_spin);
+    controls.append(&tag_dropdown);
+    controls.append(&folder_dropdown);
+    let hint = Label::builder()
+        .label("Scroll to zoom, drag to pan, click a note to open it")
+        .hexpand(true)
+        .xalign(1.0)
+        .css_classes(vec!["dim-label"])
+        .build();
+    controls.append(&hint);
+
+    let view_for_local = view.clone();
+    let depth_spin_for_local = depth_spin.clone();
+    local_toggle.connect_toggled(move |toggle| {
+        depth_spin_for_local.set_sensitive(toggle.is_active());
+        view_for_local.state.borrow_mut().options.local = toggle.is_active();
+        view_for_local.rebuild();
+    });
+
+    let view_for_depth = view.clone();
+    depth_spin.connect_value_changed(move |spin| {
+        view_for_depth.state.borrow_mut().options.depth = spin.value() as usize;
+        view_for_depth.rebuild();
+    });
+
+    let view_for_tag = view.clone();
+    let tag_choice_for_select = tag_choice.clone();
+    tag_dropdown.connect_selected_notify(move |dropdown| {
+        view_for_tag.state.borrow_mut().options.tag = tag_choice_for_select.selected(dropdown);
+        view_for_tag.rebuild();
+    });
+
+    let view_for_folder = view.clone();
+    let folder_choice_for_select = folder_choice.clone();
+    folder_dropdown.connect_selected_notify(move |dropdown| {
+        view_for_folder.state.borrow_mut().options.folder = folder_choice_for_select.selected(dropdown);
+        view_for_folder.rebuild();
+    });
+
+    let content_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .build();
+    content_box.append(&controls);
+    content_box.append(&drawing_area);
+    dialog.set_child(Some(&content_box));
+
+    view.connect_signals();
+
+    // Follow the index while the window is open: new tags and folders become filters
+    let view_for_index = view.downgrade();
+    let tag_dropdown_for_index = tag_dropdown.downgrade();
+    let folder_dropdown_for_index = folder_dropdown.downgrade();
+    let listener_id = index.connect_changed(move || {
+        let view = match view_for_index.upgrade().filter(|view| view.drawing_area.is_mapped()) {
+            Some(view) => view,
+            None => return,
+        };
+        let summaries = view.index.summaries();
+        if let Some(dropdown) = tag_dropdown_for_index.upgrade() {
+            tag_choice.set_values(&dropdown, tag_values(&summaries));
+        }
+        if let Some(dropdown) = folder_dropdown_for_index.upgrade() {
+            folder_choice.set_values(&dropdown, folder_values(&summaries));
+        }
+        view.rebuild();
+    });
+    let index_for_close = index.clone();
+    dialog.connect_close_request(move |_| {
+        index_for_close.disconnect(listener_id);
+        Propagation::Proceed
+    });
+
+    view.rebuild();
+    dialog.present();
+}
+
+/// Tags of the notes, sorted, for the tag filter
+fn tag_values(summaries: &[NoteSummary]) -> Vec<String> {
+    let mut tags: Vec<String> = summaries.iter().flat_map(|note| note.tags.iter().cloned()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Folders of the notes, sorted, for the folder filter
+fn folder_values(summaries: &[NoteSummary]) -> Vec<String> {
+    let mut folders: Vec<String> = summaries.iter().map(|note| note.folder.clone()).collect();
+    folders.sort();
+    folders.dedup();
+    folders
+}
+
+/// Choices of a filter dropdown: "All …", then one per value
+#[derive(Clone)]
+struct FilterChoice {
+    model: StringList,
+    values: Rc<RefCell<Vec<String>>>,
+    /// How a value is shown in the dropdown
+    label: fn(&str) -> String,
+}
+
+impl FilterChoice {
+    fn new(all_label: &str, label: fn(&str) -> String, values: Vec<String>) -> Self {
+        let model = StringList::new(&[all_label]);
+        for value in &values {
+            model.append(&label(value));
+        }
+        FilterChoice { model, values: Rc::new(RefCell::new(values)), label }
+    }
+
+    /// The value chosen in the dropdown, or None for "All …"
+    fn selected(&self, dropdown: &DropDown) -> Option<String> {
+        let index = (dropdown.selected() as usize).checked_sub(1)?;
+        self.values.borrow().get(index).cloned()
+    }
+
+    /// Replace the values, keeping the chosen one selected while it is still there
+    fn set_values(&self, dropdown: &DropDown, values: Vec<String>) {
+        if *self.values.borrow() == values {
+            return;
+        }
+        let chosen = self.selected(dropdown);
+        let labels: Vec<String> = values.iter().map(|value| (self.label)(value)).collect();
+        let removed = self.values.borrow().len() as u32;
+        let position = chosen.and_then(|chosen| values.iter().position(|value| *value == chosen));
+        // The values change first, as replacing the labels can move the selection
+        *self.values.borrow_mut() = values;
+        self.model.splice(1, removed, &labels.iter().map(String::as_str).collect::<Vec<_>>());
+        dropdown.set_selected(position.map_or(0, |position| position as u32 + 1));
+    }
+}
+
+impl GraphView {
+    fn downgrade(&self) -> WeakGraphView {
+        WeakGraphView {
+            drawing_area: self.drawing_area.downgrade(),
+            state: Rc::downgrade(&self.state),
+            index: self.index.clone(),
+            parent: self.parent.downgrade(),
+        }
+    }
+
+    fn connect_signals(&self) {
+        let view = self.clone();
+        self.drawing_area.set_draw_func(move |_, cr, width, height| {
+            view.draw(cr, width as f64, height as f64);
+        });
+
+        // Zoom around the pointer
+        let pointer = Rc::new(RefCell::new((0.0, 0.0)));
+        let motion = EventControllerMotion::new();
+        let view = self.clone();
+        let pointer_for_motion = pointer.clone();
+        motion.connect_motion(move |_, x, y| {
+            *pointer_for_motion.borrow_mut() = (x, y);
+            let hovered = view.node_at(x, y);
+            if view.state.borrow().hovered != hovered {
+                view.state.borrow_mut().hovered = hovered;
+                view.drawing_area.queue_draw();
+            }
+        });
+        self.drawing_area.add_controller(motion);
+
+        let scroll = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+        let view = self.clone();
+        scroll.connect_scroll(move |_, _dx, dy| {
+            let (x, y) = *pointer.borrow();
+            let (center_x, center_y) = view.center();
+            let mut state = view.state.borrow_mut();
+            let factor = if dy < 0.0 { 1.1 } else { 1.0 / 1.1 };
+            let zoom = (state.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+            // Keep the graph point under the pointer in place
+            let graph_x = (x - center_x - state.pan.0) / state.zoom;
+            let graph_y = (y - center_y - state.pan.1) / state.zoom;
+            state.pan = (x - center_x - graph_x * zoom, y - center_y - graph_y * zoom);
+            state.zoom = zoom;
+            drop(state);
+            view.drawing_area.queue_draw();
+            Propagation::Stop
+        });
+        self.drawing_area.add_controller(scroll);
+
+        // Drag a node to move it, or the background to pan
+        let drag = GestureDrag::new();
+        let view = self.clone();
+        drag.connect_drag_begin(move |_, x, y| {
+            let node = view.node_at(x, y);
+            let mut state = view.state.borrow_mut();
+            state.dragged = node;
+            state.drag_origin = match node {
+                Some(i) => (state.nodes[i].x, state.nodes[i].y),
+                None => state.pan,
+            };
+        });
+        let view = self.clone();
+        drag.connect_drag_update(move |_, offset_x, offset_y| {
+            let mut state = view.state.borrow_mut();
+            let (origin_x, origin_y) = state.drag_origin;
+            match state.dragged {
+                Some(i) => {
+                    let zoom = state.zoom;
+                    state.nodes[i].x = origin_x + offset_x / zoom;
+                    state.nodes[i].y = origin_y + offset_y / zoom;
+                }
+                None => state.pan = (origin_x + offset_x, origin_y + offset_y),
+            }
+            drop(state);
+            view.drawing_area.queue_draw();
+        });
+        self.drawing_area.add_controller(drag);
+
+        // Click opens the note (and re-centres the local graph on it)
+        let click = GestureClick::new();
+        let view = self.clone();
+        click.connect_released(move |_, _n_press, x, y| {
+            let title = match view.node_at(x, y) {
+                Some(i) => view.state.borrow().nodes[i].title.clone(),
+                None => return,
+            };
+            if let Err(e) = WidgetExt::activate_action(&view.parent, "win.open-note", Some(&title.to_variant())) {
+                eprintln!("Error opening note {}: {}", title, e);
+            }
+            let local = view.state.borrow().options.local;
+            view.state.borrow_mut().options.center = Some(title);
+            if local {
+                view.rebuild();
+            } else {
+                view.drawing_area.queue_draw();
+            }
+        });
+        self.drawing_area.add_controller(click);
+    }
+
+    /// Rebuild nodes and edges from the index and restart the layout
+    fn rebuild(&self) {
+        let options = self.state.borrow().options.clone();
+        let (nodes, edges) = build_graph(&self.index.summaries(), &options);
+
+        let mut state = self.state.borrow_mut();
+        // Keep positions of notes that were already shown so updates don't jump
+        let previous: HashMap<String, (f64, f64)> = state.nodes.iter()
+            .map(|node| (node.title.clone(), (node.x, node.y)))
+            .collect();
+        state.nodes = nodes;
+        for node in state.nodes.iter_mut() {
+            if let Some(&(x, y)) = previous.get(&node.title) {
+                node.x = x;
+                node.y = y;
+            }
+        }
+        state.edges = edges;
+        state.hovered = None;
+        state.dragged = None;
+        state.temperature = SPRING_LENGTH;
+        let start_ticking = !state.layout_running;
+        state.layout_running = true;
+        drop(state);
+
+        if start_ticking {
+            let state = self.state.clone();
+            self.drawing_area.add_tick_callback(move |area, _clock| {
+                let mut state = state.borrow_mut();
+                layout_step(&mut state);
+                area.queue_draw();
+                if state.temperature < MIN_TEMPERATURE {
+                    state.layout_running = false;
+                    glib::ControlFlow::Break
+                } else {
+                    glib::ControlFlow::Continue
+                }
+            });
+        }
+        self.drawing_area.queue_draw();
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (self.drawing_area.width() as f64 / 2.0, self.drawing_area.height() as f64 / 2.0)
+    }
+
+    /// Node under a point in widget coordinates
+    fn node_at(&self, x: f64, y: f64) -> Option<usize> {
+        let (center_x, center_y) = self.center();
+        let state = self.state.borrow();
+        let graph_x = (x - center_x - state.pan.0) / state.zoom;
+        let graph_y = (y - center_y - state.pan.1) / state.zoom;
+        // Last drawn is on top
+        state.nodes.iter().rposition(|node| {
+            let hit_radius = node.radius() + 3.0 / state.zoom;
+            (node.x - graph_x).powi(2) + (node.y - graph_y).powi(2) <= hit_radius.powi(2)
+        })
+    }
+
+    fn draw(&self, cr: &cairo::Context, width: f64, height: f64) {
+        let state = self.state.borrow();
+        let dark = self.parent.has_css_class("dark-mode");
+        let (background, foreground) = if dark { (0.12, 0.85) } else { (0.98, 0.2) };
+
+        cr.set_source_rgb(background, background, background);
+        cr.paint().ok();
+
+        if state.nodes.is_empty() {
+            cr.set_source_rgb(foreground, foreground, foreground);
+            cr.set_font_size(14.0);
+            cr.move_to(20.0, 30.0);
+            cr.show_text("No notes to show").ok();
+            return;
+        }
+
+        let to_screen = |x: f64, y: f64| (
+            width / 2.0 + state.pan.0 + x * state.zoom,
+            height / 2.0 + state.pan.1 + y * state.zoom,
+        );
+
+        // Edges: links solid, shared tags dashed and fainter
+        cr.set_line_width(1.0);
+        for &(from, to, kind) in &state.edges {
+            let (x1, y1) = to_screen(state.nodes[from].x, state.nodes[from].y);
+            let (x2, y2) = to_screen(state.nodes[to].x, state.nodes[to].y);
+            match kind {
+                EdgeKind::Link => {
+                    cr.set_dash(&[], 0.0);
+                    cr.set_source_rgba(foreground, foreground, foreground, 0.35);
+                }
+                EdgeKind::SharedTag => {
+                    cr.set_dash(&[4.0, 4.0], 0.0);
+                    cr.set_source_rgba(0.6, 0.4, 0.9, 0.3);
+                }
+            }
+            cr.move_to(x1, y1);
+            cr.line_to(x2, y2);
+            cr.stroke().ok();
+        }
+        cr.set_dash(&[], 0.0);
+
+        // Nodes, with the active and hovered notes highlighted
+        cr.set_font_size(12.0);
+        for (i, node) in state.nodes.iter().enumerate() {
+            let (x, y) = to_screen(node.x, node.y);
+            let is_active = state.options.center.as_deref() == Some(node.title.as_str());
+            let is_hovered = state.hovered == Some(i);
+
+            if is_active {
+                cr.set_source_rgb(0.21, 0.52, 0.89);
+            } else if is_hovered {
+                cr.set_source_rgb(0.95, 0.6, 0.2);
+            } else {
+                cr.set_source_rgba(foreground, foreground, foreground, 0.75);
+            }
+            cr.arc(x, y, node.radius() * state.zoom.sqrt(), 0.0, std::f64::consts::TAU);
+            cr.fill().ok();
+
+            if state.zoom >= LABEL_ZOOM || is_active || is_hovered {
+                cr.set_source_rgba(foreground, foreground, foreground, if is_hovered { 1.0 } else { 0.8 });
+                let offset = node.radius() * state.zoom.sqrt() + 4.0;
+                if let Ok(extents) = cr.text_extents(&node.title) {
+                    cr.move_to(x - extents.width() / 2.0, y + offset + extents.height());
+                    cr.show_text(&node.title).ok();
+                }
+            }
+        }
+    }
+}
+
+/// Nodes and edges for the notes selected by `options`
+fn build_graph(notes: &[NoteSummary], options: &GraphOptions) -> (Vec<GraphNode>, Vec<(usize, usize, EdgeKind)>) {
+    let notes: Vec<&NoteSummary> = notes.iter()
+        .filter(|note| options.tag.as_ref().is_none_or(|tag| note.tags.contains(tag)))
+        .filter(|note| options.folder.as_ref().is_none_or(|folder| {
+            note.folder == *folder || note.folder.starts_with(&format!("{}/", folder))
+        }))
+        .collect();
+
+    // Edges between all filtered notes; each pair once
+    let by_title: HashMap<String, usize> = notes.iter()
+        .enumerate()
+        .map(|(i, note)| (markdown::title_key(&note.title), i))
+        .collect();
+    let mut edge_set: HashSet<(usize, usize)> = HashSet::new();
+    let mut edges = Vec::new();
+    let mut add_edge = |a: usize, b: usize, kind: EdgeKind| {
+        let key = (a.min(b), a.max(b));
+        if a != b && edge_set.insert(key) {
+            edges.push((key.0, key.1, kind));
+        }
+    };
+    for (i, note) in notes.iter().enumerate() {
+        for target in &note.links {
+            if let Some(&j) = by_title.get(target) {
+                add_edge(i, j, EdgeKind::Link);
+            }
+        }
+    }
+    let mut by_tag: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, note) in notes.iter().enumerate() {
+        for tag in &note.tags {
+            by_tag.entry(tag.as_str()).or_default().push(i);
+        }
+    }
+    for members in by_tag.values() {
+        for (n, &a) in members.iter().enumerate() {
+            for &b in &members[n + 1..] {
+                add_edge(a, b, EdgeKind::SharedTag);
+            }
+        }
+    }
+
+    // Local mode keeps notes within `depth` edges of the centre
+    let center = options.center.as_ref().and_then(|title| by_title.get(&markdown::title_key(title)).copied());
+    let keep: Vec<bool> = match (options.local, center) {
+        (true, Some(center)) => {
+            let mut distance: Vec<Option<usize>> = vec![None; notes.len()];
+            distance[center] = Some(0);
+            let mut queue = VecDeque::from([center]);
+            while let Some(current) = queue.pop_front() {
+                let next_distance = distance[current].unwrap_or(0) + 1;
+                if next_distance > options.depth {
+                    continue;
+                }
+                for &(a, b, _) in &edges {
+                    let neighbour = if a == current { b } else if b == current { a } else { continue };
+                    if distance[neighbour].is_none() {
+                        distance[neighbour] = Some(next_distance);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            distance.iter().map(Option::is_some).collect()
+        }
+        (true, None) => vec![false; notes.len()],
+        (false, _) => vec![true; notes.len()],
+    };
+
+    // Renumber the kept notes
+    let mut new_index = vec![usize::MAX; notes.len()];
+    let mut nodes = Vec::new();
+    for (i, note) in notes.iter().enumerate() {
+        if keep[i] {
+            new_index[i] = nodes.len();
+            // Start on a golden-angle spiral so the layout is deterministic
+            let angle = nodes.len() as f64 * 2.399_963;
+            let distance = SPRING_LENGTH * (nodes.len() as f64).sqrt();
+            nodes.push(GraphNode { title: note.title.clone(), x: distance * angle.cos(), y: distance * angle.sin(), degree: 0 });
+        }
+    }
+    let edges: Vec<(usize, usize, EdgeKind)> = edges.into_iter()
+        .filter(|&(a, b, _)| keep[a] && keep[b])
+        .map(|(a, b, kind)| (new_index[a], new_index[b], kind))
+        .collect();
+    for &(a, b, _) in &edges {
+        nodes[a].degree += 1;
+        nodes[b].degree += 1;
+    }
+
+    (nodes, edges)
+}
+
+/// One Fruchterman-Reingold step: nodes repel each other, edges pull like springs
+fn layout_step(state: &mut GraphState) {
+    let count = state.nodes.len();
+    let mut displacement = vec![(0.0, 0.0); count];
+    let k_squared = SPRING_LENGTH * SPRING_LENGTH;
+
+    for i in 0..count {
+        for j in (i + 1)..count {
+            let dx = state.nodes[i].x - state.nodes[j].x;
+            let dy = state.nodes[i].y - state.nodes[j].y;
+            let distance_squared = (dx * dx + dy * dy).max(0.01);
+            let force = k_squared / distance_squared;
+            displacement[i].0 += dx * force;
+            displacement[i].1 += dy * force;
+            displacement[j].0 -= dx * force;
+            displacement[j].1 -= dy * force;
+        }
+    }
+
+    for &(a, b, kind) in &state.edges {
+        let dx = state.nodes[a].x - state.nodes[b].x;
+        let dy = state.nodes[a].y - state.nodes[b].y;
+        let distance = (dx * dx + dy * dy).sqrt().max(0.1);
+        // Shared tags pull more weakly than links
+        let strength = if kind == EdgeKind::Link { 1.0 } else { 0.3 };
+        let force = distance / SPRING_LENGTH * strength;
+        displacement[a].0 -= dx * force;
+        displacement[a].1 -= dy * force;
+        displacement[b].0 += dx * force;
+        displacement[b].1 += dy * force;
+    }
+
+    for (i, node) in state.nodes.iter_mut().enumerate() {
+        if state.dragged == Some(i) {
+            continue; // The pointer holds it
+        }
+        let (mut dx, mut dy) = displacement[i];
+        dx -= node.x * GRAVITY * SPRING_LENGTH;
+        dy -= node.y * GRAVITY * SPRING_LENGTH;
+        let length = (dx * dx + dy * dy).sqrt();
+        if length > 0.0 {
+            let step = length.min(state.temperature);
+            node.x += dx / length * step;
+            node.y += dy / length * step;
+        }
+    }
+    state.temperature *= COOLING;
+}
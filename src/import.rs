@@ -0,0 +1,695 @@
+// Importers for notes kept in other apps: an Obsidian vault, a Simplenote export, an
+// Evernote notebook (.enex), a Standard Notes backup or a folder of text files.
+// Every note is created through the note layer, in one folder of the notes directory,
+// under a title no other note has. Its creation date goes in a `created:` front matter
+// field, its modification date on the file, its tags in `tags:` and its attached files
+// next to it, linked from the text.
+
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use crate::enex;
+use crate::frontmatter;
+use crate::markdown::{link_destination, title_key};
+use crate::note::Note;
+
+/// Where notes are imported from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Obsidian,
+    Simplenote,
+    Evernote,
+    StandardNotes,
+    TextFiles,
+}
+
+impl Source {
+    pub const ALL: [Source; 5] = [Source::Obsidian, Source::Simplenote, Source::Evernote, Source::StandardNotes, Source::TextFiles];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Obsidian => "Obsidian vault",
+            Source::Simplenote => "Simplenote",
+            Source::Evernote => "Evernote notebook (.enex)",
+            Source::StandardNotes => "Standard Notes backup",
+            Source::TextFiles => "Folder of text files",
+        }
+    }
+
+    /// What to choose, shown in the import dialog
+    pub fn description(self) -> &'static str {
+        match self {
+            Source::Obsidian => "Choose the vault folder. Notes keep their folders, and images and other files are copied next to them.",
+            Source::Simplenote => "Choose the .zip from Export Notes in Simplenote's settings, or the notes.json inside it. Notes in the trash are left out.",
+            Source::Evernote => "Choose an .enex file exported from a notebook. Notes are converted to Markdown, with their attachments.",
+            Source::StandardNotes => "Choose a decrypted backup (.zip or .txt) from Backups in Standard Notes' preferences. Encrypted backups cannot be read.",
+            Source::TextFiles => "Choose a folder. Each .txt file in it and its subfolders becomes a note.",
+        }
+    }
+
+    /// Whether the source is a folder rather than a file
+    pub fn is_folder(self) -> bool {
+        matches!(self, Source::Obsidian | Source::TextFiles)
+    }
+
+    /// File name patterns offered when choosing the file
+    pub fn patterns(self) -> &'static [&'static str] {
+        match self {
+            Source::Simplenote => &["*.zip", "*.json"],
+            Source::Evernote => &["*.enex"],
+            Source::StandardNotes => &["*.zip", "*.txt", "*.json"],
+            Source::Obsidian | Source::TextFiles => &[],
+        }
+    }
+
+    /// Folder the notes go into, named after what is imported
+    pub fn default_folder(self, path: &Path) -> String {
+        let name = if self.is_folder() { path.file_name() } else { path.file_stem() };
+        match self {
+            Source::Simplenote => "Simplenote".to_string(),
+            Source::StandardNotes => "Standard Notes".to_string(),
+            _ => name.map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+/// What an import did
+#[derive(Default)]
+pub struct Report {
+    /// Titles of the imported notes
+    pub imported: Vec<String>,
+    /// Number of attached files copied
+    pub attachments: usize,
+    /// Notes imported under another title, because theirs was taken or cannot be a
+    /// file name: (original title, new title)
+    pub renamed: Vec<(String, String)>,
+    /// Notes and files left out, and why
+    pub skipped: Vec<(String, String)>,
+}
+
+impl Report {
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Imported {} {}", self.imported.len(), if self.imported.len() == 1 { "note" } else { "notes" });
+        if self.attachments > 0 {
+            summary.push_str(&format!(" and {} {}", self.attachments, if self.attachments == 1 { "attachment" } else { "attachments" }));
+        }
+        summary.push('.');
+        if !self.skipped.is_empty() {
+            summary.push_str(&format!(" {} skipped.", self.skipped.len()));
+        }
+        summary
+    }
+
+    /// Every skipped, renamed and imported note, one per line
+    pub fn details(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.skipped.is_empty() {
+            lines.push("Skipped:".to_string());
+            lines.extend(self.skipped.iter().map(|(name, reason)| format!("  {} ({})", name, reason)));
+            lines.push(String::new());
+        }
+        if !self.renamed.is_empty() {
+            lines.push("Renamed:".to_string());
+            lines.extend(self.renamed.iter().map(|(old, new)| format!("  {} → {}", old, new)));
+            lines.push(String::new());
+        }
+        if !self.imported.is_empty() {
+            lines.push("Imported:".to_string());
+            lines.extend(self.imported.iter().map(|title| format!("  {}", title)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// A note read from the source, before it is written
+struct NewNote {
+    title: String,
+    /// Folder inside the import folder ("" for the import folder itself)
+    folder: String,
+    content: String,
+    created: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    tags: Vec<String>,
+}
+
+/// A title usable as a file name and as a `[[link]]` target: its first line, without
+/// path separators and the characters links use, at most 100 characters
+fn clean_title(title: &str) -> String {
+    let first_line = title.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+    let cleaned: String = first_line.chars()
+        .filter(|c| !c.is_control() && !matches!(c, '[' | ']' | '|' | '#' | '^'))
+        .map(|c| if matches!(c, '/' | '\\') { '-' } else { c })
+        .take(100)
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim();
+    if cleaned.is_empty() { "Untitled".to_string() } else { cleaned.to_string() }
+}
+
+/// A tag as it could be typed inline: no spaces, commas or brackets
+fn clean_tag(tag: &str) -> String {
+    tag.trim()
+        .trim_start_matches('#')
+        .chars()
+        .filter(|c| !matches!(c, ',' | '[' | ']' | '"' | '\''))
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .collect()
+}
+
+/// A folder path inside the notes directory, without empty, `.` or `..` parts
+fn clean_folder(folder: &str) -> String {
+    folder.split(['/', '\\'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text.trim())
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Creation and modification times of a file, where the file system keeps them
+fn file_dates(path: &Path) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    match fs::metadata(path) {
+        Ok(metadata) => (
+            metadata.created().ok().map(DateTime::from),
+            metadata.modified().ok().map(DateTime::from),
+        ),
+        Err(_) => (None, None),
+    }
+}
+
+fn is_image(name: &str) -> bool {
+    let extension = name.rsplit_once('.').map(|(_, extension)| extension.to_lowercase()).unwrap_or_default();
+    matches!(extension.as_str(), "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "bmp" | "avif")
+}
+
+/// Markdown showing an attached file: the image, or a link to any other file
+fn attachment_markdown(label: &str, dest: &str) -> String {
+    let label = label.replace(['[', ']'], "");
+    if is_image(dest) {
+        format!("![{}]({})", label, link_destination(dest))
+    } else {
+        format!("[{}]({})", label, link_destination(dest))
+    }
+}
+
+/// Paths of the files under a folder relative to `root`, skipping hidden files and
+/// folders (such as `.obsidian` and `.trash`)
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read folder {:?}: {}", dir, e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')))
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+/// Text of an export file, or of the first file inside an export archive that
+/// `is_export` accepts
+fn read_export(path: &Path, is_export: impl Fn(&str) -> bool) -> Result<String, String> {
+    if !path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("zip")) {
+        return fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e));
+    }
+    let file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let name = archive.file_names()
+        .find(|name| is_export(name))
+        .map(str::to_string)
+        .ok_or_else(|| format!("{:?} does not hold a notes export.", path))?;
+    let mut text = String::new();
+    archive.by_name(&name)
+        .map_err(|e| e.to_string())
+        .and_then(|mut entry| entry.read_to_string(&mut text).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Failed to read {} from {:?}: {}", name, path, e))?;
+    Ok(text)
+}
+
+/// Writes the imported notes and files and keeps the report
+struct Importer<'a> {
+    /// Folder of the notes directory everything goes into
+    folder: String,
+    /// Titles of every note, case-folded, so each new one gets a free title even on
+    /// file systems that ignore case
+    titles: HashSet<String>,
+    /// Imported notes and their modification times, to restore if they are rewritten
+    written: Vec<(PathBuf, Option<SystemTime>)>,
+    report: Report,
+    /// Number of notes imported so far, for a progress display on another thread
+    progress: &'a AtomicUsize,
+}
+
+impl<'a> Importer<'a> {
+    fn new(folder: &str, progress: &'a AtomicUsize) -> Result<Self, String> {
+        let titles = Note::get_all()?.into_iter().map(|note| title_key(&note.title)).collect();
+        Ok(Importer {
+            folder: clean_folder(folder),
+            titles,
+            written: Vec::new(),
+            report: Report::default(),
+            progress,
+        })
+    }
+
+    /// Folder of the notes directory for a folder of the source
+    fn notes_folder(&self, folder: &str) -> String {
+        clean_folder(&format!("{}/{}", self.folder, folder))
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        self.report.skipped.push((name.to_string(), reason.to_string()));
+    }
+
+    fn add_note(&mut self, note: NewNote) {
+        let base = clean_title(&note.title);
+        let title = Note::unique_title_among(&base, |title| self.titles.contains(&title_key(title)));
+        let original = note.title.lines().next().unwrap_or("").trim();
+        if title != original && !original.is_empty() {
+            self.report.renamed.push((original.to_string(), title.clone()));
+        }
+
+        let mut content = note.content;
+        if let Some(created) = note.created {
+            if frontmatter::parse(&content).get("created").is_none() {
+                let created = created.with_timezone(&Local).to_rfc3339_opts(SecondsFormat::Secs, false);
+                content = frontmatter::set_field(&content, "created", Some(&created));
+            }
+        }
+        if !note.tags.is_empty() {
+            let mut tags = frontmatter::parse(&content).get_list("tags").to_vec();
+            for tag in note.tags.iter().map(|tag| clean_tag(tag)) {
+                if !tag.is_empty() && !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            content = frontmatter::set_field(&content, "tags", Some(&format!("[{}]", tags.join(", "))));
+        }
+
+        let modified = note.modified.map(SystemTime::from);
+        let created = Note::new_in_folder(&self.notes_folder(&note.folder), &title).and_then(|mut new_note| {
+            new_note.content = content;
+            new_note.save()?;
+            if let Some(modified) = modified {
+                new_note.set_modified_time(modified)?;
+            }
+            Ok(new_note.path)
+        });
+        match created {
+            Ok(path) => {
+                self.titles.insert(title_key(&title));
+                self.written.push((path, modified));
+                self.report.imported.push(title);
+                self.progress.store(self.report.imported.len(), Ordering::Relaxed);
+            }
+            Err(e) => self.skip(original, &e),
+        }
+    }
+
+    /// Write a file attached to notes in `folder` of the source to its `attachments`
+    /// folder, under a name no other file there has. Returns its path from the notes.
+    fn add_attachment(&mut self, folder: &str, name: &str, data: &[u8]) -> Result<String, String> {
+        let dir = crate::utils::get_notes_dir().join(self.notes_folder(folder)).join("attachments");
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create folder {:?}: {}", dir, e))?;
+        let name = name.replace(['/', '\\'], "-");
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => (stem.to_string(), format!(".{}", extension)),
+            _ => (name.clone(), String::new()),
+        };
+        let mut file_name = name.clone();
+        let mut number = 1;
+        while dir.join(&file_name).exists() {
+            number += 1;
+            file_name = format!("{} ({}){}", stem, number, extension);
+        }
+        fs::write(dir.join(&file_name), data)
+            .map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+        self.report.attachments += 1;
+        Ok(format!("attachments/{}", file_name))
+    }
+
+    /// Copy a file of the source to the same place in the import folder
+    fn copy_file(&mut self, source: &Path, relative: &str) {
+        let destination = crate::utils::get_notes_dir().join(&self.folder).join(relative);
+        if destination.exists() {
+            self.skip(relative, "a file with this name is already there");
+            return;
+        }
+        let copied = destination.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::copy(source, &destination));
+        match copied {
+            Ok(_) => self.report.attachments += 1,
+            Err(e) => self.skip(relative, &format!("Failed to copy: {}", e)),
+        }
+    }
+
+    /// Point `[[links]]` between the imported notes at the new titles of renamed notes
+    fn finish(self) -> Report {
+        if self.report.renamed.is_empty() {
+            return self.report;
+        }
+        for (path, modified) in &self.written {
+            let mut note = match Note::load(path) {
+                Ok(note) => note,
+                Err(e) => {
+                    eprintln!("Error loading imported note {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            let mut content = note.content.clone();
+            for (old, new) in &self.report.renamed {
+                if let Some(renamed) = crate::markdown::rename_wiki_links(&content, old, new) {
+                    content = renamed;
+                }
+            }
+            if content == note.content {
+                continue;
+            }
+            note.content = content;
+            let saved = note.save().and_then(|_| match modified {
+                Some(modified) => note.set_modified_time(*modified),
+                None => Ok(()),
+            });
+            if let Err(e) = saved {
+                eprintln!("Failed to update links in imported note {:?}: {}", path, e);
+            }
+        }
+        self.report
+    }
+}
+
+/// Import notes into `folder` of the notes directory ("" for the top level).
+/// `progress` counts the notes written so far while the import runs.
+pub fn import(source: Source, path: &Path, folder: &str, progress: &AtomicUsize) -> Result<Report, String> {
+    let mut importer = Importer::new(folder, progress)?;
+    match source {
+        Source::Obsidian => import_obsidian(&mut importer, path)?,
+        Source::Simplenote => import_simplenote(&mut importer, path)?,
+        Source::Evernote => import_evernote(&mut importer, path)?,
+        Source::StandardNotes => import_standard_notes(&mut importer, path)?,
+        Source::TextFiles => import_text_files(&mut importer, path)?,
+    }
+    Ok(importer.finish())
+}
+
+/// Obsidian's `![[file]]` embeds of images and other files as Markdown links from
+/// the note at `note_path`. Embedded notes stay as they are.
+fn convert_embeds(content: &str, note_path: &str, files: &[String]) -> String {
+    let mut converted = String::with_capacity(content.len());
+    let mut copied = 0;
+    for link in crate::markdown::wiki_links(content) {
+        if !content[..link.start].ends_with('!') {
+            continue;
+        }
+        // Obsidian finds a file by its path in the vault or, usually, by its name
+        let target = link.target.trim();
+        let file = files.iter()
+            .find(|file| *file == target)
+            .or_else(|| files.iter().find(|file| file.ends_with(&format!("/{}", target))));
+        let file = match file {
+            Some(file) => file,
+            None => continue,
+        };
+        let name = file.rsplit('/').next().unwrap_or(file);
+        // An image's label may be its display size, such as `|300` or `|300x200`
+        let is_size = link.label.chars().all(|c| c.is_ascii_digit() || c == 'x');
+        let label = if link.label == link.target || is_size { name } else { link.label.as_str() };
+        let dest = crate::export::relative_path(note_path, file);
+        converted.push_str(&content[copied..link.start - 1]);
+        converted.push_str(&attachment_markdown(label, &dest));
+        copied = link.end;
+    }
+    converted.push_str(&content[copied..]);
+    converted
+}
+
+fn import_obsidian(importer: &mut Importer, vault: &Path) -> Result<(), String> {
+    let mut files = Vec::new();
+    collect_files(vault, vault, &mut files)?;
+    let (notes, attachments): (Vec<String>, Vec<String>) = files.into_iter()
+        .partition(|file| file.to_lowercase().ends_with(".md"));
+
+    // Other files keep their place in the vault, so relative links to them keep working
+    for relative in &attachments {
+        importer.copy_file(&vault.join(relative), relative);
+    }
+
+    for relative in &notes {
+        let path = vault.join(relative);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                importer.skip(relative, &format!("Failed to read: {}", e));
+                continue;
+            }
+        };
+        let (created, modified) = file_dates(&path);
+        let (folder, file_name) = relative.rsplit_once('/').unwrap_or(("", relative));
+        importer.add_note(NewNote {
+            title: file_name[..file_name.len() - 3].to_string(),
+            folder: folder.to_string(),
+            content: convert_embeds(&content, relative, &attachments),
+            created,
+            modified,
+            tags: Vec::new(),
+        });
+    }
+    Ok(())
+}
+
+fn import_text_files(importer: &mut Importer, folder: &Path) -> Result<(), String> {
+    let mut files = Vec::new();
+    collect_files(folder, folder, &mut files)?;
+    for relative in files {
+        let (subfolder, file_name) = relative.rsplit_once('/').unwrap_or(("", &relative));
+        let title = match file_name.rsplit_once('.') {
+            Some((stem, extension)) if extension.eq_ignore_ascii_case("txt") && !stem.is_empty() => stem,
+            _ => {
+                importer.skip(&relative, "not a .txt file");
+                continue;
+            }
+        };
+        let path = folder.join(&relative);
+        let text = match fs::read(&path).map(String::from_utf8) {
+            Ok(Ok(text)) => text,
+            Ok(Err(_)) => {
+                importer.skip(&relative, "not UTF-8 text");
+                continue;
+            }
+            Err(e) => {
+                importer.skip(&relative, &format!("Failed to read: {}", e));
+                continue;
+            }
+        };
+        let (created, modified) = file_dates(&path);
+        importer.add_note(NewNote {
+            title: title.to_string(),
+            folder: subfolder.to_string(),
+            content: text.trim_start_matches('\u{feff}').to_string(),
+            created,
+            modified,
+            tags: Vec::new(),
+        });
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimplenoteExport {
+    #[serde(default)]
+    active_notes: Vec<SimplenoteNote>,
+    #[serde(default)]
+    trashed_notes: Vec<SimplenoteNote>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimplenoteNote {
+    #[serde(default)]
+    content: String,
+    creation_date: Option<String>,
+    last_modified: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+fn import_simplenote(importer: &mut Importer, path: &Path) -> Result<(), String> {
+    let json = read_export(path, |name| name.ends_with("notes.json"))?;
+    let export: SimplenoteExport = serde_json::from_str(&json)
+        .map_err(|e| format!("This is not a Simplenote export: {}", e))?;
+    for note in export.active_notes {
+        // The first line of a Simplenote note is its title, often as a heading
+        let title = note.content.lines()
+            .map(|line| line.trim_start_matches('#').trim())
+            .find(|line| !line.is_empty())
+            .unwrap_or("")
+            .to_string();
+        importer.add_note(NewNote {
+            title,
+            folder: String::new(),
+            created: note.creation_date.as_deref().and_then(parse_date),
+            modified: note.last_modified.as_deref().and_then(parse_date),
+            content: note.content,
+            tags: note.tags,
+        });
+    }
+    for note in export.trashed_notes {
+        importer.skip(&clean_title(&note.content), "in the trash");
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct StandardNotesBackup {
+    items: Vec<StandardNotesItem>,
+}
+
+#[derive(Deserialize)]
+struct StandardNotesItem {
+    #[serde(default)]
+    uuid: String,
+    #[serde(default)]
+    content_type: String,
+    /// An object, or a string when the item is encrypted
+    #[serde(default)]
+    content: serde_json::Value,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    #[serde(default)]
+    deleted: bool,
+}
+
+fn import_standard_notes(importer: &mut Importer, path: &Path) -> Result<(), String> {
+    let json = read_export(path, |name| !name.contains('/') && (name.ends_with(".txt") || name.ends_with(".json")))?;
+    let backup: StandardNotesBackup = serde_json::from_str(&json)
+        .map_err(|e| format!("This is not a Standard Notes backup: {}", e))?;
+    let notes: Vec<&StandardNotesItem> = backup.items.iter()
+        .filter(|item| item.content_type == "Note" && !item.deleted)
+        .collect();
+    if !notes.is_empty() && notes.iter().all(|item| !item.content.is_object()) {
+        return Err("This backup is encrypted. Download a decrypted backup from Backups in Standard Notes' preferences.".to_string());
+    }
+
+    // Tags list the notes they are on
+    let mut note_tags: HashMap<&str, Vec<String>> = HashMap::new();
+    for tag in backup.items.iter().filter(|item| item.content_type == "Tag" && !item.deleted) {
+        let title = tag.content["title"].as_str().unwrap_or("");
+        for reference in tag.content["references"].as_array().into_iter().flatten() {
+            if let (Some(uuid), false) = (reference["uuid"].as_str(), title.is_empty()) {
+                note_tags.entry(uuid).or_default().push(title.to_string());
+            }
+        }
+    }
+
+    for item in notes {
+        let content = &item.content;
+        let text = content["text"].as_str().unwrap_or("");
+        let title = match content["title"].as_str().map(str::trim) {
+            Some(title) if !title.is_empty() => title.to_string(),
+            _ => clean_title(text),
+        };
+        if !content.is_object() {
+            importer.skip(&item.uuid, "encrypted");
+        } else if content["trashed"].as_bool() == Some(true) {
+            importer.skip(&title, "in the trash");
+        } else if content["noteType"].as_str() == Some("super") {
+            importer.skip(&title, "a Super note; export it as Markdown from Standard Notes");
+        } else {
+            let client_updated = content.pointer("/appData/org.standardnotes.sn/client_updated_at").and_then(|date| date.as_str());
+            importer.add_note(NewNote {
+                title,
+                folder: String::new(),
+                content: text.to_string(),
+                created: item.created_at.as_deref().and_then(parse_date),
+                modified: client_updated.or(item.updated_at.as_deref()).and_then(parse_date),
+                tags: note_tags.get(item.uuid.as_str()).cloned().unwrap_or_default(),
+            });
+        }
+    }
+
+    for file in backup.items.iter().filter(|item| item.content_type == "SN|File" && !item.deleted) {
+        let name = file.content["name"].as_str().unwrap_or(&file.uuid);
+        importer.skip(name, "files are not part of backups");
+    }
+    Ok(())
+}
+
+/// File extension for an attachment without a file name
+fn extension_for(mime: &str) -> String {
+    match mime.rsplit('/').next() {
+        Some("jpeg") => "jpg".to_string(),
+        Some(subtype) if !subtype.is_empty() && subtype.chars().all(char::is_alphanumeric) => subtype.to_string(),
+        _ => "bin".to_string(),
+    }
+}
+
+fn import_evernote(importer: &mut Importer, path: &Path) -> Result<(), String> {
+    let xml = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    for note in enex::parse(&xml)? {
+        // Attachments are written first, so the text can link to them
+        let mut attachments: Vec<(String, String)> = Vec::new();
+        for (index, resource) in note.resources.iter().enumerate() {
+            let name = resource.file_name.clone()
+                .unwrap_or_else(|| format!("attachment-{}.{}", index + 1, extension_for(&resource.mime)));
+            if let Some(e) = &resource.error {
+                importer.skip(&name, e);
+                continue;
+            }
+            match importer.add_attachment("", &name, &resource.data) {
+                Ok(dest) => attachments.push((resource.hash(), attachment_markdown(&name, &dest))),
+                Err(e) => importer.skip(&name, &e),
+            }
+        }
+
+        let mut shown: HashSet<String> = HashSet::new();
+        let mut content = enex::enml_to_markdown(&note.content, &mut |hash| {
+            let (_, markdown) = attachments.iter().find(|(known, _)| known == hash)?;
+            shown.insert(hash.to_string());
+            Some(markdown.clone())
+        });
+        // Attachments the text does not show are listed after it
+        let unshown: Vec<&String> = attachments.iter()
+            .filter(|(hash, _)| !shown.contains(hash))
+            .map(|(_, markdown)| markdown)
+            .collect();
+        if !unshown.is_empty() {
+            content.push('\n');
+            for markdown in unshown {
+                content.push_str(&format!("- {}\n", markdown));
+            }
+        }
+
+        importer.add_note(NewNote {
+            title: note.title,
+            folder: String::new(),
+            content,
+            created: note.created,
+            modified: note.updated,
+            tags: note.tags,
+        });
+    }
+    Ok(())
+}
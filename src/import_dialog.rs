@@ -0,0 +1,273 @@
+use gtk::prelude::*;
+use gtk::{gio, glib, ApplicationWindow, Box, Button, DropDown, Entry, FileDialog, FileFilter, Label, Orientation, ProgressBar, ScrolledWindow, Stack, TextView};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::import::{self, Source};
+use crate::utils::labelled_row;
+
+/// Walk through an import: which app the notes come from, its export and the folder
+/// they go into, then what was imported, renamed and skipped. `on_imported` runs once
+/// notes have been written.
+pub fn show_import_dialog(parent: &ApplicationWindow, on_imported: impl Fn() + 'static) {
+    let dialog = ApplicationWindow::builder()
+        .transient_for(parent)
+        .modal(true)
+        .title("Import Notes")
+        .default_width(480)
+        .css_classes(vec!["import-dialog"])
+        .build();
+
+    let stack = Stack::builder()
+        .transition_type(gtk::StackTransitionType::SlideLeft)
+        .build();
+
+    // --- First page: what to import ---
+    let choose_page = Box::builder()
+        .orientation(Orientation::Vertical)
+        .margin_start(20)
+        .margin_end(20)
+        .margin_top(20)
+        .margin_bottom(20)
+        .spacing(12)
+        .build();
+
+    let source_labels: Vec<&str> = Source::ALL.iter().map(|source| source.label()).collect();
+    let source_choice = DropDown::from_strings(&source_labels);
+    source_choice.set_hexpand(true);
+    choose_page.append(&labelled_row("From", &source_choice));
+
+    let description = Label::builder()
+        .label(Source::ALL[0].description())
+        .wrap(true)
+        .xalign(0.0)
+        .css_classes(vec!["dim-label"])
+        .build();
+    choose_page.append(&description);
+
+    let path_label = Label::builder()
+        .label("Nothing chosen")
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::Middle)
+        .build();
+    let choose_button = Button::builder()
+        .label("Choose…")
+        .build();
+    let path_row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .hexpand(true)
+        .build();
+    path_row.append(&path_label);
+    path_row.append(&choose_button);
+    choose_page.append(&labelled_row("Export", &path_row));
+
+    let folder_entry = Entry::builder()
+        .placeholder_text("Top level")
+        .hexpand(true)
+        .build();
+    choose_page.append(&labelled_row("Into Folder", &folder_entry));
+
+    let button_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(gtk::Align::End)
+        .spacing(8)
+        .margin_top(8)
+        .build();
+    let cancel_button = Button::builder()
+        .label("Cancel")
+        .build();
+    let import_button = Button::builder()
+        .label("Import")
+        .css_classes(vec!["suggested-action"])
+        .sensitive(false)
+        .build();
+    button_box.append(&cancel_button);
+    button_box.append(&import_button);
+    choose_page.append(&button_box);
+
+    // --- Second page: progress while the import runs ---
+    let progress_page = Box::builder()
+        .orientation(Orientation::Vertical)
+        .margin_start(20)
+        .margin_end(20)
+        .margin_top(20)
+        .margin_bottom(20)
+        .spacing(12)
+        .valign(gtk::Align::Center)
+        .build();
+    let progress_label = Label::builder()
+        .label("Importing…")
+        .xalign(0.0)
+        .build();
+    progress_page.append(&progress_label);
+    let progress_bar = ProgressBar::new();
+    progress_page.append(&progress_bar);
+
+    // --- Third page: the report ---
+    let report_page = Box::builder()
+        .orientation(Orientation::Vertical)
+        .margin_start(20)
+        .margin_end(20)
+        .margin_top(20)
+        .margin_bottom(20)
+        .spacing(12)
+        .build();
+    let summary_label = Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .css_classes(vec!["heading"])
+        .build();
+    report_page.append(&summary_label);
+    let details_view = TextView::builder()
+        .editable(false)
+        .cursor_visible(false)
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .build();
+    let details_window = ScrolledWindow::builder()
+        .child(&details_view)
+        .vexpand(true)
+        .min_content_height(240)
+        .build();
+    report_page.append(&details_window);
+    let close_button = Button::builder()
+        .label("Close")
+        .halign(gtk::Align::End)
+        .css_classes(vec!["suggested-action"])
+        .build();
+    report_page.append(&close_button);
+
+    stack.add_named(&choose_page, Some("choose"));
+    stack.add_named(&progress_page, Some("progress"));
+    stack.add_named(&report_page, Some("report"));
+
+    let chosen: Rc<RefCell<Option<PathBuf>>> = Rc::new(RefCell::new(None));
+
+    // Another app needs another export: forget the chosen one
+    let chosen_for_source = chosen.clone();
+    let path_label_for_source = path_label.clone();
+    let import_button_for_source = import_button.clone();
+    source_choice.connect_selected_notify(move |choice| {
+        let source = Source::ALL.get(choice.selected() as usize).copied().unwrap_or(Source::Obsidian);
+        description.set_text(source.description());
+        *chosen_for_source.borrow_mut() = None;
+        path_label_for_source.set_text("Nothing chosen");
+        import_button_for_source.set_sensitive(false);
+    });
+
+    let dialog_for_choose = dialog.clone();
+    let source_choice_for_choose = source_choice.clone();
+    let chosen_for_choose = chosen.clone();
+    let folder_entry_for_choose = folder_entry.clone();
+    let import_button_for_choose = import_button.clone();
+    choose_button.connect_clicked(move |_| {
+        let source = Source::ALL.get(source_choice_for_choose.selected() as usize).copied().unwrap_or(Source::Obsidian);
+        let file_dialog = FileDialog::builder()
+            .title(format!("Choose {}", source.label()))
+            .modal(true)
+            .build();
+
+        let chosen = chosen_for_choose.clone();
+        let path_label = path_label.clone();
+        let folder_entry = folder_entry_for_choose.clone();
+        let import_button = import_button_for_choose.clone();
+        let on_chosen = move |result: Result<gio::File, glib::Error>| {
+            let path = match result.ok().and_then(|file| file.path()) {
+                Some(path) => path,
+                None => return, // Cancelled
+            };
+            path_label.set_text(&path.display().to_string());
+            path_label.set_tooltip_text(Some(&path.display().to_string()));
+            folder_entry.set_text(&source.default_folder(&path));
+            *chosen.borrow_mut() = Some(path);
+            import_button.set_sensitive(true);
+        };
+
+        if source.is_folder() {
+            file_dialog.select_folder(Some(&dialog_for_choose), gio::Cancellable::NONE, on_chosen);
+        } else {
+            let filter = FileFilter::new();
+            filter.set_name(Some(source.label()));
+            for pattern in source.patterns() {
+                filter.add_pattern(pattern);
+            }
+            file_dialog.set_default_filter(Some(&filter));
+            file_dialog.open(Some(&dialog_for_choose), gio::Cancellable::NONE, on_chosen);
+        }
+    });
+
+    let dialog_for_cancel = dialog.clone();
+    cancel_button.connect_clicked(move |_| {
+        dialog_for_cancel.close();
+    });
+
+    // The import runs on a worker thread, as large exports take a while; the
+    // progress page shows the notes written so far until it is done
+    let dialog_for_import = dialog.clone();
+    let stack_for_import = stack.clone();
+    let on_imported = Rc::new(on_imported);
+    import_button.connect_clicked(move |_| {
+        let path = match chosen.borrow().clone() {
+            Some(path) => path,
+            None => return,
+        };
+        let source = Source::ALL.get(source_choice.selected() as usize).copied().unwrap_or(Source::Obsidian);
+        let folder = folder_entry.text().trim().to_string();
+        progress_label.set_text("Importing…");
+        stack_for_import.set_visible_child_name("progress");
+        dialog_for_import.set_deletable(false);
+
+        let progress = Arc::new(AtomicUsize::new(0));
+        let progress_for_thread = progress.clone();
+        let progress_label = progress_label.clone();
+        let progress_bar = progress_bar.clone();
+        let progress_source = glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+            let count = progress.load(Ordering::Relaxed);
+            if count > 0 {
+                progress_label.set_text(&format!("Importing… {} {} so far", count, if count == 1 { "note" } else { "notes" }));
+            }
+            progress_bar.pulse();
+            glib::ControlFlow::Continue
+        });
+
+        let dialog = dialog_for_import.clone();
+        let stack = stack_for_import.clone();
+        let summary_label = summary_label.clone();
+        let details_view = details_view.clone();
+        let on_imported = on_imported.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let result = gio::spawn_blocking(move || import::import(source, &path, &folder, &progress_for_thread))
+                .await
+                .unwrap_or_else(|_| Err("The import stopped unexpectedly.".to_string()));
+            progress_source.remove();
+            dialog.set_deletable(true);
+            match result {
+                Ok(report) => {
+                    summary_label.set_text(&report.summary());
+                    details_view.buffer().set_text(&report.details());
+                    stack.set_visible_child_name("report");
+                    if !report.imported.is_empty() {
+                        on_imported();
+                    }
+                }
+                Err(e) => {
+                    stack.set_visible_child_name("choose");
+                    crate::utils::show_error_dialog(&dialog, "Import Error", &e);
+                }
+            }
+        });
+    });
+
+    let dialog_for_close = dialog.clone();
+    close_button.connect_clicked(move |_| {
+        dialog_for_close.close();
+    });
+
+    dialog.set_child(Some(&stack));
+    dialog.set_default_widget(Some(&choose_button));
+    dialog.present();
+}
@@ -0,0 +1,252 @@
+use gtk::prelude::*;
+use gtk::{glib, ApplicationWindow, Box, Button, Calendar, MenuButton, Orientation, Popover, TextView};
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::rc::Rc;
+
+use crate::note::Note;
+use crate::settings::Settings;
+use crate::templates;
+
+// Folder of the notes directory holding the daily notes
+pub const JOURNAL_FOLDER: &str = "journal";
+// Daily notes are titled by their date, so they sort and link naturally
+const TITLE_FORMAT: &str = "%Y-%m-%d";
+// Content of new daily notes when no template is set: a heading such as "Sunday, October 18, 2026"
+const DEFAULT_TEMPLATE: &str = "# {{date:%A, %B %-d, %Y}}\n\n{{cursor}}";
+
+fn daily_title(date: NaiveDate) -> String {
+    date.format(TITLE_FORMAT).to_string()
+}
+
+/// The date of a daily note title, if it is one
+fn parse_daily_title(title: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(title, TITLE_FORMAT).ok()
+}
+
+/// Dates that have a daily note
+fn entry_dates() -> BTreeSet<NaiveDate> {
+    let journal_dir = crate::utils::get_notes_dir().join(JOURNAL_FOLDER);
+    let entries = match fs::read_dir(&journal_dir) {
+        Ok(entries) => entries,
+        Err(_) => return BTreeSet::new(), // No daily notes yet
+    };
+    entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| parse_daily_title(path.file_stem()?.to_str()?))
+        .collect()
+}
+
+/// Daily notes: opens or creates the note for a date in the journal folder, with
+/// previous/next entry buttons and a calendar marking the days that have entries
+#[derive(Clone)]
+pub struct Journal {
+    window: ApplicationWindow,
+    text_view: TextView,
+    settings: Rc<RefCell<Settings>>,
+    container: Box,
+    calendar: Calendar,
+    calendar_button: MenuButton,
+    /// Date of the open note, if it is a daily note
+    current: Rc<Cell<Option<NaiveDate>>>,
+    /// Set while the calendar is moved programmatically, so it does not open notes
+    syncing: Rc<Cell<bool>>,
+}
+
+impl Journal {
+    pub fn new(window: &ApplicationWindow, text_view: &TextView, settings: &Rc<RefCell<Settings>>) -> Self {
+        let previous_button = Button::builder()
+            .icon_name("go-previous-symbolic")
+            .action_name("win.journal-previous")
+            .tooltip_text("Previous Daily Note")
+            .css_classes(vec!["icon-only-button"])
+            .build();
+        let next_button = Button::builder()
+            .icon_name("go-next-symbolic")
+            .action_name("win.journal-next")
+            .tooltip_text("Next Daily Note")
+            .css_classes(vec!["icon-only-button"])
+            .build();
+
+        let calendar = Calendar::new();
+        let today_button = Button::builder()
+            .label("Today")
+            .action_name("win.open-today")
+            .margin_top(6)
+            .build();
+        let popover_box = Box::builder()
+            .orientation(Orientation::Vertical)
+            .build();
+        popover_box.append(&calendar);
+        popover_box.append(&today_button);
+        let popover = Popover::builder()
+            .child(&popover_box)
+            .build();
+        let calendar_button = MenuButton::builder()
+            .icon_name("x-office-calendar-symbolic")
+            .tooltip_text("Daily Notes")
+            .popover(&popover)
+            .css_classes(vec!["icon-only-button"])
+            .build();
+
+        let container = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .css_classes(vec!["journal-controls"])
+            .build();
+        container.append(&previous_button);
+        container.append(&calendar_button);
+        container.append(&next_button);
+
+        let journal = Journal {
+            window: window.clone(),
+            text_view: text_view.clone(),
+            settings: settings.clone(),
+            container,
+            calendar,
+            calendar_button,
+            current: Rc::new(Cell::new(None)),
+            syncing: Rc::new(Cell::new(false)),
+        };
+        journal.connect_signals(&popover);
+        journal
+    }
+
+    /// The controls to place in the sidebar header
+    pub fn widget(&self) -> &Box {
+        &self.container
+    }
+
+    fn connect_signals(&self, popover: &Popover) {
+        // Start at the open daily note (or today) each time the calendar opens
+        let journal = self.clone();
+        popover.connect_show(move |_| {
+            let date = journal.current.get().unwrap_or_else(|| Local::now().date_naive());
+            journal.show_month_of(date);
+        });
+
+        let journal = self.clone();
+        self.calendar.connect_day_selected(move |calendar| {
+            if journal.syncing.get() {
+                return;
+            }
+            let selected = calendar.date();
+            if let Some(date) = NaiveDate::from_ymd_opt(selected.year(), selected.month() as u32, selected.day_of_month() as u32) {
+                journal.calendar_button.popdown();
+                journal.open_date(date);
+            }
+        });
+
+        // Browsing months keeps the entry marks current
+        let journal = self.clone();
+        self.calendar.connect_next_month(move |_| journal.mark_entries());
+        let journal = self.clone();
+        self.calendar.connect_prev_month(move |_| journal.mark_entries());
+        let journal = self.clone();
+        self.calendar.connect_next_year(move |_| journal.mark_entries());
+        let journal = self.clone();
+        self.calendar.connect_prev_year(move |_| journal.mark_entries());
+    }
+
+    /// Tell the journal which note is open, so previous/next work from its date
+    pub fn set_note(&self, title: Option<&str>) {
+        self.current.set(title.and_then(parse_daily_title));
+    }
+
+    pub fn show_calendar(&self) {
+        self.calendar_button.popup();
+    }
+
+    pub fn open_today(&self) {
+        self.open_date(Local::now().date_naive());
+    }
+
+    /// Open the closest daily note before the open one (or before today)
+    pub fn open_previous(&self) {
+        let reference = self.current.get().unwrap_or_else(|| Local::now().date_naive());
+        if let Some(date) = entry_dates().range(..reference).next_back() {
+            self.open_date(*date);
+        }
+    }
+
+    /// Open the closest daily note after the open one, or today's once there are no later entries
+    pub fn open_next(&self) {
+        let today = Local::now().date_naive();
+        let reference = match self.current.get() {
+            Some(date) => date,
+            None => return, // Nothing comes after today
+        };
+        let next = entry_dates().range(reference + Duration::days(1)..).next().copied()
+            .or_else(|| (reference < today).then_some(today));
+        if let Some(date) = next {
+            self.open_date(date);
+        }
+    }
+
+    /// Open the daily note of a date, creating it from the template if needed
+    fn open_date(&self, date: NaiveDate) {
+        let title = daily_title(date);
+        let mut cursor = None;
+        if !Note::path_for_title(&title).exists() {
+            // Dates in the template are those of the note's day, even when it is created later
+            let day = Local.from_local_datetime(&date.and_time(Local::now().time()))
+                .earliest()
+                .unwrap_or_else(Local::now);
+            let (content, cursor_offset) = templates::render(&self.template(), &title, &HashMap::new(), &day);
+            cursor = cursor_offset;
+            let created = Note::new_in_folder(JOURNAL_FOLDER, &title).and_then(|mut note| {
+                note.content = content;
+                note.save()
+            });
+            if let Err(e) = created {
+                eprintln!("Error creating daily note {}: {}", title, e);
+                return;
+            }
+        }
+        if let Err(e) = WidgetExt::activate_action(&self.window, "win.open-note", Some(&title.to_variant())) {
+            eprintln!("Error opening daily note {}: {}", title, e);
+            return;
+        }
+        if let Some(offset) = cursor {
+            let buffer = self.text_view.buffer();
+            buffer.place_cursor(&buffer.iter_at_offset(offset as i32));
+        }
+    }
+
+    /// The template from the settings (a template name, or the title of a note), or the built-in heading
+    fn template(&self) -> String {
+        let name = self.settings.borrow().journal.template_note.trim().to_string();
+        if name.is_empty() {
+            return DEFAULT_TEMPLATE.to_string();
+        }
+        templates::load(&name)
+            .or_else(|_| Note::load(&Note::path_for_title(&name)).map(|note| note.content))
+            .unwrap_or_else(|e| {
+                eprintln!("Error loading daily note template {}: {}", name, e);
+                DEFAULT_TEMPLATE.to_string()
+            })
+    }
+
+    fn show_month_of(&self, date: NaiveDate) {
+        self.syncing.set(true);
+        match glib::DateTime::from_local(date.year(), date.month() as i32, date.day() as i32, 0, 0, 0.0) {
+            Ok(date_time) => self.calendar.select_day(&date_time),
+            Err(e) => eprintln!("Error showing {} in the calendar: {}", date, e),
+        }
+        self.syncing.set(false);
+        self.mark_entries();
+    }
+
+    /// Mark the days of the displayed month that have a daily note
+    fn mark_entries(&self) {
+        self.calendar.clear_marks();
+        let shown = self.calendar.date();
+        for date in entry_dates() {
+            if date.year() == shown.year() && date.month() as i32 == shown.month() {
+                self.calendar.mark_day(date.day());
+            }
+        }
+    }
+}
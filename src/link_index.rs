@@ -0,0 +1,411 @@
+// Index of the [[links]] between notes. Built once at startup and kept up to date
+// by watching the notes directory, so saves, renames and deletes from anywhere
+// (including outside the app) only re-read the files that changed.
+
+use gtk::prelude::*;
+use gtk::{gio, glib};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::markdown;
+use crate::note::Note;
+
+// Wait for a burst of file events (one save emits several) before notifying listeners
+const NOTIFY_DELAY_MS: u64 = 200;
+// Longest context line shown for a backlink or mention
+const MAX_CONTEXT_CHARS: usize = 160;
+
+/// Callback run after the index changes
+type Listener = Rc<dyn Fn()>;
+
+/// Identifies a listener registered with `LinkIndex::connect_changed`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListenerId(u64);
+
+/// What the index keeps for each note
+struct IndexedNote {
+    title: String,
+    folder: String,
+    tags: Vec<String>,
+    content: String,
+    /// Targets of the note's wiki links, lowercased
+    links: Vec<String>,
+}
+
+impl IndexedNote {
+    fn from_note(note: Note) -> Self {
+        let links = markdown::wiki_links(&note.content).into_iter()
+            .map(|link| markdown::title_key(&link.target))
+            .collect();
+        IndexedNote { folder: note.folder(), tags: note.tags(), title: note.title, content: note.content, links }
+    }
+}
+
+/// The index entry of a note without its content, for views such as the graph
+#[derive(Debug, Clone)]
+pub struct NoteSummary {
+    pub title: String,
+    pub folder: String,
+    pub tags: Vec<String>,
+    /// Targets of the note's wiki links, lowercased
+    pub links: Vec<String>,
+}
+
+/// A note that links to another, with the line the link is on
+#[derive(Debug, Clone)]
+pub struct Backlink {
+    pub source: String,
+    pub line: usize,
+    pub context: String,
+}
+
+/// A plain-text occurrence of a note's title in another note
+#[derive(Debug, Clone)]
+pub struct Mention {
+    pub source: String,
+    pub path: PathBuf,
+    pub line: usize,
+    /// Byte range of the mention within the line
+    pub start: usize,
+    pub end: usize,
+    pub context: String,
+}
+
+/// Shared, self-updating link index. Clones refer to the same index.
+#[derive(Clone)]
+pub struct LinkIndex {
+    notes: Rc<RefCell<HashMap<PathBuf, IndexedNote>>>,
+    listeners: Rc<RefCell<Vec<(ListenerId, Listener)>>>,
+    next_listener_id: Rc<Cell<u64>>,
+    pending_notify: Rc<RefCell<Option<glib::SourceId>>>,
+    // One per folder (directory monitors are not recursive); dropping them would stop the updates
+    monitors: Rc<RefCell<HashMap<PathBuf, gio::FileMonitor>>>,
+}
+
+impl LinkIndex {
+    /// Index every note and start watching the notes directory
+    pub fn new() -> Self {
+        let notes = Note::get_all().unwrap_or_else(|e| {
+            eprintln!("Error building link index: {}", e);
+            Vec::new()
+        });
+        let notes: HashMap<PathBuf, IndexedNote> = notes.into_iter()
+            .map(|note| (note.path.clone(), IndexedNote::from_note(note)))
+            .collect();
+
+        let index = LinkIndex {
+            notes: Rc::new(RefCell::new(notes)),
+            listeners: Rc::new(RefCell::new(Vec::new())),
+            next_listener_id: Rc::new(Cell::new(0)),
+            pending_notify: Rc::new(RefCell::new(None)),
+            monitors: Rc::new(RefCell::new(HashMap::new())),
+        };
+        index.watch_dir(&crate::utils::get_notes_dir());
+        index
+    }
+
+    /// Call `callback` whenever the index changes, until `disconnect` is called with the returned id
+    pub fn connect_changed<F: Fn() + 'static>(&self, callback: F) -> ListenerId {
+        let id = ListenerId(self.next_listener_id.get());
+        self.next_listener_id.set(id.0 + 1);
+        self.listeners.borrow_mut().push((id, Rc::new(callback)));
+        id
+    }
+
+    /// Stop calling a listener, e.g. when the window it updates closes
+    pub fn disconnect(&self, id: ListenerId) {
+        self.listeners.borrow_mut().retain(|(listener_id, _)| *listener_id != id);
+    }
+
+    /// Watch a folder of the notes directory and, recursively, its subfolders
+    fn watch_dir(&self, dir: &Path) {
+        if self.monitors.borrow().contains_key(dir) {
+            return;
+        }
+        let monitor = match gio::File::for_path(dir).monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+            Ok(monitor) => monitor,
+            Err(e) => {
+                eprintln!("Error watching {:?}, backlinks will not update: {}", dir, e);
+                return;
+            }
+        };
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for path in entries.flatten().map(|entry| entry.path()) {
+                let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                if path.is_dir() && !hidden {
+                    self.watch_dir(&path);
+                }
+            }
+        }
+
+        let index = self.clone();
+        monitor.connect_changed(move |_, file, other_file, event| {
+            let path = match file.path() {
+                Some(path) => path,
+                None => return,
+            };
+            // Folders that appear (such as the journal, or a folder of imported notes)
+            // are watched, with the notes already in them; folders that go are forgotten
+            match event {
+                gio::FileMonitorEvent::Created | gio::FileMonitorEvent::MovedIn if path.is_dir() => {
+                    index.add_dir(&path);
+                    return;
+                }
+                gio::FileMonitorEvent::Renamed => {
+                    if let Some(new_path) = other_file.and_then(|other| other.path()).filter(|new_path| new_path.is_dir()) {
+                        index.forget_dir(&path);
+                        index.add_dir(&new_path);
+                        return;
+                    }
+                }
+                gio::FileMonitorEvent::Deleted | gio::FileMonitorEvent::MovedOut if index.monitors.borrow().contains_key(&path) => {
+                    index.forget_dir(&path);
+                    return;
+                }
+                _ => {}
+            }
+            let mut touched: Vec<PathBuf> = file.path().into_iter().collect();
+            if event == gio::FileMonitorEvent::Renamed {
+                touched.extend(other_file.and_then(|other| other.path()));
+            }
+            let mut changed = false;
+            for path in touched {
+                changed |= index.reindex(&path);
+            }
+            if changed {
+                index.schedule_notify();
+            }
+        });
+        self.monitors.borrow_mut().insert(dir.to_path_buf(), monitor);
+    }
+
+    /// Start watching a folder that appeared and index the notes it already holds
+    /// (a note created with its folder is written before the folder is watched)
+    fn add_dir(&self, dir: &Path) {
+        self.watch_dir(dir);
+        let mut paths = Vec::new();
+        if let Err(e) = crate::note::collect_note_paths(dir, &mut paths) {
+            eprintln!("Error reading folder {:?}: {}", dir, e);
+        }
+        for path in &paths {
+            self.reindex(path);
+        }
+        if !paths.is_empty() {
+            self.schedule_notify();
+        }
+    }
+
+    /// Stop watching a folder that is gone, with its subfolders, and drop its notes
+    fn forget_dir(&self, dir: &Path) {
+        self.monitors.borrow_mut().retain(|path, monitor| {
+            let inside = path.starts_with(dir);
+            if inside {
+                monitor.cancel();
+            }
+            !inside
+        });
+        let removed = {
+            let mut notes = self.notes.borrow_mut();
+            let count = notes.len();
+            notes.retain(|path, _| !path.starts_with(dir));
+            count != notes.len()
+        };
+        if removed {
+            self.schedule_notify();
+        }
+    }
+
+    /// Re-read one note from disk (or forget it if it is gone). Returns whether it was a note file.
+    fn reindex(&self, path: &Path) -> bool {
+        if path.extension().is_none_or(|ext| ext != "md") {
+            return false;
+        }
+        let mut notes = self.notes.borrow_mut();
+        match Note::load(path) {
+            Ok(note) if path.is_file() => {
+                notes.insert(path.to_path_buf(), IndexedNote::from_note(note));
+            }
+            _ => {
+                notes.remove(path);
+            }
+        }
+        true
+    }
+
+    fn schedule_notify(&self) {
+        if let Some(source_id) = self.pending_notify.borrow_mut().take() {
+            source_id.remove();
+        }
+        let listeners = self.listeners.clone();
+        let pending_notify = self.pending_notify.clone();
+        let source_id = glib::timeout_add_local_once(std::time::Duration::from_millis(NOTIFY_DELAY_MS), move || {
+            pending_notify.borrow_mut().take();
+            // Copy the list so listeners may register others while running
+            let listeners: Vec<Listener> = listeners.borrow().iter().map(|(_, listener)| listener.clone()).collect();
+            for listener in listeners {
+                listener();
+            }
+        });
+        *self.pending_notify.borrow_mut() = Some(source_id);
+    }
+
+    /// Every indexed note, sorted by title
+    pub fn summaries(&self) -> Vec<NoteSummary> {
+        let mut summaries: Vec<NoteSummary> = self.notes.borrow().values()
+            .map(|note| NoteSummary {
+                title: note.title.clone(),
+                folder: note.folder.clone(),
+                tags: note.tags.clone(),
+                links: note.links.clone(),
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.title.cmp(&b.title));
+        summaries
+    }
+
+    /// Notes linking to `title`, one entry per linking line, sorted by note title
+    pub fn backlinks(&self, title: &str) -> Vec<Backlink> {
+        let wanted = markdown::title_key(title);
+        let notes = self.notes.borrow();
+        let mut backlinks = Vec::new();
+
+        for note in notes.values() {
+            if note.title == title || !note.links.contains(&wanted) {
+                continue;
+            }
+            for (line_number, line) in prose_lines(&note.content) {
+                if markdown::wiki_links(line).iter().any(|link| markdown::title_key(&link.target) == wanted) {
+                    backlinks.push(Backlink {
+                        source: note.title.clone(),
+                        line: line_number,
+                        context: context_snippet(line),
+                    });
+                }
+            }
+        }
+
+        backlinks.sort_by(|a, b| a.source.cmp(&b.source).then(a.line.cmp(&b.line)));
+        backlinks
+    }
+
+    /// Places where `title` appears as plain text (not inside a link or code block)
+    /// in other notes, sorted by note title
+    pub fn unlinked_mentions(&self, title: &str) -> Vec<Mention> {
+        let notes = self.notes.borrow();
+        let mut mentions = Vec::new();
+
+        for (path, note) in notes.iter() {
+            if note.title == title {
+                continue;
+            }
+            for (line_number, line) in prose_lines(&note.content) {
+                for (start, end) in plain_mentions(line, title) {
+                    mentions.push(Mention {
+                        source: note.title.clone(),
+                        path: path.clone(),
+                        line: line_number,
+                        start,
+                        end,
+                        context: context_snippet(line),
+                    });
+                }
+            }
+        }
+
+        mentions.sort_by(|a, b| a.source.cmp(&b.source).then(a.line.cmp(&b.line)).then(a.start.cmp(&b.start)));
+        mentions
+    }
+
+    /// Turn an unlinked mention into a `[[link]]` to `title`, keeping the original
+    /// wording as the link label if its case differs
+    pub fn link_mention(&self, mention: &Mention, title: &str) -> Result<(), String> {
+        let mut note = Note::load(&mention.path)?;
+
+        let mut lines: Vec<String> = note.content.split('\n').map(str::to_string).collect();
+        let line = lines.get_mut(mention.line)
+            .ok_or_else(|| format!("Note \"{}\" has changed since it was indexed.", mention.source))?;
+        let original = line.get(mention.start..mention.end)
+            .filter(|text| text.to_lowercase() == title.to_lowercase())
+            .ok_or_else(|| format!("Note \"{}\" has changed since it was indexed.", mention.source))?
+            .to_string();
+
+        let link = if original == title {
+            format!("[[{}]]", title)
+        } else {
+            format!("[[{}|{}]]", title, original)
+        };
+        line.replace_range(mention.start..mention.end, &link);
+        note.content = lines.join("\n");
+        note.save()?;
+
+        // Don't wait for the file monitor, the panel should update right away
+        self.reindex(&mention.path);
+        self.schedule_notify();
+        Ok(())
+    }
+}
+
+/// Lines of a note outside fenced code blocks, with their line numbers
+fn prose_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut in_code_block = false;
+    content.split('\n').enumerate().filter(move |(_, line)| {
+        if markdown::is_code_fence(line) {
+            in_code_block = !in_code_block;
+            return false;
+        }
+        !in_code_block
+    })
+}
+
+/// Whole-word, case-insensitive occurrences of `title` in a line that are not inside a wiki link
+fn plain_mentions(line: &str, title: &str) -> Vec<(usize, usize)> {
+    if title.trim().is_empty() {
+        return Vec::new();
+    }
+    let links = markdown::wiki_links(line);
+    let is_word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let wanted = title.to_lowercase();
+
+    line.char_indices()
+        .filter_map(|(start, _)| {
+            let end = start + lowercase_prefix_len(&line[start..], &wanted)?;
+            let before = line[..start].chars().next_back();
+            let after = line[end..].chars().next();
+            if is_word_char(before) || is_word_char(after) {
+                return None;
+            }
+            if links.iter().any(|link| start < link.end && link.start < end) {
+                return None;
+            }
+            Some((start, end))
+        })
+        .collect()
+}
+
+/// Byte length of the start of `text` that lowercases to `lowercase`, compared char by
+/// char since lowercasing can change the byte length (the Kelvin sign U+212A becomes "k")
+fn lowercase_prefix_len(text: &str, lowercase: &str) -> Option<usize> {
+    let mut folded = String::new();
+    for (index, c) in text.char_indices() {
+        folded.extend(c.to_lowercase());
+        if folded.len() >= lowercase.len() {
+            return (folded == lowercase).then_some(index + c.len_utf8());
+        }
+        if !lowercase.starts_with(&folded) {
+            return None;
+        }
+    }
+    None
+}
+
+/// A trimmed, length-limited copy of a line for display
+fn context_snippet(line: &str) -> String {
+    let trimmed = line.trim();
+    if trimmed.chars().count() > MAX_CONTEXT_CHARS {
+        format!("{}…", trimmed.chars().take(MAX_CONTEXT_CHARS).collect::<String>())
+    } else {
+        trimmed.to_string()
+    }
+}
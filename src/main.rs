@@ -1,6 +1,44 @@
+mod backlinks;
+mod cli;
+mod docx;
+mod editor;
+mod enex;
+mod epub;
+mod export;
+mod export_dialog;
+mod focus;
+mod folding;
+mod freewrite;
+mod frontmatter;
+mod fuzzy;
+mod goals;
+mod graph;
+mod import;
+mod import_dialog;
+mod journal;
+mod link_index;
+mod manuscript;
+mod manuscript_dialog;
+mod markdown;
 mod note;
+mod outline;
+mod palette;
+mod print;
+mod print_markup;
+mod readability;
+mod settings;
+mod shortcuts;
+mod spellcheck;
+mod spelling;
+mod stats;
+mod style_panel;
+mod switcher;
+mod templates;
+mod text_stats;
+mod typewriter;
 mod ui;
 mod utils;
+mod wikilinks;
 
 use gtk::prelude::*;
 use gtk::{glib, Application};
@@ -19,6 +57,19 @@ fn main() -> glib::ExitCode {
         return glib::ExitCode::FAILURE;
     }
 
+    // `penscript <command> ...` works on the notes from the terminal, without GTK
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(command) = args.get(1).filter(|arg| cli::COMMANDS.contains(&arg.as_str()) || *arg == "--help") {
+        let command = if command == "--help" { "help" } else { command.as_str() };
+        return match cli::run(command, &args[2..]) {
+            Ok(()) => glib::ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("penscript {}: {}", command, e);
+                glib::ExitCode::FAILURE
+            }
+        };
+    }
+
     // Create a new GTK application
     let app = Application::builder().application_id(APP_ID).build();
 
@@ -33,7 +84,7 @@ fn main() -> glib::ExitCode {
 fn ensure_notes_dir_exists() -> Result<(), String> {
     let notes_dir = get_notes_dir();
     if !notes_dir.exists() {
-        println!("Notes directory not found, creating at: {:?}", notes_dir);
+        eprintln!("Notes directory not found, creating at: {:?}", notes_dir);
         fs::create_dir_all(&notes_dir)
             .map_err(|e| format!("Failed to create notes directory {:?}: {}", notes_dir, e))?;
     }
@@ -0,0 +1,263 @@
+// Long-form exports: an ordered list of notes compiled into a single book, written
+// as EPUB 3 (`epub.rs`) or DOCX (`docx.rs`). Both formats are ZIP archives holding
+// XML, with a title page, a table of contents built from the headings, one chapter
+// per note and the notes' local images embedded.
+// Everything here works on strings and paths so it stays independent of GTK.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::export::{self, TocEntry};
+use crate::note::Note;
+
+/// File format of a compiled manuscript
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Epub,
+    Docx,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Epub, Format::Docx];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Epub => "EPUB (e-books)",
+            Format::Docx => "DOCX (Word, LibreOffice)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Epub => "epub",
+            Format::Docx => "docx",
+        }
+    }
+}
+
+/// One note of the manuscript
+pub struct Chapter {
+    pub title: String,
+    pub markdown: String,
+    /// Folder of the note, where relative image paths start from
+    pub base_dir: PathBuf,
+}
+
+pub struct Manuscript {
+    pub title: String,
+    pub author: String,
+    /// Language tag such as "en" or "de-DE"
+    pub language: String,
+    /// GTK paper name for page-based formats, e.g. "iso_a4" (see `print::PAPER_SIZES`)
+    pub paper_size: String,
+    pub chapters: Vec<Chapter>,
+}
+
+impl Manuscript {
+    /// Load the notes with the given titles, in order, as chapters
+    pub fn from_titles(title: &str, author: &str, titles: &[String]) -> Result<Self, String> {
+        let mut chapters = Vec::new();
+        for note_title in titles {
+            let note = Note::load(&Note::path_for_title(note_title))?;
+            chapters.push(Chapter {
+                title: note.title.clone(),
+                base_dir: note.path.parent().map(Path::to_path_buf).unwrap_or_else(crate::utils::get_notes_dir),
+                markdown: note.content,
+            });
+        }
+        // The first chapter with a `lang` field sets the book's language
+        let language = chapters.iter()
+            .find_map(|chapter| crate::frontmatter::parse(&chapter.markdown).get("lang").map(|lang| lang.replace('_', "-")))
+            .unwrap_or_else(|| "en".to_string());
+        Ok(Manuscript {
+            title: title.to_string(),
+            author: author.to_string(),
+            language,
+            paper_size: "iso_a4".to_string(),
+            chapters,
+        })
+    }
+
+    pub fn write(&self, format: Format, destination: &Path) -> Result<(), String> {
+        if self.chapters.is_empty() {
+            return Err("The manuscript has no chapters.".to_string());
+        }
+        match format {
+            Format::Epub => crate::epub::write_epub(self, destination),
+            Format::Docx => crate::docx::write_docx(self, destination),
+        }
+    }
+
+    /// Chapter number (from 0) of a wiki link target, if that note is in the manuscript
+    pub fn chapter_index(&self, target: &str) -> Option<usize> {
+        let wanted = crate::markdown::title_key(target);
+        self.chapters.iter().position(|chapter| crate::markdown::title_key(&chapter.title) == wanted)
+    }
+}
+
+/// Table of contents entries of a chapter: its title, then its headings down to level 3.
+/// A heading of level 1 opening the note is the chapter title itself (id None).
+pub fn chapter_contents(chapter: &Chapter, headings: &[TocEntry]) -> Vec<(u8, String, Option<String>)> {
+    let mut entries = vec![(1, chapter.title.clone(), None)];
+    let skip = usize::from(export::starts_with_title(&chapter.markdown));
+    if let (1, Some(first)) = (skip, headings.first()) {
+        entries[0].1 = first.text.clone();
+    }
+    entries.extend(headings.iter()
+        .skip(skip)
+        .filter(|heading| heading.level <= 3)
+        .map(|heading| (heading.level.max(2), heading.text.clone(), Some(heading.id.clone()))));
+    entries
+}
+
+/// Compare names so that numbers sort by value ("Chapter 2" before "Chapter 10")
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut x_digits = String::new();
+                while let Some(c) = a_chars.next_if(char::is_ascii_digit) {
+                    x_digits.push(c);
+                }
+                let mut y_digits = String::new();
+                while let Some(c) = b_chars.next_if(char::is_ascii_digit) {
+                    y_digits.push(c);
+                }
+                let x_trimmed = x_digits.trim_start_matches('0');
+                let y_trimmed = y_digits.trim_start_matches('0');
+                let order = x_trimmed.len().cmp(&y_trimmed.len()).then_with(|| x_trimmed.cmp(y_trimmed));
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let order = x.to_lowercase().cmp(y.to_lowercase());
+                if order != Ordering::Equal {
+                    return order;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Titles of the notes in a folder and its subfolders ("" for all notes), in file name order
+pub fn folder_titles(folder: &str) -> Result<Vec<String>, String> {
+    let mut notes: Vec<Note> = Note::get_all()?
+        .into_iter()
+        .filter(|note| {
+            let note_folder = note.folder();
+            folder.is_empty() || note_folder == folder || note_folder.starts_with(&format!("{}/", folder))
+        })
+        .collect();
+    notes.sort_by(|a, b| natural_cmp(&a.relative_path(), &b.relative_path()));
+    Ok(notes.into_iter().map(|note| note.title).collect())
+}
+
+/// An image embedded in the book
+pub struct BookImage {
+    /// File name inside the book, e.g. "image-1.png"
+    pub name: String,
+    pub mime: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// Images collected while rendering the chapters, each file stored once
+#[derive(Default)]
+pub struct ImageStore {
+    paths: RefCell<Vec<PathBuf>>,
+    pub images: RefCell<Vec<BookImage>>,
+}
+
+impl ImageStore {
+    /// Add the local image an image link points at, if its type is one of `mime_types`.
+    /// Returns its index in `images`.
+    pub fn add(&self, dest: &str, base_dir: &Path, mime_types: &[&str]) -> Option<usize> {
+        let (path, mime) = export::local_image(dest, base_dir)?;
+        if !mime_types.contains(&mime) {
+            return None;
+        }
+        if let Some(index) = self.paths.borrow().iter().position(|known| *known == path) {
+            return Some(index);
+        }
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error reading image {:?}: {}", path, e);
+                return None;
+            }
+        };
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("img").to_lowercase();
+        let mut images = self.images.borrow_mut();
+        let name = format!("image-{}.{}", images.len() + 1, extension);
+        images.push(BookImage { name, mime, data });
+        self.paths.borrow_mut().push(path);
+        Some(images.len() - 1)
+    }
+}
+
+/// Width and height in pixels of a PNG, GIF or JPEG image
+pub fn image_size(data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| Some(u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]) as u32);
+    if data.starts_with(b"\x89PNG") {
+        let be32 = |at: usize| Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?));
+        return Some((be32(16)?, be32(20)?));
+    }
+    if data.starts_with(b"GIF8") {
+        let le16 = |at: usize| Some(u16::from_le_bytes([*data.get(at)?, *data.get(at + 1)?]) as u32);
+        return Some((le16(6)?, le16(8)?));
+    }
+    if data.starts_with(&[0xFF, 0xD8]) {
+        // Walk the JPEG segments up to the frame header, which holds the size
+        let mut pos = 2;
+        while pos + 4 <= data.len() {
+            if data[pos] != 0xFF {
+                return None;
+            }
+            let marker = data[pos + 1];
+            let length = be16(pos + 2)? as usize;
+            if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+                return Some((be16(pos + 7)?, be16(pos + 5)?));
+            }
+            pos += 2 + length;
+        }
+    }
+    None
+}
+
+/// A ZIP archive being written
+pub struct Archive {
+    zip: zip::ZipWriter<fs::File>,
+}
+
+impl Archive {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = fs::File::create(path)
+            .map_err(|e| format!("Failed to create {:?}: {}", path, e))?;
+        Ok(Archive { zip: zip::ZipWriter::new(file) })
+    }
+
+    /// Add a file; `compress` is off for entries that must be stored as they are
+    pub fn add(&mut self, name: &str, data: &[u8], compress: bool) -> Result<(), String> {
+        let method = if compress { zip::CompressionMethod::Deflated } else { zip::CompressionMethod::Stored };
+        let options = zip::write::SimpleFileOptions::default().compression_method(method);
+        self.zip.start_file(name, options)
+            .and_then(|_| self.zip.write_all(data).map_err(zip::result::ZipError::from))
+            .map_err(|e| format!("Failed to write {} to the archive: {}", name, e))
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        self.zip.finish()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to finish the archive: {}", e))
+    }
+}
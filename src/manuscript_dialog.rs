@@ -0,0 +1,270 @@
+use gtk::prelude::*;
+use gtk::{gio, glib, ApplicationWindow, Box, Button, DropDown, Entry, FileDialog, Label, ListBox, Orientation, ScrolledWindow, SelectionMode};
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use crate::manuscript::{self, Format, Manuscript};
+use crate::note::Note;
+use crate::settings::Settings;
+use crate::utils::labelled_row;
+
+/// Rebuilds the chapter rows; set once the rows' buttons can refer to it
+type Refresh = RefCell<Option<std::boxed::Box<dyn Fn()>>>;
+
+/// Ask for the chapters (loaded from a folder, then reordered, or picked one by one),
+/// the book's title and author and a format, then compile them into one file
+pub fn show_manuscript_dialog(parent: &ApplicationWindow, settings: &Rc<RefCell<Settings>>) {
+    let dialog = ApplicationWindow::builder()
+        .transient_for(parent)
+        .modal(true)
+        .title("Compile Manuscript")
+        .default_width(480)
+        .default_height(560)
+        .css_classes(vec!["manuscript-dialog"])
+        .build();
+
+    let content_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .margin_start(20)
+        .margin_end(20)
+        .margin_top(20)
+        .margin_bottom(20)
+        .spacing(12)
+        .build();
+
+    let title_entry = Entry::builder()
+        .placeholder_text("Book title")
+        .hexpand(true)
+        .build();
+    content_box.append(&labelled_row("Title", &title_entry));
+    let author_entry = Entry::builder()
+        .text(author_name())
+        .hexpand(true)
+        .build();
+    content_box.append(&labelled_row("Author", &author_entry));
+
+    let format_labels: Vec<&str> = Format::ALL.iter().map(|format| format.label()).collect();
+    let format_choice = DropDown::from_strings(&format_labels);
+    format_choice.set_hexpand(true);
+    content_box.append(&labelled_row("Format", &format_choice));
+
+    // Folders to take the chapters from ("" is all notes)
+    let mut folders: Vec<String> = Note::get_all()
+        .map(|notes| notes.iter().map(Note::folder).filter(|folder| !folder.is_empty()).collect())
+        .unwrap_or_else(|e| {
+            eprintln!("Error listing folders: {}", e);
+            Vec::new()
+        });
+    folders.sort_by(|a, b| manuscript::natural_cmp(a, b));
+    folders.dedup();
+    let mut folder_labels = vec!["Choose Notes…", "All Notes"];
+    folder_labels.extend(folders.iter().map(String::as_str));
+    let folder_choice = DropDown::from_strings(&folder_labels);
+    folder_choice.set_hexpand(true);
+    content_box.append(&labelled_row("Chapters", &folder_choice));
+
+    // The ordered chapter list
+    let chapters: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+    let chapter_list = ListBox::builder()
+        .selection_mode(SelectionMode::None)
+        .css_classes(vec!["manuscript-chapters"])
+        .build();
+    let scrolled_window = ScrolledWindow::builder()
+        .child(&chapter_list)
+        .vexpand(true)
+        .min_content_height(200)
+        .build();
+    content_box.append(&scrolled_window);
+
+    let mut note_titles: Vec<String> = Note::get_all()
+        .map(|notes| notes.into_iter().map(|note| note.title).collect())
+        .unwrap_or_default();
+    note_titles.sort_by(|a, b| manuscript::natural_cmp(a, b));
+    let note_refs: Vec<&str> = note_titles.iter().map(String::as_str).collect();
+    let note_choice = DropDown::from_strings(&note_refs);
+    note_choice.set_hexpand(true);
+    let add_button = Button::builder()
+        .label("Add Chapter")
+        .build();
+    let add_row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .build();
+    add_row.append(&note_choice);
+    add_row.append(&add_button);
+    content_box.append(&add_row);
+
+    let button_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(gtk::Align::End)
+        .spacing(8)
+        .margin_top(8)
+        .build();
+    let cancel_button = Button::builder()
+        .label("Cancel")
+        .build();
+    let compile_button = Button::builder()
+        .label("Compile…")
+        .css_classes(vec!["suggested-action"])
+        .sensitive(false)
+        .build();
+    button_box.append(&cancel_button);
+    button_box.append(&compile_button);
+    content_box.append(&button_box);
+
+    // Rebuild the rows after every change, with move and remove buttons on each
+    let refresh: Rc<Refresh> = Rc::new(RefCell::new(None));
+    // Weak, so the rows' buttons do not keep the closure that creates them alive
+    let refresh_for_rows: Weak<Refresh> = Rc::downgrade(&refresh);
+    let chapters_for_rows = chapters.clone();
+    let chapter_list_for_rows = chapter_list.clone();
+    let compile_button_for_rows = compile_button.clone();
+    *refresh.borrow_mut() = Some(std::boxed::Box::new(move || {
+        while let Some(row) = chapter_list_for_rows.first_child() {
+            chapter_list_for_rows.remove(&row);
+        }
+        let titles = chapters_for_rows.borrow().clone();
+        compile_button_for_rows.set_sensitive(!titles.is_empty());
+        for (index, title) in titles.iter().enumerate() {
+            let row = Box::builder()
+                .orientation(Orientation::Horizontal)
+                .spacing(4)
+                .margin_start(8)
+                .margin_end(4)
+                .build();
+            let label = Label::builder()
+                .label(format!("{}. {}", index + 1, title))
+                .xalign(0.0)
+                .hexpand(true)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .build();
+            row.append(&label);
+            let buttons = [
+                ("go-up-symbolic", "Move Up", index.checked_sub(1)),
+                ("go-down-symbolic", "Move Down", (index + 1 < titles.len()).then_some(index + 1)),
+            ];
+            for (icon, tooltip, swap_with) in buttons {
+                let button = Button::builder()
+                    .icon_name(icon)
+                    .tooltip_text(tooltip)
+                    .css_classes(vec!["flat"])
+                    .sensitive(swap_with.is_some())
+                    .build();
+                let chapters = chapters_for_rows.clone();
+                let refresh = refresh_for_rows.clone();
+                button.connect_clicked(move |_| {
+                    if let Some(other) = swap_with {
+                        chapters.borrow_mut().swap(index, other);
+                        if let Some(refresh) = refresh.upgrade() {
+                            run_refresh(&refresh);
+                        }
+                    }
+                });
+                row.append(&button);
+            }
+            let remove_button = Button::builder()
+                .icon_name("list-remove-symbolic")
+                .tooltip_text("Remove")
+                .css_classes(vec!["flat"])
+                .build();
+            let chapters = chapters_for_rows.clone();
+            let refresh = refresh_for_rows.clone();
+            remove_button.connect_clicked(move |_| {
+                chapters.borrow_mut().remove(index);
+                if let Some(refresh) = refresh.upgrade() {
+                    run_refresh(&refresh);
+                }
+            });
+            row.append(&remove_button);
+            chapter_list_for_rows.append(&row);
+        }
+    }));
+
+    let chapters_for_folder = chapters.clone();
+    let refresh_for_folder = refresh.clone();
+    let title_entry_for_folder = title_entry.clone();
+    folder_choice.connect_selected_notify(move |choice| {
+        let folder = match choice.selected() {
+            0 => return, // Notes are added one by one
+            1 => String::new(),
+            index => folders.get(index as usize - 2).cloned().unwrap_or_default(),
+        };
+        match manuscript::folder_titles(&folder) {
+            Ok(titles) => *chapters_for_folder.borrow_mut() = titles,
+            Err(e) => eprintln!("Error listing notes of {}: {}", folder, e),
+        }
+        // A book folder is usually named after the book
+        if title_entry_for_folder.text().is_empty() && !folder.is_empty() {
+            let name = folder.rsplit('/').next().unwrap_or(&folder);
+            title_entry_for_folder.set_text(name);
+        }
+        run_refresh(&refresh_for_folder);
+    });
+
+    let chapters_for_add = chapters.clone();
+    let refresh_for_add = refresh.clone();
+    add_button.connect_clicked(move |_| {
+        if let Some(title) = note_titles.get(note_choice.selected() as usize) {
+            chapters_for_add.borrow_mut().push(title.clone());
+            run_refresh(&refresh_for_add);
+        }
+    });
+
+    let dialog_for_cancel = dialog.clone();
+    cancel_button.connect_clicked(move |_| {
+        dialog_for_cancel.close();
+    });
+
+    let dialog_for_compile = dialog.clone();
+    let title_entry_for_compile = title_entry.clone();
+    let parent_for_compile = parent.clone();
+    let settings_for_compile = settings.clone();
+    compile_button.connect_clicked(move |_| {
+        let format = Format::ALL.get(format_choice.selected() as usize).copied().unwrap_or(Format::Epub);
+        let title = match title_entry_for_compile.text().trim() {
+            "" => "Untitled".to_string(),
+            title => title.to_string(),
+        };
+        let mut book = match Manuscript::from_titles(&title, author_entry.text().trim(), &chapters.borrow()) {
+            Ok(book) => book,
+            Err(e) => {
+                crate::utils::show_error_dialog(&parent_for_compile, "Compile Error", &e);
+                return;
+            }
+        };
+        book.paper_size = settings_for_compile.borrow().print.paper_size.clone();
+        dialog_for_compile.close();
+
+        let file_dialog = FileDialog::builder()
+            .title("Compile Manuscript")
+            .initial_name(format!("{}.{}", title, format.extension()))
+            .modal(true)
+            .build();
+        let parent_for_result = parent_for_compile.clone();
+        file_dialog.save(Some(&parent_for_compile), gio::Cancellable::NONE, move |result| {
+            let destination = match result.ok().and_then(|file| file.path()) {
+                Some(path) => path,
+                None => return, // Cancelled
+            };
+            if let Err(e) = book.write(format, &destination) {
+                crate::utils::show_error_dialog(&parent_for_result, "Compile Error", &e);
+            }
+        });
+    });
+
+    dialog.set_child(Some(&content_box));
+    dialog.present();
+    title_entry.grab_focus();
+}
+
+/// The user's full name, if the system knows it
+fn author_name() -> String {
+    let name = glib::real_name().to_string_lossy().trim().to_string();
+    if name == "Unknown" { String::new() } else { name }
+}
+
+fn run_refresh(refresh: &Refresh) {
+    if let Some(refresh) = refresh.borrow().as_ref() {
+        refresh();
+    }
+}
@@ -0,0 +1,648 @@
+// Plain-text Markdown helpers shared by the editor features.
+// Everything here works on `&str` so it stays independent of GTK.
+
+/// Indentation inserted by Tab on a list item (4 spaces nests both bullet and ordered items)
+pub const LIST_INDENT: &str = "    ";
+
+/// The kind of block prefix at the start of a line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListKind {
+    /// `- item`, `* item` or `+ item`
+    Bullet(char),
+    /// `1. item` or `1) item`
+    Ordered { number: u32, delimiter: char },
+    /// `- [ ] item` or `- [x] item`
+    Task { bullet: char, checked: bool },
+    /// `> quoted text`
+    Quote,
+}
+
+/// A line that starts with a list or quote prefix
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem {
+    /// Leading whitespace before the marker
+    pub indent: String,
+    pub kind: ListKind,
+    /// Length of indent + marker (including the trailing space), in chars
+    pub prefix_chars: usize,
+    /// Text after the prefix
+    pub content: String,
+}
+
+impl ListItem {
+    /// The marker to start the next item with when pressing Enter
+    pub fn next_marker(&self) -> String {
+        match &self.kind {
+            ListKind::Bullet(bullet) => format!("{} ", bullet),
+            ListKind::Ordered { number, delimiter } => format!("{}{} ", number + 1, delimiter),
+            ListKind::Task { bullet, .. } => format!("{} [ ] ", bullet), // New tasks start unchecked
+            ListKind::Quote => "> ".to_string(),
+        }
+    }
+
+    /// Whether the item has nothing after its marker
+    pub fn is_empty(&self) -> bool {
+        self.content.trim().is_empty()
+    }
+}
+
+/// Parse a list/quote prefix at the start of a line
+pub fn parse_list_item(line: &str) -> Option<ListItem> {
+    let indent_len = line.len() - line.trim_start_matches([' ', '\t']).len();
+    let indent = &line[..indent_len];
+    let rest = &line[indent_len..];
+
+    let (kind, marker_len) = if let Some(after) = rest.strip_prefix('>') {
+        // Quotes: "> text" or a bare ">"
+        let marker_len = if after.starts_with(' ') { 2 } else { 1 };
+        (ListKind::Quote, marker_len)
+    } else if let Some(bullet) = rest.chars().next().filter(|c| matches!(c, '-' | '*' | '+')) {
+        let after = &rest[1..];
+        if !(after.starts_with(' ') || after.is_empty()) {
+            return None; // "-word" or "**bold**" is not a list item
+        }
+        // Task items: "- [ ] " / "- [x] "
+        let task = after.strip_prefix(' ').and_then(|a| {
+            if a.starts_with("[ ]") {
+                Some(false)
+            } else if a.starts_with("[x]") || a.starts_with("[X]") {
+                Some(true)
+            } else {
+                None
+            }
+        });
+        match task {
+            Some(checked) => {
+                let marker_len = if rest[5..].starts_with(' ') { 6 } else { 5 };
+                (ListKind::Task { bullet, checked }, marker_len)
+            }
+            None => (ListKind::Bullet(bullet), if after.is_empty() { 1 } else { 2 }),
+        }
+    } else {
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.is_empty() || digits.len() > 9 {
+            return None;
+        }
+        let after = &rest[digits.len()..];
+        let delimiter = after.chars().next().filter(|c| *c == '.' || *c == ')')?;
+        let after_delim = &after[1..];
+        if !(after_delim.starts_with(' ') || after_delim.is_empty()) {
+            return None; // "3.14" is not a list item
+        }
+        let number = digits.parse().ok()?;
+        let marker_len = digits.len() + if after_delim.is_empty() { 1 } else { 2 };
+        (ListKind::Ordered { number, delimiter }, marker_len)
+    };
+
+    Some(ListItem {
+        indent: indent.to_string(),
+        kind,
+        prefix_chars: indent.chars().count() + marker_len, // Markers are ASCII
+        content: rest[marker_len..].to_string(),
+    })
+}
+
+/// Leading whitespace of a line
+pub fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Number of chars to remove from the start of a line to outdent it one level
+pub fn outdent_width(line: &str) -> usize {
+    if line.starts_with('\t') {
+        return 1;
+    }
+    line.chars()
+        .take(LIST_INDENT.len())
+        .take_while(|c| *c == ' ')
+        .count()
+}
+
+/// Character range (start, end) of the number in an ordered list line, plus its value
+pub fn ordered_number_span(line: &str) -> Option<(usize, usize, u32)> {
+    let item = parse_list_item(line)?;
+    if let ListKind::Ordered { number, .. } = item.kind {
+        let start = item.indent.chars().count();
+        let end = start + number.to_string().len();
+        Some((start, end, number))
+    } else {
+        None
+    }
+}
+
+/// Compute renumbering for an ordered list that continues after `lines[0]`.
+/// Returns `(line index, char start, char end, new number)` for every line whose
+/// number is out of sequence. Nested (deeper-indented) lines are skipped; the list
+/// ends at the first line that is not a same-level ordered item.
+pub fn renumber_ordered_list(lines: &[&str]) -> Vec<(usize, usize, usize, u32)> {
+    let mut edits = Vec::new();
+    let first = match lines.first().and_then(|l| parse_list_item(l)) {
+        Some(item) => item,
+        None => return edits,
+    };
+    let mut expected = match first.kind {
+        ListKind::Ordered { number, .. } => number + 1,
+        _ => return edits,
+    };
+    let level = first.indent.len();
+
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        let indent_len = leading_whitespace(line).len();
+        if line.trim().is_empty() {
+            break;
+        }
+        if indent_len > level {
+            continue; // Nested content belongs to the previous item
+        }
+        match (indent_len == level, ordered_number_span(line)) {
+            (true, Some((start, end, number))) => {
+                if number != expected {
+                    edits.push((index, start, end, expected));
+                }
+                expected += 1;
+            }
+            _ => break,
+        }
+    }
+
+    edits
+}
+
+/// Closing character for an auto-paired opening character
+pub fn closing_pair(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '`' => Some('`'),
+        '*' => Some('*'),
+        '_' => Some('_'),
+        '~' => Some('~'),
+        _ => None,
+    }
+}
+
+/// Whether typing `open` with no selection should insert its closing pair too.
+/// Only brackets do: emphasis markers and backticks are too common on their own.
+pub fn pairs_without_selection(open: char) -> bool {
+    matches!(open, '(' | '[' | '{')
+}
+
+/// How toggling an inline marker (e.g. `**`) should change the selection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InlineToggle {
+    /// Add the marker on both sides of the selection
+    Wrap,
+    /// The selection itself starts and ends with the marker: strip it
+    UnwrapInside,
+    /// The marker sits just outside the selection: remove it there
+    UnwrapOutside,
+}
+
+/// Decide how to toggle `marker` around `selected`, given the text just before and after it.
+/// Runs of the marker char are counted so that `*` (italic) and `**` (bold) can be told
+/// apart inside `***both***`.
+pub fn inline_toggle(before: &str, selected: &str, after: &str, marker: &str) -> InlineToggle {
+    let marker_char = match marker.chars().next() {
+        Some(c) => c,
+        None => return InlineToggle::Wrap,
+    };
+    let marker_len = marker.chars().count();
+    let has_marker = |run: usize| {
+        if marker_len == 1 { run % 2 == 1 } else { run >= marker_len }
+    };
+    let leading = |s: &str| s.chars().take_while(|c| *c == marker_char).count();
+    let trailing = |s: &str| s.chars().rev().take_while(|c| *c == marker_char).count();
+
+    let selected_len = selected.chars().count();
+    if selected_len > 2 * marker_len && has_marker(leading(selected)) && has_marker(trailing(selected)) {
+        InlineToggle::UnwrapInside
+    } else if has_marker(trailing(before)) && has_marker(leading(after)) {
+        InlineToggle::UnwrapOutside
+    } else {
+        InlineToggle::Wrap
+    }
+}
+
+/// Heading level of a line (0 if not a heading) and the length of its `## ` prefix in chars
+pub fn heading_level(line: &str) -> (usize, usize) {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    if hashes == 0 || hashes > 6 {
+        return (0, 0);
+    }
+    match line[hashes..].chars().next() {
+        Some(' ') => (hashes, hashes + 1),
+        None => (hashes, hashes),
+        _ => (0, 0), // "#tag" is not a heading
+    }
+}
+
+/// Prefix for the next heading level when cycling: none → # → ## … → ###### → none
+pub fn next_heading_prefix(level: usize) -> String {
+    if level >= 6 {
+        String::new()
+    } else {
+        format!("{} ", "#".repeat(level + 1))
+    }
+}
+
+/// Length in chars of a leading `> ` quote marker, if the line is quoted
+pub fn quote_prefix_len(line: &str) -> Option<usize> {
+    if line.starts_with("> ") {
+        Some(2)
+    } else if line.starts_with('>') {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Edit that toggles a checklist item on a line: `(char start, char len, replacement)`.
+/// Plain lines and bullets become unchecked tasks; tasks flip between `[ ]` and `[x]`.
+pub fn toggle_task(line: &str) -> (usize, usize, String) {
+    match parse_list_item(line) {
+        Some(item) => {
+            let indent = item.indent.chars().count();
+            match item.kind {
+                ListKind::Task { checked, .. } => {
+                    // The box sits after "- [", i.e. 3 chars past the indent
+                    let mark = if checked { " " } else { "x" };
+                    (indent + 3, 1, mark.to_string())
+                }
+                ListKind::Bullet(bullet) => (indent, item.prefix_chars - indent, format!("{} [ ] ", bullet)),
+                ListKind::Ordered { .. } | ListKind::Quote => {
+                    // Keep the existing marker, add a box after it
+                    (item.prefix_chars, 0, "[ ] ".to_string())
+                }
+            }
+        }
+        None => {
+            let indent = leading_whitespace(line).chars().count();
+            (indent, 0, "- [ ] ".to_string())
+        }
+    }
+}
+
+/// A Markdown ATX heading found in a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    /// Zero-based line number
+    pub line: usize,
+    pub level: usize,
+    pub title: String,
+}
+
+/// Whether a line opens or closes a fenced code block (``` or ~~~)
+pub fn is_code_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// Collect the `#` headings of a document, skipping fenced code blocks
+pub fn headings(text: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut in_code_block = false;
+
+    for (line_number, line) in text.lines().enumerate() {
+        if is_code_fence(line) {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        let (level, prefix_len) = heading_level(line);
+        if level > 0 {
+            let title = line[prefix_len..].trim().trim_end_matches('#').trim_end().to_string();
+            headings.push(Heading { line: line_number, level, title });
+        }
+    }
+
+    headings
+}
+
+/// A `[[Target]]` or `[[Target|label]]` wiki link found in a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct WikiLink {
+    /// Byte range of the whole `[[...]]` in the scanned text
+    pub start: usize,
+    pub end: usize,
+    /// Title of the linked note (without any `#heading` or `|label` part)
+    pub target: String,
+    /// Text the link displays: the label after `|`, else the target
+    pub label: String,
+}
+
+/// Collect the wiki links of a document, skipping fenced code blocks
+pub fn wiki_links(text: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    let mut in_code_block = false;
+    let mut line_start = 0;
+
+    for line in text.split_inclusive('\n') {
+        if is_code_fence(line) {
+            in_code_block = !in_code_block;
+        } else if !in_code_block {
+            let mut search_from = 0;
+            while let Some(open) = line[search_from..].find("[[").map(|i| search_from + i) {
+                let inner_start = open + 2;
+                let close = match line[inner_start..].find("]]") {
+                    Some(i) => inner_start + i,
+                    None => break,
+                };
+                let inner = &line[inner_start..close];
+                // `[[a [[b]]` links to b: restart at the innermost opening
+                if let Some(nested) = inner.rfind("[[") {
+                    search_from = inner_start + nested;
+                    continue;
+                }
+                let (target_part, label) = match inner.split_once('|') {
+                    Some((target, label)) => (target, Some(label.trim())),
+                    None => (inner, None),
+                };
+                let target = wiki_link_title(target_part).trim();
+                if !target.is_empty() && !inner.contains(['[', ']', '\n']) {
+                    links.push(WikiLink {
+                        start: line_start + open,
+                        end: line_start + close + 2,
+                        target: target.to_string(),
+                        label: label.filter(|l| !l.is_empty()).unwrap_or(target).to_string(),
+                    });
+                }
+                search_from = close + 2;
+            }
+        }
+        line_start += line.len();
+    }
+
+    links
+}
+
+/// The note title part of a link's inner text: everything before `#` or `|`
+fn wiki_link_title(inner: &str) -> &str {
+    let end = inner.find(['#', '|']).unwrap_or(inner.len());
+    &inner[..end]
+}
+
+/// Form in which link targets and note titles are compared: `[[über]]` links to "Über"
+pub fn title_key(title: &str) -> String {
+    title.trim().to_lowercase()
+}
+
+/// Point every wiki link to `old_title` at `new_title`, keeping any `#heading` and `|label`.
+/// Targets match ignoring case, as when following a link. Returns None if the text has no such links.
+pub fn rename_wiki_links(text: &str, old_title: &str, new_title: &str) -> Option<String> {
+    let old_key = title_key(old_title);
+    let links: Vec<WikiLink> = wiki_links(text).into_iter()
+        .filter(|link| title_key(&link.target) == old_key)
+        .collect();
+    if links.is_empty() {
+        return None;
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut copied_to = 0;
+    for link in links {
+        let inner = &text[link.start + 2..link.end - 2];
+        let rest = &inner[wiki_link_title(inner).len()..];
+        result.push_str(&text[copied_to..link.start]);
+        result.push_str("[[");
+        result.push_str(new_title);
+        result.push_str(rest);
+        result.push_str("]]");
+        copied_to = link.end;
+    }
+    result.push_str(&text[copied_to..]);
+    Some(result)
+}
+
+/// If the text before the cursor ends inside an unclosed `[[`, the partial title typed so far.
+/// Completion stops once a `|` label or `#` heading is being typed.
+pub fn wiki_link_query(before_cursor: &str) -> Option<&str> {
+    let open = before_cursor.rfind("[[")?;
+    let query = &before_cursor[open + 2..];
+    if query.contains([']', '|', '#']) {
+        return None;
+    }
+    Some(query)
+}
+
+/// Inline `#tags` in a document, in order of first use. Headings (`# Title`),
+/// fenced code, inline code and numbers like `#1` are not tags.
+pub fn inline_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut in_code_block = false;
+
+    for line in text.lines() {
+        if is_code_fence(line) {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let mut in_code_span = false;
+        let mut previous: Option<char> = None;
+        for (index, ch) in line.char_indices() {
+            if ch == '`' {
+                in_code_span = !in_code_span;
+            } else if ch == '#' && !in_code_span && previous.is_none_or(char::is_whitespace) {
+                let tag: String = line[index + 1..].chars()
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                    .collect();
+                let tag = tag.trim_end_matches(['-', '/']);
+                if tag.chars().any(|c| !c.is_ascii_digit()) && !tags.iter().any(|t| t == tag) {
+                    tags.push(tag.to_string());
+                }
+            }
+            previous = Some(ch);
+        }
+    }
+
+    tags
+}
+
+/// Line range `[start, end)` of the section under `headings[index]`: the heading line up to
+/// the next heading of the same or a higher level
+pub fn section_lines(headings: &[Heading], index: usize, line_count: usize) -> (usize, usize) {
+    let heading = &headings[index];
+    let end = headings[index + 1..].iter()
+        .find(|next| next.level <= heading.level)
+        .map(|next| next.line)
+        .unwrap_or(line_count);
+    (heading.line, end)
+}
+
+/// Move the section of heading `from` (with its subsections) before the section of heading `to`,
+/// or after it if `after` is set. Returns the new text and the moved heading's new line,
+/// or None if the move is a no-op or would put a section inside itself.
+pub fn move_section(text: &str, from: usize, to: usize, after: bool) -> Option<(String, usize)> {
+    let all_headings = headings(text);
+    if from >= all_headings.len() || to >= all_headings.len() {
+        return None;
+    }
+
+    // Work on lines without the final newline so it stays at the end
+    let (body, trailing_newline) = match text.strip_suffix('\n') {
+        Some(body) => (body, true),
+        None => (text, false),
+    };
+    let mut lines: Vec<&str> = body.split('\n').collect();
+
+    let (start, end) = section_lines(&all_headings, from, lines.len());
+    let (to_start, to_end) = section_lines(&all_headings, to, lines.len());
+    let insert_at = if after { to_end } else { to_start };
+    if insert_at >= start && insert_at <= end {
+        return None;
+    }
+
+    let section: Vec<&str> = lines.drain(start..end).collect();
+    let insert_at = if insert_at > start { insert_at - section.len() } else { insert_at };
+    lines.splice(insert_at..insert_at, section);
+
+    let mut result = lines.join("\n");
+    if trailing_newline {
+        result.push('\n');
+    }
+    Some((result, insert_at))
+}
+
+/// Byte range of the line containing `pos` (without its newline)
+pub fn line_range(text: &str, pos: usize) -> (usize, usize) {
+    let start = text[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let end = text[pos..].find('\n').map(|i| pos + i).unwrap_or(text.len());
+    (start, end)
+}
+
+/// Byte range of the paragraph containing `pos`: the surrounding lines up to blank lines
+pub fn paragraph_range(text: &str, pos: usize) -> (usize, usize) {
+    let (mut start, mut end) = line_range(text, pos);
+    if text[start..end].trim().is_empty() {
+        return (start, end);
+    }
+    while start > 0 {
+        let (previous_start, previous_end) = line_range(text, start - 1);
+        if text[previous_start..previous_end].trim().is_empty() {
+            break;
+        }
+        start = previous_start;
+    }
+    while end < text.len() {
+        let (next_start, next_end) = line_range(text, end + 1);
+        if text[next_start..next_end].trim().is_empty() {
+            break;
+        }
+        end = next_end;
+    }
+    (start, end)
+}
+
+/// Byte range of the sentence containing `pos`. Sentences end after `.`, `!` or `?`
+/// followed by whitespace, and never extend past their paragraph.
+pub fn sentence_range(text: &str, pos: usize) -> (usize, usize) {
+    let (paragraph_start, paragraph_end) = paragraph_range(text, pos);
+    let paragraph = &text[paragraph_start..paragraph_end];
+    let pos_in_paragraph = pos - paragraph_start;
+
+    // Offsets just after each sentence terminator (and the whitespace that follows it)
+    let mut boundaries = vec![0];
+    let mut chars = paragraph.char_indices().peekable();
+    while let Some((index, ch)) = chars.next() {
+        if matches!(ch, '.' | '!' | '?') && chars.peek().is_some_and(|(_, next)| next.is_whitespace()) {
+            let mut boundary = index + ch.len_utf8();
+            while let Some(&(next_index, next)) = chars.peek() {
+                if !next.is_whitespace() {
+                    break;
+                }
+                boundary = next_index + next.len_utf8();
+                chars.next();
+            }
+            boundaries.push(boundary);
+        }
+    }
+    boundaries.push(paragraph.len());
+
+    // At the very end of the paragraph the cursor belongs to the last sentence
+    let start = boundaries.iter().rev()
+        .find(|&&b| b <= pos_in_paragraph && (b < paragraph.len() || b == 0))
+        .copied()
+        .unwrap_or(0);
+    let end = boundaries.iter().find(|&&b| b > pos_in_paragraph).copied().unwrap_or(paragraph.len());
+    (paragraph_start + start, paragraph_start + end)
+}
+
+/// A link destination, in angle brackets when it holds spaces or parentheses
+pub fn link_destination(dest: &str) -> String {
+    if dest.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("<{}>", dest.replace('>', "%3E"))
+    } else {
+        dest.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Headings: 0 "A", 1 "A.1", 2 "B", 3 "C"
+    const DOCUMENT: &str = "Intro\n# A\na\n## A.1\na1\n# B\nb\n# C\nc\n";
+
+    #[test]
+    fn moves_the_first_section_to_the_end() {
+        assert_eq!(
+            move_section(DOCUMENT, 0, 3, true),
+            Some(("Intro\n# B\nb\n# C\nc\n# A\na\n## A.1\na1\n".to_string(), 5)),
+        );
+    }
+
+    #[test]
+    fn moves_the_last_section_to_the_start() {
+        assert_eq!(
+            move_section(DOCUMENT, 3, 0, false),
+            Some(("Intro\n# C\nc\n# A\na\n## A.1\na1\n# B\nb\n".to_string(), 1)),
+        );
+    }
+
+    #[test]
+    fn keeps_a_missing_final_newline_missing() {
+        let text = DOCUMENT.trim_end();
+        assert_eq!(
+            move_section(text, 3, 2, false),
+            Some(("Intro\n# A\na\n## A.1\na1\n# C\nc\n# B\nb".to_string(), 5)),
+        );
+    }
+
+    #[test]
+    fn moves_subsections_with_their_parent() {
+        assert_eq!(
+            move_section(DOCUMENT, 0, 2, true),
+            Some(("Intro\n# B\nb\n# A\na\n## A.1\na1\n# C\nc\n".to_string(), 3)),
+        );
+    }
+
+    #[test]
+    fn moves_a_nested_section_out_of_its_parent() {
+        assert_eq!(
+            move_section(DOCUMENT, 1, 3, true),
+            Some(("Intro\n# A\na\n# B\nb\n# C\nc\n## A.1\na1\n".to_string(), 7)),
+        );
+    }
+
+    #[test]
+    fn refuses_moves_into_the_section_itself_or_in_place() {
+        assert_eq!(move_section(DOCUMENT, 0, 1, true), None);
+        assert_eq!(move_section(DOCUMENT, 0, 0, false), None);
+        assert_eq!(move_section(DOCUMENT, 2, 3, false), None);
+        assert_eq!(move_section(DOCUMENT, 0, 9, false), None);
+    }
+
+    #[test]
+    fn ignores_headings_in_code_blocks() {
+        let text = "# A\n```\n# not a heading\n```\n# B\n";
+        assert_eq!(
+            move_section(text, 1, 0, false),
+            Some(("# B\n# A\n```\n# not a heading\n```\n".to_string(), 0)),
+        );
+    }
+}
@@ -16,12 +16,22 @@ pub struct Note {
 impl Note {
     /// Create a new empty note with the given title
     pub fn new(title: &str) -> Result<Self, String> {
-        let notes_dir = crate::utils::get_notes_dir();
-        let file_path = notes_dir.join(format!("{}.md", title));
+        Self::new_in_folder("", title)
+    }
 
-        // Create an empty file
-        File::create(&file_path)
-            .map_err(|e| format!("Failed to create note file: {}", e))?;
+    /// Create a new empty note in a folder of the notes directory ("" for the top level)
+    pub fn new_in_folder(folder: &str, title: &str) -> Result<Self, String> {
+        let folder_path = crate::utils::get_notes_dir().join(folder);
+        fs::create_dir_all(&folder_path)
+            .map_err(|e| format!("Failed to create folder {:?}: {}", folder_path, e))?;
+        let file_path = folder_path.join(format!("{}.md", title));
+
+        // Create an empty file, never replacing a note that is already there
+        File::options().write(true).create_new(true).open(&file_path)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AlreadyExists => format!("A note named \"{}\" already exists.", title),
+                _ => format!("Failed to create note file: {}", e),
+            })?;
 
         // Get metadata after creation
         let metadata = fs::metadata(&file_path)
@@ -81,6 +91,16 @@ impl Note {
         Ok(())
     }
 
+    /// Set the file's modification time, e.g. to keep the date of an imported note
+    pub fn set_modified_time(&mut self, time: SystemTime) -> Result<(), String> {
+        let file = File::options().write(true).open(&self.path)
+            .map_err(|e| format!("Failed to open note file: {}", e))?;
+        file.set_modified(time)
+            .map_err(|e| format!("Failed to set the modification time: {}", e))?;
+        self.modified_time = Some(time);
+        Ok(())
+    }
+
     /// Delete this note
     pub fn delete(&self) -> Result<(), String> {
         fs::remove_file(&self.path)
@@ -95,11 +115,13 @@ impl Note {
         }
         // Add more validation if needed (e.g., disallowed characters)
 
-        let notes_dir = crate::utils::get_notes_dir();
-        let new_path = notes_dir.join(format!("{}.md", new_title));
+        // Stay in the same folder
+        let new_path = self.path.with_file_name(format!("{}.md", new_title));
 
-        // Check if a note with the new title already exists
-        if new_path.exists() && new_path != self.path {
+        // Check if a note with the new title already exists (in any folder)
+        let taken = [new_path.clone(), Self::path_for_title(new_title)].iter()
+            .any(|path| path.exists() && *path != self.path);
+        if taken {
             return Err(format!("A note named \"\"{}\"\" already exists.", new_title));
         }
 
@@ -108,8 +130,13 @@ impl Note {
             .map_err(|e| format!("Failed to rename note file: {}", e))?;
 
         // Update the note's state
+        let old_title = std::mem::replace(&mut self.title, new_title.to_string());
         self.path = new_path;
-        self.title = new_title.to_string();
+
+        // Keep [[links]] in other notes (and this one) pointing at the note
+        if let Err(e) = self.rewrite_inbound_links(&old_title) {
+            eprintln!("Failed to update links to renamed note: {}", e);
+        }
 
         // Update modification time (optional, renaming might not update it)
         let metadata = fs::metadata(&self.path)
@@ -119,26 +146,36 @@ impl Note {
         Ok(())
     }
 
-    /// Get all notes in the notes directory
+    /// Rewrite `[[old_title]]` links in every note to point at this note's current title
+    fn rewrite_inbound_links(&mut self, old_title: &str) -> Result<(), String> {
+        for mut note in Note::get_all()? {
+            if let Some(content) = crate::markdown::rename_wiki_links(&note.content, old_title, &self.title) {
+                note.content = content;
+                if let Err(e) = note.save() {
+                    eprintln!("Failed to update links in note {:?}: {}", note.path, e);
+                    continue;
+                }
+                if note.path == self.path {
+                    self.content = note.content;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get all notes in the notes directory and its folders
     pub fn get_all() -> Result<Vec<Note>, String> {
         let notes_dir = crate::utils::get_notes_dir();
-        let entries = fs::read_dir(&notes_dir)
+        let mut paths = Vec::new();
+        collect_note_paths(&notes_dir, &mut paths)
             .map_err(|e| format!("Failed to read notes directory: {}", e))?;
 
         let mut notes = Vec::new();
-
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-
-                // Only process .md files
-                if path.is_file() && path.extension().map_or(false, |ext| ext == "md") {
-                    // Load the note which now includes metadata reading
-                    match Note::load(&path) {
-                        Ok(note) => notes.push(note),
-                        Err(e) => eprintln!("Error loading note {:?}: {}", path, e),
-                    }
-                }
+        for path in paths {
+            // Load the note which now includes metadata reading
+            match Note::load(&path) {
+                Ok(note) => notes.push(note),
+                Err(e) => eprintln!("Error loading note {:?}: {}", path, e),
             }
         }
 
@@ -150,19 +187,68 @@ impl Note {
         Ok(notes)
     }
 
+    /// File of the note with a title, in whichever folder it is. Titles are unique
+    /// across folders; a title without a note maps to the top level.
+    pub fn path_for_title(title: &str) -> PathBuf {
+        let notes_dir = crate::utils::get_notes_dir();
+        let top_level = notes_dir.join(format!("{}.md", title));
+        if top_level.exists() {
+            return top_level;
+        }
+        let file_name = format!("{}.md", title);
+        let mut paths = Vec::new();
+        if let Err(e) = collect_note_paths(&notes_dir, &mut paths) {
+            eprintln!("Error reading notes directory: {}", e);
+        }
+        paths.into_iter()
+            .find(|path| path.file_name().is_some_and(|name| *name == *file_name))
+            .unwrap_or(top_level)
+    }
+
+    /// Title of the note a link target refers to, matched as links are (see
+    /// `markdown::title_key`). Only file names are read, not the notes.
+    pub fn existing_title(target: &str) -> Option<String> {
+        if Self::path_for_title(target).exists() {
+            return Some(target.to_string());
+        }
+        let wanted = crate::markdown::title_key(target);
+        let mut paths = Vec::new();
+        if let Err(e) = collect_note_paths(&crate::utils::get_notes_dir(), &mut paths) {
+            eprintln!("Error reading notes directory: {}", e);
+        }
+        paths.iter()
+            .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()))
+            .find(|title| crate::markdown::title_key(title) == wanted)
+            .map(str::to_string)
+    }
+
     /// Generate a new unique note title with the current date
     pub fn generate_unique_title() -> String {
         let now: DateTime<Local> = Local::now();
-        let formatted_date = now.format("%Y-%m-%d").to_string();
+        Self::unique_title(&format!("Note {}", now.format("%Y-%m-%d")))
+    }
 
-        let notes_dir = crate::utils::get_notes_dir();
+    /// Generate a new unique note title with a prefix and the current date and time
+    pub fn generate_timestamped_title(prefix: &str) -> String {
+        let now: DateTime<Local> = Local::now();
+        Self::unique_title(&format!("{} {}", prefix, now.format("%Y-%m-%d %H.%M")))
+    }
+
+    /// `base`, or `base (2)`, `base (3)`, ... if a note with that title already exists
+    fn unique_title(base: &str) -> String {
+        Self::unique_title_among(base, |title| Self::path_for_title(title).exists())
+    }
+
+    /// `base`, or `base (2)`, `base (3)`, ... for the first title `is_taken` says is free.
+    /// Faster than looking up each title when many notes are created at once.
+    pub fn unique_title_among(base: &str, is_taken: impl Fn(&str) -> bool) -> String {
         let mut note_number = 1;
-        let mut title = format!("Note {}", formatted_date);
+        let mut title = base.to_string();
 
-        // Check if file already exists, increment until we find a unique name
-        while notes_dir.join(format!("{}.md", title)).exists() {
+        // Increment until we find a unique name
+        while is_taken(&title) {
             note_number += 1;
-            title = format!("Note {} ({})", formatted_date, note_number);
+            title = format!("{} ({})", base, note_number);
         }
 
         title
@@ -176,6 +262,7 @@ impl Note {
     
     /// Update note title with today's date if it's empty and old
     /// Returns Ok(true) if the title was updated, Ok(false) otherwise.
+    #[allow(dead_code)] // Kept for the planned auto-title feature
     pub fn update_title_if_empty_and_old(&mut self) -> Result<bool, String> {
         // Only update empty notes
         if !self.is_empty() {
@@ -200,6 +287,7 @@ impl Note {
     }
 
     /// Generate title from the note's content automatically
+    #[allow(dead_code)] // Kept for the planned auto-title feature
     pub fn generate_title_from_content(&self) -> Option<String> {
         // Extract the first non-empty line
         let first_line = self.content
@@ -221,8 +309,59 @@ impl Note {
         Some(title.to_string())
     }
 
+    /// Alternative titles listed under `aliases:` in the note's front matter
+    pub fn aliases(&self) -> Vec<String> {
+        crate::frontmatter::parse(&self.content).get_list("aliases").to_vec()
+    }
+
+    /// Tags from the front matter `tags:` field and inline `#tags` in the body
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = crate::frontmatter::parse(&self.content).get_list("tags").to_vec();
+        let (_, body) = crate::frontmatter::split(&self.content);
+        for tag in crate::markdown::inline_tags(body) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Folder of the note relative to the notes directory ("" for the top level)
+    pub fn folder(&self) -> String {
+        std::path::Path::new(&self.relative_path())
+            .parent()
+            .map(|parent| parent.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Path of the note file relative to the notes directory
+    pub fn relative_path(&self) -> String {
+        let notes_dir = crate::utils::get_notes_dir();
+        self.path.strip_prefix(&notes_dir)
+            .unwrap_or(&self.path)
+            .to_string_lossy()
+            .to_string()
+    }
+
     /// Check if this note has a default date-based title
+    #[allow(dead_code)] // Kept for the planned auto-title feature
     pub fn has_default_title(&self) -> bool {
         self.title.starts_with("Note 20")
     }
 }
+
+/// Recursively collect the `.md` files under a directory, skipping hidden folders
+pub fn collect_note_paths(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden {
+            if let Err(e) = collect_note_paths(&path, paths) {
+                eprintln!("Error reading folder {:?}: {}", path, e);
+            }
+        } else if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
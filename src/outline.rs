@@ -0,0 +1,237 @@
+use gtk::prelude::*;
+use gtk::{gdk, glib, Box, DragSource, DropTarget, Label, ListBox, Orientation, ScrolledWindow, TextView};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::markdown::{self, Heading};
+
+// Rebuild the outline this long after the last edit
+const REFRESH_DELAY_MS: u64 = 300;
+// Indentation per heading level in the tree
+const LEVEL_INDENT: i32 = 12;
+
+/// Sidebar listing the headings of the open note as an indented tree.
+/// Clicking jumps to a heading, dragging a heading moves its whole section,
+/// and the section containing the cursor is highlighted.
+#[derive(Clone)]
+pub struct OutlinePanel {
+    container: Box,
+    list_box: ListBox,
+    text_view: TextView,
+    headings: Rc<RefCell<Vec<Heading>>>,
+    pending_refresh: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl OutlinePanel {
+    pub fn new(text_view: &TextView) -> Self {
+        let title = Label::builder()
+            .label("Outline")
+            .xalign(0.0)
+            .margin_start(12)
+            .margin_top(12)
+            .margin_bottom(6)
+            .css_classes(vec!["outline-title"])
+            .build();
+
+        let list_box = ListBox::builder()
+            .selection_mode(gtk::SelectionMode::Single)
+            .css_classes(vec!["outline-list"])
+            .build();
+        let placeholder = Label::builder()
+            .label("No headings")
+            .xalign(0.0)
+            .margin_start(12)
+            .css_classes(vec!["dim-label"])
+            .build();
+        list_box.set_placeholder(Some(&placeholder));
+
+        let scrolled_window = ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vexpand(true)
+            .build();
+        scrolled_window.set_child(Some(&list_box));
+
+        let container = Box::builder()
+            .orientation(Orientation::Vertical)
+            .width_request(220)
+            .css_classes(vec!["outline-panel"])
+            .visible(false)
+            .build();
+        container.append(&title);
+        container.append(&scrolled_window);
+
+        let panel = OutlinePanel {
+            container,
+            list_box,
+            text_view: text_view.clone(),
+            headings: Rc::new(RefCell::new(Vec::new())),
+            pending_refresh: Rc::new(RefCell::new(None)),
+        };
+        panel.connect_signals();
+        panel
+    }
+
+    /// The widget to place next to the editor
+    pub fn widget(&self) -> &Box {
+        &self.container
+    }
+
+    /// Show or hide the outline
+    pub fn toggle(&self) {
+        let visible = !self.container.is_visible();
+        self.container.set_visible(visible);
+        if visible {
+            self.refresh();
+        }
+    }
+
+    fn connect_signals(&self) {
+        let buffer = self.text_view.buffer();
+
+        // Rebuild shortly after edits (and when another note is loaded)
+        let panel = self.clone();
+        buffer.connect_changed(move |_| {
+            panel.schedule_refresh();
+        });
+
+        // Follow the cursor
+        let panel = self.clone();
+        buffer.connect_mark_set(move |buffer, _, mark| {
+            if *mark == buffer.get_insert() && panel.container.is_visible() {
+                panel.highlight_current_section();
+            }
+        });
+
+        // Jump to the clicked heading
+        let panel = self.clone();
+        self.list_box.connect_row_activated(move |_, row| {
+            let line = match panel.headings.borrow().get(row.index() as usize) {
+                Some(heading) => heading.line,
+                None => return,
+            };
+            panel.go_to_line(line);
+        });
+    }
+
+    fn schedule_refresh(&self) {
+        if !self.container.is_visible() {
+            return;
+        }
+        if let Some(source_id) = self.pending_refresh.borrow_mut().take() {
+            source_id.remove();
+        }
+        let panel = self.clone();
+        let source_id = glib::timeout_add_local_once(std::time::Duration::from_millis(REFRESH_DELAY_MS), move || {
+            panel.pending_refresh.borrow_mut().take();
+            panel.refresh();
+        });
+        *self.pending_refresh.borrow_mut() = Some(source_id);
+    }
+
+    /// Rebuild the tree from the buffer's headings
+    fn refresh(&self) {
+        let buffer = self.text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true);
+        let headings = markdown::headings(&text);
+
+        // Skip rebuilding (and losing the drag/selection state) if nothing changed
+        if *self.headings.borrow() == headings {
+            self.highlight_current_section();
+            return;
+        }
+
+        while let Some(row) = self.list_box.row_at_index(0) {
+            self.list_box.remove(&row);
+        }
+        let top_level = headings.iter().map(|heading| heading.level).min().unwrap_or(1);
+        for (index, heading) in headings.iter().enumerate() {
+            self.list_box.append(&self.heading_row(index, heading, top_level));
+        }
+        *self.headings.borrow_mut() = headings;
+        self.highlight_current_section();
+    }
+
+    fn heading_row(&self, index: usize, heading: &Heading, top_level: usize) -> gtk::ListBoxRow {
+        let label = Label::builder()
+            .label(if heading.title.is_empty() { "(untitled)" } else { &heading.title })
+            .xalign(0.0)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .margin_start(12 + LEVEL_INDENT * (heading.level - top_level) as i32)
+            .margin_end(12)
+            .margin_top(4)
+            .margin_bottom(4)
+            .tooltip_text(&heading.title)
+            .build();
+        if heading.level == top_level {
+            label.add_css_class("outline-top-level");
+        }
+
+        let row = gtk::ListBoxRow::builder()
+            .css_classes(vec!["outline-row"])
+            .build();
+        row.set_child(Some(&label));
+
+        // Dragging a heading carries its index
+        let drag_source = DragSource::new();
+        drag_source.set_actions(gdk::DragAction::MOVE);
+        drag_source.connect_prepare(move |_, _, _| {
+            Some(gdk::ContentProvider::for_value(&(index as u32).to_value()))
+        });
+        row.add_controller(drag_source);
+
+        // Dropping on the top half moves the section before this heading, the bottom half after its section
+        let drop_target = DropTarget::new(u32::static_type(), gdk::DragAction::MOVE);
+        let panel = self.clone();
+        drop_target.connect_drop(move |target, value, _x, y| {
+            let from = match value.get::<u32>() {
+                Ok(from) => from as usize,
+                Err(_) => return false,
+            };
+            let after = y > target.widget().height() as f64 / 2.0;
+            panel.move_section(from, index, after)
+        });
+        row.add_controller(drop_target);
+
+        row
+    }
+
+    /// Move a heading's section in the buffer as one undoable edit
+    fn move_section(&self, from: usize, to: usize, after: bool) -> bool {
+        let buffer = self.text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true);
+        let (new_text, new_line) = match markdown::move_section(&text, from, to, after) {
+            Some(result) => result,
+            None => return false,
+        };
+
+        buffer.begin_user_action();
+        let (mut start, mut end) = buffer.bounds();
+        buffer.delete(&mut start, &mut end);
+        buffer.insert(&mut start, &new_text);
+        buffer.end_user_action();
+
+        self.refresh();
+        self.go_to_line(new_line);
+        true
+    }
+
+    fn go_to_line(&self, line: usize) {
+        let buffer = self.text_view.buffer();
+        if let Some(mut iter) = buffer.iter_at_line(line as i32) {
+            buffer.place_cursor(&iter);
+            self.text_view.scroll_to_iter(&mut iter, 0.0, true, 0.0, 0.1);
+            self.text_view.grab_focus();
+        }
+    }
+
+    /// Select the heading of the section the cursor is in
+    fn highlight_current_section(&self) {
+        let buffer = self.text_view.buffer();
+        let cursor_line = buffer.iter_at_mark(&buffer.get_insert()).line() as usize;
+        let current = self.headings.borrow().iter().rposition(|heading| heading.line <= cursor_line);
+        match current.and_then(|index| self.list_box.row_at_index(index as i32)) {
+            Some(row) => self.list_box.select_row(Some(&row)),
+            None => self.list_box.unselect_all(),
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use gtk::prelude::*;
+use gtk::{glib, ApplicationWindow, Box, EventControllerFocus, EventControllerKey, Label, ListBox, Orientation,
+          Overlay, ScrolledWindow, SearchEntry, TextView};
+use gtk::gdk::Key;
+use glib::Propagation;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::fuzzy::fuzzy_score;
+use crate::markdown;
+use crate::note::Note;
+use crate::settings::Settings;
+use crate::shortcuts::{self, Command, COMMANDS};
+
+// Maximum number of results shown at once
+const MAX_RESULTS: usize = 50;
+
+/// One selectable entry in the palette
+#[derive(Clone)]
+enum PaletteItem {
+    Command(&'static Command),
+    Note(String),
+    Heading { line: usize, title: String, level: usize },
+}
+
+/// Keyboard-driven overlay listing commands (`>`), notes (plain text) and headings (`#`)
+#[derive(Clone)]
+pub struct CommandPalette {
+    container: Box,
+    entry: SearchEntry,
+    results: ListBox,
+    scrolled_window: ScrolledWindow,
+    items: Rc<RefCell<Vec<PaletteItem>>>,
+    /// Note titles, most recently modified first, read when the palette opens
+    note_titles: Rc<RefCell<Vec<String>>>,
+    window: ApplicationWindow,
+    text_view: TextView,
+    settings: Rc<RefCell<Settings>>,
+}
+
+impl CommandPalette {
+    /// Build the palette and add it (hidden) to the main overlay
+    pub fn new(overlay: &Overlay, window: &ApplicationWindow, text_view: &TextView, settings: &Rc<RefCell<Settings>>) -> Self {
+        let container = Box::builder()
+            .orientation(Orientation::Vertical)
+            .halign(gtk::Align::Center)
+            .valign(gtk::Align::Start)
+            .margin_top(60)
+            .width_request(520)
+            .css_classes(vec!["command-palette"])
+            .visible(false)
+            .build();
+
+        let entry = SearchEntry::builder()
+            .placeholder_text("Type > for commands, # for headings, or a note title")
+            .search_delay(0)
+            .margin_top(8)
+            .margin_bottom(8)
+            .margin_start(8)
+            .margin_end(8)
+            .build();
+
+        let results = ListBox::builder()
+            .selection_mode(gtk::SelectionMode::Single)
+            .css_classes(vec!["palette-results"])
+            .build();
+
+        let scrolled_window = ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .max_content_height(360)
+            .propagate_natural_height(true)
+            .build();
+        scrolled_window.set_child(Some(&results));
+
+        let placeholder = Label::builder()
+            .label("No matches")
+            .xalign(0.0)
+            .margin_start(12)
+            .margin_top(8)
+            .margin_bottom(8)
+            .css_classes(vec!["dim-label"])
+            .build();
+        results.set_placeholder(Some(&placeholder));
+
+        container.append(&entry);
+        container.append(&scrolled_window);
+        overlay.add_overlay(&container);
+
+        let palette = CommandPalette {
+            container,
+            entry,
+            results,
+            scrolled_window,
+            items: Rc::new(RefCell::new(Vec::new())),
+            note_titles: Rc::new(RefCell::new(Vec::new())),
+            window: window.clone(),
+            text_view: text_view.clone(),
+            settings: settings.clone(),
+        };
+        palette.connect_signals();
+        palette
+    }
+
+    /// Show the palette with the entry prefilled (e.g. ">" for commands)
+    pub fn show(&self, prefix: &str) {
+        // Read the notes once per opening rather than on every keystroke
+        *self.note_titles.borrow_mut() = Note::get_all()
+            .map(|notes| notes.into_iter().map(|note| note.title).collect())
+            .unwrap_or_else(|e| {
+                eprintln!("Error listing notes for palette: {}", e);
+                Vec::new()
+            });
+        self.container.set_visible(true);
+        self.entry.set_text(prefix);
+        self.populate(prefix);
+        self.entry.grab_focus();
+        self.entry.set_position(-1); // Cursor after the prefix
+    }
+
+    /// Hide the palette and return focus to the editor
+    pub fn hide(&self) {
+        self.container.set_visible(false);
+        self.text_view.grab_focus();
+    }
+
+    fn connect_signals(&self) {
+        let palette = self.clone();
+        self.entry.connect_search_changed(move |entry| {
+            palette.populate(&entry.text());
+        });
+
+        // Enter runs the highlighted result
+        let palette = self.clone();
+        self.entry.connect_activate(move |_| {
+            palette.activate_selected();
+        });
+
+        // Escape closes
+        let palette = self.clone();
+        self.entry.connect_stop_search(move |_| {
+            palette.hide();
+        });
+
+        // Up/Down move through the results without leaving the entry
+        let key_controller = EventControllerKey::new();
+        key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        let palette = self.clone();
+        key_controller.connect_key_pressed(move |_, key, _keycode, _state| {
+            match key {
+                Key::Down | Key::KP_Down => palette.move_selection(1),
+                Key::Up | Key::KP_Up => palette.move_selection(-1),
+                Key::Page_Down => palette.move_selection(8),
+                Key::Page_Up => palette.move_selection(-8),
+                _ => return Propagation::Proceed,
+            }
+            Propagation::Stop
+        });
+        self.entry.add_controller(key_controller);
+
+        // Clicking elsewhere closes the palette, leaving focus where the click put it
+        let focus_controller = EventControllerFocus::new();
+        let container = self.container.clone();
+        focus_controller.connect_leave(move |_| {
+            container.set_visible(false);
+        });
+        self.container.add_controller(focus_controller);
+
+        // Mouse activation works too
+        let palette = self.clone();
+        self.results.connect_row_activated(move |_, row| {
+            palette.results.select_row(Some(row));
+            palette.activate_selected();
+        });
+    }
+
+    fn move_selection(&self, delta: i32) {
+        let count = self.items.borrow().len() as i32;
+        if count == 0 {
+            return;
+        }
+        let current = self.results.selected_row().map(|row| row.index()).unwrap_or(0);
+        let next = (current + delta).clamp(0, count - 1);
+        if let Some(row) = self.results.row_at_index(next) {
+            self.results.select_row(Some(&row));
+            // Keep the highlighted row in view (the entry keeps focus)
+            if let Some(bounds) = row.compute_bounds(&self.results) {
+                let adjustment = self.scrolled_window.vadjustment();
+                let (top, bottom) = (bounds.y() as f64, (bounds.y() + bounds.height()) as f64);
+                if top < adjustment.value() {
+                    adjustment.set_value(top);
+                } else if bottom > adjustment.value() + adjustment.page_size() {
+                    adjustment.set_value(bottom - adjustment.page_size());
+                }
+            }
+        }
+    }
+
+    /// Rebuild the result list for the current query
+    fn populate(&self, query: &str) {
+        while let Some(row) = self.results.row_at_index(0) {
+            self.results.remove(&row);
+        }
+
+        let items = if let Some(rest) = query.strip_prefix('>') {
+            self.matching_commands(rest)
+        } else if let Some(rest) = query.strip_prefix('#') {
+            self.matching_headings(rest)
+        } else {
+            matching_notes(&self.note_titles.borrow(), query)
+        };
+
+        for item in &items {
+            let (title, detail) = match item {
+                PaletteItem::Command(command) => (
+                    format!("{}: {}", command.section, command.description),
+                    shortcuts::accels_for(&self.settings.borrow(), command).first()
+                        .map(|accel| shortcuts::accel_label(accel))
+                        .unwrap_or_default(),
+                ),
+                PaletteItem::Note(title) => (title.clone(), "Note".to_string()),
+                PaletteItem::Heading { title, level, .. } => (
+                    format!("{}{}", "  ".repeat(level.saturating_sub(1)), title),
+                    format!("H{}", level),
+                ),
+            };
+            self.results.append(&palette_row(&title, &detail));
+        }
+
+        *self.items.borrow_mut() = items;
+        if let Some(row) = self.results.row_at_index(0) {
+            self.results.select_row(Some(&row));
+        }
+    }
+
+    fn matching_commands(&self, query: &str) -> Vec<PaletteItem> {
+        let mut scored: Vec<(i64, usize, &'static Command)> = COMMANDS.iter()
+            .enumerate()
+            .filter(|(_, command)| command.name != "command-palette")
+            .filter_map(|(order, command)| {
+                let haystack = format!("{} {}", command.section, command.description);
+                fuzzy_score(query, &haystack).map(|score| (score, order, command))
+            })
+            .collect();
+        // Best score first; registry order breaks ties
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored.into_iter().map(|(_, _, command)| PaletteItem::Command(command)).collect()
+    }
+
+    fn matching_headings(&self, query: &str) -> Vec<PaletteItem> {
+        let buffer = self.text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true);
+        let query = query.trim();
+        let mut scored: Vec<(i64, markdown::Heading)> = markdown::headings(&text).into_iter()
+            .filter_map(|heading| fuzzy_score(query, &heading.title).map(|score| (score, heading)))
+            .collect();
+        if !query.is_empty() {
+            scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.line.cmp(&b.1.line)));
+        }
+        scored.into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, heading)| PaletteItem::Heading { line: heading.line, title: heading.title, level: heading.level })
+            .collect()
+    }
+
+    /// Run the highlighted result
+    fn activate_selected(&self) {
+        let index = match self.results.selected_row() {
+            Some(row) => row.index() as usize,
+            None => return,
+        };
+        let item = match self.items.borrow().get(index) {
+            Some(item) => item.clone(),
+            None => return,
+        };
+        self.hide();
+
+        match item {
+            PaletteItem::Command(command) => {
+                if let Err(e) = WidgetExt::activate_action(&self.window, &command.detailed_name(), None) {
+                    eprintln!("Error running command {}: {}", command.name, e);
+                }
+            }
+            PaletteItem::Note(title) => {
+                if let Err(e) = WidgetExt::activate_action(&self.window, "win.open-note", Some(&title.to_variant())) {
+                    eprintln!("Error opening note {}: {}", title, e);
+                }
+            }
+            PaletteItem::Heading { line, .. } => {
+                let buffer = self.text_view.buffer();
+                if let Some(mut iter) = buffer.iter_at_line(line as i32) {
+                    buffer.place_cursor(&iter);
+                    self.text_view.scroll_to_iter(&mut iter, 0.0, true, 0.0, 0.1);
+                }
+            }
+        }
+    }
+}
+
+/// Notes whose title fuzzy-matches the query, most recently modified first on ties
+fn matching_notes(titles: &[String], query: &str) -> Vec<PaletteItem> {
+    // The titles are sorted by recency, so a stable sort keeps recent notes first on ties
+    let mut scored: Vec<(i64, &String)> = titles.iter()
+        .filter_map(|title| fuzzy_score(query, title).map(|score| (score, title)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().take(MAX_RESULTS).map(|(_, title)| PaletteItem::Note(title.clone())).collect()
+}
+
+/// A result row: title on the left, dimmed detail (shortcut, kind) on the right
+fn palette_row(title: &str, detail: &str) -> gtk::ListBoxRow {
+    let row_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(12)
+        .margin_start(12)
+        .margin_end(12)
+        .margin_top(6)
+        .margin_bottom(6)
+        .build();
+
+    let title_label = Label::builder()
+        .label(title)
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+
+    let detail_label = Label::builder()
+        .label(detail)
+        .xalign(1.0)
+        .css_classes(vec!["dim-label", "palette-detail"])
+        .build();
+
+    row_box.append(&title_label);
+    row_box.append(&detail_label);
+
+    let row = gtk::ListBoxRow::builder()
+        .css_classes(vec!["palette-row"])
+        .build();
+    row.set_child(Some(&row_box));
+    row
+}
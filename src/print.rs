@@ -0,0 +1,306 @@
+use gtk::prelude::*;
+use gtk::{gdk, graphene, pango, ApplicationWindow, PageSetup, PaperSize, PrintContext, PrintOperation, PrintOperationAction, Unit};
+use chrono::Local;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::print_markup::{self, Block, BlockKind};
+use crate::settings::PrintSettings;
+
+/// Paper sizes offered in the settings: label and GTK paper name
+pub const PAPER_SIZES: &[(&str, &str)] = &[
+    ("A4", "iso_a4"),
+    ("A5", "iso_a5"),
+    ("US Letter", "na_letter"),
+    ("US Legal", "na_legal"),
+];
+
+// Font size of headings relative to the body text, by level
+const HEADING_SCALES: [f64; 6] = [1.8, 1.5, 1.3, 1.15, 1.0, 1.0];
+// Indentation per list level and block quote, in points
+const INDENT: f64 = 18.0;
+
+/// The part of a block printed on one page: its layout lines between `from` and `to`
+/// (in the layout's coordinates), placed `y` points below the top of the text area
+struct Slice {
+    block: usize,
+    from: f64,
+    to: f64,
+    y: f64,
+}
+
+/// The note laid out for the print context, split into pages
+#[derive(Default)]
+struct Document {
+    blocks: Vec<Block>,
+    /// One layout per block (None for rules)
+    layouts: Vec<Option<pango::Layout>>,
+    pages: Vec<Vec<Slice>>,
+    /// Body font size, in points
+    font_size: f64,
+    header_height: f64,
+}
+
+fn rgba(red: f32, green: f32, blue: f32) -> gdk::RGBA {
+    gdk::RGBA::new(red, green, blue, 1.0)
+}
+
+fn rect(x: f64, y: f64, width: f64, height: f64) -> graphene::Rect {
+    graphene::Rect::new(x as f32, y as f32, width as f32, height as f32)
+}
+
+/// Page size and margins from the settings
+fn page_setup(settings: &PrintSettings) -> PageSetup {
+    let setup = PageSetup::new();
+    let paper_name = PAPER_SIZES.iter()
+        .find(|(_, name)| *name == settings.paper_size)
+        .map_or(PAPER_SIZES[0].1, |(_, name)| *name);
+    setup.set_paper_size(&PaperSize::new(Some(paper_name)));
+    setup.set_top_margin(settings.margin_mm, Unit::Mm);
+    setup.set_bottom_margin(settings.margin_mm, Unit::Mm);
+    setup.set_left_margin(settings.margin_mm, Unit::Mm);
+    setup.set_right_margin(settings.margin_mm, Unit::Mm);
+    setup
+}
+
+fn body_font(settings: &PrintSettings) -> pango::FontDescription {
+    let mut font = pango::FontDescription::from_string(&settings.font);
+    if font.size() <= 0 {
+        font.set_size(11 * pango::SCALE);
+    }
+    font
+}
+
+/// Top and bottom of each line of a layout, in points
+fn line_extents(layout: &pango::Layout) -> Vec<(f64, f64)> {
+    let scale = pango::SCALE as f64;
+    let mut extents = Vec::new();
+    let mut iter = layout.iter();
+    loop {
+        let (_, logical) = iter.line_extents();
+        extents.push((logical.y() as f64 / scale, (logical.y() + logical.height()) as f64 / scale));
+        if !iter.next_line() {
+            break;
+        }
+    }
+    extents
+}
+
+/// A single line of small header/footer text
+fn small_layout(context: &PrintContext, font: &pango::FontDescription, text: &str) -> pango::Layout {
+    let mut small_font = font.clone();
+    small_font.set_size(font.size() * 4 / 5);
+    let layout = context.create_pango_layout();
+    layout.set_font_description(Some(&small_font));
+    layout.set_text(text);
+    layout
+}
+
+impl Document {
+    /// Lay out every block for the page width and split the lines into pages
+    fn paginate(&mut self, context: &PrintContext, settings: &PrintSettings) {
+        let font = body_font(settings);
+        let font_size = font.size() as f64 / pango::SCALE as f64;
+        let width = context.width();
+        let footer_height = if settings.footer { font_size * 2.0 } else { 0.0 };
+        self.font_size = font_size;
+        self.header_height = if settings.header { font_size * 2.5 } else { 0.0 };
+        let body_height = (context.height() - self.header_height - footer_height).max(font_size * 2.0);
+
+        self.layouts = self.blocks.iter().map(|block| {
+            let x = INDENT * (block.indent + block.quote_depth) as f64;
+            let layout_width = (width - x).max(INDENT);
+            let mut block_font = font.clone();
+            match block.kind {
+                BlockKind::Rule => return None,
+                BlockKind::Heading(level) => {
+                    let scale = HEADING_SCALES[(level as usize).clamp(1, 6) - 1];
+                    block_font.set_size((font.size() as f64 * scale) as i32);
+                    block_font.set_weight(pango::Weight::Bold);
+                }
+                BlockKind::Code => {
+                    block_font.set_family("Monospace");
+                    block_font.set_size(font.size() * 17 / 20);
+                }
+                BlockKind::TableRow { .. } | BlockKind::Paragraph => {}
+            }
+            let layout = context.create_pango_layout();
+            layout.set_font_description(Some(&block_font));
+            layout.set_width((layout_width * pango::SCALE as f64) as i32);
+            layout.set_wrap(if block.kind == BlockKind::Code { pango::WrapMode::Char } else { pango::WrapMode::WordChar });
+            if let BlockKind::TableRow { columns, .. } = block.kind {
+                // Cells start at evenly spaced tab stops
+                let columns = columns.max(1);
+                let mut tabs = pango::TabArray::new(columns as i32, false);
+                for column in 0..columns {
+                    let position = layout_width * column as f64 / columns as f64;
+                    tabs.set_tab(column as i32, pango::TabAlign::Left, (position * pango::SCALE as f64) as i32);
+                }
+                layout.set_tabs(Some(&tabs));
+            }
+            layout.set_markup(&block.markup);
+            Some(layout)
+        }).collect();
+
+        let mut pages: Vec<Vec<Slice>> = vec![Vec::new()];
+        let mut y = 0.0;
+        for (index, (block, layout)) in self.blocks.iter().zip(&self.layouts).enumerate() {
+            let space_before = match block.kind {
+                BlockKind::Heading(_) => font_size * 1.2,
+                BlockKind::TableRow { header: false, .. } => font_size * 0.3,
+                _ => font_size * 0.6,
+            };
+            if y > 0.0 {
+                y += space_before;
+            }
+            let lines = match layout {
+                Some(layout) => line_extents(layout),
+                None => vec![(0.0, font_size)], // A rule takes one line
+            };
+
+            // Keep headings together with the start of the following text
+            if let BlockKind::Heading(_) = block.kind {
+                let height: f64 = lines.iter().map(|(top, bottom)| bottom - top).sum();
+                if y > 0.0 && y + height + font_size * 3.0 > body_height {
+                    pages.push(Vec::new());
+                    y = 0.0;
+                }
+            }
+
+            let mut slice: Option<Slice> = None;
+            for (top, bottom) in lines {
+                if y > 0.0 && y + (bottom - top) > body_height {
+                    if let (Some(slice), Some(page)) = (slice.take(), pages.last_mut()) {
+                        page.push(slice);
+                    }
+                    pages.push(Vec::new());
+                    y = 0.0;
+                }
+                match slice.as_mut() {
+                    Some(slice) => slice.to = bottom,
+                    None => slice = Some(Slice { block: index, from: top, to: bottom, y }),
+                }
+                y += bottom - top;
+            }
+            if let (Some(slice), Some(page)) = (slice, pages.last_mut()) {
+                page.push(slice);
+            }
+        }
+        self.pages = pages;
+    }
+
+    fn draw_page(&self, context: &PrintContext, settings: &PrintSettings, title: &str, page_number: usize) {
+        let width = context.width();
+        let height = context.height();
+        let font = body_font(settings);
+        let text_color = rgba(0.2, 0.2, 0.2); // The light theme's #333333
+        let muted_color = rgba(0.45, 0.45, 0.45);
+        let line_color = rgba(0.8, 0.8, 0.8);
+        let snapshot = gtk::Snapshot::new();
+
+        if settings.header {
+            let title_layout = small_layout(context, &font, title);
+            title_layout.set_width(((width * 0.7) * pango::SCALE as f64) as i32);
+            title_layout.set_ellipsize(pango::EllipsizeMode::End);
+            snapshot.append_layout(&title_layout, &muted_color);
+            let date_layout = small_layout(context, &font, &Local::now().format("%B %-d, %Y").to_string());
+            let (date_width, _) = date_layout.pixel_size();
+            snapshot.save();
+            snapshot.translate(&graphene::Point::new((width - date_width as f64) as f32, 0.0));
+            snapshot.append_layout(&date_layout, &muted_color);
+            snapshot.restore();
+        }
+
+        let top = self.header_height;
+        for slice in self.pages.get(page_number).map(Vec::as_slice).unwrap_or_default() {
+            let block = &self.blocks[slice.block];
+            let x = INDENT * (block.indent + block.quote_depth) as f64;
+            let y = top + slice.y;
+            let slice_height = slice.to - slice.from;
+
+            // A bar on the left of quoted text for each level of quoting
+            for depth in 0..block.quote_depth {
+                let bar_x = INDENT * (block.indent + depth) as f64 + 2.0;
+                snapshot.append_color(&line_color, &rect(bar_x, y, 2.0, slice_height));
+            }
+
+            let layout = match &self.layouts[slice.block] {
+                Some(layout) => layout,
+                None => {
+                    snapshot.append_color(&line_color, &rect(x, y + slice_height / 2.0, width - x, 0.75));
+                    continue;
+                }
+            };
+            if block.kind == BlockKind::Code {
+                snapshot.append_color(&rgba(0.96, 0.96, 0.96), &rect(x - 4.0, y, width - x + 8.0, slice_height));
+            }
+            // Only the lines of this slice, shifted to their place on the page
+            snapshot.push_clip(&rect(0.0, y, width, slice_height));
+            snapshot.save();
+            snapshot.translate(&graphene::Point::new(x as f32, (y - slice.from) as f32));
+            snapshot.append_layout(layout, &text_color);
+            snapshot.restore();
+            snapshot.pop();
+            if let BlockKind::TableRow { header: true, .. } = block.kind {
+                snapshot.append_color(&line_color, &rect(x, y + slice_height + 1.0, width - x, 0.75));
+            }
+        }
+
+        if settings.footer {
+            let page_layout = small_layout(context, &font, &format!("Page {} of {}", page_number + 1, self.pages.len()));
+            let (page_width, page_height) = page_layout.pixel_size();
+            snapshot.save();
+            snapshot.translate(&graphene::Point::new(((width - page_width as f64) / 2.0) as f32, (height - page_height as f64) as f32));
+            snapshot.append_layout(&page_layout, &muted_color);
+            snapshot.restore();
+        }
+
+        if let Some(node) = snapshot.to_node() {
+            node.draw(&context.cairo_context());
+        }
+    }
+}
+
+/// Print a note with the system print dialog, or write it straight to a PDF file
+/// when `pdf` is given. The Markdown is rendered with the page layout from the settings.
+pub fn print_note(parent: &ApplicationWindow, title: &str, markdown: &str, settings: &PrintSettings, pdf: Option<PathBuf>) {
+    let operation = PrintOperation::new();
+    operation.set_job_name(title);
+    operation.set_unit(Unit::Points);
+    operation.set_default_page_setup(Some(&page_setup(settings)));
+
+    let document = Rc::new(RefCell::new(Document {
+        blocks: print_markup::blocks(markdown),
+        ..Default::default()
+    }));
+
+    let document_for_begin = document.clone();
+    let settings_for_begin = settings.clone();
+    operation.connect_begin_print(move |operation, context| {
+        let mut document = document_for_begin.borrow_mut();
+        document.paginate(context, &settings_for_begin);
+        operation.set_n_pages(document.pages.len() as i32);
+    });
+
+    let settings_for_draw = settings.clone();
+    let title_for_draw = title.to_string();
+    operation.connect_draw_page(move |_, context, page_number| {
+        document.borrow().draw_page(context, &settings_for_draw, &title_for_draw, page_number as usize);
+    });
+
+    let action = match pdf {
+        Some(path) => {
+            operation.set_export_filename(path);
+            PrintOperationAction::Export
+        }
+        None => {
+            // Page size and orientation can still be changed in the print dialog
+            operation.set_embed_page_setup(true);
+            PrintOperationAction::PrintDialog
+        }
+    };
+    if let Err(e) = operation.run(action, Some(parent)) {
+        crate::utils::show_error_dialog(parent, "Print Error", &format!("Failed to print \"{}\": {}", title, e));
+    }
+}
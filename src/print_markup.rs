@@ -0,0 +1,233 @@
+// Markdown laid out for printing: the note is rendered (CommonMark with tables and
+// task lists, as for the HTML export) into a flat list of blocks, each holding
+// Pango markup for one paragraph, heading, list item, code block or table row.
+// Everything here works on strings so it stays independent of GTK.
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// Link colour, the app's accent
+const LINK_COLOR: &str = "#3584e4";
+
+/// What a block is, which decides its font and spacing on the page
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockKind {
+    Paragraph,
+    Heading(u8),
+    Code,
+    /// A row of a table; cells are separated by tabs
+    TableRow { header: bool, columns: usize },
+    /// A horizontal rule (no text)
+    Rule,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub kind: BlockKind,
+    /// Pango markup of the block's text
+    pub markup: String,
+    /// List nesting level, for indentation
+    pub indent: usize,
+    /// Number of block quotes around the block
+    pub quote_depth: usize,
+}
+
+fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    current: Option<Block>,
+    /// Next number of each open list (None for bullet lists)
+    lists: Vec<Option<u64>>,
+    quote_depth: usize,
+    /// Marker of a list item (or footnote label), written before its first text
+    pending_marker: Option<String>,
+    /// Column count of the table being read
+    table_columns: usize,
+    in_table_head: bool,
+    cell_index: usize,
+}
+
+impl Builder {
+    fn start(&mut self, kind: BlockKind) {
+        self.finish();
+        self.current = Some(Block {
+            kind,
+            markup: String::new(),
+            indent: self.lists.len(),
+            quote_depth: self.quote_depth,
+        });
+    }
+
+    /// Append markup to the current block, starting a paragraph if there is none
+    fn push(&mut self, markup: &str) {
+        if self.current.is_none() {
+            self.start(BlockKind::Paragraph);
+        }
+        let marker = self.pending_marker.take();
+        if let Some(block) = self.current.as_mut() {
+            if let Some(marker) = marker {
+                block.markup.push_str(&marker);
+            }
+            block.markup.push_str(markup);
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(mut block) = self.current.take() {
+            if block.kind == BlockKind::Code {
+                let trimmed = block.markup.trim_end_matches('\n').len();
+                block.markup.truncate(trimmed);
+            }
+            if !block.markup.is_empty() || block.kind == BlockKind::Rule {
+                self.blocks.push(block);
+            }
+        }
+    }
+}
+
+/// Render Markdown into blocks of Pango markup. Front matter and raw HTML are left out.
+pub fn blocks(markdown: &str) -> Vec<Block> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_WIKILINKS;
+    let mut builder = Builder::default();
+    let mut in_metadata = false;
+
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::MetadataBlock(_)) => in_metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+            _ if in_metadata => {}
+
+            // Blocks
+            // The first paragraph of a list item continues the item's block
+            Event::Start(Tag::Paragraph) if builder.pending_marker.is_none() => builder.start(BlockKind::Paragraph),
+            Event::Start(Tag::Heading { level, .. }) => {
+                let level = match level {
+                    HeadingLevel::H1 => 1,
+                    HeadingLevel::H2 => 2,
+                    HeadingLevel::H3 => 3,
+                    HeadingLevel::H4 => 4,
+                    HeadingLevel::H5 => 5,
+                    HeadingLevel::H6 => 6,
+                };
+                builder.start(BlockKind::Heading(level));
+            }
+            Event::Start(Tag::CodeBlock(_)) => builder.start(BlockKind::Code),
+            Event::Start(Tag::BlockQuote(_)) => {
+                builder.finish();
+                builder.quote_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                builder.finish();
+                builder.quote_depth = builder.quote_depth.saturating_sub(1);
+            }
+            Event::Start(Tag::List(first_number)) => {
+                builder.finish();
+                builder.lists.push(first_number);
+            }
+            Event::End(TagEnd::List(_)) => {
+                builder.finish();
+                builder.lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                builder.finish();
+                let marker = match builder.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                builder.start(BlockKind::Paragraph);
+                builder.pending_marker = Some(marker);
+            }
+            Event::TaskListMarker(checked) => {
+                builder.pending_marker = Some(if checked { "☑ " } else { "☐ " }.to_string());
+            }
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                builder.start(BlockKind::Paragraph);
+                builder.pending_marker = Some(format!("<sup>{}</sup> ", escape_markup(&label)));
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::Item | TagEnd::FootnoteDefinition) => {
+                builder.pending_marker = None;
+                builder.finish();
+            }
+            Event::Rule => {
+                builder.start(BlockKind::Rule);
+                builder.finish();
+            }
+
+            // Tables: one block per row
+            Event::Start(Tag::Table(alignments)) => {
+                builder.finish();
+                builder.table_columns = alignments.len();
+            }
+            Event::Start(Tag::TableHead) => {
+                builder.in_table_head = true;
+                builder.start(BlockKind::TableRow { header: true, columns: builder.table_columns });
+                builder.cell_index = 0;
+            }
+            Event::End(TagEnd::TableHead) => {
+                builder.in_table_head = false;
+                builder.finish();
+            }
+            Event::Start(Tag::TableRow) => {
+                builder.start(BlockKind::TableRow { header: false, columns: builder.table_columns });
+                builder.cell_index = 0;
+            }
+            Event::End(TagEnd::TableRow) => builder.finish(),
+            Event::Start(Tag::TableCell) => {
+                if builder.cell_index > 0 {
+                    builder.push("\t");
+                }
+                builder.cell_index += 1;
+                if builder.in_table_head {
+                    builder.push("<b>");
+                }
+            }
+            Event::End(TagEnd::TableCell) if builder.in_table_head => builder.push("</b>"),
+
+            // Inline
+            Event::Start(Tag::Emphasis) => builder.push("<i>"),
+            Event::End(TagEnd::Emphasis) => builder.push("</i>"),
+            Event::Start(Tag::Strong) => builder.push("<b>"),
+            Event::End(TagEnd::Strong) => builder.push("</b>"),
+            Event::Start(Tag::Strikethrough) => builder.push("<s>"),
+            Event::End(TagEnd::Strikethrough) => builder.push("</s>"),
+            Event::Start(Tag::Link { .. }) => {
+                builder.push(&format!("<span foreground=\"{}\" underline=\"single\">", LINK_COLOR));
+            }
+            Event::End(TagEnd::Link) => builder.push("</span>"),
+            // Images cannot be placed in the text flow; their description is shown instead
+            Event::Start(Tag::Image { .. }) => builder.push("<i>[image: "),
+            Event::End(TagEnd::Image) => builder.push("]</i>"),
+            Event::Code(code) => builder.push(&format!("<tt>{}</tt>", escape_markup(&code))),
+            Event::Text(text) => builder.push(&escape_markup(&text)),
+            Event::SoftBreak => builder.push(" "),
+            Event::HardBreak => builder.push("\n"),
+            Event::FootnoteReference(label) => builder.push(&format!("<sup>{}</sup>", escape_markup(&label))),
+            Event::InlineMath(math) | Event::DisplayMath(math) => builder.push(&escape_markup(&math)),
+            _ => {} // Raw HTML and other events
+        }
+    }
+    builder.finish();
+    builder.blocks
+}
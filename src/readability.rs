@@ -0,0 +1,385 @@
+// Offline readability scores and style checks for English prose.
+// Everything here works on `&str` so it stays independent of GTK.
+
+use crate::{frontmatter, markdown, text_stats};
+
+// Sentences with more words than this are flagged as long
+pub const LONG_SENTENCE_WORDS: usize = 25;
+
+const BE_VERBS: &[&str] = &["am", "is", "are", "was", "were", "be", "been", "being"];
+
+// Past participles that do not end in -ed
+const IRREGULAR_PARTICIPLES: &[&str] = &[
+    "awoken", "beaten", "become", "begun", "bent", "bitten", "blown", "born", "borne",
+    "bought", "bound", "broken", "brought", "built", "burnt", "caught", "chosen", "dealt", "done",
+    "drawn", "driven", "eaten", "fallen", "fed", "felt", "fought", "found", "forbidden", "forgiven",
+    "forgotten", "frozen", "given", "gone", "grown", "heard", "held", "hidden", "hit", "hung", "hurt",
+    "kept", "known", "laid", "led", "left", "lent", "lit", "lost", "made", "meant", "met", "paid",
+    "put", "read", "ridden", "run", "said", "seen", "sent", "set", "shaken", "shown", "shut", "sold",
+    "spent", "spoken", "spun", "stolen", "struck", "sung", "sworn", "taken", "taught", "thought",
+    "thrown", "told", "torn", "understood", "won", "worn", "written", "wound",
+];
+
+// Words ending in -ly that are not adverbs
+const NOT_ADVERBS: &[&str] = &[
+    "apply", "ally", "belly", "bully", "butterfly", "comply", "daily", "early", "family", "fly",
+    "friendly", "holy", "homely", "italy", "jelly", "july", "lily", "lonely", "lovely", "likely",
+    "only", "rally", "rely", "reply", "silly", "supply", "ugly", "weekly", "monthly", "yearly",
+    "elderly", "costly", "curly", "deadly", "lively", "orderly", "timely", "unlikely", "wholly",
+];
+
+const FILLER_WORDS: &[&str] = &[
+    "actually", "basically", "certainly", "definitely", "essentially", "just", "literally",
+    "quite", "rather", "really", "simply", "somewhat", "totally", "truly", "very",
+];
+
+/// A kind of style problem highlighted in the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Check {
+    LongSentence,
+    PassiveVoice,
+    Adverb,
+    FillerWord,
+    RepeatedWord,
+}
+
+impl Check {
+    /// All checks, in the order shown in the panel
+    pub const ALL: [Check; 5] = [
+        Check::LongSentence,
+        Check::PassiveVoice,
+        Check::Adverb,
+        Check::FillerWord,
+        Check::RepeatedWord,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Check::LongSentence => "Long sentences",
+            Check::PassiveVoice => "Passive voice",
+            Check::Adverb => "Adverbs",
+            Check::FillerWord => "Filler words",
+            Check::RepeatedWord => "Repeated words",
+        }
+    }
+}
+
+/// A stretch of text flagged by a check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub check: Check,
+    /// Byte range in the analysed text
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Readability formulas for a text; all None for text without words
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Scores {
+    pub words: usize,
+    pub sentences: usize,
+    pub flesch_reading_ease: Option<f64>,
+    pub flesch_kincaid_grade: Option<f64>,
+    pub gunning_fog: Option<f64>,
+    pub coleman_liau: Option<f64>,
+    pub automated_readability: Option<f64>,
+}
+
+/// Plain-English meaning of a Flesch reading ease score
+pub fn reading_ease_label(score: f64) -> &'static str {
+    match score {
+        s if s >= 90.0 => "Very easy",
+        s if s >= 80.0 => "Easy",
+        s if s >= 70.0 => "Fairly easy",
+        s if s >= 60.0 => "Standard",
+        s if s >= 50.0 => "Fairly difficult",
+        s if s >= 30.0 => "Difficult",
+        _ => "Very difficult",
+    }
+}
+
+/// Estimate the syllables of an English word: vowel groups, minus a silent final e
+fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.is_empty() {
+        return 0;
+    }
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for &c in &letters {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    // "make" has one syllable, "table" keeps two
+    let n = letters.len();
+    let consonant_le = n > 2 && letters[n - 2] == 'l' && !is_vowel(letters[n - 3]);
+    if n > 2 && letters[n - 1] == 'e' && !is_vowel(letters[n - 2]) && !consonant_le {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+/// Readability scores of Markdown text (syntax, code and front matter are ignored)
+pub fn scores(text: &str) -> Scores {
+    let prose = text_stats::strip_markdown(text);
+    let stats = text_stats::analyze(text);
+    let mut scores = Scores {
+        words: stats.words,
+        sentences: stats.sentences,
+        ..Scores::default()
+    };
+
+    let (mut word_count, mut syllable_count, mut complex_words, mut letters) = (0usize, 0usize, 0usize, 0usize);
+    for (_, _, word) in words(&prose) {
+        let word_syllables = syllables(word);
+        word_count += 1;
+        syllable_count += word_syllables;
+        if word_syllables >= 3 {
+            complex_words += 1;
+        }
+        letters += word.chars().filter(|c| c.is_alphanumeric()).count();
+    }
+    if word_count == 0 || stats.sentences == 0 {
+        return scores;
+    }
+
+    let words = word_count as f64;
+    let words_per_sentence = words / stats.sentences as f64;
+    let syllables_per_word = syllable_count as f64 / words;
+    scores.flesch_reading_ease = Some(206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word);
+    scores.flesch_kincaid_grade = Some(0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59);
+    scores.gunning_fog = Some(0.4 * (words_per_sentence + 100.0 * complex_words as f64 / words));
+    let letters_per_100 = letters as f64 / words * 100.0;
+    let sentences_per_100 = stats.sentences as f64 / words * 100.0;
+    scores.coleman_liau = Some(0.0588 * letters_per_100 - 0.296 * sentences_per_100 - 15.8);
+    scores.automated_readability = Some(4.71 * letters as f64 / words + 0.5 * words_per_sentence - 21.43);
+    scores
+}
+
+/// Words with their byte ranges: runs of letters and digits, joined by apostrophes
+fn words(text: &str) -> Vec<(usize, usize, &str)> {
+    let mut words = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let joins = matches!(c, '\'' | '’')
+            && start.is_some()
+            && chars.peek().is_some_and(|(_, next)| next.is_alphabetic());
+        if c.is_alphanumeric() || joins {
+            start.get_or_insert(i);
+        } else if let Some(word_start) = start.take() {
+            words.push((word_start, i, &text[word_start..i]));
+        }
+    }
+    if let Some(word_start) = start {
+        words.push((word_start, text.len(), &text[word_start..]));
+    }
+    words
+}
+
+/// Byte ranges of the prose blocks of a note: paragraphs, with every heading and
+/// list item on its own, skipping front matter and code blocks
+fn prose_blocks(text: &str) -> Vec<(usize, usize)> {
+    let mut offset = match frontmatter::split(text) {
+        (Some(_), body) => text.len() - body.len(),
+        (None, _) => 0,
+    };
+    let mut blocks = Vec::new();
+    let mut block_start: Option<usize> = None;
+    let mut in_code_block = false;
+
+    for line in text[offset..].split_inclusive('\n') {
+        let line_end = offset + line.trim_end_matches(['\n', '\r']).len();
+        let fence = markdown::is_code_fence(line);
+        let starts_block = markdown::heading_level(line).0 > 0 || markdown::parse_list_item(line).is_some();
+        if fence || in_code_block || line.trim().is_empty() || starts_block {
+            if let Some(start) = block_start.take() {
+                blocks.push((start, offset));
+            }
+        }
+        if fence {
+            in_code_block = !in_code_block;
+        } else if !in_code_block && !line.trim().is_empty() {
+            if markdown::heading_level(line).0 > 0 {
+                blocks.push((offset, line_end)); // Headings stand alone
+            } else {
+                block_start.get_or_insert(offset);
+            }
+        }
+        offset += line.len();
+    }
+    if let Some(start) = block_start {
+        blocks.push((start, text.len()));
+    }
+    blocks
+}
+
+/// Byte ranges of the sentences in a block of text
+fn sentences(text: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let block = &text[start..end];
+    let mut sentences = Vec::new();
+    let mut sentence_start = 0;
+    let mut chars = block.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let ends_sentence = match c {
+            '。' | '！' | '？' => true,
+            '.' | '!' | '?' => chars.peek().is_none_or(|(_, next)| next.is_whitespace()),
+            _ => false,
+        };
+        if ends_sentence {
+            let sentence_end = i + c.len_utf8();
+            sentences.push((start + sentence_start, start + sentence_end));
+            sentence_start = sentence_end;
+        }
+    }
+    if !block[sentence_start..].trim().is_empty() {
+        sentences.push((start + sentence_start, end));
+    }
+
+    // Trim leading whitespace so highlights start at the first word
+    sentences.into_iter()
+        .map(|(s, e)| (s + (text[s..e].len() - text[s..e].trim_start().len()), e))
+        .filter(|(s, e)| s < e)
+        .collect()
+}
+
+/// Run every check over a Markdown note
+pub fn findings(text: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (block_start, block_end) in prose_blocks(text) {
+        for (sentence_start, sentence_end) in sentences(text, block_start, block_end) {
+            let sentence_words: Vec<(usize, usize, &str)> = words(&text[sentence_start..sentence_end]).into_iter()
+                .map(|(s, e, word)| (sentence_start + s, sentence_start + e, word))
+                .collect();
+            check_sentence(&sentence_words, sentence_start, sentence_end, &mut findings);
+        }
+    }
+    findings.sort_by_key(|finding| (finding.start, finding.end));
+    findings
+}
+
+fn check_sentence(words: &[(usize, usize, &str)], start: usize, end: usize, findings: &mut Vec<Finding>) {
+    if words.len() > LONG_SENTENCE_WORDS {
+        findings.push(Finding { check: Check::LongSentence, start, end });
+    }
+
+    // End of the last passive construction, so "being" in "is being built" is not another
+    let mut passive_end = start;
+    for (i, &(word_start, word_end, word)) in words.iter().enumerate() {
+        let lower = word.to_lowercase();
+
+        // "was written", "is being reviewed": a form of "to be" and a past participle
+        if BE_VERBS.contains(&lower.as_str()) && word_start >= passive_end {
+            let participle = words[i + 1..].iter()
+                .take(2) // Allow one word in between ("was quickly written", "is being built")
+                .position(|(_, _, next)| is_participle(&next.to_lowercase()))
+                .map(|offset| words[i + 1 + offset].1);
+            if let Some(participle_end) = participle {
+                passive_end = participle_end;
+                findings.push(Finding { check: Check::PassiveVoice, start: word_start, end: participle_end });
+            }
+        }
+
+        if lower.len() > 4 && lower.ends_with("ly") && !NOT_ADVERBS.contains(&lower.as_str()) {
+            findings.push(Finding { check: Check::Adverb, start: word_start, end: word_end });
+        }
+
+        if FILLER_WORDS.contains(&lower.as_str()) {
+            findings.push(Finding { check: Check::FillerWord, start: word_start, end: word_end });
+        }
+
+        // "the the": the same word twice in a row
+        if let Some(&(_, _, previous)) = i.checked_sub(1).and_then(|p| words.get(p)) {
+            if previous.eq_ignore_ascii_case(word) && word.chars().any(|c| c.is_alphabetic()) {
+                findings.push(Finding { check: Check::RepeatedWord, start: word_start, end: word_end });
+            }
+        }
+    }
+}
+
+fn is_participle(word: &str) -> bool {
+    (word.len() > 3 && word.ends_with("ed")) || IRREGULAR_PARTICIPLES.contains(&word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of each finding of a check
+    fn found(text: &str, check: Check) -> Vec<&str> {
+        findings(text).into_iter()
+            .filter(|finding| finding.check == check)
+            .map(|finding| &text[finding.start..finding.end])
+            .collect()
+    }
+
+    fn sentence_texts(text: &str) -> Vec<&str> {
+        sentences(text, 0, text.len()).into_iter().map(|(start, end)| &text[start..end]).collect()
+    }
+
+    #[test]
+    fn splits_sentences_at_stops_followed_by_space() {
+        assert_eq!(sentence_texts("One. Two! Three? Four"), ["One.", "Two!", "Three?", "Four"]);
+        assert_eq!(sentence_texts("Pi is 3.14 today. Yes."), ["Pi is 3.14 today.", "Yes."]);
+        assert_eq!(sentence_texts("今日は晴れ。明日は雨。"), ["今日は晴れ。", "明日は雨。"]);
+        assert!(sentence_texts("   ").is_empty());
+    }
+
+    #[test]
+    fn checks_headings_and_list_items_on_their_own() {
+        let text = "# A heading without a stop\nThe text was written by me.\n\n- item one\n- item two\n";
+        let blocks: Vec<&str> = prose_blocks(text).into_iter().map(|(start, end)| text[start..end].trim_end()).collect();
+        assert_eq!(blocks, ["# A heading without a stop", "The text was written by me.", "- item one", "- item two"]);
+    }
+
+    #[test]
+    fn finds_passive_voice() {
+        assert_eq!(found("The letter was written by Ann.", Check::PassiveVoice), ["was written"]);
+        assert_eq!(found("The bridge is being built.", Check::PassiveVoice), ["is being built"]);
+        assert_eq!(found("It was quickly painted.", Check::PassiveVoice), ["was quickly painted"]);
+        assert!(found("She is happy and was here.", Check::PassiveVoice).is_empty());
+    }
+
+    #[test]
+    fn skips_code_blocks_and_front_matter() {
+        let text = "---\ntitle: Was tested\n---\n```\nit was parsed\n```\nAll good.\n";
+        assert!(found(text, Check::PassiveVoice).is_empty());
+    }
+
+    #[test]
+    fn finds_adverbs_fillers_and_repeated_words() {
+        let text = "He quickly ran to the the early train, which was very full.";
+        assert_eq!(found(text, Check::Adverb), ["quickly"]);
+        assert_eq!(found(text, Check::FillerWord), ["very"]);
+        assert_eq!(found(text, Check::RepeatedWord), ["the"]);
+    }
+
+    #[test]
+    fn flags_long_sentences() {
+        let long = format!("{}.", vec!["word"; LONG_SENTENCE_WORDS + 1].join(" "));
+        let short = format!("{}.", vec!["word"; LONG_SENTENCE_WORDS].join(" "));
+        assert_eq!(found(&long, Check::LongSentence).len(), 1);
+        assert!(found(&short, Check::LongSentence).is_empty());
+    }
+
+    #[test]
+    fn estimates_syllables() {
+        assert_eq!(syllables("make"), 1);
+        assert_eq!(syllables("table"), 2);
+        assert_eq!(syllables("readability"), 5);
+        assert_eq!(syllables("the"), 1);
+    }
+
+    #[test]
+    fn scores_need_words() {
+        assert_eq!(scores("```\ncode\n```\n").flesch_reading_ease, None);
+        let scores = scores("The cat sat on the mat.");
+        assert_eq!((scores.words, scores.sentences), (6, 1));
+        assert!(scores.flesch_reading_ease.is_some_and(|score| score > 90.0));
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+
+/// User preferences, persisted as TOML in the config directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)] // Missing keys fall back to defaults so old files keep loading
+pub struct Settings {
+    pub editor: EditorSettings,
+    pub writing: WritingSettings,
+    pub style: StyleSettings,
+    pub spelling: SpellingSettings,
+    pub journal: JournalSettings,
+    pub print: PrintSettings,
+    /// Rebound shortcuts: action name -> accelerators (commands not listed use their defaults)
+    pub shortcuts: BTreeMap<String, Vec<String>>,
+}
+
+/// Part of the text focus mode keeps bright
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusUnit {
+    Sentence,
+    Line,
+    #[default]
+    Paragraph,
+}
+
+impl FocusUnit {
+    /// All units, in the order shown in the settings dialog
+    pub const ALL: [FocusUnit; 3] = [FocusUnit::Sentence, FocusUnit::Line, FocusUnit::Paragraph];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FocusUnit::Sentence => "Sentence",
+            FocusUnit::Line => "Line",
+            FocusUnit::Paragraph => "Paragraph",
+        }
+    }
+}
+
+/// Distraction-free writing modes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WritingSettings {
+    /// What stays bright in focus mode
+    pub focus_unit: FocusUnit,
+    /// Keep the caret line at a fixed height while typing
+    pub typewriter_scrolling: bool,
+    /// Height of the caret line as a fraction of the editor (0.5 = centred)
+    pub typewriter_position: f64,
+    /// Words to write per day across all notes (0 = no daily goal)
+    pub daily_word_goal: usize,
+}
+
+impl Default for WritingSettings {
+    fn default() -> Self {
+        WritingSettings {
+            focus_unit: FocusUnit::default(),
+            typewriter_scrolling: false,
+            typewriter_position: 0.5,
+            daily_word_goal: 0,
+        }
+    }
+}
+
+/// Style checks highlighted while the readability panel is open
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleSettings {
+    pub long_sentences: bool,
+    pub passive_voice: bool,
+    pub adverbs: bool,
+    pub filler_words: bool,
+    pub repeated_words: bool,
+}
+
+impl Default for StyleSettings {
+    fn default() -> Self {
+        StyleSettings {
+            long_sentences: true,
+            passive_voice: true,
+            adverbs: true,
+            filler_words: true,
+            repeated_words: true,
+        }
+    }
+}
+
+/// Spell checking with the installed Hunspell dictionaries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpellingSettings {
+    pub enabled: bool,
+    /// Dictionary for notes without a `lang` field, e.g. "en_US" (empty = from the system locale)
+    pub language: String,
+}
+
+impl Default for SpellingSettings {
+    fn default() -> Self {
+        SpellingSettings {
+            enabled: true,
+            language: String::new(),
+        }
+    }
+}
+
+/// Daily notes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalSettings {
+    /// Template for new daily notes: a template name or the title of a note (empty = a dated heading)
+    pub template_note: String,
+}
+
+/// Page layout for printing and PDF export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrintSettings {
+    /// Paper size name as understood by GTK, e.g. "iso_a4" or "na_letter"
+    pub paper_size: String,
+    /// Margin on every side of the page, in millimetres
+    pub margin_mm: f64,
+    /// Pango font description of the body text, e.g. "Serif 11"
+    pub font: String,
+    /// Print the note title and date at the top of each page
+    pub header: bool,
+    /// Print page numbers at the bottom of each page
+    pub footer: bool,
+}
+
+impl Default for PrintSettings {
+    fn default() -> Self {
+        PrintSettings {
+            paper_size: "iso_a4".to_string(),
+            margin_mm: 20.0,
+            font: "Serif 11".to_string(),
+            header: true,
+            footer: true,
+        }
+    }
+}
+
+/// Markdown editing assists in the editor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorSettings {
+    /// Continue `- `, `1. `, `> ` and `- [ ]` prefixes when pressing Enter
+    pub list_continuation: bool,
+    /// Wrap the selection in brackets, backticks and emphasis markers
+    pub auto_pair: bool,
+    /// Indent/outdent list items with Tab and Shift+Tab
+    pub smart_indent: bool,
+    /// Show a formatting popover above the text selection
+    pub formatting_toolbar: bool,
+}
+
+impl Default for EditorSettings {
+    fn default() -> Self {
+        EditorSettings {
+            list_continuation: true,
+            auto_pair: true,
+            smart_indent: true,
+            formatting_toolbar: true,
+        }
+    }
+}
+
+/// Get the path to the settings file
+pub fn get_settings_path() -> PathBuf {
+    glib::user_config_dir().join("penscript/settings.toml")
+}
+
+impl Settings {
+    /// Load settings from disk, falling back to defaults if missing or invalid
+    pub fn load() -> Self {
+        let path = get_settings_path();
+        match fs::read_to_string(&path) {
+            Ok(data) => toml::from_str(&data).unwrap_or_else(|e| {
+                eprintln!("Warning: Invalid settings file {:?}, using defaults: {}", path, e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(), // No settings saved yet
+        }
+    }
+
+    /// Save settings to disk
+    pub fn save(&self) -> Result<(), String> {
+        let path = get_settings_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory {:?}: {}", parent, e))?;
+        }
+
+        let data = toml::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        fs::write(&path, data)
+            .map_err(|e| format!("Failed to write settings file: {}", e))
+    }
+}
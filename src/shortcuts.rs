@@ -0,0 +1,129 @@
+use gtk::prelude::*;
+use gtk::Application;
+
+use crate::settings::Settings;
+
+/// A command registered as a window action (`win.<name>`) with its default accelerators
+pub struct Command {
+    pub name: &'static str,
+    pub section: &'static str,
+    pub description: &'static str,
+    pub default_accels: &'static [&'static str],
+}
+
+impl Command {
+    /// Full action name as used by `set_accels_for_action` and `action_name`
+    pub fn detailed_name(&self) -> String {
+        format!("win.{}", self.name)
+    }
+}
+
+/// Sections of the shortcuts dialog, in display order
+pub const SECTIONS: &[&str] = &["General", "Notes", "Formatting"];
+
+/// Every command in the app. The shortcuts dialog is generated from this list.
+pub const COMMANDS: &[Command] = &[
+    // General
+    Command { name: "command-palette", section: "General", description: "Open command palette", default_accels: &["<Shift><Control>p"] },
+    Command { name: "show-shortcuts", section: "General", description: "Show keyboard shortcuts", default_accels: &["<Control>k"] },
+    Command { name: "show-settings", section: "General", description: "Open settings", default_accels: &["<Control>comma"] },
+    Command { name: "toggle-theme", section: "General", description: "Toggle light/dark theme", default_accels: &["<Control>t"] },
+    Command { name: "toggle-sidebar", section: "General", description: "Toggle sidebar", default_accels: &["F9"] },
+    Command { name: "toggle-outline", section: "General", description: "Toggle outline", default_accels: &["<Shift><Control>o"] },
+    Command { name: "toggle-spell-check", section: "General", description: "Toggle spell check", default_accels: &["F7"] },
+    Command { name: "toggle-readability", section: "General", description: "Toggle readability panel", default_accels: &["<Shift><Control>r"] },
+    Command { name: "toggle-fold", section: "General", description: "Fold or unfold section", default_accels: &["<Control>bracketleft"] },
+    Command { name: "unfold-all", section: "General", description: "Unfold all sections", default_accels: &["<Control>bracketright"] },
+    Command { name: "toggle-focus-mode", section: "General", description: "Toggle focus mode", default_accels: &["F8"] },
+    Command { name: "toggle-typewriter", section: "General", description: "Toggle typewriter scrolling", default_accels: &["<Shift><Control>t"] },
+    Command { name: "toggle-fullscreen", section: "General", description: "Toggle fullscreen mode", default_accels: &["F11"] },
+    // Notes
+    Command { name: "new-note", section: "Notes", description: "Create new note", default_accels: &["<Control>n"] },
+    Command { name: "new-from-template", section: "Notes", description: "New note from template", default_accels: &["<Shift><Control>n"] },
+    Command { name: "quick-switcher", section: "Notes", description: "Quick open note", default_accels: &["<Control>p", "<Control>o"] },
+    Command { name: "save-note", section: "Notes", description: "Save current note", default_accels: &["<Control>s"] },
+    Command { name: "open-today", section: "Notes", description: "Open today's daily note", default_accels: &["<Shift><Control>d"] },
+    Command { name: "journal-previous", section: "Notes", description: "Previous daily note", default_accels: &["<Alt>Left"] },
+    Command { name: "journal-next", section: "Notes", description: "Next daily note", default_accels: &["<Alt>Right"] },
+    Command { name: "journal-calendar", section: "Notes", description: "Daily notes calendar", default_accels: &["<Control>j"] },
+    Command { name: "rename-note", section: "Notes", description: "Rename current note", default_accels: &["<Control>r"] },
+    Command { name: "delete-note", section: "Notes", description: "Delete current note", default_accels: &["<Control>d"] },
+    Command { name: "toggle-backlinks", section: "Notes", description: "Show or hide backlinks", default_accels: &["<Shift><Control>b"] },
+    Command { name: "show-graph", section: "Notes", description: "Open graph view", default_accels: &["<Shift><Control>g"] },
+    Command { name: "start-freewrite", section: "Notes", description: "Start a freewrite session", default_accels: &["<Shift><Control>f"] },
+    Command { name: "abandon-freewrite", section: "Notes", description: "Abandon the freewrite session", default_accels: &[] },
+    Command { name: "show-stats", section: "Notes", description: "Show writing statistics", default_accels: &["<Shift><Control>i"] },
+    Command { name: "import-notes", section: "Notes", description: "Import notes from other apps", default_accels: &[] },
+    Command { name: "export-html", section: "Notes", description: "Export as HTML", default_accels: &["<Shift><Control>e"] },
+    Command { name: "compile-manuscript", section: "Notes", description: "Compile manuscript (EPUB or DOCX)", default_accels: &["<Shift><Control>m"] },
+    Command { name: "print-note", section: "Notes", description: "Print note", default_accels: &["<Control><Alt>p"] },
+    Command { name: "export-pdf", section: "Notes", description: "Export as PDF", default_accels: &["<Shift><Control>x"] },
+    Command { name: "search-notes", section: "Notes", description: "Search notes", default_accels: &["<Control>f"] },
+    // Formatting
+    Command { name: "format-bold", section: "Formatting", description: "Bold", default_accels: &["<Control>b"] },
+    Command { name: "format-italic", section: "Formatting", description: "Italic", default_accels: &["<Control>i"] },
+    Command { name: "format-code", section: "Formatting", description: "Inline code", default_accels: &["<Control>e"] },
+    Command { name: "format-link", section: "Formatting", description: "Insert link", default_accels: &["<Control>l"] },
+    Command { name: "format-heading", section: "Formatting", description: "Cycle heading level", default_accels: &["<Control>h"] },
+    Command { name: "format-quote", section: "Formatting", description: "Toggle quote", default_accels: &["<Shift><Control>q"] },
+    Command { name: "format-checklist", section: "Formatting", description: "Toggle checklist item", default_accels: &["<Control>Return"] },
+];
+
+/// Look up a command by action name (without the `win.` prefix)
+pub fn find_command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// Accelerators for a command: the user's binding if set, else the defaults
+pub fn accels_for(settings: &Settings, command: &Command) -> Vec<String> {
+    settings.shortcuts.get(command.name)
+        .cloned()
+        .unwrap_or_else(|| command.default_accels.iter().map(|a| a.to_string()).collect())
+}
+
+/// Store a binding, dropping the override if it matches the defaults
+pub fn set_accels(settings: &mut Settings, command: &Command, accels: Vec<String>) {
+    if accels.iter().map(String::as_str).eq(command.default_accels.iter().copied()) {
+        settings.shortcuts.remove(command.name);
+    } else {
+        settings.shortcuts.insert(command.name.to_string(), accels);
+    }
+}
+
+/// Register the current bindings of every command with the application
+pub fn apply_accels(app: &Application, settings: &Settings) {
+    for command in COMMANDS {
+        let accels = accels_for(settings, command);
+        let accel_refs: Vec<&str> = accels.iter().map(String::as_str).collect();
+        app.set_accels_for_action(&command.detailed_name(), &accel_refs);
+    }
+}
+
+/// Find another command already bound to `accel`
+pub fn find_conflict(settings: &Settings, accel: &str, except: &str) -> Option<&'static Command> {
+    let wanted = gtk::accelerator_parse(accel)?;
+    COMMANDS.iter()
+        .filter(|command| command.name != except)
+        .find(|command| {
+            accels_for(settings, command).iter()
+                .any(|other| gtk::accelerator_parse(other) == Some(wanted))
+        })
+}
+
+/// Human-readable label for an accelerator, e.g. "<Control>k" -> "Ctrl+K"
+pub fn accel_label(accel: &str) -> String {
+    match gtk::accelerator_parse(accel) {
+        Some((key, mods)) => gtk::accelerator_get_label(key, mods).to_string(),
+        None => accel.to_string(),
+    }
+}
+
+/// Label for all of a command's bindings, or "Disabled" if it has none
+pub fn accels_label(settings: &Settings, command: &Command) -> String {
+    let accels = accels_for(settings, command);
+    if accels.is_empty() {
+        "Disabled".to_string()
+    } else {
+        accels.iter().map(|a| accel_label(a)).collect::<Vec<_>>().join(", ")
+    }
+}
@@ -0,0 +1,404 @@
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib, GestureClick, TextTag, TextView};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::frontmatter;
+use crate::settings::Settings;
+use crate::spelling::{self, Dictionary};
+
+const MISSPELLED_TAG: &str = "misspelled";
+// Front matter key choosing a note's dictionary, e.g. `lang: de_DE` (as in Pandoc)
+const LANGUAGE_KEY: &str = "lang";
+// Re-check this long after the last edit
+const REFRESH_DELAY_MS: u64 = 500;
+
+/// Words added with "Add to Dictionary", one per line
+fn get_personal_dictionary_path() -> PathBuf {
+    glib::user_config_dir().join("penscript/dictionary.txt")
+}
+
+fn load_personal_dictionary() -> HashSet<String> {
+    match fs::read_to_string(get_personal_dictionary_path()) {
+        Ok(data) => data.lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(_) => HashSet::new(), // No words added yet
+    }
+}
+
+fn append_to_personal_dictionary(word: &str) -> Result<(), String> {
+    let path = get_personal_dictionary_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory {:?}: {}", parent, e))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open personal dictionary {:?}: {}", path, e))?;
+    writeln!(file, "{}", word)
+        .map_err(|e| format!("Failed to write personal dictionary: {}", e))
+}
+
+/// Installed dictionaries: language code, affix file and word list
+fn installed_dictionaries() -> Vec<(String, PathBuf, PathBuf)> {
+    let dirs = spelling::dictionary_dirs(&glib::user_data_dir(), &glib::user_config_dir());
+    spelling::available_dictionaries(&dirs)
+}
+
+/// Language codes of the installed dictionaries, for the settings dialog
+pub fn installed_languages() -> Vec<String> {
+    installed_dictionaries().into_iter().map(|(language, _, _)| language).collect()
+}
+
+/// Underlines misspelled words in the editor and offers suggestions, "Add to
+/// Dictionary" and "Ignore All" in the right-click menu. Each note is checked in
+/// the language of its `lang` front matter field, or the default from the settings.
+#[derive(Clone)]
+pub struct SpellCheck {
+    text_view: TextView,
+    settings: Rc<RefCell<Settings>>,
+    tag: TextTag,
+    dictionaries: Rc<Vec<(String, PathBuf, PathBuf)>>,
+    /// Dictionaries loaded so far (None if loading failed, so it is not retried)
+    loaded: Rc<RefCell<HashMap<String, Option<Rc<Dictionary>>>>>,
+    /// Languages whose dictionary is being loaded in the background
+    loading: Rc<RefCell<HashSet<String>>>,
+    personal_words: Rc<RefCell<HashSet<String>>>,
+    /// Words ignored until the app is closed
+    ignored_words: Rc<RefCell<HashSet<String>>>,
+    /// The misspelled word last right-clicked: char offsets and text
+    clicked_word: Rc<RefCell<Option<(i32, i32, String)>>>,
+    language_action: gio::SimpleAction,
+    pending_refresh: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl SpellCheck {
+    pub fn new(text_view: &TextView, settings: &Rc<RefCell<Settings>>) -> Self {
+        let tag = TextTag::builder()
+            .name(MISSPELLED_TAG)
+            .underline(gtk::pango::Underline::Error)
+            .underline_rgba(&gdk::RGBA::parse("#e01b24").unwrap_or(gdk::RGBA::RED))
+            .build();
+        text_view.buffer().tag_table().add(&tag);
+
+        let spell_check = SpellCheck {
+            text_view: text_view.clone(),
+            settings: settings.clone(),
+            tag,
+            dictionaries: Rc::new(installed_dictionaries()),
+            loaded: Rc::new(RefCell::new(HashMap::new())),
+            loading: Rc::new(RefCell::new(HashSet::new())),
+            personal_words: Rc::new(RefCell::new(load_personal_dictionary())),
+            ignored_words: Rc::new(RefCell::new(HashSet::new())),
+            clicked_word: Rc::new(RefCell::new(None)),
+            language_action: gio::SimpleAction::new_stateful("language", Some(glib::VariantTy::STRING), &"".to_variant()),
+            pending_refresh: Rc::new(RefCell::new(None)),
+        };
+        spell_check.connect_signals();
+        spell_check
+    }
+
+    fn connect_signals(&self) {
+        // Context menu actions, scoped to the editor
+        let actions = gio::SimpleActionGroup::new();
+
+        let replace_action = gio::SimpleAction::new("replace", Some(glib::VariantTy::STRING));
+        let spell_check = self.clone();
+        replace_action.connect_activate(move |_, parameter| {
+            if let Some(replacement) = parameter.and_then(|p| p.get::<String>()) {
+                spell_check.replace_clicked_word(&replacement);
+            }
+        });
+        actions.add_action(&replace_action);
+
+        let add_action = gio::SimpleAction::new("add-word", None);
+        let spell_check = self.clone();
+        add_action.connect_activate(move |_, _| {
+            if let Some((_, _, word)) = spell_check.clicked_word.borrow_mut().take() {
+                if let Err(e) = append_to_personal_dictionary(&word) {
+                    eprintln!("Error adding {} to the personal dictionary: {}", word, e);
+                }
+                spell_check.personal_words.borrow_mut().insert(word);
+            }
+            spell_check.refresh();
+        });
+        actions.add_action(&add_action);
+
+        let ignore_action = gio::SimpleAction::new("ignore-word", None);
+        let spell_check = self.clone();
+        ignore_action.connect_activate(move |_, _| {
+            if let Some((_, _, word)) = spell_check.clicked_word.borrow_mut().take() {
+                spell_check.ignored_words.borrow_mut().insert(word);
+            }
+            spell_check.refresh();
+        });
+        actions.add_action(&ignore_action);
+
+        let spell_check = self.clone();
+        self.language_action.connect_activate(move |_, parameter| {
+            if let Some(language) = parameter.and_then(|p| p.get::<String>()) {
+                spell_check.set_note_language(&language);
+            }
+        });
+        actions.add_action(&self.language_action);
+        self.text_view.insert_action_group("spell", Some(&actions));
+
+        // Build the context menu for the word under the pointer before it opens
+        let click = GestureClick::new();
+        click.set_button(gdk::BUTTON_SECONDARY);
+        click.set_propagation_phase(gtk::PropagationPhase::Capture);
+        let spell_check = self.clone();
+        click.connect_pressed(move |_, _, x, y| {
+            spell_check.update_menu(x, y);
+        });
+        self.text_view.add_controller(click);
+
+        let spell_check = self.clone();
+        self.text_view.buffer().connect_changed(move |_| {
+            spell_check.schedule_refresh();
+        });
+        self.update_menu(-1.0, -1.0);
+    }
+
+    /// Turn spell checking on or off
+    pub fn toggle(&self) {
+        {
+            let mut settings = self.settings.borrow_mut();
+            settings.spelling.enabled = !settings.spelling.enabled;
+            if let Err(e) = settings.save() {
+                eprintln!("Error saving settings: {}", e);
+            }
+        }
+        self.refresh();
+    }
+
+    fn schedule_refresh(&self) {
+        if let Some(source_id) = self.pending_refresh.borrow_mut().take() {
+            source_id.remove();
+        }
+        let spell_check = self.clone();
+        let source_id = glib::timeout_add_local_once(std::time::Duration::from_millis(REFRESH_DELAY_MS), move || {
+            spell_check.pending_refresh.borrow_mut().take();
+            spell_check.refresh();
+        });
+        *self.pending_refresh.borrow_mut() = Some(source_id);
+    }
+
+    /// The language set in a note's front matter, if there is a dictionary for it
+    fn note_language(&self, text: &str) -> Option<String> {
+        let requested = frontmatter::parse(text).get(LANGUAGE_KEY)?.to_string();
+        let languages: Vec<String> = self.dictionaries.iter().map(|(language, _, _)| language.clone()).collect();
+        spelling::match_language(&requested, &languages).cloned()
+    }
+
+    /// Dictionary for notes without a language: the one in the settings, else the system locale's
+    fn default_language(&self) -> Option<String> {
+        let languages: Vec<String> = self.dictionaries.iter().map(|(language, _, _)| language.clone()).collect();
+        let configured = self.settings.borrow().spelling.language.clone();
+        spelling::match_language(&configured, &languages)
+            .or_else(|| glib::language_names().iter().find_map(|name| spelling::match_language(name, &languages)))
+            .cloned()
+    }
+
+    /// A loaded dictionary. The first time a language is needed its dictionary is
+    /// loaded on a worker thread, as expanding a large one takes a while, and the
+    /// note is checked again once it is ready.
+    fn dictionary(&self, language: &str) -> Option<Rc<Dictionary>> {
+        if let Some(dictionary) = self.loaded.borrow().get(language) {
+            return dictionary.clone();
+        }
+        if !self.loading.borrow_mut().insert(language.to_string()) {
+            return None; // Already loading
+        }
+        let (aff_path, dic_path) = match self.dictionaries.iter().find(|(code, _, _)| code == language) {
+            Some((_, aff_path, dic_path)) => (aff_path.clone(), dic_path.clone()),
+            None => {
+                self.loading.borrow_mut().remove(language);
+                self.loaded.borrow_mut().insert(language.to_string(), None);
+                return None;
+            }
+        };
+
+        let spell_check = self.clone();
+        let language = language.to_string();
+        glib::MainContext::default().spawn_local(async move {
+            let result = gio::spawn_blocking(move || Dictionary::load(&aff_path, &dic_path)).await;
+            let dictionary = match result {
+                Ok(Ok(dictionary)) => Some(Rc::new(dictionary)),
+                Ok(Err(e)) => {
+                    eprintln!("Error loading {} dictionary: {}", language, e);
+                    None
+                }
+                Err(_) => {
+                    eprintln!("Error loading {} dictionary: the loading thread panicked", language);
+                    None
+                }
+            };
+            spell_check.loading.borrow_mut().remove(&language);
+            spell_check.loaded.borrow_mut().insert(language, dictionary);
+            spell_check.refresh();
+        });
+        None
+    }
+
+    fn is_accepted(&self, word: &str) -> bool {
+        let lower = word.to_lowercase();
+        let personal_words = self.personal_words.borrow();
+        let ignored_words = self.ignored_words.borrow();
+        personal_words.contains(word) || personal_words.contains(&lower)
+            || ignored_words.contains(word) || ignored_words.contains(&lower)
+    }
+
+    /// Re-check the whole note
+    pub fn refresh(&self) {
+        let buffer = self.text_view.buffer();
+        let (start, end) = buffer.bounds();
+        buffer.remove_tag(&self.tag, &start, &end);
+
+        let text = buffer.text(&start, &end, true);
+        let note_language = self.note_language(&text);
+        self.language_action.set_state(&note_language.clone().unwrap_or_default().to_variant());
+        if !self.settings.borrow().spelling.enabled {
+            return;
+        }
+        let dictionary = match note_language.or_else(|| self.default_language()).and_then(|language| self.dictionary(&language)) {
+            Some(dictionary) => dictionary,
+            None => return, // No dictionaries installed, or still loading
+        };
+
+        // Words are in order, so convert byte offsets to char offsets incrementally
+        let (mut byte_pos, mut char_pos) = (0, 0);
+        for (word_start, word_end) in spelling::checkable_words(&text) {
+            let word = &text[word_start..word_end];
+            if dictionary.check(word) || self.is_accepted(word) {
+                continue;
+            }
+            char_pos += text[byte_pos..word_start].chars().count() as i32;
+            byte_pos = word_start;
+            let length = word.chars().count() as i32;
+            buffer.apply_tag(&self.tag, &buffer.iter_at_offset(char_pos), &buffer.iter_at_offset(char_pos + length));
+        }
+    }
+
+    /// Misspelled word at a position in the editor: char offsets and text
+    fn misspelled_word_at(&self, x: f64, y: f64) -> Option<(i32, i32, String)> {
+        let (buffer_x, buffer_y) = self.text_view.window_to_buffer_coords(gtk::TextWindowType::Widget, x as i32, y as i32);
+        let iter = self.text_view.iter_at_location(buffer_x, buffer_y)?;
+        if !iter.has_tag(&self.tag) {
+            return None;
+        }
+        let mut start = iter;
+        if !start.starts_tag(Some(&self.tag)) {
+            start.backward_to_tag_toggle(Some(&self.tag));
+        }
+        let mut end = iter;
+        end.forward_to_tag_toggle(Some(&self.tag));
+        let word = self.text_view.buffer().text(&start, &end, true).to_string();
+        Some((start.offset(), end.offset(), word))
+    }
+
+    /// Rebuild the editor's context menu for the word at a position (if misspelled)
+    fn update_menu(&self, x: f64, y: f64) {
+        let clicked_word = self.misspelled_word_at(x, y);
+        let menu = gio::Menu::new();
+
+        if let Some((_, _, word)) = &clicked_word {
+            let text = {
+                let buffer = self.text_view.buffer();
+                buffer.text(&buffer.start_iter(), &buffer.end_iter(), true)
+            };
+            let suggestions = self.note_language(&text)
+                .or_else(|| self.default_language())
+                .and_then(|language| self.dictionary(&language))
+                .map(|dictionary| dictionary.suggest(word))
+                .unwrap_or_default();
+            let suggestion_section = gio::Menu::new();
+            for suggestion in &suggestions {
+                let item = gio::MenuItem::new(Some(suggestion), None);
+                item.set_action_and_target_value(Some("spell.replace"), Some(&suggestion.to_variant()));
+                suggestion_section.append_item(&item);
+            }
+            if suggestions.is_empty() {
+                // No action, so the item shows disabled
+                suggestion_section.append(Some("No Suggestions"), Some("spell.none"));
+            }
+            menu.append_section(None, &suggestion_section);
+
+            let word_section = gio::Menu::new();
+            word_section.append(Some(&format!("Add “{}” to Dictionary", word)), Some("spell.add-word"));
+            word_section.append(Some("Ignore All"), Some("spell.ignore-word"));
+            menu.append_section(None, &word_section);
+        }
+
+        let language_menu = gio::Menu::new();
+        if self.dictionaries.is_empty() {
+            language_menu.append(Some("No Dictionaries Installed"), Some("spell.none"));
+        } else {
+            let default_label = match self.default_language() {
+                Some(language) => format!("Default ({})", language),
+                None => "Default".to_string(),
+            };
+            let default_item = gio::MenuItem::new(Some(&default_label), None);
+            default_item.set_action_and_target_value(Some("spell.language"), Some(&"".to_variant()));
+            language_menu.append_item(&default_item);
+            for (language, _, _) in self.dictionaries.iter() {
+                let item = gio::MenuItem::new(Some(language), None);
+                item.set_action_and_target_value(Some("spell.language"), Some(&language.to_variant()));
+                language_menu.append_item(&item);
+            }
+        }
+        let language_section = gio::Menu::new();
+        language_section.append_submenu(Some("Spelling Language"), &language_menu);
+        menu.append_section(None, &language_section);
+
+        *self.clicked_word.borrow_mut() = clicked_word;
+        self.text_view.set_extra_menu(Some(&menu));
+    }
+
+    fn replace_clicked_word(&self, replacement: &str) {
+        let (start, end, word) = match self.clicked_word.borrow_mut().take() {
+            Some(clicked_word) => clicked_word,
+            None => return,
+        };
+        let buffer = self.text_view.buffer();
+        let mut start_iter = buffer.iter_at_offset(start);
+        let mut end_iter = buffer.iter_at_offset(end);
+        // The note may have changed while the menu was open
+        if buffer.text(&start_iter, &end_iter, true) != word {
+            return;
+        }
+        buffer.begin_user_action();
+        buffer.delete(&mut start_iter, &mut end_iter);
+        buffer.insert(&mut start_iter, replacement);
+        buffer.end_user_action();
+    }
+
+    /// Store the note's language in its front matter ("" = use the default)
+    fn set_note_language(&self, language: &str) {
+        let buffer = self.text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true).to_string();
+        let new_text = frontmatter::set_field(&text, LANGUAGE_KEY, (!language.is_empty()).then_some(language));
+        if new_text == text {
+            return;
+        }
+
+        // Only the front matter changes, so replace just that part to keep the cursor and undo history
+        let body_len = frontmatter::split(&text).1.len();
+        let old_prefix_chars = text[..text.len() - body_len].chars().count() as i32;
+        let new_prefix = &new_text[..new_text.len() - body_len];
+        buffer.begin_user_action();
+        buffer.delete(&mut buffer.start_iter(), &mut buffer.iter_at_offset(old_prefix_chars));
+        buffer.insert(&mut buffer.start_iter(), new_prefix);
+        buffer.end_user_action();
+        self.refresh();
+    }
+}
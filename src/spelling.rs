@@ -0,0 +1,760 @@
+// Offline spell checking with Hunspell dictionaries (the `.aff`/`.dic` pairs
+// Enchant and most desktop apps use). Words are expanded with their prefix and
+// suffix rules when a dictionary is loaded, which takes a moment for large ones.
+// Only part of the affix format is read: compounding (COMPOUNDFLAG, COMPOUNDRULE
+// and the like), input and output conversion (ICONV, OCONV), CHECKSHARPS,
+// KEEPCASE and the suggestion tables other than TRY and REP are ignored, so
+// languages relying on compounds (German, Dutch, Finnish, Hungarian) flag many
+// correct words.
+// Everything here works on `&str` and plain paths so it stays independent of GTK.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{frontmatter, markdown, text_stats};
+
+// Most suggestions offered for a word
+const MAX_SUGGESTIONS: usize = 6;
+// Longer words only get suggestions one edit away (two edits would be too slow)
+const MAX_TWO_EDIT_LEN: usize = 12;
+// Characters tried in suggestions when the dictionary has no TRY line
+const DEFAULT_TRY: &str = "esianrtolcdugmphbyfvkwzxjq'";
+
+/// How flags are written in a dictionary (`FLAG` in the affix file)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlagType {
+    /// One character per flag (the default)
+    Char,
+    /// Two characters per flag
+    Long,
+    /// Comma-separated numbers
+    Num,
+}
+
+/// One character position of an affix condition
+#[derive(Debug, Clone, PartialEq)]
+enum ConditionPart {
+    Any,
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl ConditionPart {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionPart::Any => true,
+            ConditionPart::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Affix {
+    strip: String,
+    add: String,
+    condition: Vec<ConditionPart>,
+    /// Flags of the affixed form: further suffixes that may follow this one
+    continuation: Vec<u32>,
+}
+
+/// All prefix or suffix rules sharing a flag
+#[derive(Debug, Clone, Default)]
+struct AffixClass {
+    /// Whether prefixes and suffixes of this class can be combined
+    cross: bool,
+    entries: Vec<Affix>,
+}
+
+/// A loaded Hunspell dictionary
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+    forbidden: HashSet<String>,
+    /// Correct, but never offered as suggestions (usually offensive words)
+    no_suggest: HashSet<String>,
+    try_chars: Vec<char>,
+    /// Common misspellings (`REP` lines): pattern and replacement
+    replacements: Vec<(String, String)>,
+    /// Characters ignored when checking (`IGNORE`, e.g. Arabic diacritics)
+    ignore_chars: Vec<char>,
+}
+
+/// Decode dictionary bytes in the encoding named by the affix file's `SET` line
+fn decode(bytes: &[u8], encoding: &str) -> Result<String, String> {
+    // Hunspell's names, where they are not also the standard labels encoding_rs knows
+    let label = match encoding.to_lowercase().as_str() {
+        "" => "utf-8".to_string(),
+        "microsoft-cp1251" => "windows-1251".to_string(),
+        "tis620-2533" => "tis-620".to_string(),
+        other => other.to_string(),
+    };
+    let encoding = match encoding_rs::Encoding::for_label(label.as_bytes()) {
+        Some(encoding) => encoding,
+        None => return Err(format!("Unsupported dictionary encoding {}", encoding)),
+    };
+    let (text, _, _) = encoding.decode(bytes);
+    Ok(text.into_owned())
+}
+
+/// Parse an affix condition such as `.`, `[^aeiou]y` or `ch`
+fn parse_condition(condition: &str) -> Vec<ConditionPart> {
+    if condition == "." {
+        return Vec::new();
+    }
+    let mut parts = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parts.push(ConditionPart::Any),
+            '[' => {
+                let mut set: Vec<char> = Vec::new();
+                let mut negated = false;
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => break,
+                        '^' if set.is_empty() && !negated => negated = true,
+                        _ => set.push(c),
+                    }
+                }
+                parts.push(ConditionPart::Set { chars: set, negated });
+            }
+            _ => parts.push(ConditionPart::Set { chars: vec![c], negated: false }),
+        }
+    }
+    parts
+}
+
+impl Dictionary {
+    /// Load a dictionary from its affix and word list files
+    pub fn load(aff_path: &Path, dic_path: &Path) -> Result<Self, String> {
+        let aff_bytes = fs::read(aff_path)
+            .map_err(|e| format!("Failed to read affix file {:?}: {}", aff_path, e))?;
+        let dic_bytes = fs::read(dic_path)
+            .map_err(|e| format!("Failed to read dictionary file {:?}: {}", dic_path, e))?;
+
+        // The encoding line is plain ASCII, so it can be found before decoding
+        let encoding = String::from_utf8_lossy(&aff_bytes).lines()
+            .find_map(|line| line.trim().strip_prefix("SET ").map(|e| e.trim().to_string()))
+            .unwrap_or_default();
+        let aff = decode(&aff_bytes, &encoding)?;
+        let dic = decode(&dic_bytes, &encoding)?;
+        Ok(Self::parse(&aff, &dic))
+    }
+
+    /// Build a dictionary from the text of its affix and word list files
+    fn parse(aff: &str, dic: &str) -> Self {
+        let mut dictionary = Dictionary::default();
+        let mut flag_type = FlagType::Char;
+        let mut flag_aliases: Vec<Vec<u32>> = Vec::new();
+        let mut alias_header_seen = false;
+        let mut prefixes: HashMap<u32, AffixClass> = HashMap::new();
+        let mut suffixes: HashMap<u32, AffixClass> = HashMap::new();
+        let (mut need_affix, mut only_in_compound, mut forbidden, mut no_suggest) = (None, None, None, None);
+        // Rules still expected after a PFX/SFX header line
+        let mut pending_entries = 0;
+
+        let parse_flags = |flags: &str, flag_type: FlagType, aliases: &[Vec<u32>]| -> Vec<u32> {
+            if !aliases.is_empty() {
+                if let Ok(index) = flags.parse::<usize>() {
+                    return aliases.get(index.wrapping_sub(1)).cloned().unwrap_or_default();
+                }
+            }
+            match flag_type {
+                FlagType::Char => flags.chars().map(|c| c as u32).collect(),
+                FlagType::Long => flags.chars().collect::<Vec<char>>()
+                    .chunks(2)
+                    .map(|pair| pair.iter().fold(0, |flag, c| (flag << 16) | *c as u32))
+                    .collect(),
+                FlagType::Num => flags.split(',').filter_map(|n| n.trim().parse().ok()).collect(),
+            }
+        };
+
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (keyword, args) = match fields.split_first() {
+                Some((keyword, args)) => (*keyword, args),
+                None => continue,
+            };
+            let first_flag = |args: &[&str], flag_type| args.first()
+                .and_then(|flag| parse_flags(flag, flag_type, &[]).first().copied());
+            match keyword {
+                "FLAG" => {
+                    flag_type = match args.first().copied() {
+                        Some("long") => FlagType::Long,
+                        Some("num") => FlagType::Num,
+                        _ => FlagType::Char, // "UTF-8" flags are single characters too
+                    };
+                }
+                // The first AF line holds the number of aliases, the rest one flag set each
+                "AF" if args.len() == 1 => {
+                    if alias_header_seen {
+                        flag_aliases.push(parse_flags(args[0], flag_type, &[]));
+                    }
+                    alias_header_seen = true;
+                }
+                "TRY" => dictionary.try_chars = args.first().map(|chars| chars.chars().collect()).unwrap_or_default(),
+                "IGNORE" => dictionary.ignore_chars = args.first().map(|chars| chars.chars().collect()).unwrap_or_default(),
+                "REP" if args.len() >= 2 => {
+                    // Underscores stand for spaces in replacements
+                    dictionary.replacements.push((args[0].replace('_', " "), args[1].replace('_', " ")));
+                }
+                "NEEDAFFIX" | "PSEUDOROOT" => need_affix = first_flag(args, flag_type),
+                "ONLYINCOMPOUND" => only_in_compound = first_flag(args, flag_type),
+                "FORBIDDENWORD" => forbidden = first_flag(args, flag_type),
+                "NOSUGGEST" => no_suggest = first_flag(args, flag_type),
+                "PFX" | "SFX" if args.len() >= 3 => {
+                    let flag = match parse_flags(args[0], flag_type, &[]).first() {
+                        Some(flag) => *flag,
+                        None => continue,
+                    };
+                    let classes = if keyword == "PFX" { &mut prefixes } else { &mut suffixes };
+                    if pending_entries == 0 {
+                        // Header: flag, cross product, number of rules
+                        pending_entries = args[2].parse().unwrap_or(0);
+                        classes.entry(flag).or_default().cross = args[1] == "Y";
+                        continue;
+                    }
+                    pending_entries -= 1;
+                    let (add, continuation) = match args[2].split_once('/') {
+                        Some((add, flags)) => (add, parse_flags(flags, flag_type, &flag_aliases)),
+                        None => (args[2], Vec::new()),
+                    };
+                    let zero_to_empty = |s: &str| if s == "0" { String::new() } else { s.to_string() };
+                    classes.entry(flag).or_default().entries.push(Affix {
+                        strip: zero_to_empty(args[1]),
+                        add: zero_to_empty(add),
+                        condition: parse_condition(args.get(3).copied().unwrap_or(".")),
+                        continuation,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let mut lines = dic.lines();
+        // The first line is the (approximate) number of words
+        if let Some(first) = lines.clone().next() {
+            if first.trim().parse::<usize>().is_ok() {
+                lines.next();
+            }
+        }
+        for line in lines {
+            // Morphological fields follow the word after whitespace
+            let entry = match line.split(['\t', ' ']).next() {
+                Some(entry) if !entry.is_empty() && !entry.starts_with('#') => entry,
+                _ => continue,
+            };
+            let (word, flags) = match entry.split_once('/') {
+                Some((word, flags)) => (word, parse_flags(flags, flag_type, &flag_aliases)),
+                None => (entry, Vec::new()),
+            };
+            let word = dictionary.strip_ignored(word);
+            let has = |flag: Option<u32>| flag.is_some_and(|flag| flags.contains(&flag));
+            if has(only_in_compound) {
+                continue;
+            }
+            if has(forbidden) {
+                dictionary.forbidden.insert(word);
+                continue;
+            }
+            let forms = expand(&word, &flags, !has(need_affix), &prefixes, &suffixes);
+            if has(no_suggest) {
+                dictionary.no_suggest.extend(forms.iter().cloned());
+            }
+            dictionary.words.extend(forms);
+        }
+        dictionary
+    }
+
+    fn strip_ignored(&self, word: &str) -> String {
+        let word = word.replace('’', "'");
+        if self.ignore_chars.is_empty() {
+            return word;
+        }
+        word.chars().filter(|c| !self.ignore_chars.contains(c)).collect()
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains(word) && !self.forbidden.contains(word)
+    }
+
+    /// Whether a word is spelled correctly. Capitalised and all-caps forms of
+    /// dictionary words are accepted, lower-case forms of proper nouns are not.
+    pub fn check(&self, word: &str) -> bool {
+        let word = self.strip_ignored(word);
+        if self.forbidden.contains(&word) {
+            return false;
+        }
+        if self.contains(&word) {
+            return true;
+        }
+        let lower = word.to_lowercase();
+        match case_of(&word) {
+            Case::Capitalized => self.contains(&lower),
+            Case::Upper => self.contains(&lower) || self.contains(&capitalize(&lower)),
+            Case::Lower | Case::Mixed => false,
+        }
+    }
+
+    /// Likely corrections for a misspelled word, best first
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let word = self.strip_ignored(word);
+        let case = case_of(&word);
+        let mut suggestions: Vec<String> = Vec::new();
+        let add = |candidate: String, suggestions: &mut Vec<String>| {
+            let candidate = apply_case(&candidate, case);
+            let acceptable = candidate.split(' ').all(|part| self.check(part))
+                && !self.no_suggest.contains(&candidate.to_lowercase());
+            if acceptable && candidate != word && !suggestions.contains(&candidate) {
+                suggestions.push(candidate);
+            }
+        };
+
+        // Proper nouns typed in lower case
+        add(capitalize(&word), &mut suggestions);
+        let lower = word.to_lowercase();
+        for (pattern, replacement) in &self.replacements {
+            for (i, _) in lower.match_indices(pattern.as_str()) {
+                add(format!("{}{}{}", &lower[..i], replacement, &lower[i + pattern.len()..]), &mut suggestions);
+            }
+        }
+        let one_edit = self.edits(&lower);
+        for candidate in &one_edit {
+            add(candidate.clone(), &mut suggestions);
+        }
+        // Two words run together ("alot" -> "a lot")
+        for (i, _) in lower.char_indices().skip(1) {
+            add(format!("{} {}", &lower[..i], &lower[i..]), &mut suggestions);
+        }
+        if suggestions.is_empty() && lower.chars().count() <= MAX_TWO_EDIT_LEN {
+            for candidate in &one_edit {
+                for second in self.edits(candidate) {
+                    if self.check(&second) {
+                        add(second, &mut suggestions);
+                    }
+                }
+                if suggestions.len() >= MAX_SUGGESTIONS {
+                    break;
+                }
+            }
+        }
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+
+    /// Everything one edit away: swapped neighbours, replaced, removed and inserted characters
+    fn edits(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let try_chars: Vec<char> = if self.try_chars.is_empty() {
+            DEFAULT_TRY.chars().collect()
+        } else {
+            self.try_chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect()
+        };
+        let to_string = |chars: &[char]| chars.iter().collect::<String>();
+        let mut edits = Vec::new();
+        for i in 0..chars.len().saturating_sub(1) {
+            let mut swapped = chars.clone();
+            swapped.swap(i, i + 1);
+            edits.push(to_string(&swapped));
+        }
+        for i in 0..chars.len() {
+            for &c in &try_chars {
+                if c != chars[i] {
+                    let mut replaced = chars.clone();
+                    replaced[i] = c;
+                    edits.push(to_string(&replaced));
+                }
+            }
+        }
+        for i in 0..chars.len() {
+            let mut removed = chars.clone();
+            removed.remove(i);
+            edits.push(to_string(&removed));
+        }
+        for i in 0..=chars.len() {
+            for &c in &try_chars {
+                let mut inserted = chars.clone();
+                inserted.insert(i, c);
+                edits.push(to_string(&inserted));
+            }
+        }
+        edits
+    }
+}
+
+/// A dictionary word with the forms its affix flags allow
+fn expand(word: &str, flags: &[u32], include_root: bool,
+          prefixes: &HashMap<u32, AffixClass>, suffixes: &HashMap<u32, AffixClass>) -> Vec<String> {
+    let mut forms = Vec::new();
+    if include_root {
+        forms.push(word.to_string());
+    }
+
+    // Suffixed forms, and whether each may also take a prefix
+    let mut suffixed: Vec<(String, bool)> = Vec::new();
+    for class in flags.iter().filter_map(|flag| suffixes.get(flag)) {
+        for affix in &class.entries {
+            let form = match apply_suffix(word, affix) {
+                Some(form) => form,
+                None => continue,
+            };
+            // Two-level suffixes ("-ation" + "-s")
+            for second in affix.continuation.iter().filter_map(|flag| suffixes.get(flag)) {
+                for second_affix in &second.entries {
+                    if let Some(second_form) = apply_suffix(&form, second_affix) {
+                        suffixed.push((second_form, false));
+                    }
+                }
+            }
+            suffixed.push((form, class.cross));
+        }
+    }
+
+    for class in flags.iter().filter_map(|flag| prefixes.get(flag)) {
+        for affix in &class.entries {
+            if let Some(form) = apply_prefix(word, affix) {
+                forms.push(form);
+            }
+            if class.cross {
+                for (form, _) in suffixed.iter().filter(|(_, cross)| *cross) {
+                    if let Some(form) = apply_prefix(form, affix) {
+                        forms.push(form);
+                    }
+                }
+            }
+        }
+    }
+    forms.extend(suffixed.into_iter().map(|(form, _)| form));
+    forms
+}
+
+fn apply_suffix(word: &str, affix: &Affix) -> Option<String> {
+    let chars: Vec<char> = word.chars().collect();
+    if !word.ends_with(affix.strip.as_str()) || affix.condition.len() > chars.len() {
+        return None;
+    }
+    let tail = &chars[chars.len() - affix.condition.len()..];
+    if !affix.condition.iter().zip(tail).all(|(part, c)| part.matches(*c)) {
+        return None;
+    }
+    let stem = &word[..word.len() - affix.strip.len()];
+    if stem.is_empty() && affix.add.is_empty() {
+        return None;
+    }
+    Some(format!("{}{}", stem, affix.add))
+}
+
+fn apply_prefix(word: &str, affix: &Affix) -> Option<String> {
+    if !word.starts_with(affix.strip.as_str()) || affix.condition.len() > word.chars().count() {
+        return None;
+    }
+    if !affix.condition.iter().zip(word.chars()).all(|(part, c)| part.matches(c)) {
+        return None;
+    }
+    Some(format!("{}{}", affix.add, &word[affix.strip.len()..]))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Case {
+    Lower,
+    Capitalized,
+    Upper,
+    Mixed,
+}
+
+fn case_of(word: &str) -> Case {
+    let mut letters = word.chars().filter(|c| c.is_alphabetic());
+    let first_upper = letters.next().is_some_and(|c| c.is_uppercase());
+    let rest: Vec<char> = letters.collect();
+    let rest_upper = rest.iter().filter(|c| c.is_uppercase()).count();
+    match (first_upper, rest_upper) {
+        (false, 0) => Case::Lower,
+        (true, 0) => Case::Capitalized,
+        (true, n) if n == rest.len() => Case::Upper,
+        _ => Case::Mixed,
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Give a suggestion the capitalisation of the misspelled word
+fn apply_case(candidate: &str, case: Case) -> String {
+    match case {
+        Case::Capitalized => capitalize(candidate),
+        Case::Upper => candidate.to_uppercase(),
+        Case::Lower | Case::Mixed => candidate.to_string(),
+    }
+}
+
+/// Directories searched for Hunspell dictionaries, most specific first
+pub fn dictionary_dirs(user_data_dir: &Path, user_config_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var("DICPATH")
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    dirs.push(user_data_dir.join("hunspell"));
+    dirs.push(user_config_dir.join("enchant/hunspell"));
+    for dir in ["/usr/share/hunspell", "/usr/share/myspell", "/usr/share/myspell/dicts", "/usr/local/share/hunspell"] {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs
+}
+
+/// Installed dictionaries by language code (the file name, e.g. `en_US`).
+/// The first directory containing a language wins.
+pub fn available_dictionaries(dirs: &[PathBuf]) -> Vec<(String, PathBuf, PathBuf)> {
+    let mut dictionaries: Vec<(String, PathBuf, PathBuf)> = Vec::new();
+    for dir in dirs {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue, // Not every directory exists
+        };
+        let mut found: Vec<(String, PathBuf, PathBuf)> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "dic"))
+            .filter_map(|dic_path| {
+                let aff_path = dic_path.with_extension("aff");
+                let language = dic_path.file_stem()?.to_str()?.to_string();
+                aff_path.exists().then_some((language, aff_path, dic_path))
+            })
+            .filter(|(language, _, _)| !dictionaries.iter().any(|(known, _, _)| known == language))
+            .collect();
+        dictionaries.append(&mut found);
+    }
+    dictionaries.sort_by(|a, b| a.0.cmp(&b.0));
+    dictionaries
+}
+
+/// The installed language best matching a requested one: the exact code, then
+/// the same language in another region (`en` or `en-GB` can use `en_US`)
+pub fn match_language<'a>(requested: &str, available: &'a [String]) -> Option<&'a String> {
+    let requested = requested.replace('-', "_");
+    let requested = requested.split('.').next().unwrap_or(""); // "en_US.UTF-8"
+    if requested.is_empty() {
+        return None;
+    }
+    let base = requested.split('_').next().unwrap_or(requested);
+    available.iter().find(|language| language.eq_ignore_ascii_case(requested))
+        .or_else(|| available.iter().find(|language| {
+            language.split(['_', '-']).next().is_some_and(|b| b.eq_ignore_ascii_case(base))
+        }))
+}
+
+/// Byte ranges of the words to spell check in a note. Front matter, code blocks,
+/// inline code, URLs, e-mail addresses, link targets, wiki links, HTML tags,
+/// `#tags` and words with digits or underscores are skipped.
+pub fn checkable_words(text: &str) -> Vec<(usize, usize)> {
+    let mut offset = match frontmatter::split(text) {
+        (Some(_), body) => text.len() - body.len(),
+        (None, _) => 0,
+    };
+    let mut words = Vec::new();
+    let mut in_code_block = false;
+    for line in text[offset..].split_inclusive('\n') {
+        if markdown::is_code_fence(line) {
+            in_code_block = !in_code_block;
+        } else if !in_code_block {
+            line_words(line, offset, &mut words);
+        }
+        offset += line.len();
+    }
+    words
+}
+
+fn line_words(line: &str, offset: usize, words: &mut Vec<(usize, usize)>) {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let char_at = |i: usize| chars.get(i).map(|(_, c)| *c);
+    let byte_at = |i: usize| chars.get(i).map(|(b, _)| *b).unwrap_or(line.len());
+    // First char index at or after `from` where `pattern` starts
+    let find = |from: usize, pattern: &str| line[byte_at(from)..].find(pattern)
+        .map(|pos| chars.partition_point(|(b, _)| *b < byte_at(from) + pos));
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let token_start = i == 0 || char_at(i - 1).is_some_and(char::is_whitespace);
+        if token_start && !c.is_whitespace() {
+            // URLs and addresses run to the next space
+            let token_end = (i..chars.len()).find(|&j| chars[j].1.is_whitespace()).unwrap_or(chars.len());
+            let token = &line[byte_at(i)..byte_at(token_end)];
+            let token = token.trim_start_matches(['(', '<', '[', '"', '\'']);
+            if is_url_or_address(token) {
+                i = token_end;
+                continue;
+            }
+        }
+
+        let skip_to = match c {
+            // `code` spans end at the same number of backticks
+            '`' => {
+                let run = (i..chars.len()).take_while(|&j| chars[j].1 == '`').count();
+                let fence: String = "`".repeat(run);
+                Some(find(i + run, &fence).map(|end| end + run).unwrap_or(i + run))
+            }
+            '[' if char_at(i + 1) == Some('[') => find(i + 2, "]]").map(|end| end + 2),
+            ']' if char_at(i + 1) == Some('(') => find(i + 2, ")").map(|end| end + 1),
+            '<' if char_at(i + 1).is_some_and(|next| next.is_ascii_alphabetic() || next == '/') => {
+                find(i + 1, ">").map(|end| end + 1)
+            }
+            '#' if token_start && char_at(i + 1).is_some_and(char::is_alphanumeric) => {
+                Some((i..chars.len()).find(|&j| chars[j].1.is_whitespace()).unwrap_or(chars.len()))
+            }
+            _ => None,
+        };
+        if let Some(end) = skip_to {
+            i = end.max(i + 1);
+            continue;
+        }
+
+        if !c.is_alphanumeric() {
+            i += 1;
+            continue;
+        }
+        // A word: letters and digits, joined by apostrophes and underscores
+        let start = i;
+        while i < chars.len() {
+            let c = chars[i].1;
+            let joins = matches!(c, '\'' | '’' | '_')
+                && char_at(i + 1).is_some_and(char::is_alphanumeric);
+            if !(c.is_alphanumeric() || joins) {
+                break;
+            }
+            i += 1;
+        }
+        let word = &line[byte_at(start)..byte_at(i)];
+        let checkable = !word.chars().any(|c| c.is_numeric() || c == '_' || text_stats::is_cjk(c));
+        if checkable {
+            words.push((offset + byte_at(start), offset + byte_at(i)));
+        }
+    }
+}
+
+/// `scheme://…`, `www.…` and `name@domain` (but not `[text](https://…)`, whose text is checked)
+fn is_url_or_address(token: &str) -> bool {
+    let scheme_url = token.split_once("://").is_some_and(|(scheme, _)| {
+        !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    let address = token.split_once('@').is_some_and(|(name, domain)| {
+        !name.is_empty() && domain.contains('.') && !token.contains(['[', ']', '('])
+    });
+    scheme_url || token.starts_with("www.") || address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "\
+SET UTF-8
+TRY esianrtolcdugmphbyfvkwz
+FLAG long
+NEEDAFFIX Nn
+FORBIDDENWORD Fb
+REP 1
+REP f ph
+PFX Re Y 1
+PFX Re 0 re .
+SFX Pl Y 3
+SFX Pl 0 s [^sxy]
+SFX Pl y ies [^aeiou]y
+SFX Pl 0 es [sx]
+SFX Ed N 2
+SFX Ed 0 ed [^ey]/Pl
+SFX Ed e ed e
+";
+
+    const DIC: &str = "\
+7
+cat/Pl
+try/PlRe
+box/Pl
+bake/Ed
+the
+phone
+Paris
+";
+
+    fn dictionary() -> Dictionary {
+        Dictionary::parse(AFF, DIC)
+    }
+
+    #[test]
+    fn expands_suffixes_by_condition() {
+        let dictionary = dictionary();
+        assert!(dictionary.check("cats"));
+        assert!(dictionary.check("tries"));
+        assert!(dictionary.check("boxes"));
+        assert!(dictionary.check("baked"));
+        assert!(!dictionary.check("catses"));
+        assert!(!dictionary.check("trys"));
+        assert!(!dictionary.check("boxs"));
+    }
+
+    #[test]
+    fn combines_prefixes_with_cross_product_suffixes() {
+        let dictionary = dictionary();
+        assert!(dictionary.check("retry"));
+        assert!(dictionary.check("retries"));
+        assert!(!dictionary.check("recats"));
+    }
+
+    #[test]
+    fn checks_case_like_hunspell() {
+        let dictionary = dictionary();
+        assert!(dictionary.check("Cats"));
+        assert!(dictionary.check("CATS"));
+        assert!(dictionary.check("PARIS"));
+        assert!(!dictionary.check("paris"));
+        assert!(!dictionary.check("cAts"));
+    }
+
+    #[test]
+    fn honours_needaffix_and_forbidden_words() {
+        let dictionary = Dictionary::parse(AFF, "3\nfly/NnPl\nsheep\nsheeps/Fb\n");
+        assert!(!dictionary.check("fly"));
+        assert!(dictionary.check("flies"));
+        assert!(dictionary.check("sheep"));
+        assert!(!dictionary.check("sheeps"));
+    }
+
+    #[test]
+    fn suggests_nearby_words() {
+        let dictionary = dictionary();
+        assert_eq!(dictionary.suggest("teh").first().map(String::as_str), Some("the"));
+        assert!(dictionary.suggest("fone").contains(&"phone".to_string()));
+        assert!(dictionary.suggest("paris").contains(&"Paris".to_string()));
+        assert!(dictionary.suggest("Cta").contains(&"Cat".to_string()));
+        assert!(dictionary.suggest("thecat").contains(&"the cat".to_string()));
+    }
+
+    #[test]
+    fn decodes_legacy_encodings() {
+        // "привет" in KOI8-R and in cp1251
+        assert_eq!(decode(&[0xd0, 0xd2, 0xc9, 0xd7, 0xc5, 0xd4], "KOI8-R").unwrap(), "привет");
+        assert_eq!(decode(&[0xef, 0xf0, 0xe8, 0xe2, 0xe5, 0xf2], "microsoft-cp1251").unwrap(), "привет");
+        assert_eq!(decode(&[0x63, 0x61, 0x66, 0xe9], "ISO8859-1").unwrap(), "café");
+        assert_eq!(decode(&[0xa4], "ISO8859-15").unwrap(), "€");
+        assert!(decode(b"word", "no-such-encoding").is_err());
+    }
+
+    #[test]
+    fn matches_languages_by_region() {
+        let available = vec!["de_DE".to_string(), "en_GB".to_string(), "en_US".to_string()];
+        assert_eq!(match_language("en_US", &available).map(String::as_str), Some("en_US"));
+        assert_eq!(match_language("en-GB", &available).map(String::as_str), Some("en_GB"));
+        assert_eq!(match_language("de", &available).map(String::as_str), Some("de_DE"));
+        assert_eq!(match_language("fr_FR", &available), None);
+    }
+
+    #[test]
+    fn skips_code_links_and_urls() {
+        let text = "Some `code` and [text](https://example.com) at www.example.com with [[Note]] and #tag\n\n```\nfenced\n```\n";
+        let words: Vec<&str> = checkable_words(text).into_iter().map(|(start, end)| &text[start..end]).collect();
+        assert_eq!(words, ["Some", "and", "text", "at", "with", "and"]);
+    }
+}
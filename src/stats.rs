@@ -0,0 +1,459 @@
+use gtk::prelude::*;
+use gtk::{cairo, glib, ApplicationWindow, Box, DrawingArea, Label, ListBox, Orientation, ScrolledWindow};
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+// Days shown in the per-day chart, and weeks in the per-week chart
+const DAYS_SHOWN: i64 = 30;
+const WEEKS_SHOWN: i64 = 12;
+// Weeks covered by the calendar heatmap (one year)
+const HEATMAP_WEEKS: i64 = 53;
+// Notes listed in the per-note totals
+const TOP_NOTES: usize = 20;
+
+/// Change in a note's word count between two saves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StatsEntry {
+    /// Unix timestamp of the save
+    time: i64,
+    note: String,
+    delta: i64,
+}
+
+// One JSON entry per line, appended on each save so the file is never rewritten
+fn get_stats_log_path() -> PathBuf {
+    glib::user_data_dir().join("penscript/writing-log.jsonl")
+}
+
+fn load_entries() -> Vec<StatsEntry> {
+    let path = get_stats_log_path();
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(_) => return Vec::new(), // Nothing recorded yet
+    };
+    let mut entries = Vec::new();
+    for (number, line) in data.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        // A line cut short by a crash only loses that save
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => eprintln!("Warning: Skipping invalid line {} of writing log {:?}: {}", number + 1, path, e),
+        }
+    }
+    entries
+}
+
+fn append_entry(entry: &StatsEntry) -> Result<(), String> {
+    let path = get_stats_log_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create data directory {:?}: {}", parent, e))?;
+    }
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize writing log entry: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open writing log {:?}: {}", path, e))?;
+    writeln!(file, "{}", line)
+        .map_err(|e| format!("Failed to write writing log: {}", e))
+}
+
+/// Writing history: word deltas logged on save, summarised by the statistics view
+/// and read by the daily word goal
+#[derive(Clone)]
+pub struct WritingStats {
+    /// The log, oldest first
+    entries: Rc<RefCell<Vec<StatsEntry>>>,
+    /// Word count of each note when it was loaded or last saved
+    baselines: Rc<RefCell<HashMap<String, usize>>>,
+}
+
+impl WritingStats {
+    pub fn load() -> Self {
+        WritingStats {
+            entries: Rc::new(RefCell::new(load_entries())),
+            baselines: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// A note was loaded: later saves are measured against its current word count
+    pub fn observe(&self, title: &str, word_count: usize) {
+        self.baselines.borrow_mut().insert(title.to_string(), word_count);
+    }
+
+    /// A note was saved: log the words added (or removed) since its last save
+    pub fn record_save(&self, title: &str, word_count: usize) {
+        let previous = self.baselines.borrow_mut().insert(title.to_string(), word_count);
+        // Notes never loaded in this session (e.g. just renamed) only get a baseline
+        let delta = match previous {
+            Some(previous) => word_count as i64 - previous as i64,
+            None => return,
+        };
+        if delta == 0 {
+            return;
+        }
+
+        let entry = StatsEntry {
+            time: Local::now().timestamp(),
+            note: title.to_string(),
+            delta,
+        };
+        if let Err(e) = append_entry(&entry) {
+            eprintln!("Error saving writing statistics: {}", e);
+        }
+        self.entries.borrow_mut().push(entry);
+    }
+
+    /// Net words saved today
+    pub fn words_today(&self) -> i64 {
+        let midnight = Local::now().date_naive().and_hms_opt(0, 0, 0)
+            .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+            .map(|midnight| midnight.timestamp())
+            .unwrap_or(i64::MAX);
+        // Entries are appended in time order, so today's are at the end
+        self.entries.borrow().iter().rev()
+            .take_while(|entry| entry.time >= midnight)
+            .map(|entry| entry.delta)
+            .sum()
+    }
+
+    /// Words added to a note (or removed) since it was loaded or last saved
+    pub fn unsaved_words(&self, title: &str, word_count: usize) -> i64 {
+        match self.baselines.borrow().get(title) {
+            Some(baseline) => word_count as i64 - *baseline as i64,
+            None => 0,
+        }
+    }
+
+    fn summary(&self) -> Summary {
+        summarize(&self.entries.borrow())
+    }
+}
+
+/// Totals derived from the log
+#[derive(Debug, Default)]
+struct Summary {
+    /// Net words per day
+    daily: BTreeMap<NaiveDate, i64>,
+    /// Words added per hour of the day
+    hourly: [i64; 24],
+    /// Net words per note, most first
+    notes: Vec<(String, i64)>,
+}
+
+fn summarize(entries: &[StatsEntry]) -> Summary {
+    let mut summary = Summary::default();
+    let mut notes: HashMap<&str, i64> = HashMap::new();
+    for entry in entries {
+        let time = match Local.timestamp_opt(entry.time, 0).single() {
+            Some(time) => time,
+            None => continue,
+        };
+        *summary.daily.entry(time.date_naive()).or_default() += entry.delta;
+        if entry.delta > 0 {
+            summary.hourly[time.hour() as usize] += entry.delta;
+        }
+        *notes.entry(entry.note.as_str()).or_default() += entry.delta;
+    }
+    summary.notes = notes.into_iter()
+        .map(|(note, words)| (note.to_string(), words))
+        .collect();
+    summary.notes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    summary
+}
+
+/// Monday of the week containing `date`
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn words_on(daily: &BTreeMap<NaiveDate, i64>, date: NaiveDate) -> i64 {
+    daily.get(&date).copied().unwrap_or(0)
+}
+
+/// Net words in the week starting on `monday`
+fn words_in_week(daily: &BTreeMap<NaiveDate, i64>, monday: NaiveDate) -> i64 {
+    daily.range(monday..monday + Duration::days(7)).map(|(_, words)| words).sum()
+}
+
+/// Current and longest runs of consecutive days with words written.
+/// The current streak is still alive if today has nothing yet but yesterday did.
+fn streaks(daily: &BTreeMap<NaiveDate, i64>, today: NaiveDate) -> (usize, usize) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for (&date, _) in daily.iter().filter(|(_, words)| **words > 0) {
+        run = match previous {
+            Some(previous) if date - previous == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(date);
+    }
+
+    let mut day = if words_on(daily, today) > 0 { today } else { today - Duration::days(1) };
+    let mut current = 0;
+    while words_on(daily, day) > 0 {
+        current += 1;
+        day -= Duration::days(1);
+    }
+    (current, longest)
+}
+
+/// Open the statistics view
+pub fn show_stats_view(parent: &ApplicationWindow, stats: &WritingStats) {
+    let dialog = ApplicationWindow::builder()
+        .transient_for(parent)
+        .title("Writing Statistics")
+        .default_width(780)
+        .default_height(680)
+        .css_classes(vec!["stats-view"])
+        .build();
+
+    let summary = Rc::new(stats.summary());
+    let today = Local::now().date_naive();
+    let dark = parent.has_css_class("dark-mode");
+
+    let content_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .margin_start(20)
+        .margin_end(20)
+        .margin_top(20)
+        .margin_bottom(20)
+        .spacing(10)
+        .build();
+
+    // Headline numbers
+    let (current_streak, longest_streak) = streaks(&summary.daily, today);
+    let cards = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(12)
+        .homogeneous(true)
+        .build();
+    cards.append(&stat_card(&words_on(&summary.daily, today).to_string(), "words today"));
+    cards.append(&stat_card(&words_in_week(&summary.daily, week_start(today)).to_string(), "words this week"));
+    cards.append(&stat_card(&format_days(current_streak), "current streak"));
+    cards.append(&stat_card(&format_days(longest_streak), "longest streak"));
+    content_box.append(&cards);
+
+    content_box.append(&section_title("Last year"));
+    content_box.append(&heatmap(&summary, today, dark));
+
+    content_box.append(&section_title(&format!("Words per day (last {} days)", DAYS_SHOWN)));
+    let days: Vec<(String, i64)> = (0..DAYS_SHOWN).rev()
+        .map(|ago| today - Duration::days(ago))
+        .map(|date| (date.format("%d").to_string(), words_on(&summary.daily, date)))
+        .collect();
+    content_box.append(&bar_chart(days, dark));
+
+    content_box.append(&section_title(&format!("Words per week (last {} weeks)", WEEKS_SHOWN)));
+    let weeks: Vec<(String, i64)> = (0..WEEKS_SHOWN).rev()
+        .map(|ago| week_start(today) - Duration::weeks(ago))
+        .map(|monday| (monday.format("%b %d").to_string(), words_in_week(&summary.daily, monday)))
+        .collect();
+    content_box.append(&bar_chart(weeks, dark));
+
+    content_box.append(&section_title("Most active hours"));
+    let hours: Vec<(String, i64)> = summary.hourly.iter()
+        .enumerate()
+        .map(|(hour, words)| (format!("{:02}", hour), *words))
+        .collect();
+    content_box.append(&bar_chart(hours, dark));
+
+    // Per-note totals; clicking a note opens it
+    content_box.append(&section_title("Words per note"));
+    let notes_list = ListBox::builder()
+        .selection_mode(gtk::SelectionMode::None)
+        .css_classes(vec!["stats-notes"])
+        .build();
+    let placeholder = Label::builder()
+        .label("Nothing written yet. Statistics are collected as notes are saved.")
+        .margin_top(8)
+        .margin_bottom(8)
+        .css_classes(vec!["dim-label"])
+        .build();
+    notes_list.set_placeholder(Some(&placeholder));
+    for (title, words) in summary.notes.iter().take(TOP_NOTES) {
+        let row = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .margin_start(8)
+            .margin_end(8)
+            .margin_top(4)
+            .margin_bottom(4)
+            .build();
+        row.append(&Label::builder()
+            .label(title)
+            .xalign(0.0)
+            .hexpand(true)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .build());
+        row.append(&Label::builder()
+            .label(format!("{} words", words))
+            .css_classes(vec!["dim-label"])
+            .build());
+        notes_list.append(&row);
+    }
+    let parent_for_notes = parent.clone();
+    let summary_for_notes = summary.clone();
+    notes_list.set_activate_on_single_click(true);
+    notes_list.connect_row_activated(move |_, row| {
+        if let Some((title, _)) = summary_for_notes.notes.get(row.index() as usize) {
+            if let Err(e) = WidgetExt::activate_action(&parent_for_notes, "win.open-note", Some(&title.to_variant())) {
+                eprintln!("Error opening note {}: {}", title, e);
+            }
+        }
+    });
+    content_box.append(&notes_list);
+
+    let scrolled_window = ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .build();
+    scrolled_window.set_child(Some(&content_box));
+    dialog.set_child(Some(&scrolled_window));
+    dialog.present();
+}
+
+fn format_days(days: usize) -> String {
+    if days == 1 { "1 day".to_string() } else { format!("{} days", days) }
+}
+
+fn stat_card(value: &str, caption: &str) -> Box {
+    let card = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(2)
+        .css_classes(vec!["stat-card"])
+        .build();
+    card.append(&Label::builder()
+        .label(value)
+        .css_classes(vec!["stat-value"])
+        .build());
+    card.append(&Label::builder()
+        .label(caption)
+        .css_classes(vec!["dim-label"])
+        .build());
+    card
+}
+
+fn section_title(title: &str) -> Label {
+    Label::builder()
+        .label(title)
+        .xalign(0.0)
+        .margin_top(10)
+        .css_classes(vec!["settings-section"])
+        .build()
+}
+
+/// Colours for the charts: (foreground grey level, accent)
+fn chart_colors(dark: bool) -> (f64, (f64, f64, f64)) {
+    (if dark { 0.85 } else { 0.2 }, (0.21, 0.52, 0.89))
+}
+
+/// Vertical bars with a label under each (thinned out when crowded)
+fn bar_chart(values: Vec<(String, i64)>, dark: bool) -> DrawingArea {
+    let area = DrawingArea::builder()
+        .height_request(140)
+        .hexpand(true)
+        .build();
+    area.set_draw_func(move |_, cr, width, height| {
+        draw_bars(cr, width as f64, height as f64, &values, dark);
+    });
+    area
+}
+
+fn draw_bars(cr: &cairo::Context, width: f64, height: f64, values: &[(String, i64)], dark: bool) {
+    let (foreground, (red, green, blue)) = chart_colors(dark);
+    let max = values.iter().map(|(_, words)| *words).max().unwrap_or(0).max(1) as f64;
+    let label_height = 16.0;
+    let top = 14.0;
+    let slot = width / values.len().max(1) as f64;
+    let bar_width = (slot * 0.7).max(1.0);
+    // Label every n-th bar so the labels do not overlap
+    let label_every = ((36.0 / slot).ceil() as usize).max(1);
+
+    cr.set_font_size(10.0);
+    cr.set_source_rgba(foreground, foreground, foreground, 0.6);
+    cr.move_to(0.0, 10.0);
+    cr.show_text(&format!("{} words", max as i64)).ok();
+
+    for (i, (label, words)) in values.iter().enumerate() {
+        let x = i as f64 * slot + (slot - bar_width) / 2.0;
+        let bar_height = (*words).max(0) as f64 / max * (height - label_height - top);
+        cr.set_source_rgb(red, green, blue);
+        cr.rectangle(x, height - label_height - bar_height, bar_width, bar_height);
+        cr.fill().ok();
+
+        if i % label_every == 0 {
+            cr.set_source_rgba(foreground, foreground, foreground, 0.6);
+            if let Ok(extents) = cr.text_extents(label) {
+                cr.move_to(x + bar_width / 2.0 - extents.width() / 2.0, height - 3.0);
+                cr.show_text(label).ok();
+            }
+        }
+    }
+}
+
+/// Calendar heatmap of the last year: one column per week, one row per weekday
+fn heatmap(summary: &Rc<Summary>, today: NaiveDate, dark: bool) -> DrawingArea {
+    let first_day = week_start(today) - Duration::weeks(HEATMAP_WEEKS - 1);
+    let area = DrawingArea::builder()
+        .height_request(7 * 14 + 4)
+        .hexpand(true)
+        .has_tooltip(true)
+        .build();
+
+    // Cell size and the date under a point
+    let cell_size = |width: f64| (width / HEATMAP_WEEKS as f64).min(14.0);
+    let date_at = move |width: f64, x: f64, y: f64| -> Option<NaiveDate> {
+        let cell = cell_size(width);
+        let (week, weekday) = ((x / cell).floor() as i64, (y / cell).floor() as i64);
+        if !(0..HEATMAP_WEEKS).contains(&week) || !(0..7).contains(&weekday) {
+            return None;
+        }
+        let date = first_day + Duration::days(week * 7 + weekday);
+        (date <= today).then_some(date)
+    };
+
+    let summary_for_draw = summary.clone();
+    area.set_draw_func(move |_, cr, width, _height| {
+        let (foreground, (red, green, blue)) = chart_colors(dark);
+        let cell = cell_size(width as f64);
+        let max = summary_for_draw.daily.values().copied().max().unwrap_or(0).max(1) as f64;
+        for week in 0..HEATMAP_WEEKS {
+            for weekday in 0..7 {
+                let date = first_day + Duration::days(week * 7 + weekday);
+                if date > today {
+                    continue;
+                }
+                let words = words_on(&summary_for_draw.daily, date);
+                if words > 0 {
+                    // Four shades, darkest for the busiest days
+                    let level = (words as f64 / max * 4.0).ceil().clamp(1.0, 4.0);
+                    cr.set_source_rgba(red, green, blue, 0.25 + 0.75 * level / 4.0);
+                } else {
+                    cr.set_source_rgba(foreground, foreground, foreground, 0.08);
+                }
+                cr.rectangle(week as f64 * cell + 1.0, weekday as f64 * cell + 1.0, cell - 2.0, cell - 2.0);
+                cr.fill().ok();
+            }
+        }
+    });
+
+    let summary_for_tooltip = summary.clone();
+    area.connect_query_tooltip(move |area, x, y, _keyboard, tooltip| {
+        match date_at(area.width() as f64, x as f64, y as f64) {
+            Some(date) => {
+                tooltip.set_text(Some(&format!("{}: {} words", date.format("%a %b %-d, %Y"), words_on(&summary_for_tooltip.daily, date))));
+                true
+            }
+            None => false,
+        }
+    });
+    area
+}
@@ -0,0 +1,301 @@
+use gtk::prelude::*;
+use gtk::{gdk, glib, Box, CheckButton, Grid, Label, Orientation, ScrolledWindow, TextTag, TextView};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::readability::{self, Check, Scores};
+use crate::settings::{Settings, StyleSettings};
+
+// Re-run the analysis this long after the last edit
+const REFRESH_DELAY_MS: u64 = 500;
+
+/// Underline colour of each check
+fn check_color(check: Check) -> &'static str {
+    match check {
+        Check::LongSentence => "#e5a50a",
+        Check::PassiveVoice => "#26a269",
+        Check::Adverb => "#9141ac",
+        Check::FillerWord => "#c64600",
+        Check::RepeatedWord => "#e01b24",
+    }
+}
+
+fn check_enabled(style: &StyleSettings, check: Check) -> bool {
+    match check {
+        Check::LongSentence => style.long_sentences,
+        Check::PassiveVoice => style.passive_voice,
+        Check::Adverb => style.adverbs,
+        Check::FillerWord => style.filler_words,
+        Check::RepeatedWord => style.repeated_words,
+    }
+}
+
+fn set_check_enabled(style: &mut StyleSettings, check: Check, enabled: bool) {
+    match check {
+        Check::LongSentence => style.long_sentences = enabled,
+        Check::PassiveVoice => style.passive_voice = enabled,
+        Check::Adverb => style.adverbs = enabled,
+        Check::FillerWord => style.filler_words = enabled,
+        Check::RepeatedWord => style.repeated_words = enabled,
+    }
+}
+
+/// Side panel with readability scores of the open note and toggles for the
+/// style checks, whose findings are underlined in the editor while it is open
+#[derive(Clone)]
+pub struct StylePanel {
+    container: Box,
+    text_view: TextView,
+    settings: Rc<RefCell<Settings>>,
+    scores_grid: Grid,
+    tags: Rc<HashMap<Check, TextTag>>,
+    count_labels: Rc<HashMap<Check, Label>>,
+    pending_refresh: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl StylePanel {
+    pub fn new(text_view: &TextView, settings: &Rc<RefCell<Settings>>) -> Self {
+        let tag_table = text_view.buffer().tag_table();
+        let mut tags = HashMap::new();
+        for check in Check::ALL {
+            let color = gdk::RGBA::parse(check_color(check)).unwrap_or(gdk::RGBA::RED);
+            let tag = TextTag::builder()
+                .name(format!("style-{:?}", check).to_lowercase())
+                .underline(gtk::pango::Underline::Single)
+                .underline_rgba(&color)
+                .build();
+            tag_table.add(&tag);
+            tags.insert(check, tag);
+        }
+
+        let title = Label::builder()
+            .label("Readability")
+            .xalign(0.0)
+            .margin_bottom(6)
+            .css_classes(vec!["outline-title"])
+            .build();
+
+        let scores_grid = Grid::builder()
+            .row_spacing(4)
+            .column_spacing(12)
+            .build();
+
+        let checks_title = Label::builder()
+            .label("Style checks")
+            .xalign(0.0)
+            .margin_top(16)
+            .margin_bottom(6)
+            .css_classes(vec!["outline-title"])
+            .build();
+
+        let checks_box = Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(4)
+            .build();
+
+        let content = Box::builder()
+            .orientation(Orientation::Vertical)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        content.append(&title);
+        content.append(&scores_grid);
+        content.append(&checks_title);
+        content.append(&checks_box);
+
+        let scrolled_window = ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vexpand(true)
+            .build();
+        scrolled_window.set_child(Some(&content));
+
+        let container = Box::builder()
+            .orientation(Orientation::Vertical)
+            .width_request(240)
+            .css_classes(vec!["style-panel"])
+            .visible(false)
+            .build();
+        container.append(&scrolled_window);
+
+        let mut count_labels = HashMap::new();
+        let panel_checks: Vec<(Check, CheckButton)> = Check::ALL.iter()
+            .map(|&check| {
+                let (row, button, count_label) = check_row(check, check_enabled(&settings.borrow().style, check));
+                checks_box.append(&row);
+                count_labels.insert(check, count_label);
+                (check, button)
+            })
+            .collect();
+
+        let panel = StylePanel {
+            container,
+            text_view: text_view.clone(),
+            settings: settings.clone(),
+            scores_grid,
+            tags: Rc::new(tags),
+            count_labels: Rc::new(count_labels),
+            pending_refresh: Rc::new(RefCell::new(None)),
+        };
+
+        // Toggling a check saves the choice and re-highlights
+        for (check, button) in panel_checks {
+            let panel_for_toggle = panel.clone();
+            button.connect_toggled(move |button| {
+                {
+                    let mut settings = panel_for_toggle.settings.borrow_mut();
+                    set_check_enabled(&mut settings.style, check, button.is_active());
+                    if let Err(e) = settings.save() {
+                        eprintln!("Error saving settings: {}", e);
+                    }
+                }
+                panel_for_toggle.refresh();
+            });
+        }
+
+        let panel_for_changes = panel.clone();
+        text_view.buffer().connect_changed(move |_| {
+            panel_for_changes.schedule_refresh();
+        });
+        panel
+    }
+
+    /// The widget to place next to the editor
+    pub fn widget(&self) -> &Box {
+        &self.container
+    }
+
+    /// Show or hide the panel (and the highlights with it)
+    pub fn toggle(&self) {
+        let visible = !self.container.is_visible();
+        self.container.set_visible(visible);
+        if visible {
+            self.refresh();
+        } else {
+            self.clear_highlights();
+        }
+    }
+
+    fn schedule_refresh(&self) {
+        if !self.container.is_visible() {
+            return;
+        }
+        if let Some(source_id) = self.pending_refresh.borrow_mut().take() {
+            source_id.remove();
+        }
+        let panel = self.clone();
+        let source_id = glib::timeout_add_local_once(std::time::Duration::from_millis(REFRESH_DELAY_MS), move || {
+            panel.pending_refresh.borrow_mut().take();
+            panel.refresh();
+        });
+        *self.pending_refresh.borrow_mut() = Some(source_id);
+    }
+
+    fn clear_highlights(&self) {
+        let buffer = self.text_view.buffer();
+        let (start, end) = buffer.bounds();
+        for tag in self.tags.values() {
+            buffer.remove_tag(tag, &start, &end);
+        }
+    }
+
+    /// Re-run the analysis on the whole note
+    fn refresh(&self) {
+        let buffer = self.text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true);
+        self.show_scores(&readability::scores(&text));
+
+        self.clear_highlights();
+        let style = self.settings.borrow().style.clone();
+        let mut counts: HashMap<Check, usize> = HashMap::new();
+        // Findings are sorted, so convert byte offsets to char offsets incrementally
+        let (mut byte_pos, mut char_pos) = (0, 0);
+        for finding in readability::findings(&text) {
+            *counts.entry(finding.check).or_default() += 1;
+            if !check_enabled(&style, finding.check) {
+                continue;
+            }
+            char_pos += text[byte_pos..finding.start].chars().count() as i32;
+            byte_pos = finding.start;
+            let length = text[finding.start..finding.end].chars().count() as i32;
+            buffer.apply_tag(
+                &self.tags[&finding.check],
+                &buffer.iter_at_offset(char_pos),
+                &buffer.iter_at_offset(char_pos + length),
+            );
+        }
+
+        for (check, label) in self.count_labels.iter() {
+            label.set_text(&counts.get(check).copied().unwrap_or(0).to_string());
+        }
+    }
+
+    fn show_scores(&self, scores: &Scores) {
+        while let Some(child) = self.scores_grid.first_child() {
+            self.scores_grid.remove(&child);
+        }
+
+        let format_score = |score: Option<f64>| score.map(|s| format!("{:.1}", s)).unwrap_or_else(|| "–".to_string());
+        let reading_ease = match scores.flesch_reading_ease {
+            Some(score) => format!("{:.0} ({})", score, readability::reading_ease_label(score)),
+            None => "–".to_string(),
+        };
+        let rows = [
+            ("Reading ease", reading_ease),
+            ("Flesch–Kincaid grade", format_score(scores.flesch_kincaid_grade)),
+            ("Gunning fog", format_score(scores.gunning_fog)),
+            ("Coleman–Liau", format_score(scores.coleman_liau)),
+            ("ARI", format_score(scores.automated_readability)),
+            ("Words", scores.words.to_string()),
+            ("Sentences", scores.sentences.to_string()),
+        ];
+        for (row, (name, value)) in rows.iter().enumerate() {
+            let name_label = Label::builder()
+                .label(*name)
+                .xalign(0.0)
+                .hexpand(true)
+                .css_classes(vec!["dim-label"])
+                .build();
+            let value_label = Label::builder()
+                .label(value)
+                .xalign(1.0)
+                .build();
+            self.scores_grid.attach(&name_label, 0, row as i32, 1, 1);
+            self.scores_grid.attach(&value_label, 1, row as i32, 1, 1);
+        }
+    }
+}
+
+/// A check's toggle, its name underlined in its colour, and its number of findings
+fn check_row(check: Check, active: bool) -> (Box, CheckButton, Label) {
+    let name = Label::builder()
+        .xalign(0.0)
+        .build();
+    let button = CheckButton::builder()
+        .active(active)
+        .child(&name)
+        .hexpand(true)
+        .build();
+    name.set_markup(&format!(
+        "<span underline=\"single\" underline_color=\"{}\">{}</span>",
+        check_color(check),
+        check.label(),
+    ));
+    let count = Label::builder()
+        .label("0")
+        .css_classes(vec!["dim-label"])
+        .build();
+    if check == Check::LongSentence {
+        name.set_tooltip_text(Some(&format!("More than {} words", readability::LONG_SENTENCE_WORDS)));
+    }
+
+    let row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    row.append(&button);
+    row.append(&count);
+    (row, button, count)
+}
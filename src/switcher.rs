@@ -0,0 +1,322 @@
+use gtk::prelude::*;
+use gtk::{glib, ApplicationWindow, Box, EventControllerKey, Label, ListBox, Orientation,
+          ScrolledWindow, SearchEntry, TextView};
+use gtk::gdk::Key;
+use glib::Propagation;
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crate::fuzzy::fuzzy_score;
+use crate::note::Note;
+
+// Maximum number of results shown at once
+const MAX_RESULTS: usize = 50;
+// Characters of the highlighted note shown in the preview
+const PREVIEW_CHARS: usize = 2000;
+
+/// What the switcher keeps of a note: enough to match and open it, but not its text
+struct SwitcherNote {
+    title: String,
+    path: PathBuf,
+    /// Path within the notes directory, e.g. "Projects/Plan.md"
+    relative_path: String,
+    aliases: Vec<String>,
+    modified_time: Option<SystemTime>,
+}
+
+impl SwitcherNote {
+    fn from_note(note: &Note) -> Self {
+        SwitcherNote {
+            title: note.title.clone(),
+            path: note.path.clone(),
+            relative_path: note.relative_path(),
+            aliases: note.aliases(),
+            modified_time: note.modified_time,
+        }
+    }
+}
+
+/// A note that matched the query, with what it matched on
+struct SwitcherMatch<'a> {
+    note: &'a SwitcherNote,
+    /// Alias or path that matched better than the title, shown next to it
+    matched_via: Option<String>,
+    score: i64,
+}
+
+/// A result row in the switcher
+#[derive(Clone)]
+enum SwitcherItem {
+    Open(String),
+    Create(String),
+}
+
+/// Rank notes by how well their title, aliases or path match, plus a recency bonus
+fn rank_notes<'a>(notes: &'a [SwitcherNote], query: &str) -> Vec<SwitcherMatch<'a>> {
+    let now = SystemTime::now();
+    let mut matches: Vec<SwitcherMatch> = notes.iter()
+        .filter_map(|note| {
+            // Titles are preferred over aliases, aliases over paths
+            let title_match = fuzzy_score(query, &note.title).map(|score| (score + 10, None));
+            let alias_matches = note.aliases.iter()
+                .filter_map(|alias| fuzzy_score(query, alias).map(|score| (score + 5, Some(alias.clone()))));
+            let path_match = fuzzy_score(query, &note.relative_path).map(|score| (score, Some(note.relative_path.clone())));
+
+            // On ties the earlier (title) match wins
+            let best = title_match.into_iter()
+                .chain(alias_matches)
+                .chain(path_match)
+                .fold(None, |best: Option<(i64, Option<String>)>, candidate| match best {
+                    Some(b) if b.0 >= candidate.0 => Some(b),
+                    _ => Some(candidate),
+                });
+
+            let (score, matched_via) = best?;
+            Some(SwitcherMatch { score: score + recency_bonus(note, now), note, matched_via })
+        })
+        .collect();
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches.truncate(MAX_RESULTS);
+    matches
+}
+
+/// Up to 30 extra points for notes edited recently, fading over about a month
+fn recency_bonus(note: &SwitcherNote, now: SystemTime) -> i64 {
+    match note.modified_time.and_then(|modified| now.duration_since(modified).ok()) {
+        Some(age) => 30 / (1 + (age.as_secs() / 86_400).min(30) as i64),
+        None => 0,
+    }
+}
+
+/// Show the quick switcher: a modal dialog that fuzzy-finds notes by title, alias or path.
+/// Enter opens the highlighted note, or creates one with the typed title if nothing matches.
+pub fn show_quick_switcher(parent: &ApplicationWindow) {
+    let dialog = ApplicationWindow::builder()
+        .transient_for(parent)
+        .modal(true)
+        .title("Open Note")
+        .default_width(760)
+        .default_height(440)
+        .css_classes(vec!["quick-switcher"])
+        .build();
+
+    let entry = SearchEntry::builder()
+        .placeholder_text("Open a note by title, alias or path\u{2026}")
+        .search_delay(0)
+        .margin_top(12)
+        .margin_bottom(8)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+
+    let results = ListBox::builder()
+        .selection_mode(gtk::SelectionMode::Single)
+        .css_classes(vec!["switcher-results"])
+        .build();
+
+    let results_scroll = ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .width_request(300)
+        .vexpand(true)
+        .build();
+    results_scroll.set_child(Some(&results));
+
+    // Read-only preview of the highlighted note
+    let preview = TextView::builder()
+        .editable(false)
+        .cursor_visible(false)
+        .wrap_mode(gtk::WrapMode::Word)
+        .monospace(true)
+        .left_margin(12)
+        .right_margin(12)
+        .top_margin(12)
+        .css_classes(vec!["switcher-preview"])
+        .build();
+
+    let preview_scroll = ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    preview_scroll.set_child(Some(&preview));
+
+    let body = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(8)
+        .margin_start(12)
+        .margin_end(12)
+        .margin_bottom(12)
+        .build();
+    body.append(&results_scroll);
+    body.append(&preview_scroll);
+
+    let content_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .build();
+    content_box.append(&entry);
+    content_box.append(&body);
+    dialog.set_child(Some(&content_box));
+
+    // Load notes once, keeping no note text; the dialog is short-lived
+    let notes: Vec<SwitcherNote> = Note::get_all()
+        .map(|notes| notes.iter().map(SwitcherNote::from_note).collect())
+        .unwrap_or_else(|e| {
+            eprintln!("Error listing notes for quick switcher: {}", e);
+            Vec::new()
+        });
+    let items: Rc<RefCell<Vec<SwitcherItem>>> = Rc::new(RefCell::new(Vec::new()));
+    // File of each result, for the preview (None for "Create note")
+    let previews: Rc<RefCell<Vec<Option<PathBuf>>>> = Rc::new(RefCell::new(Vec::new()));
+
+    // Rebuild results for the query
+    let populate = {
+        let results = results.clone();
+        let items = items.clone();
+        let previews = previews.clone();
+        move |query: &str| {
+            while let Some(row) = results.row_at_index(0) {
+                results.remove(&row);
+            }
+            let query = query.trim();
+            let ranked = rank_notes(&notes, query);
+            let exact_match = ranked.iter().any(|m| m.note.title.eq_ignore_ascii_case(query));
+
+            let mut new_items = Vec::new();
+            let mut new_previews = Vec::new();
+            for m in &ranked {
+                results.append(&switcher_row(&m.note.title, m.matched_via.as_deref()));
+                new_items.push(SwitcherItem::Open(m.note.title.clone()));
+                new_previews.push(Some(m.note.path.clone()));
+            }
+            // Offer to create the note when nothing matches it exactly
+            if !query.is_empty() && !exact_match {
+                results.append(&switcher_row(&format!("Create note \u{201C}{}\u{201D}", query), None));
+                new_items.push(SwitcherItem::Create(query.to_string()));
+                new_previews.push(None);
+            }
+
+            *items.borrow_mut() = new_items;
+            *previews.borrow_mut() = new_previews;
+            if let Some(row) = results.row_at_index(0) {
+                results.select_row(Some(&row));
+            }
+        }
+    };
+    let populate = Rc::new(populate);
+
+    // Show the preview of the highlighted note, read when it is highlighted
+    let previews_for_select = previews.clone();
+    let preview_for_select = preview.clone();
+    results.connect_row_selected(move |_, row| {
+        let text = row
+            .and_then(|row| previews_for_select.borrow().get(row.index() as usize).cloned().flatten())
+            .and_then(|path| fs::read_to_string(&path).ok())
+            .map(|content| content.chars().take(PREVIEW_CHARS).collect::<String>())
+            .unwrap_or_default();
+        preview_for_select.buffer().set_text(&text);
+    });
+
+    let populate_for_search = populate.clone();
+    entry.connect_search_changed(move |entry| {
+        populate_for_search(&entry.text());
+    });
+
+    // Open or create the highlighted result
+    let activate_selected = {
+        let results = results.clone();
+        let items = items.clone();
+        let dialog = dialog.clone();
+        let parent = parent.clone();
+        move || {
+            let item = results.selected_row()
+                .and_then(|row| items.borrow().get(row.index() as usize).cloned());
+            let (action, title) = match item {
+                Some(SwitcherItem::Open(title)) => ("win.open-note", title),
+                Some(SwitcherItem::Create(title)) => ("win.create-note", title),
+                None => return,
+            };
+            dialog.close();
+            if let Err(e) = WidgetExt::activate_action(&parent, action, Some(&title.to_variant())) {
+                eprintln!("Error opening note {}: {}", title, e);
+            }
+        }
+    };
+    let activate_selected = Rc::new(activate_selected);
+
+    let activate_for_entry = activate_selected.clone();
+    entry.connect_activate(move |_| {
+        activate_for_entry();
+    });
+
+    let activate_for_row = activate_selected.clone();
+    results.connect_row_activated(move |results, row| {
+        results.select_row(Some(row));
+        activate_for_row();
+    });
+
+    let dialog_for_stop = dialog.clone();
+    entry.connect_stop_search(move |_| {
+        dialog_for_stop.close();
+    });
+
+    // Up/Down move through results while typing
+    let key_controller = EventControllerKey::new();
+    key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let results_for_key = results.clone();
+    key_controller.connect_key_pressed(move |_, key, _keycode, _state| {
+        let delta = match key {
+            Key::Down | Key::KP_Down => 1,
+            Key::Up | Key::KP_Up => -1,
+            _ => return Propagation::Proceed,
+        };
+        let current = results_for_key.selected_row().map(|row| row.index()).unwrap_or(0);
+        if let Some(row) = results_for_key.row_at_index((current + delta).max(0)) {
+            results_for_key.select_row(Some(&row));
+        }
+        Propagation::Stop
+    });
+    entry.add_controller(key_controller);
+
+    populate("");
+    dialog.present();
+    entry.grab_focus();
+}
+
+/// A result row: note title, with the alias or path it matched through if any
+fn switcher_row(title: &str, matched_via: Option<&str>) -> gtk::ListBoxRow {
+    let row_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(2)
+        .margin_start(10)
+        .margin_end(10)
+        .margin_top(6)
+        .margin_bottom(6)
+        .build();
+
+    let title_label = Label::builder()
+        .label(title)
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .css_classes(vec!["note-title"])
+        .build();
+    row_box.append(&title_label);
+
+    if let Some(via) = matched_via {
+        let via_label = Label::builder()
+            .label(via)
+            .xalign(0.0)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .css_classes(vec!["dim-label", "note-preview"])
+            .build();
+        row_box.append(&via_label);
+    }
+
+    let row = gtk::ListBoxRow::builder()
+        .css_classes(vec!["note-row"])
+        .build();
+    row.set_child(Some(&row_box));
+    row
+}
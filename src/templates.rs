@@ -0,0 +1,323 @@
+use gtk::prelude::*;
+use gtk::{gio, glib, ApplicationWindow, Box, Button, DropDown, Entry, Label, Orientation, TextView};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::note::Note;
+use crate::utils::labelled_row;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+// Written to the templates folder when it is first created, as an example
+const EXAMPLE_TEMPLATE: (&str, &str) = (
+    "Meeting",
+    "# {{title}}\n\nDate: {{date}} {{time}}\nAttendees: {{prompt:Attendees}}\n\n## Agenda\n\n- {{cursor}}\n\n## Action items\n\n- [ ] \n",
+);
+
+/// Get the path to the templates folder (kept outside the notes, so templates are not listed as notes)
+pub fn get_templates_dir() -> PathBuf {
+    glib::user_data_dir().join("penscript/templates")
+}
+
+/// Create the templates folder with an example template the first time it is needed
+fn ensure_templates_dir() -> Result<PathBuf, String> {
+    let dir = get_templates_dir();
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create templates folder {:?}: {}", dir, e))?;
+        let (name, content) = EXAMPLE_TEMPLATE;
+        fs::write(dir.join(format!("{}.md", name)), content)
+            .map_err(|e| format!("Failed to write example template: {}", e))?;
+    }
+    Ok(dir)
+}
+
+/// Names of the templates (file names without `.md`), sorted
+pub fn list() -> Vec<String> {
+    let dir = match ensure_templates_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("Error preparing templates: {}", e);
+            return Vec::new();
+        }
+    };
+    let mut names: Vec<String> = match fs::read_dir(&dir) {
+        Ok(entries) => entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+            .filter_map(|path| path.file_stem()?.to_str().map(String::from))
+            .collect(),
+        Err(e) => {
+            eprintln!("Error reading templates folder: {}", e);
+            Vec::new()
+        }
+    };
+    names.sort_by_key(|name| name.to_lowercase());
+    names
+}
+
+/// Content of a template
+pub fn load(name: &str) -> Result<String, String> {
+    let path = get_templates_dir().join(format!("{}.md", name));
+    fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read template {:?}: {}", path, e))
+}
+
+/// Placeholders of a template: `{{name}}` or `{{name:argument}}`, with byte ranges
+fn placeholders(template: &str) -> Vec<(usize, usize, &str, Option<&str>)> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(open) = template[pos..].find("{{").map(|i| pos + i) {
+        let close = match template[open + 2..].find("}}") {
+            Some(i) => open + 2 + i,
+            None => break,
+        };
+        let inner = template[open + 2..close].trim();
+        let (name, argument) = match inner.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument.trim())),
+            None => (inner, None),
+        };
+        found.push((open, close + 2, name, argument));
+        pos = close + 2;
+    }
+    found
+}
+
+/// Labels of the `{{prompt:Label}}` placeholders, in order and without repeats
+pub fn prompts(template: &str) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for (_, _, name, argument) in placeholders(template) {
+        if let ("prompt", Some(label)) = (name, argument) {
+            if !labels.iter().any(|known| known == label) {
+                labels.push(label.to_string());
+            }
+        }
+    }
+    labels
+}
+
+/// Format a date with a user-written strftime pattern, or None if the pattern is invalid
+fn format_date(now: &DateTime<Local>, pattern: &str) -> Option<String> {
+    let items: Vec<Item> = StrftimeItems::new(pattern).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return None;
+    }
+    Some(now.format_with_items(items.into_iter()).to_string())
+}
+
+/// Fill in a template:
+/// - `{{date}}` and `{{time}}` (or `{{date:%A, %B %-d}}` with a custom format)
+/// - `{{title}}`, the new note's title
+/// - `{{prompt:Label}}`, the answer typed for that label
+/// - `{{cursor}}`, removed; returns its char offset so the caret can be placed there
+///
+/// Unknown placeholders are kept as they are.
+pub fn render(template: &str, title: &str, answers: &HashMap<String, String>, now: &DateTime<Local>) -> (String, Option<usize>) {
+    let mut rendered = String::with_capacity(template.len());
+    let mut cursor = None;
+    let mut last = 0;
+    for (start, end, name, argument) in placeholders(template) {
+        let value = match (name, argument) {
+            ("date", None) => Some(now.format(DATE_FORMAT).to_string()),
+            ("time", None) => Some(now.format(TIME_FORMAT).to_string()),
+            ("date" | "time", Some(pattern)) => format_date(now, pattern),
+            ("title", None) => Some(title.to_string()),
+            ("prompt", Some(label)) => Some(answers.get(label).cloned().unwrap_or_default()),
+            ("cursor", None) => Some(String::new()),
+            _ => None,
+        };
+        let value = match value {
+            Some(value) => value,
+            None => continue, // Left in place
+        };
+        rendered.push_str(&template[last..start]);
+        if name == "cursor" && cursor.is_none() {
+            cursor = Some(rendered.chars().count());
+        }
+        rendered.push_str(&value);
+        last = end;
+    }
+    rendered.push_str(&template[last..]);
+    (rendered, cursor)
+}
+
+/// Ask for a template, a title and the template's prompts, then create the note,
+/// open it and put the caret at `{{cursor}}`
+pub fn show_new_from_template_dialog(parent: &ApplicationWindow, text_view: &TextView) {
+    let dialog = ApplicationWindow::builder()
+        .transient_for(parent)
+        .modal(true)
+        .title("New from Template")
+        .default_width(400)
+        .resizable(false)
+        .css_classes(vec!["template-dialog"])
+        .build();
+
+    let content_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .margin_start(20)
+        .margin_end(20)
+        .margin_top(20)
+        .margin_bottom(20)
+        .spacing(12)
+        .build();
+
+    let names = list();
+    let button_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(gtk::Align::End)
+        .spacing(8)
+        .margin_top(8)
+        .build();
+    let cancel_button = Button::builder()
+        .label("Cancel")
+        .build();
+    button_box.append(&cancel_button);
+    let dialog_for_cancel = dialog.clone();
+    cancel_button.connect_clicked(move |_| {
+        dialog_for_cancel.close();
+    });
+
+    if names.is_empty() {
+        let empty_label = Label::builder()
+            .label(format!("No templates yet. Add Markdown files to {} and try again.", get_templates_dir().display()))
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        content_box.append(&empty_label);
+        content_box.append(&button_box);
+        dialog.set_child(Some(&content_box));
+        dialog.present();
+        return;
+    }
+
+    let name_refs: Vec<&str> = names.iter().map(String::as_str).collect();
+    let template_choice = DropDown::from_strings(&name_refs);
+    template_choice.set_hexpand(true);
+    content_box.append(&labelled_row("Template", &template_choice));
+
+    let title_entry = Entry::builder()
+        .text(Note::generate_unique_title())
+        .activates_default(true)
+        .hexpand(true)
+        .build();
+    content_box.append(&labelled_row("Title", &title_entry));
+
+    // One entry per {{prompt:…}} of the chosen template
+    let prompts_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(12)
+        .build();
+    content_box.append(&prompts_box);
+    let prompt_entries: Rc<RefCell<Vec<(String, Entry)>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let open_folder_button = Button::builder()
+        .label("Open Templates Folder")
+        .css_classes(vec!["flat"])
+        .hexpand(true)
+        .halign(gtk::Align::Start)
+        .build();
+    open_folder_button.connect_clicked(|_| {
+        let uri = gio::File::for_path(get_templates_dir()).uri();
+        if let Err(e) = gio::AppInfo::launch_default_for_uri(&uri, gio::AppLaunchContext::NONE) {
+            eprintln!("Error opening templates folder: {}", e);
+        }
+    });
+    button_box.prepend(&open_folder_button);
+    button_box.set_halign(gtk::Align::Fill);
+
+    let create_button = Button::builder()
+        .label("Create")
+        .css_classes(vec!["suggested-action"])
+        .build();
+    button_box.append(&create_button);
+    content_box.append(&button_box);
+
+    let show_prompts = {
+        let names = names.clone();
+        let prompts_box = prompts_box.clone();
+        let prompt_entries = prompt_entries.clone();
+        move |index: u32| {
+            while let Some(child) = prompts_box.first_child() {
+                prompts_box.remove(&child);
+            }
+            prompt_entries.borrow_mut().clear();
+            let template = names.get(index as usize).map(|name| load(name)).unwrap_or_else(|| Ok(String::new()));
+            for label in prompts(&template.unwrap_or_default()) {
+                let entry = Entry::builder()
+                    .activates_default(true)
+                    .hexpand(true)
+                    .build();
+                prompts_box.append(&labelled_row(&label, &entry));
+                prompt_entries.borrow_mut().push((label, entry));
+            }
+        }
+    };
+    show_prompts(0);
+    template_choice.connect_selected_notify(move |choice| show_prompts(choice.selected()));
+
+    let dialog_for_create = dialog.clone();
+    let title_entry_for_create = title_entry.clone();
+    let parent_for_create = parent.clone();
+    let text_view_for_create = text_view.clone();
+    create_button.connect_clicked(move |_| {
+        let name = match names.get(template_choice.selected() as usize) {
+            Some(name) => name,
+            None => return,
+        };
+        let title = title_entry_for_create.text().trim().to_string();
+        if let Err(e) = crate::utils::check_note_title(&title) {
+            crate::utils::show_error_dialog(&parent_for_create, "Create Error", &e);
+            return;
+        }
+        if Note::path_for_title(&title).exists() {
+            crate::utils::show_error_dialog(&parent_for_create, "Create Error", &format!("A note named \"{}\" already exists.", title));
+            return;
+        }
+        let template = match load(name) {
+            Ok(template) => template,
+            Err(e) => {
+                crate::utils::show_error_dialog(&parent_for_create, "Template Error", &e);
+                return;
+            }
+        };
+        let answers: HashMap<String, String> = prompt_entries.borrow().iter()
+            .map(|(label, entry)| (label.clone(), entry.text().to_string()))
+            .collect();
+        let (content, cursor) = render(&template, &title, &answers, &Local::now());
+
+        let created = Note::new(&title).and_then(|mut note| {
+            note.content = content;
+            note.save()
+        });
+        if let Err(e) = created {
+            crate::utils::show_error_dialog(&parent_for_create, "Create Error", &format!("Failed to create new note: {}", e));
+            return;
+        }
+        dialog_for_create.close();
+
+        // Opening the note loads it into the editor right away
+        if let Err(e) = WidgetExt::activate_action(&parent_for_create, "win.open-note", Some(&title.to_variant())) {
+            eprintln!("Error opening note {}: {}", title, e);
+            return;
+        }
+        let buffer = text_view_for_create.buffer();
+        let iter = match cursor {
+            Some(offset) => buffer.iter_at_offset(offset as i32),
+            None => buffer.end_iter(),
+        };
+        buffer.place_cursor(&iter);
+        text_view_for_create.scroll_to_iter(&mut iter.clone(), 0.1, false, 0.0, 0.0);
+        text_view_for_create.grab_focus();
+    });
+
+    dialog.set_child(Some(&content_box));
+    dialog.set_default_widget(Some(&create_button));
+    dialog.present();
+    title_entry.grab_focus();
+}
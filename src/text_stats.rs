@@ -0,0 +1,289 @@
+// Document statistics on the prose of a note: Markdown syntax, code blocks and
+// front matter are stripped first, and CJK text is counted one word per character.
+// Everything here works on `&str` so it stays independent of GTK.
+
+use crate::{frontmatter, markdown};
+
+// Average silent reading and speaking speeds, in words per minute
+const READING_WPM: usize = 238;
+const SPEAKING_WPM: usize = 150;
+
+/// Counts for a document or a selection
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TextStats {
+    pub words: usize,
+    /// Characters excluding line breaks
+    pub characters: usize,
+    pub characters_no_spaces: usize,
+    pub sentences: usize,
+    pub paragraphs: usize,
+}
+
+impl TextStats {
+    /// Estimated reading time in minutes (rounded up)
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(READING_WPM)
+    }
+
+    /// Estimated time to read the text aloud in minutes (rounded up)
+    pub fn speaking_minutes(&self) -> usize {
+        self.words.div_ceil(SPEAKING_WPM)
+    }
+}
+
+/// Statistics of Markdown text
+pub fn analyze(text: &str) -> TextStats {
+    let prose = strip_markdown(text);
+    let mut stats = TextStats {
+        words: count_plain_words(&prose),
+        ..TextStats::default()
+    };
+    for c in prose.chars().filter(|c| *c != '\n') {
+        stats.characters += 1;
+        if !c.is_whitespace() {
+            stats.characters_no_spaces += 1;
+        }
+    }
+
+    for paragraph in prose.split("\n\n") {
+        if count_plain_words(paragraph) == 0 {
+            continue;
+        }
+        stats.paragraphs += 1;
+        stats.sentences += count_sentences(paragraph);
+    }
+    stats
+}
+
+/// Number of words in Markdown text
+pub fn count_words(text: &str) -> usize {
+    count_plain_words(&strip_markdown(text))
+}
+
+/// Whether a character is written without spaces between words (Chinese and Japanese)
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana, Katakana
+        | 0x31F0..=0x31FF   // Katakana extensions
+        | 0x3400..=0x4DBF   // CJK extension A
+        | 0x4E00..=0x9FFF   // CJK unified ideographs
+        | 0xF900..=0xFAFF   // CJK compatibility ideographs
+        | 0xFF66..=0xFF9D   // Half-width Katakana
+        | 0x20000..=0x2FA1F // CJK extensions B-F and supplements
+    )
+}
+
+/// Count words in plain text: runs of letters and digits (joined by apostrophes,
+/// hyphens and the like, as in "don't", "e-mail" or "3.14"), each CJK character on its own
+fn count_plain_words(text: &str) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = 0;
+    let mut in_word = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if is_cjk(c) {
+            words += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+                in_word = true;
+            }
+        } else if in_word && matches!(c, '\'' | '’' | '-' | '.' | ',' | '_') {
+            // Only joins if the word continues right after it
+            in_word = chars.get(i + 1).is_some_and(|next| next.is_alphanumeric() && !is_cjk(*next));
+        } else {
+            in_word = false;
+        }
+    }
+    words
+}
+
+/// Count sentences in a paragraph: text up to `.`, `!`, `?` (or their CJK forms)
+/// followed by a space or the end, plus any trailing text without a final stop
+fn count_sentences(paragraph: &str) -> usize {
+    let chars: Vec<char> = paragraph.chars().collect();
+    let mut sentences = 0;
+    let mut has_words = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_alphanumeric() {
+            has_words = true;
+        }
+        let ends_sentence = match c {
+            '。' | '！' | '？' => true,
+            '.' | '!' | '?' | '…' => chars[i + 1..].iter()
+                .find(|next| !matches!(next, '.' | '!' | '?' | '"' | '\'' | '’' | '”' | ')'))
+                .is_none_or(|next| next.is_whitespace()),
+            _ => false,
+        };
+        if ends_sentence && has_words {
+            sentences += 1;
+            has_words = false;
+        }
+    }
+    if has_words {
+        sentences += 1;
+    }
+    sentences
+}
+
+/// The prose of a note: no front matter, code blocks, block markers, link targets or emphasis markers.
+/// Paragraphs stay separated by blank lines.
+pub fn strip_markdown(text: &str) -> String {
+    let (_, body) = frontmatter::split(text);
+    let mut prose = String::with_capacity(body.len());
+    let mut in_code_block = false;
+
+    for line in body.lines() {
+        if markdown::is_code_fence(line) {
+            in_code_block = !in_code_block;
+            prose.push('\n');
+            continue;
+        }
+        if in_code_block || is_rule_or_table_separator(line) {
+            prose.push('\n');
+            continue;
+        }
+
+        // Quote markers, then heading or list markers
+        let mut content = line.trim_start();
+        while let Some(rest) = content.strip_prefix('>') {
+            content = rest.trim_start();
+        }
+        let (level, prefix_len) = markdown::heading_level(content);
+        let content = if level > 0 {
+            content[prefix_len..].trim_end_matches('#').to_string()
+        } else {
+            match markdown::parse_list_item(content) {
+                Some(item) => item.content,
+                None => content.to_string(),
+            }
+        };
+        prose.push_str(&strip_inline(&content));
+        prose.push('\n');
+    }
+    prose
+}
+
+/// `---`, `***`, `___` rules and `|---|:--:|` table separators
+fn is_rule_or_table_separator(line: &str) -> bool {
+    let trimmed = line.trim();
+    let rule = trimmed.len() >= 3
+        && ['-', '*', '_'].iter().any(|marker| trimmed.chars().all(|c| c == *marker || c == ' '));
+    let table = trimmed.contains('-') && trimmed.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '));
+    rule || table
+}
+
+/// Remove inline syntax from a line, keeping the text readers see
+fn strip_inline(line: &str) -> String {
+    // Wiki links read as their label
+    let mut text = String::with_capacity(line.len());
+    let mut last = 0;
+    for link in markdown::wiki_links(line) {
+        text.push_str(&line[last..link.start]);
+        text.push_str(&link.label);
+        last = link.end;
+    }
+    text.push_str(&line[last..]);
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut plain = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            // [text](url) and ![alt](url) keep only the text
+            '!' if chars.get(i + 1) == Some(&'[') => {}
+            '[' => {
+                if let Some(close) = find_char(&chars, i + 1, ']') {
+                    if chars.get(close + 1) == Some(&'(') {
+                        if let Some(end) = find_char(&chars, close + 2, ')') {
+                            plain.extend(&chars[i + 1..close]);
+                            i = end + 1;
+                            continue;
+                        }
+                    }
+                }
+                plain.push(c);
+            }
+            // HTML tags and <autolinks>
+            '<' if chars.get(i + 1).is_some_and(|next| next.is_ascii_alphabetic() || *next == '/') => {
+                if let Some(end) = find_char(&chars, i + 1, '>') {
+                    i = end + 1;
+                    continue;
+                }
+                plain.push(c);
+            }
+            '*' | '~' | '`' => {}
+            // `_` marks emphasis at word edges but belongs to words like snake_case
+            '_' => {
+                let inside_word = i > 0
+                    && chars[i - 1].is_alphanumeric()
+                    && chars.get(i + 1).is_some_and(|next| next.is_alphanumeric());
+                if inside_word {
+                    plain.push(c);
+                }
+            }
+            '|' => plain.push(' '),
+            _ => plain.push(c),
+        }
+        i += 1;
+    }
+    plain
+}
+
+fn find_char(chars: &[char], from: usize, wanted: char) -> Option<usize> {
+    chars.get(from..)?.iter().position(|c| *c == wanted).map(|i| from + i)
+}
+
+/// "N min", or hours and minutes for long texts
+pub fn format_minutes(minutes: usize) -> String {
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h {} min", minutes / 60, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_each_cjk_character_as_a_word() {
+        assert_eq!(count_words("我爱你"), 3);
+        assert_eq!(count_words("これはペンです"), 7);
+        assert_eq!(count_words("東京 is big"), 4);
+        assert_eq!(count_words("Rust语言"), 3);
+    }
+
+    #[test]
+    fn leaves_hangul_words_whole() {
+        // Korean is written with spaces between words
+        assert_eq!(count_words("안녕하세요 세계"), 2);
+    }
+
+    #[test]
+    fn joins_words_across_apostrophes_and_hyphens() {
+        assert_eq!(count_words("Don't e-mail 3.14 people."), 4);
+        assert_eq!(count_words("end - start"), 2);
+    }
+
+    #[test]
+    fn ignores_markdown_syntax_and_front_matter() {
+        let text = "---\ntitle: Words here\n---\n# Heading\n\n- **bold** [link](https://example.com/a/b)\n\n```\nlet code = 1;\n```\n";
+        assert_eq!(count_words(text), 3);
+    }
+
+    #[test]
+    fn counts_cjk_sentences_and_paragraphs() {
+        let stats = analyze("今日は晴れ。明日は雨！\n\nOne. Two?");
+        assert_eq!(stats.sentences, 4);
+        assert_eq!(stats.paragraphs, 2);
+    }
+
+    #[test]
+    fn ends_sentences_at_stops_but_not_inside_numbers() {
+        let stats = analyze("It costs 3.50 dollars... or more. Really");
+        assert_eq!(stats.sentences, 3);
+    }
+}
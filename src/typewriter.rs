@@ -0,0 +1,136 @@
+use gtk::prelude::*;
+use gtk::{glib, Adjustment, ScrolledWindow, TextView};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::settings::Settings;
+
+// Bottom margin of the editor when typewriter scrolling is off
+const DEFAULT_BOTTOM_MARGIN: i32 = 100;
+// Fraction of the remaining distance scrolled per frame
+const SCROLL_EASING: f64 = 0.25;
+
+/// Typewriter scrolling: keeps the caret line at a fixed height of the editor
+/// (centred by default) by scrolling smoothly as the caret moves
+#[derive(Clone)]
+pub struct Typewriter {
+    text_view: TextView,
+    adjustment: Adjustment,
+    settings: Rc<RefCell<Settings>>,
+    /// Whether a scroll animation is running
+    animating: Rc<Cell<bool>>,
+}
+
+impl Typewriter {
+    /// Must be installed after the text view has been put in its scrolled window
+    pub fn install(text_view: &TextView, scrolled_window: &ScrolledWindow, settings: &Rc<RefCell<Settings>>) -> Self {
+        let typewriter = Typewriter {
+            text_view: text_view.clone(),
+            adjustment: scrolled_window.vadjustment(),
+            settings: settings.clone(),
+            animating: Rc::new(Cell::new(false)),
+        };
+        typewriter.connect_signals();
+        typewriter
+    }
+
+    fn connect_signals(&self) {
+        // The bottom margin depends on the editor's height
+        let typewriter = self.clone();
+        self.adjustment.connect_page_size_notify(move |_| {
+            typewriter.update_margin();
+        });
+
+        let buffer = self.text_view.buffer();
+        let typewriter = self.clone();
+        buffer.connect_changed(move |_| {
+            typewriter.recentre();
+        });
+
+        let typewriter = self.clone();
+        buffer.connect_mark_set(move |buffer, _, mark| {
+            if *mark == buffer.get_insert() {
+                typewriter.recentre();
+            }
+        });
+    }
+
+    /// Turn typewriter scrolling on or off (and remember the choice)
+    pub fn toggle(&self) {
+        {
+            let mut settings = self.settings.borrow_mut();
+            settings.writing.typewriter_scrolling = !settings.writing.typewriter_scrolling;
+            if let Err(e) = settings.save() {
+                eprintln!("Error saving settings: {}", e);
+            }
+        }
+        self.update_margin();
+        self.recentre();
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.settings.borrow().writing.typewriter_scrolling
+    }
+
+    /// Caret height as a fraction of the editor, kept away from the very edges
+    fn position(&self) -> f64 {
+        self.settings.borrow().writing.typewriter_position.clamp(0.1, 0.9)
+    }
+
+    /// Grow the bottom margin so the last line can still reach the caret height
+    fn update_margin(&self) {
+        let margin = if self.is_enabled() {
+            let below_caret = self.adjustment.page_size() * (1.0 - self.position());
+            DEFAULT_BOTTOM_MARGIN.max(below_caret as i32)
+        } else {
+            DEFAULT_BOTTOM_MARGIN
+        };
+        if self.text_view.bottom_margin() != margin {
+            self.text_view.set_bottom_margin(margin);
+        }
+    }
+
+    /// Scroll value that puts the caret line at the typewriter height
+    fn target_value(&self) -> f64 {
+        let buffer = self.text_view.buffer();
+        let location = self.text_view.iter_location(&buffer.iter_at_mark(&buffer.get_insert()));
+        let (_, caret_y) = self.text_view.buffer_to_window_coords(
+            gtk::TextWindowType::Widget,
+            location.x(),
+            location.y() + location.height() / 2,
+        );
+
+        let page_size = self.adjustment.page_size();
+        let target = self.adjustment.value() + caret_y as f64 - page_size * self.position();
+        let max_value = (self.adjustment.upper() - page_size).max(self.adjustment.lower());
+        target.clamp(self.adjustment.lower(), max_value)
+    }
+
+    /// Start easing the caret line towards the typewriter height
+    fn recentre(&self) {
+        // Also drops the extra margin once the mode is switched off in the settings
+        self.update_margin();
+        if !self.is_enabled() || self.animating.get() {
+            return;
+        }
+        self.animating.set(true);
+
+        // The target is recomputed every frame since the layout may still be catching up with edits
+        let typewriter = self.clone();
+        self.text_view.add_tick_callback(move |_, _| {
+            if !typewriter.is_enabled() {
+                typewriter.animating.set(false);
+                return glib::ControlFlow::Break;
+            }
+            let value = typewriter.adjustment.value();
+            let distance = typewriter.target_value() - value;
+            if distance.abs() < 1.0 {
+                typewriter.adjustment.set_value(value + distance);
+                typewriter.animating.set(false);
+                return glib::ControlFlow::Break;
+            }
+            typewriter.adjustment.set_value(value + distance * SCROLL_EASING);
+            glib::ControlFlow::Continue
+        });
+    }
+}
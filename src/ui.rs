@@ -1,18 +1,42 @@
 use gtk::prelude::*;
-use gtk::{glib, Application, ApplicationWindow, Paned, Orientation, Label,
+use gtk::{gio, glib, Application, ApplicationWindow, Paned, Orientation, Label,
           ListBox, ScrolledWindow, Box, TextView, Button,
           EventControllerKey, CssProvider, Overlay, WindowHandle, WindowControls,
-          SearchEntry};
+          Entry, MenuButton, SearchEntry};
 use glib::{clone, Propagation};
-use gtk::gdk::{Key, ModifierType};
+use gtk::gdk::Key;
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::path::PathBuf;
 use chrono::{DateTime, Local};
-use std::sync::Once;
 
+use crate::backlinks::BacklinksPanel;
+use crate::editor::{apply_format, install_formatting_toolbar, install_markdown_assists, FormatCommand};
+use crate::export_dialog::show_export_dialog;
+use crate::focus::{Edge, FocusMode};
+use crate::freewrite::Freewrite;
+use crate::goals::WordGoals;
+use crate::folding::Folding;
+use crate::graph::show_graph_view;
+use crate::import_dialog::show_import_dialog;
+use crate::journal::Journal;
+use crate::link_index::LinkIndex;
+use crate::manuscript_dialog::show_manuscript_dialog;
 use crate::note::Note;
-use crate::utils::{show_error_dialog, show_confirmation_dialog, schedule_auto_save};
+use crate::outline::OutlinePanel;
+use crate::palette::CommandPalette;
+use crate::print::{print_note, PAPER_SIZES};
+use crate::settings::{FocusUnit, Settings};
+use crate::shortcuts::{self, Command, COMMANDS, SECTIONS};
+use crate::stats::{show_stats_view, WritingStats};
+use crate::spellcheck::{self, SpellCheck};
+use crate::style_panel::StylePanel;
+use crate::switcher::show_quick_switcher;
+use crate::templates::show_new_from_template_dialog;
+use crate::text_stats::{self, count_words};
+use crate::typewriter::Typewriter;
+use crate::utils::{check_note_title, show_error_dialog, show_confirmation_dialog, schedule_auto_save};
+use crate::wikilinks::install_wiki_links;
 
 // Struct to handle active note state
 // #[derive(Clone)] // Remove derive Clone
@@ -42,7 +66,7 @@ const AUTO_SAVE_DELAY_MS: u32 = 2000; // 2 seconds
 
 // Flag to indicate programmatic text changes
 thread_local! {
-    static PROGRAMMATIC_TEXT_CHANGE: RefCell<bool> = RefCell::new(false);
+    static PROGRAMMATIC_TEXT_CHANGE: RefCell<bool> = const { RefCell::new(false) };
 }
 
 // Add app name constant
@@ -55,6 +79,12 @@ const INITIAL_SIDEBAR_WIDTH: i32 = 250; // Fixed width for a clean look
 pub fn build_ui(app: &Application) {
     // Load CSS for styling
     load_css();
+
+    // Load user preferences shared by the editor and the settings dialog
+    let settings = Rc::new(RefCell::new(Settings::load()));
+
+    // Register keyboard shortcuts for the window actions (see shortcuts::COMMANDS)
+    shortcuts::apply_accels(app, &settings.borrow());
     
     // Create the main application window - undecorated
     let window = ApplicationWindow::builder()
@@ -118,16 +148,31 @@ pub fn build_ui(app: &Application) {
         .css_classes(vec!["sidebar-header"])
         .build();
     
-    // Add "New Note" button with minimalist styling
+    // Add "New Note" split button with minimalist styling: the arrow offers templates
     let new_note_button = Button::builder()
         .icon_name("list-add-symbolic")
-        .tooltip_text("New Note")
+        .action_name("win.new-note")
         .css_classes(vec!["icon-only-button"])
+        .build();
+    tooltip_with_accel(&new_note_button, "New Note", "new-note");
+    let new_note_menu = gio::Menu::new();
+    new_note_menu.append(Some("New from Template…"), Some("win.new-from-template"));
+    let new_note_menu_button = MenuButton::builder()
+        .icon_name("pan-down-symbolic")
+        .menu_model(&new_note_menu)
+        .css_classes(vec!["icon-only-button", "split-button-arrow"])
+        .build();
+    tooltip_with_accel(&new_note_menu_button, "New from Template", "new-from-template");
+    let new_note_split_button = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .css_classes(vec!["linked"])
         .margin_end(8)
         .build();
-        
+    new_note_split_button.append(&new_note_button);
+    new_note_split_button.append(&new_note_menu_button);
+
     sidebar_header_box.append(&notes_label);
-    sidebar_header_box.append(&new_note_button);
+    sidebar_header_box.append(&new_note_split_button);
 
     // ListBox and rest of sidebar setup
     let list_box = ListBox::builder()
@@ -230,6 +275,18 @@ pub fn build_ui(app: &Application) {
     
     // Ensure text_view is editable
     text_view.set_editable(true);
+
+    // [[Wiki links]]: highlighting, Ctrl+click and title completion (before the assists, see docs)
+    install_wiki_links(&text_view, &window);
+
+    // List continuation, smart indent and auto-pairing
+    install_markdown_assists(&text_view, &settings);
+
+    // Formatting popover shown above text selections
+    install_formatting_toolbar(&text_view, &settings);
+
+    // Fold toggles for headings, code blocks and front matter
+    let folding = Folding::install(&text_view);
     
     // Create a ScrolledWindow to contain the TextView with scrolling
     let editor_scrolled_window = ScrolledWindow::builder()
@@ -241,6 +298,9 @@ pub fn build_ui(app: &Application) {
     
     editor_scrolled_window.set_child(Some(&text_view));
 
+    // Keeps the caret line at a fixed height while typing (when enabled)
+    let typewriter = Typewriter::install(&text_view, &editor_scrolled_window, &settings);
+
     // --- Status Bar Setup ---
     // App logo (center)
     let app_logo = Label::builder()
@@ -268,7 +328,10 @@ pub fn build_ui(app: &Application) {
         .margin_start(10)
         .margin_end(10)
         .css_classes(vec!["word-count"])
+        .tooltip_text("Document statistics")
         .build();
+    // Clicking the word count shows the full statistics of the note or selection
+    install_document_stats_popover(&word_count_label, &text_view);
 
     // Modify the bottom bar to match editor color
     let bottom_bar = Box::builder()
@@ -293,45 +356,95 @@ pub fn build_ui(app: &Application) {
     // Create keyboard shortcuts button - use icon-only styling
     let shortcuts_button = Button::builder()
         .icon_name("input-keyboard-symbolic")
-        .tooltip_text("Keyboard Shortcuts (Ctrl+K)")
+        .action_name("win.show-shortcuts")
         .css_classes(vec!["icon-only-button"])
         .build();
+    tooltip_with_accel(&shortcuts_button, "Keyboard Shortcuts", "show-shortcuts");
+    
+    // Create settings button - use icon-only styling
+    let settings_button = Button::builder()
+        .icon_name("preferences-system-symbolic")
+        .action_name("win.show-settings")
+        .css_classes(vec!["icon-only-button"])
+        .build();
+    tooltip_with_accel(&settings_button, "Settings", "show-settings");
     
     // Create sidebar toggle button - use icon-only styling
     let sidebar_toggle = Button::builder()
         .icon_name("view-sidebar-start-symbolic")
-        .tooltip_text("Toggle Sidebar (Ctrl+B)")
+        .action_name("win.toggle-sidebar")
         .css_classes(vec!["icon-only-button"])
         .build();
+    tooltip_with_accel(&sidebar_toggle, "Toggle Sidebar", "toggle-sidebar");
     
     // Create theme toggle button - use icon-only styling
     let theme_toggle_button = Button::builder()
         .icon_name("weather-clear-night-symbolic")
-        .tooltip_text("Toggle Light/Dark Theme (Ctrl+T)")
+        .action_name("win.toggle-theme")
         .css_classes(vec!["icon-only-button"])
         .build();
+    tooltip_with_accel(&theme_toggle_button, "Toggle Light/Dark Theme", "toggle-theme");
     
     // Create fullscreen toggle button - use icon-only styling
     let fullscreen_button = Button::builder()
         .icon_name("view-fullscreen-symbolic")
-        .tooltip_text("Toggle Fullscreen Mode (F11)")
+        .action_name("win.toggle-fullscreen")
         .css_classes(vec!["icon-only-button"])
         .build();
+    tooltip_with_accel(&fullscreen_button, "Toggle Fullscreen Mode", "toggle-fullscreen");
     
     // Add buttons to controls container
     controls_container.append(&shortcuts_button);
+    controls_container.append(&settings_button);
     controls_container.append(&sidebar_toggle);
     controls_container.append(&theme_toggle_button);
     controls_container.append(&fullscreen_button);
     
+    // Daily note controls in the sidebar header: previous entry, calendar, next entry
+    let journal = Journal::new(&window, &text_view, &settings);
+    sidebar_header_box.insert_child_after(journal.widget(), Some(&notes_label));
+
+    // Word deltas of each save, for the statistics view and the daily goal
+    let writing_stats = WritingStats::load();
+
+    // Progress towards the open note's word goal and the daily goal
+    let word_goals = WordGoals::new(&window, &settings, &writing_stats);
+
+    // Strict freewrite sessions, with their progress shown in the bottom bar
+    let freewrite = Freewrite::new(&window, &text_view, &left_pane);
+
     // Add all elements to the bottom bar in proper order
     bottom_bar.append(&status_label);
     bottom_bar.append(&app_logo);
     bottom_bar.append(&controls_container);
+    bottom_bar.append(freewrite.widget());
+    bottom_bar.append(word_goals.widget());
     bottom_bar.append(&word_count_label);
     
+    // Outline of the open note, shown to the right of the editor
+    let outline_panel = OutlinePanel::new(&text_view);
+    let editor_row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    editor_row.append(&editor_scrolled_window);
+    editor_row.append(outline_panel.widget());
+
+    // Readability scores and style checks, also to the right of the editor
+    let style_panel = StylePanel::new(&text_view, &settings);
+    editor_row.append(style_panel.widget());
+
+    // Red underlines on misspelled words, with suggestions in the context menu
+    let spell_check = SpellCheck::new(&text_view, &settings);
+
     // Add editor components to right pane
-    right_pane.append(&editor_scrolled_window);
+    right_pane.append(&editor_row);
+
+    // Backlinks of the open note, kept current by the link index
+    let link_index = LinkIndex::new();
+    let backlinks_panel = BacklinksPanel::new(&window, &link_index);
+    right_pane.append(backlinks_panel.widget());
 
     // --- Main Layout Assembly ---
     // Create main vertical box to hold all components
@@ -356,21 +469,51 @@ pub fn build_ui(app: &Application) {
     // Set the overlay as the window child
     window.set_child(Some(&main_overlay));
 
-    // --- Button functionality ---
+    // Focus mode dims the text around the cursor and hides the bars until the pointer reaches an edge
+    let focus_mode = FocusMode::new(&window, &text_view, &settings, vec![
+        (top_bar.clone().upcast(), Edge::Top),
+        (bottom_bar.clone().upcast(), Edge::Bottom),
+        (left_pane.clone().upcast(), Edge::Left),
+    ]);
+
+    // --- Window actions ---
+    // Every command is a `win.*` action; buttons use action_name and shortcuts come
+    // from set_accels_for_action (see shortcuts::apply_accels)
+
     // Fullscreen toggle functionality
+    let toggle_fullscreen_action = gio::SimpleAction::new("toggle-fullscreen", None);
     let window_for_fullscreen = window.clone();
-    fullscreen_button.connect_clicked(move |button| {
+    let fullscreen_button_for_action = fullscreen_button.clone();
+    toggle_fullscreen_action.connect_activate(move |_, _| {
         let is_fullscreen = window_for_fullscreen.is_fullscreen();
         if is_fullscreen {
             window_for_fullscreen.unfullscreen();
             window_for_fullscreen.remove_css_class("fullscreen-mode");
-            button.set_icon_name("view-restore-symbolic");
+            fullscreen_button_for_action.set_icon_name("view-restore-symbolic");
         } else {
             window_for_fullscreen.fullscreen();
             window_for_fullscreen.add_css_class("fullscreen-mode");
-            button.set_icon_name("view-fullscreen-symbolic");
+            fullscreen_button_for_action.set_icon_name("view-fullscreen-symbolic");
         }
     });
+    window.add_action(&toggle_fullscreen_action);
+
+    // Escape to exit fullscreen. Not an accelerator: those run before the focused widget,
+    // while this key controller (bubble phase) only sees Escape once the palette, the
+    // link completion or another popup had the chance to close itself with it.
+    let escape_controller = EventControllerKey::new();
+    let window_for_escape = window.clone();
+    let fullscreen_button_for_escape = fullscreen_button.clone();
+    escape_controller.connect_key_pressed(move |_, key, _keycode, state| {
+        if key != Key::Escape || !state.is_empty() || !window_for_escape.is_fullscreen() {
+            return Propagation::Proceed;
+        }
+        window_for_escape.unfullscreen();
+        window_for_escape.remove_css_class("fullscreen-mode");
+        fullscreen_button_for_escape.set_icon_name("view-fullscreen-symbolic");
+        Propagation::Stop
+    });
+    window.add_controller(escape_controller);
     
     // Theme toggle functionality
     let window_for_theme = window.clone();
@@ -382,7 +525,11 @@ pub fn build_ui(app: &Application) {
     let editor_scrolled_window_for_theme = editor_scrolled_window.clone(); // Add scrolled window
     let controls_container_for_theme = controls_container.clone();
     
-    theme_toggle_button.connect_clicked(move |button| {
+    let theme_toggle_button_for_action = theme_toggle_button.clone();
+    
+    let toggle_theme_action = gio::SimpleAction::new("toggle-theme", None);
+    toggle_theme_action.connect_activate(move |_, _| {
+        let button = &theme_toggle_button_for_action;
         if window_for_theme.has_css_class("dark-mode") {
             // Switch to light mode
             window_for_theme.remove_css_class("dark-mode");
@@ -415,12 +562,14 @@ pub fn build_ui(app: &Application) {
             button.set_icon_name("weather-clear-night-symbolic");
         }
     });
+    window.add_action(&toggle_theme_action);
     
     // Sidebar toggle functionality
     let window_for_sidebar = window.clone();
     let left_pane_for_sidebar = left_pane.clone();
     let paned_for_sidebar = paned.clone();
-    sidebar_toggle.connect_clicked(move |_| {
+    let toggle_sidebar_action = gio::SimpleAction::new("toggle-sidebar", None);
+    toggle_sidebar_action.connect_activate(move |_, _| {
         if window_for_sidebar.has_css_class("sidebar-hidden") {
             window_for_sidebar.remove_css_class("sidebar-hidden");
             left_pane_for_sidebar.set_visible(true);
@@ -432,16 +581,61 @@ pub fn build_ui(app: &Application) {
             paned_for_sidebar.set_position(0); // Hide by moving divider to the edge
         }
     });
+    window.add_action(&toggle_sidebar_action);
     
     // Keyboard shortcuts dialog
     let window_for_shortcuts = window.clone();
-    shortcuts_button.connect_clicked(move |_| {
-        show_shortcuts_dialog(&window_for_shortcuts);
+    let settings_for_shortcuts = settings.clone();
+    let show_shortcuts_action = gio::SimpleAction::new("show-shortcuts", None);
+    show_shortcuts_action.connect_activate(move |_, _| {
+        show_shortcuts_dialog(&window_for_shortcuts, &settings_for_shortcuts);
+    });
+    window.add_action(&show_shortcuts_action);
+
+    // Settings dialog
+    let window_for_settings = window.clone();
+    let settings_for_dialog = settings.clone();
+    let spell_check_for_settings = spell_check.clone();
+    let show_settings_action = gio::SimpleAction::new("show-settings", None);
+    show_settings_action.connect_activate(move |_, _| {
+        let spell_check = spell_check_for_settings.clone();
+        show_settings_dialog(&window_for_settings, &settings_for_dialog, move || spell_check.refresh());
+    });
+    window.add_action(&show_settings_action);
+
+    // Focus the sidebar search
+    let search_entry_for_action = search_entry.clone();
+    let search_notes_action = gio::SimpleAction::new("search-notes", None);
+    search_notes_action.connect_activate(move |_, _| {
+        search_entry_for_action.grab_focus();
     });
+    window.add_action(&search_notes_action);
+
+    // Formatting commands on the editor selection
+    let mut format_actions = Vec::new();
+    for command in FormatCommand::ALL {
+        let text_view_for_format = text_view.clone();
+        let format_action = gio::SimpleAction::new(command.action_name(), None);
+        format_action.connect_activate(move |_, _| {
+            apply_format(&text_view_for_format, command);
+            text_view_for_format.grab_focus();
+        });
+        window.add_action(&format_action);
+        format_actions.push(format_action);
+    }
+    // Accelerators apply to the whole window: only format while the editor has focus,
+    // so keys typed in the search, rename or palette entries reach those entries
+    let set_format_actions_enabled = move |text_view: &TextView| {
+        for format_action in &format_actions {
+            format_action.set_enabled(text_view.has_focus());
+        }
+    };
+    set_format_actions_enabled(&text_view);
+    text_view.connect_has_focus_notify(set_format_actions_enabled);
 
     // --- Active Note Logic ---
     let active_note: Rc<RefCell<Option<ActiveNote>>> = Rc::new(RefCell::new(None));
-    
+
     // --- Row Selection Logic ---
     // Clone variables needed for the closure
     let active_note_for_select = active_note.clone();
@@ -449,6 +643,11 @@ pub fn build_ui(app: &Application) {
     let window_for_select = window.clone();
     let status_label_for_select = status_label.clone();
     let word_count_label_for_select = word_count_label.clone();
+    let backlinks_panel_for_select = backlinks_panel.clone();
+    let folding_for_select = folding.clone();
+    let word_goals_for_select = word_goals.clone();
+    let journal_for_select = journal.clone();
+    let writing_stats_for_select = writing_stats.clone();
 
     // --- Fix the selection handler ---
     list_box.connect_row_selected(move |_listbox, row_opt| {
@@ -459,12 +658,13 @@ pub fn build_ui(app: &Application) {
                 if active.has_changes {
                     // Cancel any pending auto-save timer first
                     if let Some(source_id) = active.auto_save_source_id.take() {
-                        let _ = source_id.remove();
+                        source_id.remove();
                     }
                     // Attempt to save synchronously
                     match active.note.save() {
                         Ok(_) => {
                             active.has_changes = false;
+                            writing_stats_for_select.record_save(&active.title, count_words(&active.note.content));
                             status_label_for_select.set_text("Saved"); // Give feedback
 
                             // Refresh the list to update preview/timestamp
@@ -498,7 +698,7 @@ pub fn build_ui(app: &Application) {
                 } else {
                     // No changes, but if there's an auto-save timer for some reason, cancel it.
                     if let Some(source_id) = active.auto_save_source_id.take() {
-                        let _ = source_id.remove();
+                        source_id.remove();
                     }
                 }
             }
@@ -516,8 +716,7 @@ pub fn build_ui(app: &Application) {
                 .map(|label| label.label().to_string());
 
             if let Some(title) = title {
-                let notes_dir = crate::utils::get_notes_dir();
-                let file_path = notes_dir.join(format!("{}.md", title));
+                let file_path = Note::path_for_title(&title);
 
                 // When loading a note, update the window title properly
                 match Note::load(&file_path) {
@@ -537,6 +736,11 @@ pub fn build_ui(app: &Application) {
                         let count_text = format!("{} words", word_count);
                         status_label_for_select.set_text("Ready"); // Reset status
                         word_count_label_for_select.set_text(&count_text);
+                        writing_stats_for_select.observe(&title, word_count);
+                        word_goals_for_select.set_note(Some((&title, &note.content)), word_count);
+                        journal_for_select.set_note(Some(&title));
+                        backlinks_panel_for_select.show_for(Some(&title));
+                        folding_for_select.set_note(Some(note.relative_path()));
                         text_view_for_select.grab_focus(); // Focus editor
                     },
                     Err(e) => {
@@ -544,9 +748,13 @@ pub fn build_ui(app: &Application) {
                         PROGRAMMATIC_TEXT_CHANGE.with(|ptc| *ptc.borrow_mut() = true);
                         text_view_for_select.buffer().set_text("");
                         PROGRAMMATIC_TEXT_CHANGE.with(|ptc| *ptc.borrow_mut() = false);
-                        window_for_select.set_title(Some(&format!("{}", APP_NAME))); // Just use app name
+                        window_for_select.set_title(Some(APP_NAME)); // Just use app name
                         status_label_for_select.set_text("Error loading note");
                         *active_note_for_select.borrow_mut() = None;
+                        backlinks_panel_for_select.show_for(None);
+                        folding_for_select.set_note(None);
+                        word_goals_for_select.set_note(None, 0);
+                        journal_for_select.set_note(None);
                         text_view_for_select.grab_focus(); // Focus editor even on error
                     }
                 }
@@ -559,6 +767,10 @@ pub fn build_ui(app: &Application) {
                 window_for_select.set_title(Some("JustWrite"));
                 status_label_for_select.set_text("Ready");
                 word_count_label_for_select.set_text("0 words");
+                backlinks_panel_for_select.show_for(None);
+                folding_for_select.set_note(None);
+                word_goals_for_select.set_note(None, 0);
+                journal_for_select.set_note(None);
                 text_view_for_select.grab_focus(); // Focus editor
             }
         } else {
@@ -570,6 +782,10 @@ pub fn build_ui(app: &Application) {
             window_for_select.set_title(Some("JustWrite"));
             status_label_for_select.set_text("Ready");
             word_count_label_for_select.set_text("0 words");
+            backlinks_panel_for_select.show_for(None);
+            folding_for_select.set_note(None);
+            word_goals_for_select.set_note(None, 0);
+            journal_for_select.set_note(None);
             text_view_for_select.grab_focus(); // Focus editor
         }
         // No need to call update_ui_for_selection here, as header buttons are removed
@@ -581,6 +797,8 @@ pub fn build_ui(app: &Application) {
     let text_view_for_changes = text_view.clone();
     let status_label_for_changes = status_label.clone();
     let word_count_label_for_changes = word_count_label.clone();
+    let word_goals_for_changes = word_goals.clone();
+    let writing_stats_for_changes = writing_stats.clone();
     // Make these available for refresh_note_list in auto-save
     let list_box_for_auto_save_refresh = list_box.clone(); 
     let window_for_auto_save_refresh = window.clone();
@@ -594,13 +812,14 @@ pub fn build_ui(app: &Application) {
         let content = text_view_for_changes.buffer().text(
             &text_view_for_changes.buffer().start_iter(),
             &text_view_for_changes.buffer().end_iter(),
-            false
+            true // Include folded (invisible) text
         ).to_string();
         
         // Update word count immediately regardless of active note state
         let word_count = count_words(&content);
         let count_text = format!("{} words", word_count);
         word_count_label_for_changes.set_text(&count_text);
+        word_goals_for_changes.update(&content, word_count);
         
         // Separate mutable borrow scope to avoid conflicts
         let mut _update_title = false; // Prefixed
@@ -616,7 +835,7 @@ pub fn build_ui(app: &Application) {
                 
                 // Cancel any existing auto-save
                 if let Some(source_id) = active.auto_save_source_id.take() {
-                    let _ = source_id.remove();
+                    source_id.remove();
                 }
                 
                 // Check for title update (outside active auto-save code)
@@ -650,10 +869,12 @@ pub fn build_ui(app: &Application) {
                 let status_label_clone_for_refresh = status_label_for_changes.clone();
                 let word_count_label_clone_for_refresh = word_count_label_for_changes.clone();
                 let text_view_clone_for_refresh = text_view_for_auto_save_refresh.clone();
+                let writing_stats_for_save = writing_stats_for_changes.clone();
                 
                 active.auto_save_source_id = Some(schedule_auto_save(AUTO_SAVE_DELAY_MS, move || {
                     match note_to_save.save() {
                         Ok(_) => {
+                            writing_stats_for_save.record_save(&note_to_save.title, count_words(&note_to_save.content));
                             status_label_ref.set_text("Auto-saved");
                             let mut needs_list_refresh = false;
                             if let Ok(mut guard) = active_note_ref.try_borrow_mut() {
@@ -732,52 +953,7 @@ pub fn build_ui(app: &Application) {
         */
     });
 
-    // --- Global Keyboard Shortcuts ---
-    let key_controller = EventControllerKey::new();
-    let fullscreen_button_for_key = fullscreen_button.clone();
-    let theme_toggle_for_key = theme_toggle_button.clone();
-    let sidebar_toggle_for_key = sidebar_toggle.clone();
-    let window_for_key = window.clone();
-    let shortcuts_button_for_key = shortcuts_button.clone();
-    
-    key_controller.connect_key_pressed(move |_, key, _keycode, state| {
-        // F11 for fullscreen
-        if key == Key::F11 {
-            fullscreen_button_for_key.emit_clicked();
-            return Propagation::Stop;
-        }
-        
-        // Ctrl+T for theme toggle
-        if key == Key::t && state.contains(ModifierType::CONTROL_MASK) {
-            theme_toggle_for_key.emit_clicked();
-            return Propagation::Stop;
-        }
-        
-        // Ctrl+B for sidebar toggle
-        if key == Key::b && state.contains(ModifierType::CONTROL_MASK) {
-            sidebar_toggle_for_key.emit_clicked();
-            return Propagation::Stop;
-        }
-        
-        // Ctrl+K for keyboard shortcuts dialog
-        if key == Key::k && state.contains(ModifierType::CONTROL_MASK) {
-            shortcuts_button_for_key.emit_clicked();
-            return Propagation::Stop;
-        }
-        
-        // Escape to exit fullscreen
-        if key == Key::Escape && window_for_key.is_fullscreen() {
-            window_for_key.unfullscreen();
-            fullscreen_button_for_key.set_icon_name("view-fullscreen-symbolic");
-            return Propagation::Stop;
-        }
-        
-        Propagation::Proceed
-    });
-    
-    window.add_controller(key_controller.clone());
-
-    // --- New Note Button Logic ---
+    // --- New Note Action Logic ---
     let list_box_for_new = list_box.clone();
     let active_note_for_new = active_note.clone();
     let text_view_for_new = text_view.clone();
@@ -785,19 +961,22 @@ pub fn build_ui(app: &Application) {
     let status_label_for_new = status_label.clone();
     let word_count_label_for_new = word_count_label.clone(); // Clone for new note
     let text_view_for_new_refresh = text_view.clone(); // Clone for refresh_note_list call
+    let writing_stats_for_new = writing_stats.clone();
 
-    new_note_button.connect_clicked(move |_| {
+    let new_note_action = gio::SimpleAction::new("new-note", None);
+    new_note_action.connect_activate(move |_, _| {
         // Synchronously save the currently active note if it has changes
         {
             let mut active_opt = active_note_for_new.borrow_mut(); // This is a clone of the main active_note Rc<RefCell>
             if let Some(active) = active_opt.as_mut() {
                 if active.has_changes {
                     if let Some(source_id) = active.auto_save_source_id.take() {
-                        let _ = source_id.remove();
+                        source_id.remove();
                     }
                     match active.note.save() {
                         Ok(_) => {
                             active.has_changes = false;
+                            writing_stats_for_new.record_save(&active.title, count_words(&active.note.content));
                             status_label_for_new.set_text("Saved"); // Give feedback
 
                             // Refresh the list to update preview/timestamp for the saved note
@@ -829,7 +1008,7 @@ pub fn build_ui(app: &Application) {
                     }
                 } else {
                     if let Some(source_id) = active.auto_save_source_id.take() {
-                        let _ = source_id.remove();
+                        source_id.remove();
                     }
                 }
             }
@@ -867,6 +1046,348 @@ pub fn build_ui(app: &Application) {
             }
         }
     });
+    window.add_action(&new_note_action);
+
+    // --- New Note from Template ---
+    let window_for_template = window.clone();
+    let text_view_for_template = text_view.clone();
+    let new_from_template_action = gio::SimpleAction::new("new-from-template", None);
+    new_from_template_action.connect_activate(move |_, _| {
+        show_new_from_template_dialog(&window_for_template, &text_view_for_template);
+    });
+    window.add_action(&new_from_template_action);
+
+    // --- Import ---
+    let window_for_import = window.clone();
+    let list_box_for_import = list_box.clone();
+    let active_note_for_import = active_note.clone();
+    let status_label_for_import = status_label.clone();
+    let word_count_label_for_import = word_count_label.clone();
+    let text_view_for_import = text_view.clone();
+    let import_notes_action = gio::SimpleAction::new("import-notes", None);
+    import_notes_action.connect_activate(move |_, _| {
+        let list_box_for_refresh = list_box_for_import.clone();
+        let active_note_for_refresh = active_note_for_import.clone();
+        let window_for_refresh = window_for_import.clone();
+        let status_label_for_refresh = status_label_for_import.clone();
+        let word_count_label_for_refresh = word_count_label_for_import.clone();
+        let text_view_for_refresh = text_view_for_import.clone();
+        show_import_dialog(&window_for_import, move || {
+            refresh_note_list(&list_box_for_refresh, &active_note_for_refresh, &window_for_refresh, &status_label_for_refresh, &word_count_label_for_refresh, &text_view_for_refresh);
+        });
+    });
+    window.add_action(&import_notes_action);
+
+    // --- Export ---
+    let window_for_export = window.clone();
+    let text_view_for_export = text_view.clone();
+    let active_note_for_export = active_note.clone();
+    let export_html_action = gio::SimpleAction::new("export-html", None);
+    export_html_action.connect_activate(move |_, _| {
+        let note = active_note_for_export.borrow().as_ref().map(|active| (active.title.clone(), active.path.clone()));
+        show_export_dialog(&window_for_export, &text_view_for_export, note);
+    });
+    window.add_action(&export_html_action);
+
+    let window_for_manuscript = window.clone();
+    let settings_for_manuscript = settings.clone();
+    let compile_manuscript_action = gio::SimpleAction::new("compile-manuscript", None);
+    compile_manuscript_action.connect_activate(move |_, _| {
+        show_manuscript_dialog(&window_for_manuscript, &settings_for_manuscript);
+    });
+    window.add_action(&compile_manuscript_action);
+
+    // --- Print / PDF ---
+    // Both use the editor's text, so unsaved changes are included
+    for (name, to_pdf) in [("print-note", false), ("export-pdf", true)] {
+        let window_for_print = window.clone();
+        let text_view_for_print = text_view.clone();
+        let active_note_for_print = active_note.clone();
+        let settings_for_print = settings.clone();
+        let print_action = gio::SimpleAction::new(name, None);
+        print_action.connect_activate(move |_, _| {
+            let title = match active_note_for_print.borrow().as_ref() {
+                Some(active) => active.title.clone(),
+                None => return,
+            };
+            let buffer = text_view_for_print.buffer();
+            let markdown = buffer.text(&buffer.start_iter(), &buffer.end_iter(), true).to_string();
+            let print_settings = settings_for_print.borrow().print.clone();
+            if !to_pdf {
+                print_note(&window_for_print, &title, &markdown, &print_settings, None);
+                return;
+            }
+            let file_dialog = gtk::FileDialog::builder()
+                .title("Export as PDF")
+                .initial_name(format!("{}.pdf", title))
+                .modal(true)
+                .build();
+            let window_for_result = window_for_print.clone();
+            file_dialog.save(Some(&window_for_print), gio::Cancellable::NONE, move |result| {
+                if let Some(path) = result.ok().and_then(|file| file.path()) {
+                    print_note(&window_for_result, &title, &markdown, &print_settings, Some(path));
+                }
+            });
+        });
+        window.add_action(&print_action);
+    }
+
+    // --- Save / Rename / Delete Actions (act on the active note) ---
+    let active_note_for_save = active_note.clone();
+    let list_box_for_save = list_box.clone();
+    let window_for_save = window.clone();
+    let status_label_for_save = status_label.clone();
+    let word_count_label_for_save = word_count_label.clone();
+    let text_view_for_save = text_view.clone();
+    let writing_stats_for_save = writing_stats.clone();
+    let save_note_action = gio::SimpleAction::new("save-note", None);
+    save_note_action.connect_activate(move |_, _| {
+        let saved_title = {
+            let mut active_opt = active_note_for_save.borrow_mut();
+            let active = match active_opt.as_mut() {
+                Some(active) if active.has_changes => active,
+                _ => return, // Nothing to save
+            };
+            if let Some(source_id) = active.auto_save_source_id.take() {
+                source_id.remove();
+            }
+            match active.note.save() {
+                Ok(_) => {
+                    active.has_changes = false;
+                    writing_stats_for_save.record_save(&active.title, count_words(&active.note.content));
+                    active.title.clone()
+                }
+                Err(e) => {
+                    show_error_dialog(&window_for_save, "Save Error", &format!("Failed to save note '{}': {}", active.title, e));
+                    return;
+                }
+            }
+        };
+
+        status_label_for_save.set_text("Saved");
+        refresh_note_list(&list_box_for_save, &active_note_for_save, &window_for_save, &status_label_for_save, &word_count_label_for_save, &text_view_for_save);
+        select_note_by_title(&list_box_for_save, &saved_title);
+
+        let status_label_clone = status_label_for_save.clone();
+        glib::timeout_add_seconds_local(2, move || {
+            if status_label_clone.text() == "Saved" {
+                status_label_clone.set_text("Ready");
+            }
+            glib::ControlFlow::Break
+        });
+    });
+    window.add_action(&save_note_action);
+
+    let active_note_for_rename = active_note.clone();
+    let list_box_for_rename = list_box.clone();
+    let window_for_rename = window.clone();
+    let status_label_for_rename = status_label.clone();
+    let word_count_label_for_rename = word_count_label.clone();
+    let text_view_for_rename = text_view.clone();
+    let rename_note_action = gio::SimpleAction::new("rename-note", None);
+    rename_note_action.connect_activate(move |_, _| {
+        let title = active_note_for_rename.borrow().as_ref().map(|active| active.title.clone());
+        if let Some(title) = title {
+            rename_note_interactive(&title, &list_box_for_rename, &active_note_for_rename, &window_for_rename, &status_label_for_rename, &word_count_label_for_rename, &text_view_for_rename);
+        }
+    });
+    window.add_action(&rename_note_action);
+
+    let active_note_for_delete = active_note.clone();
+    let list_box_for_delete = list_box.clone();
+    let window_for_delete = window.clone();
+    let status_label_for_delete = status_label.clone();
+    let word_count_label_for_delete = word_count_label.clone();
+    let text_view_for_delete = text_view.clone();
+    let delete_note_action = gio::SimpleAction::new("delete-note", None);
+    delete_note_action.connect_activate(move |_, _| {
+        let title = active_note_for_delete.borrow().as_ref().map(|active| active.title.clone());
+        if let Some(title) = title {
+            delete_note_interactive(&title, &list_box_for_delete, &active_note_for_delete, &window_for_delete, &status_label_for_delete, &word_count_label_for_delete, &text_view_for_delete);
+        }
+    });
+    window.add_action(&delete_note_action);
+
+    // --- Open / Create Note by Title (used by the palette and other navigation) ---
+    let list_box_for_open = list_box.clone();
+    let active_note_for_open = active_note.clone();
+    let window_for_open = window.clone();
+    let status_label_for_open = status_label.clone();
+    let word_count_label_for_open = word_count_label.clone();
+    let text_view_for_open = text_view.clone();
+    let open_note_action = gio::SimpleAction::new("open-note", Some(glib::VariantTy::STRING));
+    open_note_action.connect_activate(move |_, parameter| {
+        if let Some(title) = parameter.and_then(|p| p.get::<String>()) {
+            // Row selection saves the current note and loads this one
+            if !select_note_by_title(&list_box_for_open, &title) && Note::path_for_title(&title).exists() {
+                // Written outside the list (e.g. a new daily note): list it first
+                refresh_note_list(&list_box_for_open, &active_note_for_open, &window_for_open, &status_label_for_open, &word_count_label_for_open, &text_view_for_open);
+                select_note_by_title(&list_box_for_open, &title);
+            }
+        }
+    });
+    window.add_action(&open_note_action);
+
+    let list_box_for_create = list_box.clone();
+    let active_note_for_create = active_note.clone();
+    let window_for_create = window.clone();
+    let status_label_for_create = status_label.clone();
+    let word_count_label_for_create = word_count_label.clone();
+    let text_view_for_create = text_view.clone();
+    let create_note_action = gio::SimpleAction::new("create-note", Some(glib::VariantTy::STRING));
+    create_note_action.connect_activate(move |_, parameter| {
+        let title = match parameter.and_then(|p| p.get::<String>()) {
+            Some(title) => title.trim().to_string(),
+            None => return,
+        };
+        if let Err(e) = check_note_title(&title) {
+            show_error_dialog(&window_for_create, "Create Error", &e);
+            return;
+        }
+        // Never overwrite an existing note: just open it
+        let path = Note::path_for_title(&title);
+        if !path.exists() {
+            if let Err(e) = Note::new(&title) {
+                show_error_dialog(&window_for_create, "Create Error", &format!("Failed to create new note: {}", e));
+                return;
+            }
+            refresh_note_list(&list_box_for_create, &active_note_for_create, &window_for_create, &status_label_for_create, &word_count_label_for_create, &text_view_for_create);
+        }
+        select_note_by_title(&list_box_for_create, &title);
+    });
+    window.add_action(&create_note_action);
+
+    // --- Command Palette ---
+    let command_palette = CommandPalette::new(&main_overlay, &window, &text_view, &settings);
+    let command_palette_action = gio::SimpleAction::new("command-palette", None);
+    command_palette_action.connect_activate(move |_, _| {
+        command_palette.show(">");
+    });
+    window.add_action(&command_palette_action);
+
+    // --- Folding ---
+    let toggle_fold_action = gio::SimpleAction::new("toggle-fold", None);
+    let folding_for_toggle = folding.clone();
+    toggle_fold_action.connect_activate(move |_, _| {
+        folding_for_toggle.toggle_at_cursor();
+    });
+    window.add_action(&toggle_fold_action);
+
+    let unfold_all_action = gio::SimpleAction::new("unfold-all", None);
+    unfold_all_action.connect_activate(move |_, _| {
+        folding.unfold_all();
+    });
+    window.add_action(&unfold_all_action);
+
+    // --- Focus Mode ---
+    let toggle_focus_mode_action = gio::SimpleAction::new("toggle-focus-mode", None);
+    toggle_focus_mode_action.connect_activate(move |_, _| {
+        focus_mode.toggle();
+    });
+    window.add_action(&toggle_focus_mode_action);
+
+    // --- Typewriter Scrolling ---
+    let toggle_typewriter_action = gio::SimpleAction::new("toggle-typewriter", None);
+    toggle_typewriter_action.connect_activate(move |_, _| {
+        typewriter.toggle();
+    });
+    window.add_action(&toggle_typewriter_action);
+
+    // --- Freewrite ---
+    let start_freewrite_action = gio::SimpleAction::new("start-freewrite", None);
+    let freewrite_for_start = freewrite.clone();
+    start_freewrite_action.connect_activate(move |_, _| {
+        freewrite_for_start.prompt_start();
+    });
+    window.add_action(&start_freewrite_action);
+
+    let abandon_freewrite_action = gio::SimpleAction::new("abandon-freewrite", None);
+    abandon_freewrite_action.connect_activate(move |_, _| {
+        freewrite.abandon();
+    });
+    window.add_action(&abandon_freewrite_action);
+
+    // --- Outline ---
+    let toggle_outline_action = gio::SimpleAction::new("toggle-outline", None);
+    toggle_outline_action.connect_activate(move |_, _| {
+        outline_panel.toggle();
+    });
+    window.add_action(&toggle_outline_action);
+
+    // --- Writing Statistics ---
+    let show_stats_action = gio::SimpleAction::new("show-stats", None);
+    let window_for_stats = window.clone();
+    show_stats_action.connect_activate(move |_, _| {
+        show_stats_view(&window_for_stats, &writing_stats);
+    });
+    window.add_action(&show_stats_action);
+
+    // --- Daily Notes ---
+    let journal_for_today = journal.clone();
+    let open_today_action = gio::SimpleAction::new("open-today", None);
+    open_today_action.connect_activate(move |_, _| {
+        journal_for_today.open_today();
+    });
+    window.add_action(&open_today_action);
+
+    let journal_for_previous = journal.clone();
+    let journal_previous_action = gio::SimpleAction::new("journal-previous", None);
+    journal_previous_action.connect_activate(move |_, _| {
+        journal_for_previous.open_previous();
+    });
+    window.add_action(&journal_previous_action);
+
+    let journal_for_next = journal.clone();
+    let journal_next_action = gio::SimpleAction::new("journal-next", None);
+    journal_next_action.connect_activate(move |_, _| {
+        journal_for_next.open_next();
+    });
+    window.add_action(&journal_next_action);
+
+    let journal_calendar_action = gio::SimpleAction::new("journal-calendar", None);
+    journal_calendar_action.connect_activate(move |_, _| {
+        journal.show_calendar();
+    });
+    window.add_action(&journal_calendar_action);
+
+    // --- Spell Check ---
+    let toggle_spell_check_action = gio::SimpleAction::new("toggle-spell-check", None);
+    toggle_spell_check_action.connect_activate(move |_, _| {
+        spell_check.toggle();
+    });
+    window.add_action(&toggle_spell_check_action);
+
+    // --- Readability ---
+    let toggle_readability_action = gio::SimpleAction::new("toggle-readability", None);
+    toggle_readability_action.connect_activate(move |_, _| {
+        style_panel.toggle();
+    });
+    window.add_action(&toggle_readability_action);
+
+    // --- Graph View ---
+    let show_graph_action = gio::SimpleAction::new("show-graph", None);
+    let window_for_graph = window.clone();
+    let active_note_for_graph = active_note.clone();
+    show_graph_action.connect_activate(move |_, _| {
+        let active_title = active_note_for_graph.borrow().as_ref().map(|active| active.title.clone());
+        show_graph_view(&window_for_graph, &link_index, active_title);
+    });
+    window.add_action(&show_graph_action);
+
+    // --- Backlinks Panel ---
+    let toggle_backlinks_action = gio::SimpleAction::new("toggle-backlinks", None);
+    toggle_backlinks_action.connect_activate(move |_, _| {
+        backlinks_panel.toggle();
+    });
+    window.add_action(&toggle_backlinks_action);
+
+    // --- Quick Switcher ---
+    let quick_switcher_action = gio::SimpleAction::new("quick-switcher", None);
+    let window_for_switcher = window.clone();
+    quick_switcher_action.connect_activate(move |_, _| {
+        show_quick_switcher(&window_for_switcher);
+    });
+    window.add_action(&quick_switcher_action);
 
     // Remove rename_button_ref and delete_button_ref connections as they are moved to rows
 
@@ -884,9 +1405,68 @@ pub fn build_ui(app: &Application) {
     window.present();
 }
 
-/// Count words in text
-fn count_words(text: &str) -> usize {
-    text.split_whitespace().count()
+/// Popover on the word count label with the statistics of the note, or of the selection if there is one
+fn install_document_stats_popover(label: &Label, text_view: &TextView) {
+    let grid = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(16)
+        .margin_start(8)
+        .margin_end(8)
+        .margin_top(8)
+        .margin_bottom(8)
+        .build();
+    let popover = gtk::Popover::builder()
+        .child(&grid)
+        .position(gtk::PositionType::Top)
+        .css_classes(vec!["document-stats"])
+        .build();
+    popover.set_parent(label);
+    label.set_cursor_from_name(Some("pointer"));
+
+    let click = gtk::GestureClick::new();
+    let text_view_for_click = text_view.clone();
+    click.connect_released(move |_, _, _, _| {
+        let buffer = text_view_for_click.buffer();
+        let (scope, text) = match buffer.selection_bounds() {
+            Some((start, end)) => ("Selection", buffer.text(&start, &end, true)),
+            None => ("Document", buffer.text(&buffer.start_iter(), &buffer.end_iter(), true)),
+        };
+        let stats = text_stats::analyze(&text);
+        let rows = [
+            ("Words", stats.words.to_string()),
+            ("Characters", stats.characters.to_string()),
+            ("Characters (no spaces)", stats.characters_no_spaces.to_string()),
+            ("Sentences", stats.sentences.to_string()),
+            ("Paragraphs", stats.paragraphs.to_string()),
+            ("Reading time", text_stats::format_minutes(stats.reading_minutes())),
+            ("Speaking time", text_stats::format_minutes(stats.speaking_minutes())),
+        ];
+
+        while let Some(child) = grid.first_child() {
+            grid.remove(&child);
+        }
+        let title = Label::builder()
+            .label(scope)
+            .xalign(0.0)
+            .css_classes(vec!["settings-section"])
+            .build();
+        grid.attach(&title, 0, 0, 2, 1);
+        for (row, (name, value)) in rows.iter().enumerate() {
+            let name_label = Label::builder()
+                .label(*name)
+                .xalign(0.0)
+                .css_classes(vec!["dim-label"])
+                .build();
+            let value_label = Label::builder()
+                .label(value)
+                .xalign(1.0)
+                .build();
+            grid.attach(&name_label, 0, row as i32 + 1, 1, 1);
+            grid.attach(&value_label, 1, row as i32 + 1, 1, 1);
+        }
+        popover.popup();
+    });
+    label.add_controller(click);
 }
 
 /// Find an empty note or create a new one, updating title if necessary
@@ -1020,196 +1600,28 @@ fn refresh_note_list(list_box: &ListBox, active_note_ref: &Rc<RefCell<Option<Act
                 // Store note title in row data for button handlers
                 let note_title = note.title.clone();
                 
-                // Clone necessary Rcs and other variables for the closures
+                // Rename via the shared dialog flow (also used by win.rename-note)
+                let list_box_for_edit = list_box.clone();
                 let active_note_clone_for_edit = active_note_ref.clone();
-                let window_clone_for_edit = window_ref.clone(); // ApplicationWindow is Clone
+                let window_clone_for_edit = window_ref.clone();
                 let status_label_clone_for_edit = status_label_ref.clone();
-                let note_title_for_edit_outer = note_title.clone(); // Used to identify if active note is this one
-
-                // Clones for the on_confirm callback of show_rename_dialog
-                let active_note_clone_for_rename_confirm = active_note_ref.clone();
-                let window_clone_for_rename_confirm = window_ref.clone();
-                let status_label_clone_for_rename_confirm = status_label_ref.clone();
-                let list_box_clone_for_rename_confirm = list_box.clone();
-                // We'll also need word_count_label and text_view for later potential updates if active note changes
-                let word_count_label_clone_for_rename_confirm = word_count_label_ref.clone();
-                let text_view_clone_for_rename_confirm = text_view_ref.clone();
-
-                let row_for_button_closure = row.clone(); // Capture the current row for the button
-
+                let word_count_label_clone_for_edit = word_count_label_ref.clone();
+                let text_view_clone_for_edit = text_view_ref.clone();
+                let note_title_for_edit = note_title.clone();
                 edit_button.connect_clicked(move |_| {
-                    let mut proceed_with_rename = true;
-                    // Check if the note to be renamed is the active note and has changes
-                    if let Some(active_note_guard) = active_note_clone_for_edit.borrow().as_ref() {
-                        if active_note_guard.title == note_title_for_edit_outer && active_note_guard.has_changes {
-                            // Attempt to save
-                            if let Some(active_mut_guard) = active_note_clone_for_edit.borrow_mut().as_mut() {
-                                match active_mut_guard.note.save() {
-                                    Ok(_) => {
-                                        active_mut_guard.has_changes = false;
-                                        status_label_clone_for_edit.set_text("Saved");
-
-                                        // Refresh list after saving before rename
-                                        let list_box_for_refresh = list_box_clone_for_rename_confirm.clone(); 
-                                        let active_note_for_refresh = active_note_clone_for_edit.clone(); 
-                                        let window_for_refresh = window_clone_for_edit.clone();
-                                        let status_label_for_refresh = status_label_clone_for_edit.clone();
-                                        let word_count_label_for_refresh = word_count_label_clone_for_rename_confirm.clone(); 
-                                        let text_view_for_refresh = text_view_clone_for_rename_confirm.clone(); 
-                                        let _original_title_for_reselect = active_mut_guard.title.clone(); // Ensure not prefixed
-
-                                        refresh_note_list(&list_box_for_refresh, &active_note_for_refresh, &window_for_refresh, &status_label_for_refresh, &word_count_label_for_refresh, &text_view_for_refresh);
-                                        select_note_by_title(&list_box_for_refresh, &_original_title_for_reselect); // Ensure uses non-prefixed
-
-                                        let status_label_reset = status_label_clone_for_edit.clone();
-                                        glib::timeout_add_seconds_local(2, move || {
-                                            if status_label_reset.text() == "Saved" {
-                                                status_label_reset.set_text("Ready");
-                                            }
-                                            glib::ControlFlow::Break
-                                        });
-                                    }
-                                    Err(e) => {
-                                        show_error_dialog(
-                                            &window_clone_for_edit,
-                                            "Save Error",
-                                            &format!("Failed to save changes to note '{}' before renaming: {}. Rename aborted.", active_mut_guard.title, e),
-                                        );
-                                        proceed_with_rename = false;
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    if !proceed_with_rename {
-                        return; // Abort rename
-                    }
-
-                    if let Some(window) = row_for_button_closure.root().and_then(|r| r.downcast::<gtk::Window>().ok()) {
-                        if let Ok(app_window) = window.downcast::<ApplicationWindow>() {
-                            let note_title_for_dialog = note_title_for_edit_outer.clone(); // Title to show in dialog
-                            let original_title_for_confirm = note_title_for_edit_outer.clone(); // Original title to identify note
-
-                            show_rename_dialog(
-                                &app_window,
-                                note_title_for_dialog,
-                                clone!(@strong list_box_clone_for_rename_confirm, 
-                                       @strong original_title_for_confirm,
-                                       @strong active_note_clone_for_rename_confirm,
-                                       @strong window_clone_for_rename_confirm,
-                                       @strong status_label_clone_for_rename_confirm,
-                                       @strong word_count_label_clone_for_rename_confirm,
-                                       @strong text_view_clone_for_rename_confirm => move |new_title| {
-                                    let notes_dir = crate::utils::get_notes_dir();
-                                    let original_file_path = notes_dir.join(format!("{}.md", original_title_for_confirm));
-
-                                    match Note::load(&original_file_path) {
-                                        Ok(mut note_to_rename) => {
-                                            if let Err(e) = note_to_rename.rename(&new_title) {
-                                                show_error_dialog(&window_clone_for_rename_confirm, "Rename Failed", &format!("Could not rename the note: {}", e));
-                                                return;
-                                            }
-
-                                            // If rename was successful, check if it was the active note
-                                            let mut active_note_guard = active_note_clone_for_rename_confirm.borrow_mut();
-                                            if let Some(active) = active_note_guard.as_mut() {
-                                                if active.title == original_title_for_confirm {
-                                                    active.title = new_title.clone();
-                                                    active.path = note_to_rename.path.clone();
-                                                    active.note.title = new_title.clone();
-                                                    active.note.path = note_to_rename.path.clone();
-                                                    active.note.modified_time = note_to_rename.modified_time;
-                                                    active.has_changes = false; // Changes were saved before rename or it's a fresh state
-                                                    window_clone_for_rename_confirm.set_title(Some(&format!("{} - {}", APP_NAME, new_title)));
-                                                    status_label_clone_for_rename_confirm.set_text("Renamed");
-                                                     let status_label_reset = status_label_clone_for_rename_confirm.clone();
-                                                    glib::timeout_add_seconds_local(2, move || {
-                                                        if status_label_reset.text() == "Renamed" {
-                                                           status_label_reset.set_text("Ready");
-                                                        }
-                                                        glib::ControlFlow::Break
-                                                    });
-                                                }
-                                            }
-                                            drop(active_note_guard); // Release borrow
-
-                                            refresh_note_list(&list_box_clone_for_rename_confirm, &active_note_clone_for_rename_confirm, &window_clone_for_rename_confirm, &status_label_clone_for_rename_confirm, &word_count_label_clone_for_rename_confirm, &text_view_clone_for_rename_confirm);
-                                            select_note_by_title(&list_box_clone_for_rename_confirm, &new_title);
-                                        }
-                                        Err(e) => {
-                                             show_error_dialog(&window_clone_for_rename_confirm, "Rename Failed", &format!("Could not load note for renaming: {}", e));
-                                        }
-                                    }
-                                })
-                            );
-                        }
-                    }
+                    rename_note_interactive(&note_title_for_edit, &list_box_for_edit, &active_note_clone_for_edit, &window_clone_for_edit, &status_label_clone_for_edit, &word_count_label_clone_for_edit, &text_view_clone_for_edit);
                 });
                 
-                // Connect the delete button to show confirmation dialog
+                // Delete with confirmation (also used by win.delete-note)
                 let list_box_for_delete = list_box.clone();
-                let row_for_delete = row.clone();
-                let note_title_for_delete = note_title.clone(); // Clone for delete button
                 let active_note_clone_for_delete = active_note_ref.clone();
                 let window_clone_for_delete = window_ref.clone();
                 let status_label_clone_for_delete = status_label_ref.clone();
                 let word_count_label_clone_for_delete = word_count_label_ref.clone();
                 let text_view_clone_for_delete = text_view_ref.clone();
-                
+                let note_title_for_delete = note_title.clone();
                 delete_button.connect_clicked(move |_| {
-                    if let Some(window) = row_for_delete.root().and_then(|r| r.downcast::<gtk::Window>().ok()) {
-                        // Ensure window is ApplicationWindow
-                        if let Ok(app_window) = window.downcast::<ApplicationWindow>() {
-                            show_confirmation_dialog(
-                                &app_window, // Pass correct type
-                                "Confirm Deletion",
-                                &format!("Delete note \"{}\"?", note_title_for_delete),
-                                "This action cannot be undone.",
-                                clone!(@strong list_box_for_delete, 
-                                       @strong note_title_for_delete, 
-                                       @strong active_note_clone_for_delete, 
-                                       @strong window_clone_for_delete, 
-                                       @strong status_label_clone_for_delete, 
-                                       @strong word_count_label_clone_for_delete, 
-                                       @strong text_view_clone_for_delete => move || {
-                                    // Get the current note using cloned title
-                                    if let Ok(note) = Note::load(&crate::utils::get_notes_dir()
-                                        .join(format!("{}.md", note_title_for_delete))) {
-                                        // Delete it
-                                        if let Ok(()) = note.delete() {
-                                            // Refresh the list
-                                            refresh_note_list(&list_box_for_delete, &active_note_clone_for_delete, &window_clone_for_delete, &status_label_clone_for_delete, &word_count_label_clone_for_delete, &text_view_clone_for_delete);
-                                            // Optionally clear editor if deleted note was active
-                                            // This will be handled by the data consistency fix for delete: Issue #2
-                                            // If the deleted note was active, clear the editor and active_note state.
-                                            let active_note_guard = active_note_clone_for_delete.borrow_mut(); // Removed mut from let binding
-                                            if let Some(active) = active_note_guard.as_ref() {
-                                                if active.title == note_title_for_delete {
-                                                    // Drop the mutable borrow before calling text_view_clone_for_delete methods or other functions that might borrow active_note
-                                                    drop(active_note_guard);
-
-                                                    text_view_clone_for_delete.buffer().set_text("");
-                                                    *active_note_clone_for_delete.borrow_mut() = None;
-                                                    window_clone_for_delete.set_title(Some(APP_NAME));
-                                                    status_label_clone_for_delete.set_text("Ready");
-                                                    word_count_label_clone_for_delete.set_text("0 words");
-                                                    // No specific note to select, list_box.connect_row_selected will handle if selection clears or moves.
-                                                    // text_view_clone_for_delete.grab_focus(); // Focus editor
-                                                }
-                                            }
-
-                                        } else {
-                                            // Handle delete error
-                                            if let Some(root_window) = list_box_for_delete.root().and_then(|r| r.downcast::<ApplicationWindow>().ok()) {
-                                                show_error_dialog(&root_window, "Delete Failed", "Could not delete the note.");
-                                            }
-                                        }
-                                    }
-                                })
-                            );
-                        }
-                    }
+                    delete_note_interactive(&note_title_for_delete, &list_box_for_delete, &active_note_clone_for_delete, &window_clone_for_delete, &status_label_clone_for_delete, &word_count_label_clone_for_delete, &text_view_clone_for_delete);
                 });
 
                 // Add the row to the ListBox
@@ -1260,6 +1672,171 @@ fn refresh_note_list(list_box: &ListBox, active_note_ref: &Rc<RefCell<Option<Act
     }
 }
 
+/// Reload the open note's text if its file changed on disk, keeping the cursor position
+fn reload_active_note_content(active_note_ref: &Rc<RefCell<Option<ActiveNote>>>, text_view_ref: &TextView) {
+    let mut active_note_guard = active_note_ref.borrow_mut();
+    let active = match active_note_guard.as_mut() {
+        Some(active) => active,
+        None => return,
+    };
+    let reloaded = match Note::load(&active.path) {
+        Ok(note) => note,
+        Err(e) => {
+            eprintln!("Error reloading note {:?}: {}", active.path, e);
+            return;
+        }
+    };
+    if reloaded.content == active.note.content {
+        return;
+    }
+
+    let buffer = text_view_ref.buffer();
+    let cursor_offset = buffer.iter_at_mark(&buffer.get_insert()).offset();
+    PROGRAMMATIC_TEXT_CHANGE.with(|ptc| *ptc.borrow_mut() = true);
+    buffer.set_text(&reloaded.content);
+    PROGRAMMATIC_TEXT_CHANGE.with(|ptc| *ptc.borrow_mut() = false);
+    buffer.place_cursor(&buffer.iter_at_offset(cursor_offset));
+    active.note = reloaded;
+}
+
+/// Save the note if it is active with unsaved changes, then show the rename dialog for it
+fn rename_note_interactive(title: &str, list_box: &ListBox, active_note_ref: &Rc<RefCell<Option<ActiveNote>>>, window_ref: &ApplicationWindow, status_label_ref: &Label, word_count_label_ref: &Label, text_view_ref: &TextView) {
+    // Save pending edits first so the renamed file has the latest content.
+    // This applies to any open note: renaming rewrites [[links]] in other notes too.
+    {
+        let mut active_opt = active_note_ref.borrow_mut();
+        if let Some(active) = active_opt.as_mut() {
+            // A pending auto-save would write to the old path (or stale links) after the rename
+            if let Some(source_id) = active.auto_save_source_id.take() {
+                source_id.remove();
+            }
+            if active.has_changes {
+                match active.note.save() {
+                    Ok(_) => {
+                        active.has_changes = false;
+                        status_label_ref.set_text("Saved");
+                    }
+                    Err(e) => {
+                        show_error_dialog(
+                            window_ref,
+                            "Save Error",
+                            &format!("Failed to save changes to note '{}' before renaming: {}. Rename aborted.", active.title, e),
+                        );
+                        return; // Abort rename
+                    }
+                }
+            }
+        }
+    }
+
+    let original_title_for_confirm = title.to_string();
+    show_rename_dialog(
+        window_ref,
+        title.to_string(),
+        clone!(@strong list_box,
+               @strong original_title_for_confirm,
+               @strong active_note_ref,
+               @strong window_ref,
+               @strong status_label_ref,
+               @strong word_count_label_ref,
+               @strong text_view_ref => move |new_title| {
+            let original_file_path = Note::path_for_title(&original_title_for_confirm);
+
+            match Note::load(&original_file_path) {
+                Ok(mut note_to_rename) => {
+                    if let Err(e) = note_to_rename.rename(&new_title) {
+                        show_error_dialog(&window_ref, "Rename Failed", &format!("Could not rename the note: {}", e));
+                        return;
+                    }
+
+                    // If rename was successful, check if it was the active note
+                    let mut active_note_guard = active_note_ref.borrow_mut();
+                    if let Some(active) = active_note_guard.as_mut() {
+                        if active.title == original_title_for_confirm {
+                            active.title = new_title.clone();
+                            active.path = note_to_rename.path.clone();
+                            active.note.title = new_title.clone();
+                            active.note.path = note_to_rename.path.clone();
+                            active.note.modified_time = note_to_rename.modified_time;
+                            active.has_changes = false; // Changes were saved before rename or it's a fresh state
+                            window_ref.set_title(Some(&format!("{} - {}", APP_NAME, new_title)));
+                            status_label_ref.set_text("Renamed");
+                            let status_label_reset = status_label_ref.clone();
+                            glib::timeout_add_seconds_local(2, move || {
+                                if status_label_reset.text() == "Renamed" {
+                                    status_label_reset.set_text("Ready");
+                                }
+                                glib::ControlFlow::Break
+                            });
+                        }
+                    }
+                    drop(active_note_guard); // Release borrow
+
+                    // Links to the renamed note may have been rewritten in the open note
+                    reload_active_note_content(&active_note_ref, &text_view_ref);
+
+                    refresh_note_list(&list_box, &active_note_ref, &window_ref, &status_label_ref, &word_count_label_ref, &text_view_ref);
+                    select_note_by_title(&list_box, &new_title);
+                }
+                Err(e) => {
+                    show_error_dialog(&window_ref, "Rename Failed", &format!("Could not load note for renaming: {}", e));
+                }
+            }
+        })
+    );
+}
+
+/// Ask for confirmation, then delete the note and clear the editor if it was active
+fn delete_note_interactive(title: &str, list_box: &ListBox, active_note_ref: &Rc<RefCell<Option<ActiveNote>>>, window_ref: &ApplicationWindow, status_label_ref: &Label, word_count_label_ref: &Label, text_view_ref: &TextView) {
+    let note_title_for_delete = title.to_string();
+    show_confirmation_dialog(
+        window_ref,
+        "Confirm Deletion",
+        &format!("Delete note \"{}\"?", title),
+        "This action cannot be undone.",
+        clone!(@strong list_box,
+               @strong note_title_for_delete,
+               @strong active_note_ref,
+               @strong window_ref,
+               @strong status_label_ref,
+               @strong word_count_label_ref,
+               @strong text_view_ref => move || {
+            let note_path = Note::path_for_title(&note_title_for_delete);
+            let deleted = Note::load(&note_path).and_then(|note| note.delete());
+            if let Err(e) = deleted {
+                show_error_dialog(&window_ref, "Delete Failed", &format!("Could not delete the note: {}", e));
+                return;
+            }
+
+            // If the deleted note was active, clear the editor and active_note state first,
+            // so neither a pending auto-save nor the buffer change recreates the file.
+            let was_active = {
+                let mut active_note_guard = active_note_ref.borrow_mut();
+                match active_note_guard.as_mut() {
+                    Some(active) if active.title == note_title_for_delete => {
+                        if let Some(source_id) = active.auto_save_source_id.take() {
+                            source_id.remove();
+                        }
+                        *active_note_guard = None;
+                        true
+                    }
+                    _ => false,
+                }
+            };
+            if was_active {
+                PROGRAMMATIC_TEXT_CHANGE.with(|ptc| *ptc.borrow_mut() = true);
+                text_view_ref.buffer().set_text("");
+                PROGRAMMATIC_TEXT_CHANGE.with(|ptc| *ptc.borrow_mut() = false);
+                window_ref.set_title(Some(APP_NAME));
+                status_label_ref.set_text("Ready");
+                word_count_label_ref.set_text("0 words");
+            }
+
+            refresh_note_list(&list_box, &active_note_ref, &window_ref, &status_label_ref, &word_count_label_ref, &text_view_ref);
+        })
+    );
+}
+
 /// Load CSS styling for the application
 fn load_css() {
     // Define possible CSS file locations
@@ -1386,13 +1963,16 @@ where
 }
 
 /// Find and select a note by its title
-fn select_note_by_title(list_box: &ListBox, title_to_find: &str) {
+/// Returns false if no row has that title
+fn select_note_by_title(list_box: &ListBox, title_to_find: &str) -> bool {
     let mut row_index = 0;
 
     while let Some(row) = list_box.row_at_index(row_index) {
-        // Find the title label within the row's Box (now vertical)
+        // Find the title label within the row (outer box -> content box -> title label)
         let title_label_opt = row.child()
-            .and_then(|child_box| child_box.downcast::<Box>().ok())
+            .and_then(|outer_box| outer_box.downcast::<Box>().ok())
+            .and_then(|hbox| hbox.first_child()) // Get content box
+            .and_then(|content_box| content_box.downcast::<Box>().ok())
             .and_then(|vbox| vbox.first_child()) // Get the first child (title label)
             .and_then(|widget| widget.downcast::<Label>().ok());
 
@@ -1401,23 +1981,25 @@ fn select_note_by_title(list_box: &ListBox, title_to_find: &str) {
                 list_box.select_row(Some(&row));
                 // We don't call row.grab_focus() here anymore.
                 // The text_view focus will be handled by the list_box.connect_row_selected callback.
-                return;
+                return true;
             }
         }
 
         row_index += 1;
     }
+    false
 }
 
-/// Show keyboard shortcuts dialog
-fn show_shortcuts_dialog(parent: &ApplicationWindow) {
+/// Show keyboard shortcuts dialog, generated from the live bindings.
+/// Clicking a shortcut lets the user press a new one; conflicts are confirmed first.
+fn show_shortcuts_dialog(parent: &ApplicationWindow, settings: &Rc<RefCell<Settings>>) {
     // Create a dialog window
     let dialog = ApplicationWindow::builder()
         .transient_for(parent)
         .modal(true)
         .title("Keyboard Shortcuts")
-        .default_width(400)
-        .default_height(400)
+        .default_width(440)
+        .default_height(520)
         .css_classes(vec!["shortcuts-dialog"])
         .build();
     
@@ -1440,42 +2022,140 @@ fn show_shortcuts_dialog(parent: &ApplicationWindow) {
         .css_classes(vec!["shortcuts-title"])
         .build();
     content_box.append(&title_label);
+
+    let hint_label = Label::builder()
+        .label("Click a shortcut to change it. Backspace disables it, Escape cancels.")
+        .xalign(0.0)
+        .wrap(true)
+        .css_classes(vec!["dim-label"])
+        .build();
+    content_box.append(&hint_label);
     
-    // Create sections
-    add_shortcut_section(&content_box, "General", &[
-        ("Ctrl+K", "Show keyboard shortcuts"),
-        ("Ctrl+T", "Toggle light/dark theme"),
-        ("Ctrl+B", "Toggle sidebar"),
-        ("F11", "Toggle fullscreen mode"),
-        ("Escape", "Exit fullscreen")
-    ]);
-    
-    add_shortcut_section(&content_box, "Editing", &[
-        ("Ctrl+S", "Save current note"),
-        ("Ctrl+N", "Create new note"),
-        ("Ctrl+D", "Delete selected note"),
-        ("Ctrl+R", "Rename selected note")
-    ]);
+    // Create sections from the command registry
+    let key_buttons: Rc<RefCell<Vec<(&'static Command, Button)>>> = Rc::new(RefCell::new(Vec::new()));
+    // The command waiting for a new key press, if any
+    let capturing: Rc<RefCell<Option<&'static Command>>> = Rc::new(RefCell::new(None));
+
+    for section in SECTIONS {
+        let commands: Vec<&'static Command> = COMMANDS.iter()
+            .filter(|command| command.section == *section)
+            .collect();
+        for (command, button) in add_shortcut_section(&content_box, section, &commands, &settings.borrow()) {
+            let capturing_for_button = capturing.clone();
+            let key_buttons_for_button = key_buttons.clone();
+            let settings_for_button = settings.clone();
+            button.connect_clicked(move |button| {
+                // Restore any other button that was waiting for a key
+                refresh_shortcut_labels(&key_buttons_for_button, &settings_for_button.borrow());
+                *capturing_for_button.borrow_mut() = Some(command);
+                button.set_label("Press a shortcut\u{2026}");
+            });
+            key_buttons.borrow_mut().push((command, button));
+        }
+    }
     
-    // Add a close button
+    // Add reset and close buttons
     let button_box = Box::builder()
         .orientation(Orientation::Horizontal)
         .halign(gtk::Align::End)
+        .spacing(8)
         .margin_top(20)
         .build();
+
+    let reset_button = Button::builder()
+        .label("Reset to Defaults")
+        .build();
         
     let close_button = Button::builder()
         .label("Close")
         .build();
         
+    button_box.append(&reset_button);
     button_box.append(&close_button);
     content_box.append(&button_box);
+
+    // Reset every binding
+    let settings_for_reset = settings.clone();
+    let key_buttons_for_reset = key_buttons.clone();
+    let parent_for_reset = parent.clone();
+    reset_button.connect_clicked(move |_| {
+        settings_for_reset.borrow_mut().shortcuts.clear();
+        save_and_apply_shortcuts(&parent_for_reset, &settings_for_reset);
+        refresh_shortcut_labels(&key_buttons_for_reset, &settings_for_reset.borrow());
+    });
     
     // Connect close button
     let dialog_clone = dialog.clone();
     close_button.connect_clicked(move |_| {
         dialog_clone.close();
     });
+
+    // Capture the next key press while a shortcut button is waiting
+    let key_controller = EventControllerKey::new();
+    key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let dialog_for_key = dialog.clone();
+    let parent_for_key = parent.clone();
+    let settings_for_key = settings.clone();
+    let key_buttons_for_key = key_buttons.clone();
+    key_controller.connect_key_pressed(move |_, key, _keycode, state| {
+        let command = match *capturing.borrow() {
+            Some(command) => command,
+            None => return Propagation::Proceed,
+        };
+        if is_modifier_key(key) {
+            return Propagation::Stop; // Wait for the actual key
+        }
+        *capturing.borrow_mut() = None;
+
+        let mods = state & gtk::accelerator_get_default_mod_mask();
+        match key {
+            Key::Escape if mods.is_empty() => {}
+            Key::BackSpace if mods.is_empty() => {
+                shortcuts::set_accels(&mut settings_for_key.borrow_mut(), command, Vec::new());
+                save_and_apply_shortcuts(&parent_for_key, &settings_for_key);
+            }
+            _ if gtk::accelerator_valid(key, mods) => {
+                let accel = gtk::accelerator_name(key.to_lower(), mods).to_string();
+                let conflict = shortcuts::find_conflict(&settings_for_key.borrow(), &accel, command.name);
+                match conflict {
+                    Some(other) => {
+                        // Ask before stealing the binding from another command
+                        let settings_for_confirm = settings_for_key.clone();
+                        let parent_for_confirm = parent_for_key.clone();
+                        let key_buttons_for_confirm = key_buttons_for_key.clone();
+                        let accel_for_confirm = accel.clone();
+                        show_confirmation_dialog(
+                            &dialog_for_key,
+                            "Shortcut Conflict",
+                            &format!("{} is already used by \"{}\".", shortcuts::accel_label(&accel), other.description),
+                            &format!("Reassign it to \"{}\"?", command.description),
+                            move || {
+                                {
+                                    let mut settings = settings_for_confirm.borrow_mut();
+                                    let remaining: Vec<String> = shortcuts::accels_for(&settings, other).into_iter()
+                                        .filter(|a| gtk::accelerator_parse(a) != gtk::accelerator_parse(&accel_for_confirm))
+                                        .collect();
+                                    shortcuts::set_accels(&mut settings, other, remaining);
+                                    shortcuts::set_accels(&mut settings, command, vec![accel_for_confirm.clone()]);
+                                }
+                                save_and_apply_shortcuts(&parent_for_confirm, &settings_for_confirm);
+                                refresh_shortcut_labels(&key_buttons_for_confirm, &settings_for_confirm.borrow());
+                            },
+                        );
+                    }
+                    None => {
+                        shortcuts::set_accels(&mut settings_for_key.borrow_mut(), command, vec![accel]);
+                        save_and_apply_shortcuts(&parent_for_key, &settings_for_key);
+                    }
+                }
+            }
+            _ => {} // Not usable as a shortcut (e.g. a bare letter)
+        }
+
+        refresh_shortcut_labels(&key_buttons_for_key, &settings_for_key.borrow());
+        Propagation::Stop
+    });
+    dialog.add_controller(key_controller);
     
     // Set content
     scrolled_window.set_child(Some(&content_box));
@@ -1485,8 +2165,373 @@ fn show_shortcuts_dialog(parent: &ApplicationWindow) {
     dialog.present();
 }
 
-/// Helper to add a section of shortcuts to the dialog
-fn add_shortcut_section(container: &Box, title: &str, shortcuts: &[(&str, &str)]) {
+/// Whether a key is only a modifier (Shift, Ctrl, ...) and can't end a shortcut
+fn is_modifier_key(key: Key) -> bool {
+    matches!(key,
+        Key::Shift_L | Key::Shift_R | Key::Control_L | Key::Control_R |
+        Key::Alt_L | Key::Alt_R | Key::Super_L | Key::Super_R |
+        Key::Meta_L | Key::Meta_R | Key::ISO_Level3_Shift | Key::Caps_Lock)
+}
+
+/// Persist the shortcut settings and register them with the application
+fn save_and_apply_shortcuts(window: &ApplicationWindow, settings: &Rc<RefCell<Settings>>) {
+    if let Some(app) = window.application() {
+        shortcuts::apply_accels(&app, &settings.borrow());
+    }
+    if let Err(e) = settings.borrow().save() {
+        eprintln!("Error saving settings: {}", e);
+    }
+}
+
+/// Update the shortcut buttons in the dialog to show the current bindings
+fn refresh_shortcut_labels(key_buttons: &Rc<RefCell<Vec<(&'static Command, Button)>>>, settings: &Settings) {
+    for (command, button) in key_buttons.borrow().iter() {
+        button.set_label(&shortcuts::accels_label(settings, command));
+    }
+}
+
+/// Give a widget a tooltip with the command's first shortcut, e.g. "New Note (Ctrl+N)".
+/// The shortcut is looked up each time the tooltip shows, so it follows rebindings.
+fn tooltip_with_accel(widget: &impl IsA<gtk::Widget>, label: &'static str, action_name: &'static str) {
+    widget.set_has_tooltip(true);
+    widget.connect_query_tooltip(move |widget, _, _, _, tooltip| {
+        let app = widget.root()
+            .and_then(|root| root.downcast::<ApplicationWindow>().ok())
+            .and_then(|window| window.application());
+        let accel = app.zip(shortcuts::find_command(action_name))
+            .and_then(|(app, command)| app.accels_for_action(&command.detailed_name()).first().map(|accel| accel.to_string()));
+        match accel {
+            Some(accel) => tooltip.set_text(Some(&format!("{} ({})", label, shortcuts::accel_label(&accel)))),
+            None => tooltip.set_text(Some(label)),
+        }
+        true
+    });
+}
+
+/// Show the settings dialog; every change is saved immediately.
+/// `on_close` re-applies settings that are not read on every use.
+fn show_settings_dialog<F: Fn() + 'static>(parent: &ApplicationWindow, settings: &Rc<RefCell<Settings>>, on_close: F) {
+    let dialog = ApplicationWindow::builder()
+        .transient_for(parent)
+        .modal(true)
+        .title("Settings")
+        .default_width(420)
+        .default_height(360)
+        .css_classes(vec!["settings-dialog"])
+        .build();
+
+    let scrolled_window = ScrolledWindow::new();
+
+    let content_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .margin_start(20)
+        .margin_end(20)
+        .margin_top(20)
+        .margin_bottom(20)
+        .spacing(10)
+        .build();
+
+    let title_label = Label::builder()
+        .label("Settings")
+        .css_classes(vec!["settings-title"])
+        .build();
+    content_box.append(&title_label);
+
+    let editor = settings.borrow().editor.clone();
+    let editor_section = add_settings_section(&content_box, "Markdown Editing");
+    add_settings_switch(&editor_section, settings, "Continue lists and quotes on Enter",
+        editor.list_continuation, |s, active| s.editor.list_continuation = active);
+    add_settings_switch(&editor_section, settings, "Indent list items with Tab / Shift+Tab",
+        editor.smart_indent, |s, active| s.editor.smart_indent = active);
+    add_settings_switch(&editor_section, settings, "Auto-pair brackets and emphasis markers",
+        editor.auto_pair, |s, active| s.editor.auto_pair = active);
+    add_settings_switch(&editor_section, settings, "Show formatting toolbar on selection",
+        editor.formatting_toolbar, |s, active| s.editor.formatting_toolbar = active);
+
+    let writing = settings.borrow().writing.clone();
+    let writing_section = add_settings_section(&content_box, "Writing");
+    let focus_units: Vec<&str> = FocusUnit::ALL.iter().map(|unit| unit.label()).collect();
+    let focus_unit_index = FocusUnit::ALL.iter().position(|unit| *unit == writing.focus_unit).unwrap_or(0);
+    add_settings_choice(&writing_section, settings, "Focus mode highlights the current",
+        &focus_units, focus_unit_index, |s, index| {
+            if let Some(unit) = FocusUnit::ALL.get(index) {
+                s.writing.focus_unit = *unit;
+            }
+        });
+    add_settings_spin(&writing_section, settings, "Daily word goal (0 = off)",
+        (0.0, 100000.0), 50.0, writing.daily_word_goal as f64, |s, value| s.writing.daily_word_goal = value as usize);
+    add_settings_switch(&writing_section, settings, "Typewriter scrolling",
+        writing.typewriter_scrolling, |s, active| s.writing.typewriter_scrolling = active);
+    add_settings_scale(&writing_section, settings, "Typewriter line height (% from top)",
+        (20.0, 80.0), writing.typewriter_position * 100.0, |s, percent| s.writing.typewriter_position = percent / 100.0);
+
+    let journal = settings.borrow().journal.clone();
+    let journal_section = add_settings_section(&content_box, "Daily Notes");
+    add_settings_entry(&journal_section, settings, "Template or note (empty = dated heading)",
+        &journal.template_note, |s, text| s.journal.template_note = text.trim().to_string());
+
+    let spelling = settings.borrow().spelling.clone();
+    let spelling_section = add_settings_section(&content_box, "Spelling");
+    add_settings_switch(&spelling_section, settings, "Check spelling",
+        spelling.enabled, |s, active| s.spelling.enabled = active);
+    // "Automatic" follows the system language
+    let languages = spellcheck::installed_languages();
+    let mut language_options = vec!["Automatic"];
+    language_options.extend(languages.iter().map(String::as_str));
+    let language_index = languages.iter().position(|language| *language == spelling.language).map_or(0, |i| i + 1);
+    let languages_for_choice = languages.clone();
+    add_settings_choice(&spelling_section, settings, "Default language",
+        &language_options, language_index, move |s, index| {
+            s.spelling.language = index.checked_sub(1)
+                .and_then(|i| languages_for_choice.get(i))
+                .cloned()
+                .unwrap_or_default();
+        });
+
+    let print = settings.borrow().print.clone();
+    let print_section = add_settings_section(&content_box, "Printing");
+    let paper_labels: Vec<&str> = PAPER_SIZES.iter().map(|(label, _)| *label).collect();
+    let paper_index = PAPER_SIZES.iter().position(|(_, name)| *name == print.paper_size).unwrap_or(0);
+    add_settings_choice(&print_section, settings, "Paper size",
+        &paper_labels, paper_index, |s, index| {
+            if let Some((_, name)) = PAPER_SIZES.get(index) {
+                s.print.paper_size = name.to_string();
+            }
+        });
+    add_settings_spin(&print_section, settings, "Margins (mm)",
+        (5.0, 50.0), 1.0, print.margin_mm, |s, value| s.print.margin_mm = value);
+    add_settings_entry(&print_section, settings, "Font (e.g. Serif 11)",
+        &print.font, |s, text| s.print.font = text.trim().to_string());
+    add_settings_switch(&print_section, settings, "Header with title and date",
+        print.header, |s, active| s.print.header = active);
+    add_settings_switch(&print_section, settings, "Page numbers in the footer",
+        print.footer, |s, active| s.print.footer = active);
+
+    let button_box = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .halign(gtk::Align::End)
+        .margin_top(20)
+        .build();
+
+    let close_button = Button::builder()
+        .label("Close")
+        .build();
+
+    button_box.append(&close_button);
+    content_box.append(&button_box);
+
+    let dialog_clone = dialog.clone();
+    close_button.connect_clicked(move |_| {
+        dialog_clone.close();
+    });
+
+    dialog.connect_close_request(move |_| {
+        on_close();
+        glib::Propagation::Proceed
+    });
+
+    scrolled_window.set_child(Some(&content_box));
+    dialog.set_child(Some(&scrolled_window));
+
+    dialog.present();
+}
+
+/// Helper to add a titled section to the settings dialog, returning the box for its rows
+fn add_settings_section(container: &Box, title: &str) -> Box {
+    let section_label = Label::builder()
+        .label(title)
+        .xalign(0.0)
+        .css_classes(vec!["settings-section"])
+        .margin_top(10)
+        .build();
+    container.append(&section_label);
+
+    let section_box = Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(8)
+        .margin_start(20)
+        .build();
+    container.append(&section_box);
+    section_box
+}
+
+/// Helper to add a labelled switch that updates and saves a setting when toggled
+fn add_settings_switch<F>(container: &Box, settings: &Rc<RefCell<Settings>>, label: &str, active: bool, apply: F)
+where
+    F: Fn(&mut Settings, bool) + 'static,
+{
+    let row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(20)
+        .build();
+
+    let label = Label::builder()
+        .label(label)
+        .xalign(0.0)
+        .hexpand(true)
+        .build();
+
+    let switch = gtk::Switch::builder()
+        .active(active)
+        .valign(gtk::Align::Center)
+        .build();
+
+    let settings_for_switch = settings.clone();
+    switch.connect_active_notify(move |switch| {
+        let mut settings = settings_for_switch.borrow_mut();
+        apply(&mut settings, switch.is_active());
+        if let Err(e) = settings.save() {
+            eprintln!("Error saving settings: {}", e);
+        }
+    });
+
+    row.append(&label);
+    row.append(&switch);
+    container.append(&row);
+}
+
+/// Helper to add a labelled text entry that updates and saves a setting as it is edited
+fn add_settings_entry<F>(container: &Box, settings: &Rc<RefCell<Settings>>, label: &str, text: &str, apply: F)
+where
+    F: Fn(&mut Settings, &str) + 'static,
+{
+    let row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(20)
+        .build();
+
+    let label = Label::builder()
+        .label(label)
+        .xalign(0.0)
+        .hexpand(true)
+        .build();
+
+    let entry = Entry::builder()
+        .text(text)
+        .valign(gtk::Align::Center)
+        .build();
+
+    let settings_for_entry = settings.clone();
+    entry.connect_changed(move |entry| {
+        let mut settings = settings_for_entry.borrow_mut();
+        apply(&mut settings, &entry.text());
+        if let Err(e) = settings.save() {
+            eprintln!("Error saving settings: {}", e);
+        }
+    });
+
+    row.append(&label);
+    row.append(&entry);
+    container.append(&row);
+}
+
+/// Helper to add a labelled drop-down that updates and saves a setting when changed
+fn add_settings_choice<F>(container: &Box, settings: &Rc<RefCell<Settings>>, label: &str, options: &[&str], selected: usize, apply: F)
+where
+    F: Fn(&mut Settings, usize) + 'static,
+{
+    let row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(20)
+        .build();
+
+    let label = Label::builder()
+        .label(label)
+        .xalign(0.0)
+        .hexpand(true)
+        .build();
+
+    let drop_down = gtk::DropDown::from_strings(options);
+    drop_down.set_selected(selected as u32);
+    drop_down.set_valign(gtk::Align::Center);
+
+    let settings_for_choice = settings.clone();
+    drop_down.connect_selected_notify(move |drop_down| {
+        let mut settings = settings_for_choice.borrow_mut();
+        apply(&mut settings, drop_down.selected() as usize);
+        if let Err(e) = settings.save() {
+            eprintln!("Error saving settings: {}", e);
+        }
+    });
+
+    row.append(&label);
+    row.append(&drop_down);
+    container.append(&row);
+}
+
+/// Helper to add a labelled slider that updates and saves a setting when moved
+fn add_settings_scale<F>(container: &Box, settings: &Rc<RefCell<Settings>>, label: &str, range: (f64, f64), value: f64, apply: F)
+where
+    F: Fn(&mut Settings, f64) + 'static,
+{
+    let row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(20)
+        .build();
+
+    let label = Label::builder()
+        .label(label)
+        .xalign(0.0)
+        .hexpand(true)
+        .build();
+
+    let scale = gtk::Scale::with_range(Orientation::Horizontal, range.0, range.1, 1.0);
+    scale.set_value(value);
+    scale.set_digits(0);
+    scale.set_draw_value(true);
+    scale.set_width_request(140);
+    scale.set_valign(gtk::Align::Center);
+
+    let settings_for_scale = settings.clone();
+    scale.connect_value_changed(move |scale| {
+        let mut settings = settings_for_scale.borrow_mut();
+        apply(&mut settings, scale.value());
+        if let Err(e) = settings.save() {
+            eprintln!("Error saving settings: {}", e);
+        }
+    });
+
+    row.append(&label);
+    row.append(&scale);
+    container.append(&row);
+}
+
+/// Helper to add a labelled number field that updates and saves a setting when changed
+fn add_settings_spin<F>(container: &Box, settings: &Rc<RefCell<Settings>>, label: &str, range: (f64, f64), step: f64, value: f64, apply: F)
+where
+    F: Fn(&mut Settings, f64) + 'static,
+{
+    let row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(20)
+        .build();
+
+    let label = Label::builder()
+        .label(label)
+        .xalign(0.0)
+        .hexpand(true)
+        .build();
+
+    let spin_button = gtk::SpinButton::with_range(range.0, range.1, step);
+    spin_button.set_value(value);
+    spin_button.set_valign(gtk::Align::Center);
+
+    let settings_for_spin = settings.clone();
+    spin_button.connect_value_changed(move |spin_button| {
+        let mut settings = settings_for_spin.borrow_mut();
+        apply(&mut settings, spin_button.value());
+        if let Err(e) = settings.save() {
+            eprintln!("Error saving settings: {}", e);
+        }
+    });
+
+    row.append(&label);
+    row.append(&spin_button);
+    container.append(&row);
+}
+
+/// Helper to add a section of shortcuts to the dialog, returning each command's key button
+fn add_shortcut_section(container: &Box, title: &str, commands: &[&'static Command], settings: &Settings) -> Vec<(&'static Command, Button)> {
     // Add section title
     let section_label = Label::builder()
         .label(title)
@@ -1503,21 +2548,23 @@ fn add_shortcut_section(container: &Box, title: &str, shortcuts: &[(&str, &str)]
         .margin_start(20)
         .build();
     
-    for (i, (key, description)) in shortcuts.iter().enumerate() {
-        let key_label = Label::builder()
-            .label(*key) // Dereference to solve the type issue
-            .xalign(0.0)
+    let mut buttons = Vec::new();
+    for (i, command) in commands.iter().enumerate() {
+        let key_button = Button::builder()
+            .label(shortcuts::accels_label(settings, command))
             .css_classes(vec!["shortcut-key"])
             .build();
             
         let desc_label = Label::builder()
-            .label(*description) // Dereference to solve the type issue
+            .label(command.description)
             .xalign(0.0)
             .build();
             
-        grid.attach(&key_label, 0, i as i32, 1, 1);
+        grid.attach(&key_button, 0, i as i32, 1, 1);
         grid.attach(&desc_label, 1, i as i32, 1, 1);
+        buttons.push((*command, key_button));
     }
     
     container.append(&grid);
+    buttons
 }
@@ -1,6 +1,7 @@
 use std::path::PathBuf;
+use gtk::prelude::*;
 use gtk::{glib};
-use gtk::{ApplicationWindow, AlertDialog};
+use gtk::{ApplicationWindow, AlertDialog, Box, Label, Orientation};
 use gtk::gio;
 
 /// Get the path to the notes directory
@@ -9,6 +10,31 @@ pub fn get_notes_dir() -> PathBuf {
     user_data_dir.join("penscript/notes")
 }
 
+/// Check the title of a new note: it names a file in the notes directory, so it
+/// cannot be empty, contain a folder separator or start with a dot (hidden files)
+pub fn check_note_title(title: &str) -> Result<(), String> {
+    if title.is_empty() || title.contains('/') || title.starts_with('.') {
+        return Err(format!("\"{}\" is not a valid note title.", title));
+    }
+    Ok(())
+}
+
+/// A form row in the dialogs: a label of fixed width followed by a widget
+pub fn labelled_row(label: &str, widget: &impl IsA<gtk::Widget>) -> Box {
+    let row = Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(12)
+        .build();
+    let label = Label::builder()
+        .label(label)
+        .xalign(0.0)
+        .width_chars(10)
+        .build();
+    row.append(&label);
+    row.append(widget);
+    row
+}
+
 /// Show an error dialog using AlertDialog
 pub fn show_error_dialog(parent: &ApplicationWindow, title: &str, message: &str) {
     let dialog = AlertDialog::builder()
@@ -0,0 +1,310 @@
+use gtk::prelude::*;
+use gtk::{glib, ApplicationWindow, EventControllerKey, EventControllerMotion, GestureClick, Label,
+          ListBox, Popover, TextBuffer, TextTag, TextView};
+use gtk::gdk::{Key, ModifierType};
+use glib::Propagation;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::fuzzy::fuzzy_score;
+use crate::markdown::{self, WikiLink};
+use crate::note::Note;
+
+// Name of the text tag that styles [[links]]
+const LINK_TAG: &str = "wiki-link";
+// Maximum number of titles offered while typing a link
+const MAX_SUGGESTIONS: usize = 8;
+
+/// Highlight `[[links]]` in the editor, open them with Ctrl+click and
+/// autocomplete note titles after `[[`.
+/// Must be installed before the Markdown assists so the completion popup gets Enter/Tab first.
+pub fn install_wiki_links(text_view: &TextView, window: &ApplicationWindow) {
+    let buffer = text_view.buffer();
+    let link_tag = TextTag::builder()
+        .name(LINK_TAG)
+        .foreground("#3584e4")
+        .underline(gtk::pango::Underline::Single)
+        .build();
+    buffer.tag_table().add(&link_tag);
+
+    // Re-tag links on every change (including notes being loaded)
+    buffer.connect_changed(highlight_links);
+
+    // Ctrl+click opens the link target, creating the note if it does not exist
+    let click = GestureClick::new();
+    click.set_button(gtk::gdk::BUTTON_PRIMARY);
+    click.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let text_view_for_click = text_view.clone();
+    let window_for_click = window.clone();
+    click.connect_pressed(move |gesture, _n_press, x, y| {
+        if !gesture.current_event_state().contains(ModifierType::CONTROL_MASK) {
+            return;
+        }
+        if let Some(link) = link_at_position(&text_view_for_click, x, y) {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            // Switching notes replaces the buffer, so leave the click handler first
+            let window = window_for_click.clone();
+            glib::idle_add_local_once(move || open_link_target(&window, &link.target));
+        }
+    });
+    text_view.add_controller(click);
+
+    // Pointer cursor over links
+    let motion = EventControllerMotion::new();
+    let text_view_for_motion = text_view.clone();
+    motion.connect_motion(move |_, x, y| {
+        let cursor = if link_at_position(&text_view_for_motion, x, y).is_some() { "pointer" } else { "text" };
+        text_view_for_motion.set_cursor_from_name(Some(cursor));
+    });
+    text_view.add_controller(motion);
+
+    LinkCompletion::new(text_view).connect_signals();
+}
+
+/// Apply the link tag to every wiki link in the buffer
+fn highlight_links(buffer: &TextBuffer) {
+    let (start, end) = buffer.bounds();
+    buffer.remove_tag_by_name(LINK_TAG, &start, &end);
+
+    let text = buffer.text(&start, &end, true);
+    // Links are in order, so convert byte offsets to char offsets incrementally
+    let (mut byte_pos, mut char_pos) = (0, 0);
+    for link in markdown::wiki_links(&text) {
+        char_pos += text[byte_pos..link.start].chars().count() as i32;
+        let link_chars = text[link.start..link.end].chars().count() as i32;
+        buffer.apply_tag_by_name(
+            LINK_TAG,
+            &buffer.iter_at_offset(char_pos),
+            &buffer.iter_at_offset(char_pos + link_chars),
+        );
+        char_pos += link_chars;
+        byte_pos = link.end;
+    }
+}
+
+/// The highlighted wiki link under a point in widget coordinates, if any
+fn link_at_position(text_view: &TextView, x: f64, y: f64) -> Option<WikiLink> {
+    let (buffer_x, buffer_y) = text_view.window_to_buffer_coords(gtk::TextWindowType::Widget, x as i32, y as i32);
+    let iter = text_view.iter_at_location(buffer_x, buffer_y)?;
+    let buffer = text_view.buffer();
+    let tag = buffer.tag_table().lookup(LINK_TAG)?;
+    if !iter.has_tag(&tag) {
+        return None;
+    }
+
+    let mut line_start = iter;
+    line_start.set_line_offset(0);
+    let mut line_end = iter;
+    if !line_end.ends_line() {
+        line_end.forward_to_line_end();
+    }
+    let line = buffer.text(&line_start, &line_end, true);
+    let byte_offset = line.char_indices()
+        .nth(iter.line_offset() as usize)
+        .map(|(i, _)| i)
+        .unwrap_or(line.len());
+    markdown::wiki_links(&line).into_iter()
+        .find(|link| link.start <= byte_offset && byte_offset < link.end)
+}
+
+/// Open the note a link points at (matching titles case-insensitively), or create it
+fn open_link_target(window: &ApplicationWindow, target: &str) {
+    let title = Note::existing_title(target).unwrap_or_else(|| target.to_string());
+    if let Err(e) = WidgetExt::activate_action(window, "win.create-note", Some(&title.to_variant())) {
+        eprintln!("Error opening linked note {}: {}", title, e);
+    }
+}
+
+/// Popup suggesting note titles while a `[[link` is being typed
+#[derive(Clone)]
+struct LinkCompletion {
+    text_view: TextView,
+    popover: Popover,
+    list_box: ListBox,
+    /// Titles of all notes, loaded when a completion starts
+    titles: Rc<RefCell<Vec<String>>>,
+    /// Titles currently shown, in row order
+    suggestions: Rc<RefCell<Vec<String>>>,
+}
+
+impl LinkCompletion {
+    fn new(text_view: &TextView) -> Self {
+        let list_box = ListBox::builder()
+            .selection_mode(gtk::SelectionMode::Single)
+            .css_classes(vec!["link-completion"])
+            .build();
+
+        let popover = Popover::builder()
+            .child(&list_box)
+            .autohide(false) // Typing continues in the editor
+            .has_arrow(false)
+            .position(gtk::PositionType::Bottom)
+            .can_focus(false)
+            .build();
+        popover.set_parent(text_view);
+
+        LinkCompletion {
+            text_view: text_view.clone(),
+            popover,
+            list_box,
+            titles: Rc::new(RefCell::new(Vec::new())),
+            suggestions: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    fn connect_signals(&self) {
+        let buffer = self.text_view.buffer();
+
+        // Typing updates the suggestions
+        let completion = self.clone();
+        buffer.connect_end_user_action(move |_| {
+            completion.update();
+        });
+
+        // Moving the cursor out of the link closes the popup
+        let completion = self.clone();
+        buffer.connect_mark_set(move |buffer, _, mark| {
+            if completion.popover.is_visible() && *mark == buffer.get_insert() {
+                completion.update();
+            }
+        });
+
+        // Up/Down/Enter/Tab/Escape drive the popup while it is open
+        let key_controller = EventControllerKey::new();
+        key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        let completion = self.clone();
+        key_controller.connect_key_pressed(move |_, key, _keycode, _state| {
+            if !completion.popover.is_visible() {
+                return Propagation::Proceed;
+            }
+            match key {
+                Key::Down | Key::KP_Down => completion.move_selection(1),
+                Key::Up | Key::KP_Up => completion.move_selection(-1),
+                Key::Return | Key::KP_Enter | Key::Tab => completion.accept(),
+                Key::Escape => completion.popover.popdown(),
+                _ => return Propagation::Proceed,
+            }
+            Propagation::Stop
+        });
+        self.text_view.add_controller(key_controller);
+
+        let completion = self.clone();
+        self.list_box.connect_row_activated(move |list_box, row| {
+            list_box.select_row(Some(row));
+            completion.accept();
+        });
+    }
+
+    /// The partial title between `[[` and the cursor, if the cursor is inside an open link
+    fn current_query(&self) -> Option<String> {
+        let buffer = self.text_view.buffer();
+        if buffer.has_selection() || !self.text_view.has_focus() {
+            return None;
+        }
+        let cursor = buffer.iter_at_mark(&buffer.get_insert());
+        let mut line_start = cursor;
+        line_start.set_line_offset(0);
+        let before = buffer.text(&line_start, &cursor, true);
+        markdown::wiki_link_query(&before).map(str::to_string)
+    }
+
+    fn update(&self) {
+        let query = match self.current_query() {
+            Some(query) => query,
+            None => {
+                self.popover.popdown();
+                return;
+            }
+        };
+
+        // Fresh title list for each new completion
+        if !self.popover.is_visible() {
+            *self.titles.borrow_mut() = Note::get_all()
+                .map(|notes| notes.into_iter().map(|note| note.title).collect())
+                .unwrap_or_default();
+        }
+
+        let mut scored: Vec<(i64, String)> = self.titles.borrow().iter()
+            .filter_map(|title| fuzzy_score(&query, title).map(|score| (score, title.clone())))
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        let suggestions: Vec<String> = scored.into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, title)| title)
+            .collect();
+
+        if suggestions.is_empty() {
+            self.popover.popdown();
+            return;
+        }
+
+        while let Some(row) = self.list_box.row_at_index(0) {
+            self.list_box.remove(&row);
+        }
+        for title in &suggestions {
+            let label = Label::builder()
+                .label(title)
+                .xalign(0.0)
+                .margin_start(8)
+                .margin_end(8)
+                .margin_top(4)
+                .margin_bottom(4)
+                .build();
+            self.list_box.append(&label);
+        }
+        *self.suggestions.borrow_mut() = suggestions;
+        if let Some(row) = self.list_box.row_at_index(0) {
+            self.list_box.select_row(Some(&row));
+        }
+
+        // Point the popup at the cursor
+        let buffer = self.text_view.buffer();
+        let location = self.text_view.iter_location(&buffer.iter_at_mark(&buffer.get_insert()));
+        let (x, y) = self.text_view.buffer_to_window_coords(gtk::TextWindowType::Widget, location.x(), location.y());
+        self.popover.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x, y, 1, location.height())));
+        if !self.popover.is_visible() {
+            self.popover.popup();
+        }
+    }
+
+    fn move_selection(&self, delta: i32) {
+        let count = self.suggestions.borrow().len() as i32;
+        let current = self.list_box.selected_row().map(|row| row.index()).unwrap_or(0);
+        if let Some(row) = self.list_box.row_at_index((current + delta).clamp(0, count - 1)) {
+            self.list_box.select_row(Some(&row));
+        }
+    }
+
+    /// Replace the partial title with the highlighted one and close the link
+    fn accept(&self) {
+        let title = self.list_box.selected_row()
+            .and_then(|row| self.suggestions.borrow().get(row.index() as usize).cloned());
+        let (title, query) = match (title, self.current_query()) {
+            (Some(title), Some(query)) => (title, query),
+            _ => {
+                self.popover.popdown();
+                return;
+            }
+        };
+        self.popover.popdown();
+
+        let buffer = self.text_view.buffer();
+        buffer.begin_user_action();
+        let mut cursor = buffer.iter_at_mark(&buffer.get_insert());
+        let mut query_start = cursor;
+        query_start.backward_chars(query.chars().count() as i32);
+        buffer.delete(&mut query_start, &mut cursor);
+        buffer.insert(&mut query_start, &title);
+
+        // Step over a `]]` that auto-pairing already inserted, else close the link
+        let mut after = query_start;
+        after.forward_chars(2);
+        if buffer.text(&query_start, &after, true) == "]]" {
+            buffer.place_cursor(&after);
+        } else {
+            buffer.insert(&mut query_start, "]]");
+            buffer.place_cursor(&query_start);
+        }
+        buffer.end_user_action();
+    }
+}